@@ -0,0 +1,1214 @@
+//! Boolean operations between closed triangle meshes.
+//!
+//! Every pair of triangles whose boxes overlap is tested for intersection. Whether an edge of one mesh
+//! crosses a triangle of the other is decided with exact predicates, and ties (a vertex exactly on a plane,
+//! an edge exactly through another edge) are broken as if the second mesh had been moved by the
+//! infinitesimal offset `(ε, ε², ε³)`. Every decision is then consistent with some configuration in
+//! general position, so the intersection curves always close up, however the meshes touch.
+//!
+//! Each crossing becomes a vertex shared by both meshes. The triangles are split into polygons along the
+//! curves, which are then ear-clipped; as both meshes are cut at the same vertices, their pieces fit together
+//! exactly. The pieces of each mesh between curves are classified as inside or outside the other mesh by
+//! counting the crossings of a ray from one of their original vertices, again with exact predicates, and
+//! flipping the classification across each curve. The operation keeps the pieces it needs.
+//!
+//! Where the meshes touch without crossing, as two cubes sharing part of a face do, the perturbation leaves
+//! faces of zero area and copies of vertices along the contact. A final pass collapses edges between
+//! coincident vertices, flips away triangles whose corners lie on one line, and drops pieces that enclose no
+//! volume, such as all of `a - a`. Coincident vertices remain only where merging them would make the
+//! surface non-manifold, as where two cubes touch along a whole face.
+
+use core::fmt;
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+
+use glam::{DVec2, DVec3, Vec3};
+
+use crate::{
+    bvh::{Aabb, Bvh},
+    mesh::{blend_nearest, Attribute, BuildError, EdgeId, FaceId, HalfEdgeMesh, VertexId},
+    predicates::{cross_signs, orient2d, orient3d},
+    union_find::UnionFind,
+};
+
+/// Which boolean operation to perform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BooleanOperation {
+    /// Everything inside either mesh.
+    Union,
+    /// Everything inside both meshes.
+    Intersection,
+    /// Everything inside the first mesh but not the second.
+    Difference,
+}
+
+/// One of the two meshes of a boolean operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operand {
+    /// The mesh the operation was called on.
+    First,
+    /// The mesh passed to the operation.
+    Second,
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::First => "first",
+            Self::Second => "second",
+        })
+    }
+}
+
+/// The input face a face of a boolean result was cut from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceFace {
+    pub operand: Operand,
+    pub face: FaceId,
+}
+
+/// The result of [`HalfEdgeMesh::boolean`].
+#[derive(Clone, Debug)]
+pub struct BooleanResult {
+    pub mesh: HalfEdgeMesh,
+    /// The input face each face of `mesh` was cut from, indexed by face.
+    pub source_faces: Vec<SourceFace>,
+}
+
+/// Why a boolean operation failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BooleanError {
+    /// An operand has removed elements that have not been garbage collected.
+    HasGarbage { operand: Operand },
+    /// An operand has faces that are not triangles.
+    NotTriangleMesh { operand: Operand },
+    /// An operand has boundary edges, so it does not enclose a volume.
+    NotClosed { operand: Operand },
+    /// The intersection curves did not form closed loops. This only happens when an operand intersects
+    /// itself.
+    Inconsistent,
+    /// The cut pieces did not form a valid mesh.
+    Build(BuildError),
+}
+
+impl fmt::Display for BooleanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HasGarbage { operand } => {
+                write!(f, "the {operand} mesh has not been garbage collected")
+            }
+            Self::NotTriangleMesh { operand } => {
+                write!(f, "the {operand} mesh is not a triangle mesh")
+            }
+            Self::NotClosed { operand } => write!(f, "the {operand} mesh is not closed"),
+            Self::Inconsistent => {
+                f.write_str("intersection curves do not close; an operand intersects itself")
+            }
+            Self::Build(err) => write!(f, "result is invalid: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for BooleanError {}
+
+impl From<BuildError> for BooleanError {
+    fn from(err: BuildError) -> Self {
+        Self::Build(err)
+    }
+}
+
+/// The first non-zero sign, which decides a predicate under the offset `(ε, ε², ε³)`.
+fn leading(signs: [f64; 3]) -> f64 {
+    signs.into_iter().find(|&s| s != 0.0).unwrap_or(0.0)
+}
+
+/// The perturbed side of the plane through `triangle` that `p` lies on. Exactly one of them belongs to the
+/// moved mesh; `point_moved` says whether that is `p`.
+fn side_of_plane([r, s, t]: [DVec3; 3], p: DVec3, point_moved: bool) -> f64 {
+    let orientation = orient3d(r, s, t, p);
+    if orientation != 0.0 {
+        return orientation.signum();
+    }
+    // Moving p by d changes the orientation by d · ((s - r) × (t - r)).
+    let sign = leading(cross_signs(r, s, r, t));
+    if point_moved {
+        sign
+    } else {
+        -sign
+    }
+}
+
+/// The perturbed sign of `orient3d(p, q, r, s)`, where `pq` and `rs` belong to different meshes and
+/// `edge_moved` says whether `pq` belongs to the moved one.
+fn edge_orientation(p: DVec3, q: DVec3, r: DVec3, s: DVec3, edge_moved: bool) -> f64 {
+    let orientation = orient3d(p, q, r, s);
+    if orientation != 0.0 {
+        return orientation.signum();
+    }
+    // Moving r and s by d changes the orientation by d · ((q - p) × (r - s)).
+    let sign = leading(cross_signs(p, q, s, r));
+    if edge_moved {
+        -sign
+    } else {
+        sign
+    }
+}
+
+/// Where the segment `pq` crosses `triangle` under the perturbation, as a parameter along it.
+fn crossing([p, q]: [DVec3; 2], triangle: [DVec3; 3], edge_moved: bool) -> Option<f64> {
+    if side_of_plane(triangle, p, edge_moved) == side_of_plane(triangle, q, edge_moved) {
+        return None;
+    }
+    let [r, s, t] = triangle;
+    let sides = [
+        edge_orientation(p, q, r, s, edge_moved),
+        edge_orientation(p, q, s, t, edge_moved),
+        edge_orientation(p, q, t, r, edge_moved),
+    ];
+    if sides[0] == 0.0 || sides[0] != sides[1] || sides[1] != sides[2] {
+        return None;
+    }
+    let (above, below) = (orient3d(r, s, t, p), orient3d(r, s, t, q));
+    let parameter = if above == below {
+        0.5
+    } else {
+        above / (above - below)
+    };
+    Some(parameter.clamp(0.0, 1.0))
+}
+
+/// One operand, with the offset of its vertices in the numbering shared by both.
+struct Input<'a> {
+    mesh: &'a HalfEdgeMesh,
+    operand: Operand,
+    offset: u32,
+}
+
+impl Input<'_> {
+    fn moved(&self) -> bool {
+        self.operand == Operand::Second
+    }
+
+    /// The shared vertex numbers of the corners of `f`.
+    fn corners(&self, f: FaceId) -> [u32; 3] {
+        let mut corners = self
+            .mesh
+            .face_vertices(f)
+            .map(|v| self.offset + v.index() as u32);
+        [(); 3].map(|_| corners.next().expect("faces are triangles"))
+    }
+
+    fn edges(&self, f: FaceId) -> impl Iterator<Item = EdgeId> + '_ {
+        self.mesh.face_halfedges(f).map(|h| self.mesh.edge(h))
+    }
+
+    fn contains(&self, vertex: u32) -> bool {
+        (self.offset..self.offset + self.mesh.vertex_count() as u32).contains(&vertex)
+    }
+}
+
+/// An edge of one operand and a face of the other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct CrossingKey {
+    operand: Operand,
+    edge: EdgeId,
+    face: FaceId,
+}
+
+/// The intersection of two meshes: crossing vertices and the curve segments between them.
+struct Intersection<'a> {
+    inputs: [Input<'a>; 2],
+    /// Every vertex in the shared numbering: both operands' vertices, then the crossings.
+    positions: Vec<DVec3>,
+    crossings: HashMap<CrossingKey, Option<u32>>,
+    /// The crossings on each edge, with their parameter from the edge's first vertex.
+    edge_points: HashMap<(Operand, EdgeId), Vec<(f64, u32)>>,
+    /// The curve segments through each face.
+    segments: HashMap<(Operand, FaceId), Vec<[u32; 2]>>,
+}
+
+impl<'a> Intersection<'a> {
+    fn new(first: &'a HalfEdgeMesh, second: &'a HalfEdgeMesh) -> Self {
+        let positions = first
+            .positions()
+            .iter()
+            .chain(second.positions())
+            .map(|p| p.as_dvec3())
+            .collect();
+        Self {
+            inputs: [
+                Input {
+                    mesh: first,
+                    operand: Operand::First,
+                    offset: 0,
+                },
+                Input {
+                    mesh: second,
+                    operand: Operand::Second,
+                    offset: first.vertex_count() as u32,
+                },
+            ],
+            positions,
+            crossings: HashMap::new(),
+            edge_points: HashMap::new(),
+            segments: HashMap::new(),
+        }
+    }
+
+    fn input(&self, operand: Operand) -> &Input<'a> {
+        &self.inputs[operand as usize]
+    }
+
+    fn triangle(&self, operand: Operand, f: FaceId) -> [DVec3; 3] {
+        self.input(operand)
+            .corners(f)
+            .map(|v| self.positions[v as usize])
+    }
+
+    /// The crossing vertex of `key`, if its edge crosses its face.
+    fn crossing(&mut self, key: CrossingKey) -> Option<u32> {
+        if let Some(&vertex) = self.crossings.get(&key) {
+            return vertex;
+        }
+        let input = self.input(key.operand);
+        let ends = input
+            .mesh
+            .edge_vertices(key.edge)
+            .map(|v| self.positions[(input.offset + v.index() as u32) as usize]);
+        let other = match key.operand {
+            Operand::First => Operand::Second,
+            Operand::Second => Operand::First,
+        };
+        let triangle = self.triangle(other, key.face);
+        let vertex = crossing(ends, triangle, input.moved()).map(|t| {
+            let vertex = self.positions.len() as u32;
+            self.positions.push(ends[0].lerp(ends[1], t));
+            self.edge_points
+                .entry((key.operand, key.edge))
+                .or_default()
+                .push((t, vertex));
+            vertex
+        });
+        self.crossings.insert(key, vertex);
+        vertex
+    }
+
+    /// Intersect face `a` of the first mesh with face `b` of the second.
+    fn intersect(&mut self, a: FaceId, b: FaceId) -> Result<(), BooleanError> {
+        let mut keys: Vec<CrossingKey> = self.inputs[0]
+            .edges(a)
+            .map(|edge| CrossingKey {
+                operand: Operand::First,
+                edge,
+                face: b,
+            })
+            .collect();
+        keys.extend(self.inputs[1].edges(b).map(|edge| CrossingKey {
+            operand: Operand::Second,
+            edge,
+            face: a,
+        }));
+        let ends: Vec<u32> = keys
+            .into_iter()
+            .filter_map(|key| self.crossing(key))
+            .collect();
+        match ends[..] {
+            [] => Ok(()),
+            [u, w] => {
+                for key in [(Operand::First, a), (Operand::Second, b)] {
+                    self.segments.entry(key).or_default().push([u, w]);
+                }
+                Ok(())
+            }
+            _ => Err(BooleanError::Inconsistent),
+        }
+    }
+
+    /// The triangles of `operand` after cutting along the curves, with the face each came from.
+    fn cut(&self, operand: Operand) -> Result<Vec<([u32; 3], FaceId)>, BooleanError> {
+        let input = self.input(operand);
+        let mesh = input.mesh;
+        let mut triangles = Vec::new();
+        for f in mesh.faces() {
+            let corners = input.corners(f);
+            let sides = [0, 1, 2].map(|i| {
+                let h = mesh.face_halfedges(f).nth(i).expect("faces are triangles");
+                let e = mesh.edge(h);
+                let mut points: Vec<u32> = self
+                    .edge_points
+                    .get(&(operand, e))
+                    .map(|points| points.iter().map(|&(_, v)| v).collect())
+                    .unwrap_or_default();
+                if mesh.edge_vertices(e)[0] != mesh.origin(h) {
+                    points.reverse();
+                }
+                points
+            });
+            let segments = self.segments.get(&(operand, f));
+            if segments.is_none() && sides.iter().all(Vec::is_empty) {
+                triangles.push((corners, f));
+                continue;
+            }
+            let segments = segments.map_or(&[][..], Vec::as_slice);
+            let pieces = cut_triangle(&self.positions, corners, &sides, segments)?;
+            triangles.extend(pieces.into_iter().map(|triangle| (triangle, f)));
+        }
+        Ok(triangles)
+    }
+
+    /// Whether vertex `v` of one operand lies inside the `other` operand under the perturbation, by the
+    /// parity of the crossings of a ray along +x.
+    fn is_inside(&self, v: u32, other: Operand) -> bool {
+        let p = self.positions[v as usize];
+        let input = self.input(other);
+        let bounds = Aabb::from_points(input.mesh.positions().iter().copied());
+        let far = DVec3::new(
+            p.x.max(f64::from(bounds.max.x))
+                + f64::from((bounds.max - bounds.min).max_element())
+                + 1.0,
+            p.y,
+            p.z,
+        );
+        let crossings = input
+            .mesh
+            .faces()
+            .filter(|&f| {
+                let triangle = self.triangle(other, f);
+                // The perturbation is infinitesimal, so only boxes strictly clear of the ray can be skipped.
+                let box_ = triangle
+                    .iter()
+                    .fold((DVec3::INFINITY, DVec3::NEG_INFINITY), |(lo, hi), &q| {
+                        (lo.min(q), hi.max(q))
+                    });
+                if box_.1.x < p.x
+                    || box_.0.y > p.y
+                    || box_.1.y < p.y
+                    || box_.0.z > p.z
+                    || box_.1.z < p.z
+                {
+                    return false;
+                }
+                crossing([p, far], triangle, !input.moved()).is_some()
+            })
+            .count();
+        crossings % 2 == 1
+    }
+}
+
+fn undirected(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+/// A polygon being cut out of a triangle: a counter-clockwise outer loop and clockwise holes.
+#[derive(Clone, Debug, Default)]
+struct Region {
+    outer: Vec<u32>,
+    holes: Vec<Vec<u32>>,
+}
+
+fn signed_area(polygon: &[u32], coords: &HashMap<u32, DVec2>) -> f64 {
+    (0..polygon.len())
+        .map(|i| coords[&polygon[i]].perp_dot(coords[&polygon[(i + 1) % polygon.len()]]))
+        .sum()
+}
+
+fn contains_point(polygon: &[u32], coords: &HashMap<u32, DVec2>, p: DVec2) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (
+            coords[&polygon[i]],
+            coords[&polygon[(i + 1) % polygon.len()]],
+        );
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Whether the segments `ab` and `cd` cross at a point interior to both.
+fn segments_cross(a: DVec2, b: DVec2, c: DVec2, d: DVec2) -> bool {
+    orient2d(a, b, c) * orient2d(a, b, d) < 0.0 && orient2d(c, d, a) * orient2d(c, d, b) < 0.0
+}
+
+/// Whether `p` lies on the segment `ab`, away from its ends, up to rounding. Clipping an ear across such a
+/// point would give a degenerate triangle, and an edge the other side of the point's curve may also use.
+fn on_segment(a: DVec2, b: DVec2, p: DVec2) -> bool {
+    let d = b - a;
+    let length_squared = d.length_squared();
+    let t = (p - a).dot(d) / length_squared;
+    length_squared > 0.0
+        && t > 1e-9
+        && t < 1.0 - 1e-9
+        && orient2d(a, b, p).abs() <= 1e-9 * length_squared
+}
+
+/// Splice `hole` into `outer` through a bridge from the hole's rightmost vertex to the nearest outer vertex
+/// that can see it, falling back to the nearest outer vertex outright.
+fn bridge(outer: &mut Vec<u32>, hole: &[u32], others: &[Vec<u32>], coords: &HashMap<u32, DVec2>) {
+    let m = (0..hole.len())
+        .max_by(|&i, &j| coords[&hole[i]].x.total_cmp(&coords[&hole[j]].x))
+        .expect("holes are not empty");
+    let from = coords[&hole[m]];
+    let mut candidates: Vec<usize> = (0..outer.len()).collect();
+    candidates.sort_by(|&i, &j| {
+        let (a, b) = (coords[&outer[i]], coords[&outer[j]]);
+        from.distance_squared(a)
+            .total_cmp(&from.distance_squared(b))
+    });
+    let loops: Vec<&[u32]> = [outer.as_slice(), hole]
+        .into_iter()
+        .chain(others.iter().map(Vec::as_slice))
+        .collect();
+    let visible = |k: usize| {
+        let to = coords[&outer[k]];
+        !loops.iter().any(|polygon| {
+            (0..polygon.len()).any(|i| {
+                let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                ![a, b].contains(&outer[k])
+                    && ![a, b].contains(&hole[m])
+                    && segments_cross(from, to, coords[&a], coords[&b])
+            })
+        })
+    };
+    let k = candidates
+        .iter()
+        .copied()
+        .find(|&k| visible(k))
+        .unwrap_or(candidates[0]);
+    let mut spliced: Vec<u32> = outer[..=k].to_vec();
+    spliced.extend(hole[m..].iter().chain(&hole[..=m]));
+    spliced.push(outer[k]);
+    spliced.extend(&outer[k + 1..]);
+    *outer = spliced;
+}
+
+/// Triangulate a counter-clockwise polygon, which may touch itself and have degenerate parts, by clipping
+/// the best-shaped ear each time. Where no proper ear is left, the largest candidate is clipped anyway, so
+/// the result is always a valid triangulation of the polygon's loop, if not always of its shape.
+///
+/// `edges` holds the edges already in the cut triangle; no ear is clipped across one of them where that can
+/// be avoided, since in degenerate cuts the polygons on both sides of a curve may otherwise pick the same
+/// diagonal.
+fn ear_clip(
+    mut polygon: Vec<u32>,
+    coords: &HashMap<u32, DVec2>,
+    edges: &mut HashSet<(u32, u32)>,
+) -> Vec<[u32; 3]> {
+    for i in 0..polygon.len() {
+        edges.insert(undirected(polygon[i], polygon[(i + 1) % polygon.len()]));
+    }
+    let mut triangles = Vec::new();
+    while polygon.len() > 3 {
+        let n = polygon.len();
+        let ear = |i: usize| {
+            let (a, b, c) = (polygon[(i + n - 1) % n], polygon[i], polygon[(i + 1) % n]);
+            (a, b, c, coords[&a], coords[&b], coords[&c])
+        };
+        let mut best: Option<(usize, f64)> = None;
+        for i in 0..n {
+            let (a, b, c, pa, pb, pc) = ear(i);
+            let area = orient2d(pa, pb, pc);
+            if area <= 0.0 || a == c || edges.contains(&undirected(a, c)) {
+                continue;
+            }
+            let blocked = polygon.iter().any(|&v| {
+                let p = coords[&v];
+                ![a, b, c].contains(&v)
+                    && orient2d(pa, pb, p) > 0.0
+                    && orient2d(pb, pc, p) > 0.0
+                    && orient2d(pc, pa, p) > 0.0
+                    || on_segment(pc, pa, p)
+            });
+            if blocked {
+                continue;
+            }
+            let quality = area
+                / (pa.distance_squared(pb) + pb.distance_squared(pc) + pc.distance_squared(pa));
+            if best.is_none_or(|(_, q)| quality > q) {
+                best = Some((i, quality));
+            }
+        }
+        let i = best.map_or_else(
+            || {
+                (0..n)
+                    .max_by(|&i, &j| {
+                        let (a, _, c, pa, pb, pc) = ear(i);
+                        let (d, _, f, pd, pe, pf) = ear(j);
+                        let free = |a, c| a != c && !edges.contains(&undirected(a, c));
+                        ((a != c), free(a, c), orient2d(pa, pb, pc))
+                            .partial_cmp(&((d != f), free(d, f), orient2d(pd, pe, pf)))
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .expect("polygon has vertices")
+            },
+            |(i, _)| i,
+        );
+        let (a, b, c, ..) = ear(i);
+        edges.insert(undirected(a, c));
+        triangles.push([a, b, c]);
+        polygon.remove(i);
+    }
+    triangles.push([polygon[0], polygon[1], polygon[2]]);
+    triangles
+}
+
+/// Cut the triangle with vertices `corners` along `segments`. `sides[i]` lists the crossings on the side
+/// from `corners[i]` to the next corner, in order; every other segment end lies inside the triangle.
+fn cut_triangle(
+    positions: &[DVec3],
+    corners: [u32; 3],
+    sides: &[Vec<u32>; 3],
+    segments: &[[u32; 2]],
+) -> Result<Vec<[u32; 3]>, BooleanError> {
+    // Work in the coordinate plane the triangle is most nearly parallel to, keeping it counter-clockwise.
+    let [p0, p1, p2] = corners.map(|v| positions[v as usize]);
+    let normal = (p1 - p0).cross(p2 - p0);
+    let axis = normal.abs().max_position();
+    let (mut i, mut j) = ((axis + 1) % 3, (axis + 2) % 3);
+    if normal[axis] < 0.0 {
+        std::mem::swap(&mut i, &mut j);
+    }
+    let mut boundary = Vec::new();
+    for (corner, side) in corners.iter().zip(sides) {
+        boundary.push(*corner);
+        boundary.extend(side);
+    }
+    let mut neighbours: HashMap<u32, Vec<u32>> = HashMap::new();
+    for &[u, w] in segments {
+        neighbours.entry(u).or_default().push(w);
+        neighbours.entry(w).or_default().push(u);
+    }
+    let coords: HashMap<u32, DVec2> = boundary
+        .iter()
+        .chain(neighbours.keys())
+        .map(|&v| {
+            let p = positions[v as usize];
+            (v, DVec2::new(p[i], p[j]))
+        })
+        .collect();
+
+    // Crossings on the sides end one segment; those inside continue through two.
+    let on_side: HashSet<u32> = sides.iter().flatten().copied().collect();
+    for (v, around) in &neighbours {
+        if around.len() != if on_side.contains(v) { 1 } else { 2 } {
+            return Err(BooleanError::Inconsistent);
+        }
+    }
+    let mut visited: HashSet<u32> = HashSet::new();
+    let walk = |start: u32, visited: &mut HashSet<u32>| {
+        let mut path = vec![start];
+        visited.insert(start);
+        let (mut previous, mut current) = (u32::MAX, start);
+        loop {
+            let next = neighbours[&current]
+                .iter()
+                .copied()
+                .find(|&v| v != previous)
+                .unwrap_or(previous);
+            if next == start || visited.contains(&next) && !on_side.contains(&next) {
+                return path;
+            }
+            path.push(next);
+            visited.insert(next);
+            if on_side.contains(&next) {
+                return path;
+            }
+            (previous, current) = (current, next);
+        }
+    };
+    let mut chains = Vec::new();
+    for &v in &boundary {
+        if on_side.contains(&v) && !visited.contains(&v) {
+            chains.push(walk(v, &mut visited));
+        }
+    }
+    let mut interior: Vec<u32> = neighbours
+        .keys()
+        .copied()
+        .filter(|v| !on_side.contains(v))
+        .collect();
+    interior.sort_unstable();
+    let mut loops = Vec::new();
+    for v in interior {
+        if !visited.contains(&v) {
+            loops.push(walk(v, &mut visited));
+        }
+    }
+
+    // Split the triangle along each chain between two sides, then punch out each closed loop.
+    let mut regions = vec![Region {
+        outer: boundary,
+        holes: Vec::new(),
+    }];
+    for chain in chains {
+        let (u, w) = (chain[0], chain[chain.len() - 1]);
+        let found = regions.iter().enumerate().find_map(|(r, region)| {
+            let i = region.outer.iter().position(|&v| v == u)?;
+            let j = region.outer.iter().position(|&v| v == w)?;
+            Some((r, i, j))
+        });
+        let Some((r, i, j)) = found else {
+            return Err(BooleanError::Inconsistent);
+        };
+        let region = regions.swap_remove(r);
+        let n = region.outer.len();
+        let arc = |from: usize, to: usize| -> Vec<u32> {
+            let len = (to + n - from) % n + 1;
+            (0..len).map(|k| region.outer[(from + k) % n]).collect()
+        };
+        let inner = &chain[1..chain.len() - 1];
+        let mut first = arc(i, j);
+        first.extend(inner.iter().rev());
+        let mut second = arc(j, i);
+        second.extend(inner);
+        let (mut first, mut second) = (
+            Region {
+                outer: first,
+                holes: Vec::new(),
+            },
+            Region {
+                outer: second,
+                holes: Vec::new(),
+            },
+        );
+        for hole in region.holes {
+            if contains_point(&first.outer, &coords, coords[&hole[0]]) {
+                first.holes.push(hole);
+            } else {
+                second.holes.push(hole);
+            }
+        }
+        regions.push(first);
+        regions.push(second);
+    }
+    for mut curve in loops {
+        let p = coords[&curve[0]];
+        let inside = |region: &Region| {
+            contains_point(&region.outer, &coords, p)
+                && !region
+                    .holes
+                    .iter()
+                    .any(|hole| contains_point(hole, &coords, p))
+        };
+        let r = regions.iter().position(inside).unwrap_or(0);
+        if signed_area(&curve, &coords) < 0.0 {
+            curve.reverse();
+        }
+        let (enclosed, kept) = std::mem::take(&mut regions[r].holes)
+            .into_iter()
+            .partition(|hole| contains_point(&curve, &coords, coords[&hole[0]]));
+        regions[r].holes = kept;
+        regions[r].holes.push(curve.iter().rev().copied().collect());
+        regions.push(Region {
+            outer: curve,
+            holes: enclosed,
+        });
+    }
+
+    let mut edges = HashSet::new();
+    let mut triangles = Vec::new();
+    for Region {
+        mut outer,
+        mut holes,
+    } in regions
+    {
+        while let Some(hole) = holes.pop() {
+            bridge(&mut outer, &hole, &holes, &coords);
+        }
+        triangles.extend(ear_clip(outer, &coords, &mut edges));
+    }
+    Ok(triangles)
+}
+
+/// Sort the pieces of one operand into inside and outside the other: regions between curves are seeded by
+/// an original vertex per connected component, and flip across each curve.
+fn classify(
+    intersection: &Intersection,
+    operand: Operand,
+    triangles: &[([u32; 3], FaceId)],
+    curves: &HashSet<(u32, u32)>,
+) -> Vec<bool> {
+    let mut faces_of_edge: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (t, (corners, _)) in triangles.iter().enumerate() {
+        for k in 0..3 {
+            faces_of_edge
+                .entry(undirected(corners[k], corners[(k + 1) % 3]))
+                .or_default()
+                .push(t);
+        }
+    }
+    let mut regions = UnionFind::new(triangles.len());
+    let mut components = UnionFind::new(triangles.len());
+    let mut across = Vec::new();
+    for (edge, faces) in &faces_of_edge {
+        for pair in faces.windows(2) {
+            components.union(pair[0], pair[1]);
+            if curves.contains(edge) {
+                across.push((pair[0], pair[1]));
+            } else {
+                regions.union(pair[0], pair[1]);
+            }
+        }
+    }
+    let mut neighbours: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (a, b) in across {
+        let (a, b) = (regions.find(a), regions.find(b));
+        neighbours.entry(a).or_default().push(b);
+        neighbours.entry(b).or_default().push(a);
+    }
+
+    let input = intersection.input(operand);
+    let other = match operand {
+        Operand::First => Operand::Second,
+        Operand::Second => Operand::First,
+    };
+    let mut inside: HashMap<usize, bool> = HashMap::new();
+    let mut seeded = HashSet::new();
+    for (t, (corners, _)) in triangles.iter().enumerate() {
+        let Some(&v) = corners.iter().find(|&&v| input.contains(v)) else {
+            continue;
+        };
+        if !seeded.insert(components.find(t)) {
+            continue;
+        }
+        let region = regions.find(t);
+        inside.insert(region, intersection.is_inside(v, other));
+        let mut queue = VecDeque::from([region]);
+        while let Some(region) = queue.pop_front() {
+            let value = inside[&region];
+            for &next in neighbours.get(&region).into_iter().flatten() {
+                if let Entry::Vacant(entry) = inside.entry(next) {
+                    entry.insert(!value);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    (0..triangles.len())
+        .map(|t| inside.get(&regions.find(t)).copied().unwrap_or(false))
+        .collect()
+}
+
+/// Whether the corners of triangle `f` lie on one line, exactly.
+fn is_degenerate(mesh: &HalfEdgeMesh, f: FaceId) -> bool {
+    let mut corners = mesh.face_positions(f).map(|p| p.as_dvec3());
+    let [a, b, c] = [(); 3].map(|_| corners.next().expect("faces are triangles"));
+    cross_signs(a, b, a, c) == [0.0; 3]
+}
+
+/// The corner positions of triangle `f`, bit for bit, starting from the least so that copies compare equal.
+fn corner_key(mesh: &HalfEdgeMesh, f: FaceId) -> [[u32; 3]; 3] {
+    let mut corners: Vec<[u32; 3]> = mesh
+        .face_positions(f)
+        .map(|p| p.to_array().map(f32::to_bits))
+        .collect();
+    let first = (0..3)
+        .min_by_key(|&i| corners[i])
+        .expect("faces are triangles");
+    corners.rotate_left(first);
+    [corners[0], corners[1], corners[2]]
+}
+
+/// How many times one edge may be flipped while clearing slivers. A flip passes a sliver's middle corner on to
+/// the triangle beyond, so a line of slivers flips each edge along it about once; the bound stops a cycle of
+/// flips from running forever.
+const MAX_FLIPS_PER_EDGE: u8 = 4;
+
+/// The work [`remove_slivers`] did.
+#[derive(Clone, Copy, Debug, Default)]
+struct SliverCleanup {
+    /// Faces taken off the worklist.
+    passes: usize,
+    collapses: usize,
+    flips: usize,
+}
+
+/// Clear away the zero-area triangles that the perturbation leaves where the operands touch: edges whose ends
+/// coincide are collapsed, and a triangle whose corners lie on one line has its longest edge flipped, which
+/// hands its middle corner to the triangle beyond. Either may be refused where it would make the surface
+/// non-manifold, and such slivers stay. Only the `seeds`, the faces along the intersection curves, and the
+/// faces each change touches are examined.
+fn remove_slivers(
+    mesh: &mut HalfEdgeMesh,
+    seeds: impl IntoIterator<Item = FaceId>,
+) -> SliverCleanup {
+    fn push(queue: &mut VecDeque<FaceId>, queued: &mut [bool], f: FaceId) {
+        if !std::mem::replace(&mut queued[f.index()], true) {
+            queue.push_back(f);
+        }
+    }
+
+    let mut cleanup = SliverCleanup::default();
+    let mut queued = vec![false; mesh.face_count()];
+    let mut queue = VecDeque::new();
+    for f in seeds {
+        push(&mut queue, &mut queued, f);
+    }
+    let mut flips = vec![0; mesh.edge_count()];
+
+    while let Some(f) = queue.pop_front() {
+        queued[f.index()] = false;
+        if mesh.is_removed_face(f) {
+            continue;
+        }
+        cleanup.passes += 1;
+
+        let coincident = mesh.face_halfedges(f).find(|&h| {
+            let [a, b] = mesh.edge_vertices(mesh.edge(h));
+            mesh.position(a) == mesh.position(b)
+        });
+        let collapsed = coincident.and_then(|h| {
+            let t = mesh.twin(h);
+            mesh.collapse_edge(h)
+                .or_else(|_| mesh.collapse_edge(t))
+                .ok()
+        });
+        if let Some(survivor) = collapsed {
+            cleanup.collapses += 1;
+            for g in mesh.vertex_faces(survivor).collect::<Vec<_>>() {
+                push(&mut queue, &mut queued, g);
+            }
+            continue;
+        }
+
+        if !is_degenerate(mesh, f) {
+            continue;
+        }
+        let longest = mesh
+            .face_halfedges(f)
+            .max_by(|&g, &h| {
+                let length = |h| mesh.halfedge_vector(h).as_dvec3().length_squared();
+                length(g).total_cmp(&length(h))
+            })
+            .expect("faces have edges");
+        let e = mesh.edge(longest);
+        if flips[e.index()] < MAX_FLIPS_PER_EDGE && mesh.flip_edge(e).is_ok() {
+            flips[e.index()] += 1;
+            cleanup.flips += 1;
+            let h = mesh.edge_halfedge(e);
+            for g in [mesh.face(h), mesh.face(mesh.twin(h))]
+                .into_iter()
+                .flatten()
+            {
+                push(&mut queue, &mut queued, g);
+            }
+        }
+    }
+    cleanup
+}
+
+/// The cut pieces a boolean operation keeps, joined into one mesh.
+struct Pieces {
+    mesh: HalfEdgeMesh,
+    /// The input face each face was cut from.
+    source: Attribute<FaceId, Option<SourceFace>>,
+    /// The faces along the intersection curves.
+    seeds: Vec<FaceId>,
+}
+
+impl HalfEdgeMesh {
+    /// Combine this mesh with `other`. Both must be closed triangle meshes without self-intersections. The
+    /// result carries no attributes; [`BooleanResult::source_faces`] says where each face came from. Where
+    /// the meshes only touch, the result is cleaned up as the [module documentation](self) describes.
+    pub fn boolean(
+        &self,
+        other: &HalfEdgeMesh,
+        operation: BooleanOperation,
+    ) -> Result<BooleanResult, BooleanError> {
+        let Pieces {
+            mut mesh,
+            source,
+            seeds,
+        } = self.pieces(other, operation)?;
+        remove_slivers(&mut mesh, seeds);
+        mesh.garbage_collect();
+
+        // Pieces that enclose no volume, as all of `a - a` does, come down to zero-area faces and faces
+        // matched by a reversed copy of themselves. Drop those made of nothing else.
+        let keys: HashSet<[[u32; 3]; 3]> = mesh.faces().map(|f| corner_key(&mesh, f)).collect();
+        let mut pieces = UnionFind::new(mesh.face_count());
+        for e in mesh.edges() {
+            let h = mesh.edge_halfedge(e);
+            if let (Some(f), Some(g)) = (mesh.face(h), mesh.face(mesh.twin(h))) {
+                pieces.union(f.index(), g.index());
+            }
+        }
+        let mut solid = vec![false; mesh.face_count()];
+        for f in mesh.faces() {
+            let [a, b, c] = corner_key(&mesh, f);
+            if !is_degenerate(&mesh, f) && !keys.contains(&[a, c, b]) {
+                solid[pieces.find(f.index())] = true;
+            }
+        }
+        let mut vertex_map = vec![u32::MAX; mesh.vertex_count()];
+        let mut positions = Vec::new();
+        let mut polygons = Vec::new();
+        let mut source_faces = Vec::new();
+        for f in mesh.faces() {
+            if !solid[pieces.find(f.index())] {
+                continue;
+            }
+            let polygon: Vec<u32> = mesh
+                .face_vertices(f)
+                .map(|v| {
+                    if vertex_map[v.index()] == u32::MAX {
+                        vertex_map[v.index()] = positions.len() as u32;
+                        positions.push(mesh.position(v));
+                    }
+                    vertex_map[v.index()]
+                })
+                .collect();
+            polygons.push(polygon);
+            source_faces.push(mesh.attribute(source, f).expect("every face has a source"));
+        }
+        Ok(BooleanResult {
+            mesh: HalfEdgeMesh::from_polygons(positions, &polygons)?,
+            source_faces,
+        })
+    }
+
+    /// Cut both meshes along their intersection curves and join the pieces `operation` keeps, before any
+    /// cleanup.
+    fn pieces(
+        &self,
+        other: &HalfEdgeMesh,
+        operation: BooleanOperation,
+    ) -> Result<Pieces, BooleanError> {
+        for (mesh, operand) in [(self, Operand::First), (other, Operand::Second)] {
+            if mesh.has_garbage() {
+                return Err(BooleanError::HasGarbage { operand });
+            }
+            if mesh.faces().any(|f| mesh.face_degree(f) != 3) {
+                return Err(BooleanError::NotTriangleMesh { operand });
+            }
+            if mesh.edges().any(|e| mesh.is_boundary_edge(e)) {
+                return Err(BooleanError::NotClosed { operand });
+            }
+        }
+
+        let mut intersection = Intersection::new(self, other);
+        let bvh = Bvh::new(other);
+        // Pad the query boxes so that rounding in the box test cannot drop a pair that touches.
+        let margin = 1e-5
+            * (bvh
+                .bounds()
+                .union(Bvh::new(self).bounds())
+                .half_extents()
+                .length()
+                + 1.0);
+        for a in self.faces() {
+            let corners: Vec<Vec3> = self.face_vertices(a).map(|v| self.position(v)).collect();
+            let bounds = Aabb::from_points(corners);
+            let bounds = Aabb::new(bounds.min - margin, bounds.max + margin);
+            for b in bvh.faces_in_box(other, &bounds) {
+                intersection.intersect(a, b)?;
+            }
+        }
+        for points in intersection.edge_points.values_mut() {
+            points.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        }
+        let curves: HashSet<(u32, u32)> = intersection
+            .segments
+            .values()
+            .flatten()
+            .map(|&[u, w]| undirected(u, w))
+            .collect();
+
+        let mut vertex_map = vec![u32::MAX; intersection.positions.len()];
+        let mut positions = Vec::new();
+        let mut polygons = Vec::new();
+        let mut source_faces = Vec::new();
+        for operand in [Operand::First, Operand::Second] {
+            let triangles = intersection.cut(operand)?;
+            let inside = classify(&intersection, operand, &triangles, &curves);
+            let (keep_inside, reverse) = match (operation, operand) {
+                (BooleanOperation::Union, _) => (false, false),
+                (BooleanOperation::Intersection, _) => (true, false),
+                (BooleanOperation::Difference, Operand::First) => (false, false),
+                (BooleanOperation::Difference, Operand::Second) => (true, true),
+            };
+            for ((mut corners, face), inside) in triangles.into_iter().zip(inside) {
+                if inside != keep_inside {
+                    continue;
+                }
+                if reverse {
+                    corners.reverse();
+                }
+                polygons.push(corners.map(|v| {
+                    if vertex_map[v as usize] == u32::MAX {
+                        vertex_map[v as usize] = positions.len() as u32;
+                        positions.push(intersection.positions[v as usize].as_vec3());
+                    }
+                    vertex_map[v as usize]
+                }));
+                source_faces.push(SourceFace { operand, face });
+            }
+        }
+
+        let mut mesh = HalfEdgeMesh::from_polygons(positions, &polygons)?;
+        let source = mesh
+            .add_attribute("source_face", None, blend_nearest)
+            .expect("a new mesh has no attributes");
+        for (f, &face) in mesh.faces().zip(&source_faces).collect::<Vec<_>>() {
+            mesh.set_attribute(source, f, Some(face));
+        }
+        // The faces along the intersection curves, where slivers can be, in a fixed order so that the cleanup
+        // does not depend on hashing.
+        let mut seeds: Vec<FaceId> = curves
+            .iter()
+            .flat_map(|&(u, w)| [u, w])
+            .map(|v| vertex_map[v as usize])
+            .filter(|&v| v != u32::MAX)
+            .flat_map(|v| mesh.vertex_faces(VertexId::new(v as usize)))
+            .collect();
+        seeds.sort_unstable();
+        seeds.dedup();
+        Ok(Pieces {
+            mesh,
+            source,
+            seeds,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cube(min: Vec3, max: Vec3) -> HalfEdgeMesh {
+        let positions: Vec<Vec3> = (0..8)
+            .map(|i| {
+                Vec3::select(
+                    glam::BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0),
+                    max,
+                    min,
+                )
+            })
+            .collect();
+        let quads = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ];
+        let triangles: Vec<u32> = quads
+            .iter()
+            .flat_map(|q| [q[0], q[1], q[2], q[0], q[2], q[3]])
+            .collect();
+        HalfEdgeMesh::from_triangles(positions, &triangles).unwrap()
+    }
+
+    fn check(result: &BooleanResult, volume: f64) {
+        let mesh = &result.mesh;
+        assert!(mesh.validate().is_ok());
+        assert!(mesh.edges().all(|e| !mesh.is_boundary_edge(e)));
+        assert!(mesh.faces().all(|f| !is_degenerate(mesh, f)));
+        assert_eq!(result.source_faces.len(), mesh.face_count());
+        let actual = mesh.statistics(1).volume.unwrap_or(0.0);
+        assert!(
+            (actual - volume).abs() < 1e-6,
+            "volume {actual}, expected {volume}"
+        );
+    }
+
+    #[test]
+    fn offset_cubes() {
+        let a = cube(Vec3::ZERO, Vec3::ONE);
+        let b = cube(Vec3::splat(0.5), Vec3::splat(1.5));
+        check(&a.boolean(&b, BooleanOperation::Union).unwrap(), 1.875);
+        check(
+            &a.boolean(&b, BooleanOperation::Intersection).unwrap(),
+            0.125,
+        );
+        check(&a.boolean(&b, BooleanOperation::Difference).unwrap(), 0.875);
+    }
+
+    #[test]
+    fn cubes_sharing_faces_leave_no_slivers() {
+        let a = cube(Vec3::ZERO, Vec3::ONE);
+        let b = cube(Vec3::new(0.5, 0.5, 0.0), Vec3::new(1.5, 1.5, 1.0));
+        let intersection = a.boolean(&b, BooleanOperation::Intersection).unwrap();
+        check(&intersection, 0.25);
+        let mut positions: Vec<[u32; 3]> = intersection
+            .mesh
+            .positions()
+            .iter()
+            .map(|p| p.to_array().map(f32::to_bits))
+            .collect();
+        let count = positions.len();
+        positions.sort_unstable();
+        positions.dedup();
+        assert_eq!(positions.len(), count);
+        check(&a.boolean(&b, BooleanOperation::Union).unwrap(), 1.75);
+        check(&a.boolean(&b, BooleanOperation::Difference).unwrap(), 0.75);
+    }
+
+    #[test]
+    fn slivers_are_cleared_from_the_curves_outward() {
+        // A cube sharing its top plane with the first of two disjoint cubes; the second cube is far from the
+        // intersection curves.
+        let near = cube(Vec3::ZERO, Vec3::ONE);
+        let far = cube(Vec3::splat(10.0), Vec3::splat(11.0));
+        let positions = [near.positions(), far.positions()].concat();
+        let triangles: Vec<u32> = [&near, &far]
+            .into_iter()
+            .scan(0, |offset, mesh| {
+                let start = *offset;
+                *offset += mesh.vertex_count() as u32;
+                Some(mesh.faces().flat_map(move |f| {
+                    mesh.face_vertices(f).map(move |v| start + v.index() as u32)
+                }))
+            })
+            .flatten()
+            .collect();
+        let a = HalfEdgeMesh::from_triangles(positions, &triangles).unwrap();
+        let b = cube(Vec3::new(0.5, 0.5, 0.5), Vec3::new(1.5, 1.5, 1.0));
+
+        let Pieces {
+            mut mesh, seeds, ..
+        } = a.pieces(&b, BooleanOperation::Union).unwrap();
+        let slivers = mesh.faces().filter(|&f| is_degenerate(&mesh, f)).count();
+        assert!(slivers > 0);
+        let faces = mesh.face_count();
+
+        let cleanup = remove_slivers(&mut mesh, seeds);
+        assert!(mesh.faces().all(|f| !is_degenerate(&mesh, f)));
+        // Fewer faces are examined than one sweep over the mesh would take, as the far cube never is.
+        assert!(cleanup.passes < faces, "{cleanup:?}");
+        assert!(cleanup.flips > 0 && cleanup.flips <= slivers, "{cleanup:?}");
+        assert!(
+            cleanup.collapses > 0 && cleanup.collapses <= slivers,
+            "{cleanup:?}"
+        );
+        // Nothing is left for a pass over every face to do.
+        let all: Vec<FaceId> = mesh.faces().collect();
+        let again = remove_slivers(&mut mesh, all);
+        assert_eq!((again.collapses, again.flips), (0, 0));
+    }
+
+    #[test]
+    fn a_mesh_with_itself() {
+        let a = cube(Vec3::ZERO, Vec3::ONE);
+        let union = a.boolean(&a, BooleanOperation::Union).unwrap();
+        check(&union, 1.0);
+        assert_eq!(union.mesh.face_count(), 12);
+        let difference = a.boolean(&a, BooleanOperation::Difference).unwrap();
+        assert_eq!(difference.mesh.face_count(), 0);
+        assert!(difference.source_faces.is_empty());
+    }
+
+    #[test]
+    fn open_meshes_are_refused() {
+        let a = cube(Vec3::ZERO, Vec3::ONE);
+        let open =
+            HalfEdgeMesh::from_triangles(vec![Vec3::ZERO, Vec3::X, Vec3::Y], &[0, 1, 2]).unwrap();
+        assert_eq!(
+            a.boolean(&open, BooleanOperation::Union).unwrap_err(),
+            BooleanError::NotClosed {
+                operand: Operand::Second
+            }
+        );
+    }
+}
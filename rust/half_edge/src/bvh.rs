@@ -0,0 +1,857 @@
+//! A bounding volume hierarchy over the faces of a mesh, for ray casts, closest points and overlap queries.
+//!
+//! Faces are fanned into triangles, and the triangles sorted into a binary tree of axis-aligned boxes by
+//! splitting at the median centroid along the longest axis, until at most [`LEAF_SIZE`] remain. The tree
+//! refers to vertices by handle rather than copying their positions, so after vertices move only the boxes
+//! need refitting; after the connectivity changes it must be rebuilt.
+
+use glam::Vec3;
+
+use crate::mesh::{FaceId, HalfEdgeMesh, VertexId};
+
+/// The most triangles kept in one leaf.
+pub const LEAF_SIZE: usize = 4;
+
+/// Marks the root's missing parent.
+const NO_PARENT: u32 = u32::MAX;
+
+/// An axis-aligned box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// The box containing nothing, which any point grows.
+    pub const EMPTY: Self = Self {
+        min: Vec3::INFINITY,
+        max: Vec3::NEG_INFINITY,
+    };
+
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest box containing `points`.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        points.into_iter().fold(Self::EMPTY, |bounds, p| Self {
+            min: bounds.min.min(p),
+            max: bounds.max.max(p),
+        })
+    }
+
+    /// The smallest box containing both boxes.
+    #[must_use]
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    pub fn center(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn half_extents(&self) -> Vec3 {
+        0.5 * (self.max - self.min)
+    }
+
+    /// Whether the boxes share any point, including touching faces.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    /// The squared distance from `p` to the nearest point of the box; zero inside it.
+    pub fn distance_squared(&self, p: Vec3) -> f32 {
+        (self.min - p)
+            .max(p - self.max)
+            .max(Vec3::ZERO)
+            .length_squared()
+    }
+
+    /// Where `ray` enters the box, if it does so before `t_max`. Zero if the ray starts inside.
+    fn ray_entry(&self, ray: &Ray, inverse_direction: Vec3, t_max: f32) -> Option<f32> {
+        let (mut near, mut far) = (0.0f32, t_max);
+        for axis in 0..3 {
+            // Parallel to the slab: the products below would be NaN for an origin on its planes.
+            if ray.direction[axis] == 0.0 {
+                if ray.origin[axis] < self.min[axis] || ray.origin[axis] > self.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (self.min[axis] - ray.origin[axis]) * inverse_direction[axis];
+            let t2 = (self.max[axis] - ray.origin[axis]) * inverse_direction[axis];
+            near = near.max(t1.min(t2));
+            far = far.min(t1.max(t2));
+        }
+        (near <= far).then_some(near)
+    }
+}
+
+/// A half-line from `origin` along `direction`. The direction need not have unit length; distances along the
+/// ray are measured in multiples of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self { origin, direction }
+    }
+
+    /// The point `t` along the ray.
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + t * self.direction
+    }
+}
+
+/// A point on the surface found by a query.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SurfaceHit {
+    pub face: FaceId,
+    /// The corners of the triangle of `face` the point lies in. Polygons are fanned from the origin of
+    /// [`HalfEdgeMesh::face_halfedge`], so for a triangle these are its vertices in order from there.
+    pub vertices: [VertexId; 3],
+    /// The weights of `vertices` that give `point`.
+    pub barycentric: Vec3,
+    pub point: Vec3,
+    /// For ray casts, how far along the ray the point is; for closest points, its distance from the query
+    /// point.
+    pub distance: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Triangle {
+    face: FaceId,
+    vertices: [VertexId; 3],
+}
+
+impl Triangle {
+    fn corners(&self, mesh: &HalfEdgeMesh) -> [Vec3; 3] {
+        self.vertices.map(|v| mesh.position(v))
+    }
+
+    fn hit(&self, barycentric: Vec3, point: Vec3, distance: f32) -> SurfaceHit {
+        SurfaceHit {
+            face: self.face,
+            vertices: self.vertices,
+            barycentric,
+            point,
+            distance,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Content {
+    /// Triangles `start..end`.
+    Leaf {
+        start: u32,
+        end: u32,
+    },
+    Inner {
+        children: [u32; 2],
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    bounds: Aabb,
+    parent: u32,
+    content: Content,
+}
+
+/// A bounding volume hierarchy over the faces of one mesh. Queries take the mesh it was built from, which may
+/// have had vertices moved since as long as [`Bvh::refit`] or [`Bvh::refit_vertices`] was called.
+#[derive(Clone, Debug, Default)]
+pub struct Bvh {
+    /// In depth-first order, so children always come after their parent.
+    nodes: Vec<Node>,
+    triangles: Vec<Triangle>,
+    /// The leaves holding the triangles of each face, indexed by face.
+    face_leaves: Vec<Vec<u32>>,
+}
+
+fn centroid(mesh: &HalfEdgeMesh, triangle: &Triangle) -> Vec3 {
+    triangle.corners(mesh).into_iter().sum::<Vec3>() / 3.0
+}
+
+/// Build the subtree over `triangles`, which start at `offset` in the full list, and return its root.
+fn build_node(
+    mesh: &HalfEdgeMesh,
+    triangles: &mut [Triangle],
+    offset: usize,
+    parent: u32,
+    nodes: &mut Vec<Node>,
+    face_leaves: &mut [Vec<u32>],
+) -> u32 {
+    let index = nodes.len() as u32;
+    if triangles.len() <= LEAF_SIZE {
+        for triangle in triangles.iter() {
+            let leaves = &mut face_leaves[triangle.face.index()];
+            if leaves.last() != Some(&index) {
+                leaves.push(index);
+            }
+        }
+        nodes.push(Node {
+            bounds: Aabb::from_points(triangles.iter().flat_map(|t| t.corners(mesh))),
+            parent,
+            content: Content::Leaf {
+                start: offset as u32,
+                end: (offset + triangles.len()) as u32,
+            },
+        });
+        return index;
+    }
+
+    let extent = Aabb::from_points(triangles.iter().map(|t| centroid(mesh, t))).half_extents();
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+    let mid = triangles.len() / 2;
+    triangles.select_nth_unstable_by(mid, |a, b| {
+        centroid(mesh, a)[axis].total_cmp(&centroid(mesh, b)[axis])
+    });
+
+    nodes.push(Node {
+        bounds: Aabb::EMPTY,
+        parent,
+        content: Content::Inner { children: [0, 0] },
+    });
+    let (left, right) = triangles.split_at_mut(mid);
+    let left = build_node(mesh, left, offset, index, nodes, face_leaves);
+    let right = build_node(mesh, right, offset + mid, index, nodes, face_leaves);
+    let bounds = nodes[left as usize]
+        .bounds
+        .union(nodes[right as usize].bounds);
+    let node = &mut nodes[index as usize];
+    node.bounds = bounds;
+    node.content = Content::Inner {
+        children: [left, right],
+    };
+    index
+}
+
+/// Where `ray` crosses the triangle, as the ray parameter and barycentric coordinates, with the
+/// Möller-Trumbore test. Both sides of the triangle count. Rays through an edge or vertex are accepted with
+/// a little slack, so that rounding cannot let them slip between neighbouring triangles.
+fn intersect_triangle(ray: &Ray, [a, b, c]: [Vec3; 3]) -> Option<(f32, Vec3)> {
+    const SLACK: f32 = 1e-6;
+    let (ab, ac) = (b - a, c - a);
+    let p = ray.direction.cross(ac);
+    let determinant = ab.dot(p);
+    if determinant.abs() <= f32::MIN_POSITIVE {
+        return None;
+    }
+    let inverse = determinant.recip();
+    let s = ray.origin - a;
+    let u = s.dot(p) * inverse;
+    if !(-SLACK..=1.0 + SLACK).contains(&u) {
+        return None;
+    }
+    let q = s.cross(ab);
+    let v = ray.direction.dot(q) * inverse;
+    if v < -SLACK || u + v > 1.0 + SLACK {
+        return None;
+    }
+    let t = ac.dot(q) * inverse;
+    let barycentric = Vec3::new(1.0 - u - v, u, v).max(Vec3::ZERO);
+    (t >= 0.0).then(|| (t, barycentric / barycentric.element_sum()))
+}
+
+/// The barycentric coordinates of the point of the triangle nearest `p`, following Ericson, "Real-Time
+/// Collision Detection", section 5.1.5.
+fn closest_on_triangle(p: Vec3, [a, b, c]: [Vec3; 3]) -> Vec3 {
+    let (ab, ac, ap) = (b - a, c - a, p - a);
+    let (d1, d2) = (ab.dot(ap), ac.dot(ap));
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return Vec3::X;
+    }
+    let bp = p - b;
+    let (d3, d4) = (ab.dot(bp), ac.dot(bp));
+    if d3 >= 0.0 && d4 <= d3 {
+        return Vec3::Y;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let v = d1 / (d1 - d3);
+        return Vec3::new(1.0 - v, v, 0.0);
+    }
+    let cp = p - c;
+    let (d5, d6) = (ab.dot(cp), ac.dot(cp));
+    if d6 >= 0.0 && d5 <= d6 {
+        return Vec3::Z;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let w = d2 / (d2 - d6);
+        return Vec3::new(1.0 - w, 0.0, w);
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return Vec3::new(0.0, 1.0 - w, w);
+    }
+    let denominator = (va + vb + vc).recip();
+    let (v, w) = (vb * denominator, vc * denominator);
+    Vec3::new(1.0 - v - w, v, w)
+}
+
+fn interpolate([a, b, c]: [Vec3; 3], barycentric: Vec3) -> Vec3 {
+    barycentric.x * a + barycentric.y * b + barycentric.z * c
+}
+
+/// Whether the triangle and the box overlap, by the separating axis test of Akenine-Möller, "Fast 3D
+/// Triangle-Box Overlap Testing".
+fn triangle_overlaps_box(corners: [Vec3; 3], bounds: &Aabb) -> bool {
+    let (center, half) = (bounds.center(), bounds.half_extents());
+    let v = corners.map(|p| p - center);
+    let edges = [v[1] - v[0], v[2] - v[1], v[0] - v[2]];
+    let separates = |axis: Vec3| {
+        let projected = v.map(|p| axis.dot(p));
+        let radius = half.dot(axis.abs());
+        projected.iter().all(|&d| d > radius) || projected.iter().all(|&d| d < -radius)
+    };
+    let box_axes = [Vec3::X, Vec3::Y, Vec3::Z];
+    if box_axes.into_iter().any(separates) || separates(edges[0].cross(edges[1])) {
+        return false;
+    }
+    !edges
+        .iter()
+        .any(|&e| box_axes.iter().any(|&axis| separates(axis.cross(e))))
+}
+
+impl Bvh {
+    /// Build the hierarchy over every face of `mesh`.
+    pub fn new(mesh: &HalfEdgeMesh) -> Self {
+        let mut triangles = Vec::new();
+        for f in mesh.faces() {
+            let vertices: Vec<VertexId> = mesh.face_vertices(f).collect();
+            for i in 1..vertices.len() - 1 {
+                triangles.push(Triangle {
+                    face: f,
+                    vertices: [vertices[0], vertices[i], vertices[i + 1]],
+                });
+            }
+        }
+        let mut nodes = Vec::new();
+        let mut face_leaves = vec![Vec::new(); mesh.face_count()];
+        if !triangles.is_empty() {
+            build_node(
+                mesh,
+                &mut triangles,
+                0,
+                NO_PARENT,
+                &mut nodes,
+                &mut face_leaves,
+            );
+        }
+        Self {
+            nodes,
+            triangles,
+            face_leaves,
+        }
+    }
+
+    /// The box around the whole mesh, empty if it has no faces.
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::EMPTY, |root| root.bounds)
+    }
+
+    fn fit(&mut self, mesh: &HalfEdgeMesh, node: usize) {
+        self.nodes[node].bounds = match self.nodes[node].content {
+            Content::Leaf { start, end } => Aabb::from_points(
+                self.triangles[start as usize..end as usize]
+                    .iter()
+                    .flat_map(|t| t.corners(mesh)),
+            ),
+            Content::Inner { children: [a, b] } => self.nodes[a as usize]
+                .bounds
+                .union(self.nodes[b as usize].bounds),
+        };
+    }
+
+    /// Recompute every box from the current vertex positions. The mesh's connectivity must not have changed.
+    pub fn refit(&mut self, mesh: &HalfEdgeMesh) {
+        for node in (0..self.nodes.len()).rev() {
+            self.fit(mesh, node);
+        }
+    }
+
+    /// Recompute only the boxes around faces at `moved` vertices, and their ancestors. Cheaper than
+    /// [`Bvh::refit`] when few vertices moved.
+    pub fn refit_vertices(&mut self, mesh: &HalfEdgeMesh, moved: &[VertexId]) {
+        let mut dirty = Vec::new();
+        let mut marked = vec![false; self.nodes.len()];
+        for &v in moved {
+            for f in mesh.vertex_faces(v) {
+                for &leaf in &self.face_leaves[f.index()] {
+                    let mut node = leaf;
+                    while node != NO_PARENT && !marked[node as usize] {
+                        marked[node as usize] = true;
+                        dirty.push(node as usize);
+                        node = self.nodes[node as usize].parent;
+                    }
+                }
+            }
+        }
+        dirty.sort_unstable_by_key(|&node| std::cmp::Reverse(node));
+        for node in dirty {
+            self.fit(mesh, node);
+        }
+    }
+
+    /// The first point where `ray` meets the surface, no further along it than `max_distance`.
+    pub fn cast_ray(
+        &self,
+        mesh: &HalfEdgeMesh,
+        ray: &Ray,
+        max_distance: f32,
+    ) -> Option<SurfaceHit> {
+        let inverse_direction = ray.direction.recip();
+        let mut nearest = max_distance;
+        let mut best = None;
+        let mut stack: Vec<u32> = Vec::new();
+        if self
+            .nodes
+            .first()?
+            .bounds
+            .ray_entry(ray, inverse_direction, nearest)
+            .is_some()
+        {
+            stack.push(0);
+        }
+        while let Some(node) = stack.pop() {
+            match self.nodes[node as usize].content {
+                Content::Leaf { start, end } => {
+                    for triangle in &self.triangles[start as usize..end as usize] {
+                        if let Some((t, barycentric)) =
+                            intersect_triangle(ray, triangle.corners(mesh))
+                        {
+                            if t <= nearest {
+                                nearest = t;
+                                best = Some(triangle.hit(barycentric, ray.at(t), t));
+                            }
+                        }
+                    }
+                }
+                Content::Inner { children } => {
+                    let entries = children.map(|child| {
+                        self.nodes[child as usize]
+                            .bounds
+                            .ray_entry(ray, inverse_direction, nearest)
+                    });
+                    // Push the nearer child last so that it is searched first.
+                    let order = match entries {
+                        [Some(a), Some(b)] if a < b => [1, 0],
+                        _ => [0, 1],
+                    };
+                    for i in order {
+                        if entries[i].is_some() {
+                            stack.push(children[i]);
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// The point of the surface nearest `point`, if any lies within `max_distance` of it.
+    pub fn closest_point(
+        &self,
+        mesh: &HalfEdgeMesh,
+        point: Vec3,
+        max_distance: f32,
+    ) -> Option<SurfaceHit> {
+        let mut nearest = max_distance * max_distance;
+        let mut best = None;
+        let mut stack: Vec<u32> = Vec::new();
+        if self.nodes.first()?.bounds.distance_squared(point) <= nearest {
+            stack.push(0);
+        }
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node as usize];
+            // The box may have been pushed before a closer point was found.
+            if node.bounds.distance_squared(point) > nearest {
+                continue;
+            }
+            match node.content {
+                Content::Leaf { start, end } => {
+                    for triangle in &self.triangles[start as usize..end as usize] {
+                        let corners = triangle.corners(mesh);
+                        let barycentric = closest_on_triangle(point, corners);
+                        let closest = interpolate(corners, barycentric);
+                        let distance = closest.distance_squared(point);
+                        if distance <= nearest {
+                            nearest = distance;
+                            best = Some(triangle.hit(barycentric, closest, distance.sqrt()));
+                        }
+                    }
+                }
+                Content::Inner { children } => {
+                    let distances = children
+                        .map(|child| self.nodes[child as usize].bounds.distance_squared(point));
+                    let order = if distances[0] < distances[1] {
+                        [1, 0]
+                    } else {
+                        [0, 1]
+                    };
+                    for i in order {
+                        if distances[i] <= nearest {
+                            stack.push(children[i]);
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Every face with a triangle for which `overlaps` holds, searching the subtrees whose boxes pass
+    /// `visit`.
+    fn collect_faces(
+        &self,
+        mesh: &HalfEdgeMesh,
+        visit: impl Fn(&Aabb) -> bool,
+        overlaps: impl Fn([Vec3; 3]) -> bool,
+    ) -> Vec<FaceId> {
+        let mut faces = Vec::new();
+        let mut stack: Vec<u32> = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node as usize];
+            if !visit(&node.bounds) {
+                continue;
+            }
+            match node.content {
+                Content::Leaf { start, end } => faces.extend(
+                    self.triangles[start as usize..end as usize]
+                        .iter()
+                        .filter(|t| overlaps(t.corners(mesh)))
+                        .map(|t| t.face),
+                ),
+                Content::Inner { children } => stack.extend(children),
+            }
+        }
+        faces.sort_unstable();
+        faces.dedup();
+        faces
+    }
+
+    /// Every face that comes within `radius` of `center`, in index order.
+    pub fn faces_in_sphere(&self, mesh: &HalfEdgeMesh, center: Vec3, radius: f32) -> Vec<FaceId> {
+        let radius_squared = radius * radius;
+        self.collect_faces(
+            mesh,
+            |bounds| bounds.distance_squared(center) <= radius_squared,
+            |corners| {
+                let closest = interpolate(corners, closest_on_triangle(center, corners));
+                closest.distance_squared(center) <= radius_squared
+            },
+        )
+    }
+
+    /// Every face that overlaps `bounds`, in index order.
+    pub fn faces_in_box(&self, mesh: &HalfEdgeMesh, bounds: &Aabb) -> Vec<FaceId> {
+        self.collect_faces(
+            mesh,
+            |node| node.intersects(bounds),
+            |corners| triangle_overlaps_box(corners, bounds),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A unit cube made of quads.
+    fn cube() -> HalfEdgeMesh {
+        let positions: Vec<Vec3> = (0..8)
+            .map(|i| Vec3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2) as f32))
+            .collect();
+        let quads: [&[u32]; 6] = [
+            &[0, 2, 3, 1],
+            &[4, 5, 7, 6],
+            &[0, 1, 5, 4],
+            &[2, 6, 7, 3],
+            &[0, 4, 6, 2],
+            &[1, 3, 7, 5],
+        ];
+        HalfEdgeMesh::from_polygons(positions, quads).unwrap()
+    }
+
+    /// A bumpy `n` by `n` height field over the unit square, shifted by `offset`.
+    fn terrain(n: u32, offset: Vec3) -> HalfEdgeMesh {
+        let positions: Vec<Vec3> = (0..=n)
+            .flat_map(|j| {
+                (0..=n).map(move |i| {
+                    let (x, y) = (i as f32 / n as f32, j as f32 / n as f32);
+                    offset + Vec3::new(x, y, 0.2 * (5.0 * x).sin() * (3.0 * y).cos())
+                })
+            })
+            .collect();
+        let mut triangles = Vec::new();
+        for j in 0..n {
+            for i in 0..n {
+                let v = j * (n + 1) + i;
+                triangles.extend([v, v + 1, v + n + 2, v, v + n + 2, v + n + 1]);
+            }
+        }
+        HalfEdgeMesh::from_triangles(positions, &triangles).unwrap()
+    }
+
+    /// Points on a lattice over `bounds`, `n` to a side.
+    fn lattice(bounds: Aabb, n: u32) -> Vec<Vec3> {
+        let step = (bounds.max - bounds.min) / (n - 1) as f32;
+        (0..n * n * n)
+            .map(|i| {
+                bounds.min
+                    + step * Vec3::new((i % n) as f32, (i / n % n) as f32, (i / n / n) as f32)
+            })
+            .collect()
+    }
+
+    /// Every triangle of the mesh, fanned like the hierarchy does.
+    fn triangles(mesh: &HalfEdgeMesh) -> Vec<(FaceId, [Vec3; 3])> {
+        mesh.faces()
+            .flat_map(|f| {
+                let corners: Vec<Vec3> = mesh.face_vertices(f).map(|v| mesh.position(v)).collect();
+                (1..corners.len() - 1)
+                    .map(move |i| (f, [corners[0], corners[i], corners[i + 1]]))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn brute_force_ray(mesh: &HalfEdgeMesh, ray: &Ray) -> Option<f32> {
+        triangles(mesh)
+            .into_iter()
+            .filter_map(|(_, corners)| intersect_triangle(ray, corners).map(|(t, _)| t))
+            .min_by(f32::total_cmp)
+    }
+
+    fn brute_force_distance(mesh: &HalfEdgeMesh, p: Vec3) -> f32 {
+        triangles(mesh)
+            .into_iter()
+            .map(|(_, corners)| interpolate(corners, closest_on_triangle(p, corners)).distance(p))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Check that ray casts and closest points agree with testing every triangle.
+    fn check_against_brute_force(bvh: &Bvh, mesh: &HalfEdgeMesh) {
+        let bounds = Aabb::new(Vec3::splat(-0.5), Vec3::splat(1.5));
+        for p in lattice(bounds, 6) {
+            let expected = brute_force_distance(mesh, p);
+            let hit = bvh.closest_point(mesh, p, f32::INFINITY).unwrap();
+            assert!(
+                (hit.distance - expected).abs() < 1e-5,
+                "{p}: {} vs {expected}",
+                hit.distance
+            );
+            assert!((hit.point.distance(p) - hit.distance).abs() < 1e-5);
+
+            for direction in [
+                Vec3::NEG_Z,
+                Vec3::new(0.3, -0.2, -1.0),
+                Vec3::new(-1.0, 0.5, 0.1),
+            ] {
+                let ray = Ray::new(p, direction);
+                let expected = brute_force_ray(mesh, &ray);
+                let hit = bvh.cast_ray(mesh, &ray, f32::INFINITY);
+                assert_eq!(hit.is_some(), expected.is_some(), "{ray:?}");
+                if let (Some(hit), Some(t)) = (hit, expected) {
+                    assert!((hit.distance - t).abs() < 1e-5, "{ray:?}");
+                    assert!(ray.at(hit.distance).distance(hit.point) < 1e-5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rays_through_faces_edges_and_vertices_hit_the_cube() {
+        let mesh = cube();
+        let bvh = Bvh::new(&mesh);
+        let center = Vec3::splat(0.5);
+        let vertices: Vec<Vec3> = mesh.positions().to_vec();
+        let edge_midpoints = mesh.edges().map(|e| {
+            mesh.edge_vertices(e)
+                .map(|v| mesh.position(v))
+                .into_iter()
+                .sum::<Vec3>()
+                / 2.0
+        });
+        let face_centers = mesh.faces().map(|f| {
+            mesh.face_vertices(f)
+                .map(|v| mesh.position(v))
+                .sum::<Vec3>()
+                / 4.0
+        });
+        // Rays from outside aimed at each target, and from the centre out through it, both arrive at t = 1.
+        let targets: Vec<Vec3> = vertices
+            .into_iter()
+            .chain(edge_midpoints)
+            .chain(face_centers)
+            .collect();
+        assert_eq!(targets.len(), 8 + 12 + 6);
+        for target in targets {
+            for ray in [
+                Ray::new(2.0 * target - center, center - target),
+                Ray::new(center, target - center),
+            ] {
+                let hit = bvh
+                    .cast_ray(&mesh, &ray, f32::INFINITY)
+                    .unwrap_or_else(|| panic!("{ray:?} missed"));
+                assert!(
+                    (hit.distance - 1.0).abs() < 1e-5,
+                    "{ray:?}: {}",
+                    hit.distance
+                );
+                assert!(hit.point.distance(target) < 1e-5, "{ray:?}: {}", hit.point);
+                let corners = hit.vertices.map(|v| mesh.position(v));
+                assert!(interpolate(corners, hit.barycentric).distance(target) < 1e-5);
+                assert!(mesh
+                    .face_vertices(hit.face)
+                    .any(|v| hit.vertices.contains(&v)));
+            }
+        }
+
+        let outside = Ray::new(Vec3::new(0.5, 0.5, -1.0), Vec3::Z);
+        assert!(bvh.cast_ray(&mesh, &outside, 0.5).is_none());
+        assert!(bvh
+            .cast_ray(&mesh, &Ray::new(outside.origin, Vec3::NEG_Z), f32::INFINITY)
+            .is_none());
+        assert!(bvh
+            .cast_ray(
+                &mesh,
+                &Ray::new(Vec3::new(2.0, 0.5, 0.5), Vec3::Z),
+                f32::INFINITY
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn closest_points_from_inside_and_outside() {
+        let mesh = cube();
+        let bvh = Bvh::new(&mesh);
+        let unit = Aabb::new(Vec3::ZERO, Vec3::ONE);
+        for p in lattice(Aabb::new(Vec3::splat(-0.75), Vec3::splat(1.75)), 9) {
+            let expected = if unit.distance_squared(p) > 0.0 {
+                unit.distance_squared(p).sqrt()
+            } else {
+                p.min(Vec3::ONE - p).min_element()
+            };
+            let hit = bvh.closest_point(&mesh, p, f32::INFINITY).unwrap();
+            assert!(
+                (hit.distance - expected).abs() < 1e-5,
+                "{p}: {} vs {expected}",
+                hit.distance
+            );
+            assert!((hit.point.distance(p) - expected).abs() < 1e-5);
+            assert!(unit.distance_squared(hit.point) < 1e-10);
+        }
+        assert!(bvh.closest_point(&mesh, Vec3::splat(3.0), 1.0).is_none());
+
+        let mesh = terrain(12, Vec3::ZERO);
+        check_against_brute_force(&Bvh::new(&mesh), &mesh);
+    }
+
+    #[test]
+    fn overlapping_faces_of_two_meshes_are_found() {
+        let a = terrain(10, Vec3::ZERO);
+        let b = terrain(7, Vec3::new(0.35, 0.4, 0.05));
+        let bvh = Bvh::new(&b);
+        let mut pairs = 0;
+        for f in a.faces() {
+            let bounds = Aabb::from_points(a.face_vertices(f).map(|v| a.position(v)));
+            let mut expected: Vec<FaceId> = triangles(&b)
+                .into_iter()
+                .filter(|&(_, corners)| triangle_overlaps_box(corners, &bounds))
+                .map(|(g, _)| g)
+                .collect();
+            expected.dedup();
+            let found = bvh.faces_in_box(&b, &bounds);
+            assert_eq!(found, expected, "face {f}");
+            pairs += found.len();
+
+            let center = bounds.center();
+            let mut expected: Vec<FaceId> = triangles(&b)
+                .into_iter()
+                .filter(|&(_, corners)| {
+                    interpolate(corners, closest_on_triangle(center, corners)).distance(center)
+                        <= 0.1
+                })
+                .map(|(g, _)| g)
+                .collect();
+            expected.dedup();
+            assert_eq!(bvh.faces_in_sphere(&b, center, 0.1), expected, "face {f}");
+        }
+        assert!(pairs > 0);
+
+        let far = terrain(7, Vec3::new(5.0, 0.0, 0.0));
+        let bvh = Bvh::new(&far);
+        assert!(a.faces().all(|f| bvh
+            .faces_in_box(
+                &far,
+                &Aabb::from_points(a.face_vertices(f).map(|v| a.position(v)))
+            )
+            .is_empty()));
+    }
+
+    #[test]
+    fn refitting_follows_moved_vertices() {
+        let mut mesh = terrain(12, Vec3::ZERO);
+        let mut bvh = Bvh::new(&mesh);
+        // Raise a patch in the middle well above the rest, and push a corner out sideways.
+        let moved: Vec<VertexId> = mesh
+            .vertices()
+            .filter(|&v| {
+                let p = mesh.position(v);
+                (p.x - 0.5).abs() < 0.2 && (p.y - 0.5).abs() < 0.2 || p.x + p.y == 0.0
+            })
+            .collect();
+        for &v in &moved {
+            let p = mesh.position(v);
+            let offset = if p.x + p.y == 0.0 {
+                Vec3::new(-0.3, -0.3, 0.0)
+            } else {
+                Vec3::new(0.0, 0.0, 0.6)
+            };
+            mesh.set_position(v, p + offset);
+        }
+
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 2.0), Vec3::NEG_Z);
+        let stale = bvh.cast_ray(&mesh, &ray, f32::INFINITY);
+        bvh.refit_vertices(&mesh, &moved);
+        let hit = bvh.cast_ray(&mesh, &ray, f32::INFINITY).unwrap();
+        assert!((hit.point.z - 0.6 - 0.2 * 2.5f32.sin() * 1.5f32.cos()).abs() < 1e-5);
+        assert_ne!(stale, Some(hit));
+        assert_eq!(bvh.bounds(), Bvh::new(&mesh).bounds());
+        check_against_brute_force(&bvh, &mesh);
+
+        // A full refit gives the same boxes as the incremental one.
+        let mut full = bvh.clone();
+        full.refit(&mesh);
+        assert!(full
+            .nodes
+            .iter()
+            .zip(&bvh.nodes)
+            .all(|(a, b)| a.bounds == b.bounds));
+    }
+}
@@ -0,0 +1,273 @@
+//! Discrete differential geometry: normals, angles, areas and curvature.
+//!
+//! Curvature follows Meyer et al., "Discrete Differential-Geometry Operators for Triangulated 2-Manifolds":
+//! mean curvature from the cotangent Laplacian, Gaussian curvature from the angle defect, both normalised by
+//! the mixed Voronoi area. Principal directions come from a least-squares fit of the second fundamental form
+//! to the normal curvatures along the edges. Curvature needs triangles; normals, angles and areas work on any
+//! polygons.
+
+use core::{f32::consts::PI, fmt};
+
+use glam::{Mat3, Vec3};
+
+use crate::mesh::{EdgeId, FaceId, HalfEdgeId, HalfEdgeMesh, VertexId};
+
+/// How the normals of the faces around a vertex are averaged into a vertex normal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NormalWeighting {
+    /// Every face counts the same.
+    Uniform,
+    /// Faces are weighted by their area, so small slivers barely matter.
+    #[default]
+    Area,
+    /// Faces are weighted by their corner angle at the vertex, which does not depend on how the surface is
+    /// triangulated.
+    Angle,
+}
+
+/// Curvature at one vertex. Mean curvature is positive where the surface bends away from its normal, as on
+/// the outside of a sphere.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VertexCurvature {
+    pub mean: f32,
+    pub gaussian: f32,
+    /// The smaller principal curvature.
+    pub min: f32,
+    /// The larger principal curvature.
+    pub max: f32,
+    /// Unit tangent direction of `min`.
+    pub min_direction: Vec3,
+    /// Unit tangent direction of `max`.
+    pub max_direction: Vec3,
+}
+
+/// Why curvature could not be computed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurvatureError {
+    /// Curvature is only defined here for triangle meshes.
+    NotTriangleMesh,
+}
+
+impl fmt::Display for CurvatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTriangleMesh => f.write_str("curvature requires a triangle mesh"),
+        }
+    }
+}
+
+impl std::error::Error for CurvatureError {}
+
+/// The cotangent of the angle between `u` and `v`, clamped so that degenerate corners stay finite.
+fn cotangent(u: Vec3, v: Vec3) -> f32 {
+    u.dot(v) / u.cross(v).length().max(1e-12)
+}
+
+impl HalfEdgeMesh {
+    /// The area-weighted normal of `f` (Newell's method), whose length is twice the face's area. Works for
+    /// non-planar polygons.
+    pub fn face_normal_unnormalized(&self, f: FaceId) -> Vec3 {
+        let mut normal = Vec3::ZERO;
+        for h in self.face_halfedges(f) {
+            let (p, q) = (self.position(self.origin(h)), self.position(self.dest(h)));
+            normal += p.cross(q);
+        }
+        normal
+    }
+
+    /// The unit normal of `f`, or zero if the face is degenerate.
+    pub fn face_normal(&self, f: FaceId) -> Vec3 {
+        self.face_normal_unnormalized(f).normalize_or_zero()
+    }
+
+    pub fn face_area(&self, f: FaceId) -> f32 {
+        0.5 * self.face_normal_unnormalized(f).length()
+    }
+
+    pub fn face_centroid(&self, f: FaceId) -> Vec3 {
+        let (sum, count) = self
+            .face_positions(f)
+            .fold((Vec3::ZERO, 0.0), |(sum, count), p| (sum + p, count + 1.0));
+        sum / count
+    }
+
+    /// The interior angle of `face(h)` at the origin of `h`, or zero for a boundary half-edge.
+    pub fn corner_angle(&self, h: HalfEdgeId) -> f32 {
+        if self.is_boundary_halfedge(h) {
+            return 0.0;
+        }
+        let u = self.halfedge_vector(h);
+        let v = -self.halfedge_vector(self.prev(h));
+        u.angle_between(v)
+    }
+
+    /// The normal at `v`, averaged from the normals of its faces. Zero for isolated vertices.
+    pub fn vertex_normal(&self, v: VertexId, weighting: NormalWeighting) -> Vec3 {
+        let mut normal = Vec3::ZERO;
+        for h in self.vertex_outgoing_halfedges(v) {
+            let Some(f) = self.face(h) else {
+                continue;
+            };
+            normal += match weighting {
+                NormalWeighting::Uniform => self.face_normal(f),
+                NormalWeighting::Area => self.face_normal_unnormalized(f),
+                NormalWeighting::Angle => self.face_normal(f) * self.corner_angle(h),
+            };
+        }
+        normal.normalize_or_zero()
+    }
+
+    /// Normals of every vertex, indexed by vertex. Removed vertices get zero.
+    pub fn vertex_normals(&self, weighting: NormalWeighting) -> Vec<Vec3> {
+        (0..self.vertex_count())
+            .map(VertexId::new)
+            .map(|v| {
+                if self.is_removed_vertex(v) {
+                    Vec3::ZERO
+                } else {
+                    self.vertex_normal(v, weighting)
+                }
+            })
+            .collect()
+    }
+
+    /// Half the sum of the cotangents of the angles opposite edge `e` in the triangles on either side. These
+    /// are the weights of the cotangent Laplacian. Boundary edges only have one term.
+    pub fn cotan_weight(&self, e: EdgeId) -> f32 {
+        let h = self.edge_halfedge(e);
+        [h, self.twin(h)]
+            .into_iter()
+            .filter(|&h| !self.is_boundary_halfedge(h))
+            .map(|h| {
+                // The corner opposite `h` in its triangle is the origin of `prev(h)`.
+                let apex = self.position(self.origin(self.prev(h)));
+                let (a, b) = (self.position(self.origin(h)), self.position(self.dest(h)));
+                0.5 * cotangent(a - apex, b - apex)
+            })
+            .sum()
+    }
+
+    /// The mixed Voronoi area of `v`: the part of its triangles closer to it than to their other corners,
+    /// with obtuse triangles split so that the areas of all vertices still sum to the mesh's area.
+    pub fn vertex_area(&self, v: VertexId) -> f32 {
+        let mut area = 0.0;
+        for h in self.vertex_outgoing_halfedges(v) {
+            if self.is_boundary_halfedge(h) {
+                continue;
+            }
+            let p = self.position(v);
+            let q = self.position(self.dest(h));
+            let r = self.position(self.origin(self.prev(h)));
+            let triangle = 0.5 * (q - p).cross(r - p).length();
+            let obtuse_at = |a: Vec3, b: Vec3, c: Vec3| (b - a).dot(c - a) < 0.0;
+            area += if obtuse_at(p, q, r) {
+                0.5 * triangle
+            } else if obtuse_at(q, r, p) || obtuse_at(r, p, q) {
+                0.25 * triangle
+            } else {
+                0.125
+                    * ((r - p).length_squared() * cotangent(p - q, r - q)
+                        + (q - p).length_squared() * cotangent(p - r, q - r))
+            };
+        }
+        area
+    }
+
+    /// Gaussian curvature at `v` from the angle defect, divided by the vertex area. At boundary vertices the
+    /// defect is measured against a half turn.
+    pub fn gaussian_curvature(&self, v: VertexId) -> f32 {
+        let full = if self.is_boundary_vertex(v) {
+            PI
+        } else {
+            2.0 * PI
+        };
+        let angles: f32 = self
+            .vertex_outgoing_halfedges(v)
+            .map(|h| self.corner_angle(h))
+            .sum();
+        (full - angles) / self.vertex_area(v).max(1e-12)
+    }
+
+    /// The cotangent Laplacian of positions at `v`, which equals `-2 H n` on a smooth surface.
+    fn laplace_position(&self, v: VertexId) -> Vec3 {
+        let p = self.position(v);
+        let sum: Vec3 = self
+            .vertex_outgoing_halfedges(v)
+            .map(|h| self.cotan_weight(self.edge(h)) * (self.position(self.dest(h)) - p))
+            .sum();
+        sum / self.vertex_area(v).max(1e-12)
+    }
+
+    /// Mean curvature at `v` from the cotangent Laplacian, signed against the area-weighted vertex normal.
+    pub fn mean_curvature(&self, v: VertexId) -> f32 {
+        let normal = self.vertex_normal(v, NormalWeighting::Area);
+        -0.5 * self.laplace_position(v).dot(normal)
+    }
+
+    /// Mean, Gaussian and principal curvatures and principal directions of every vertex, indexed by vertex.
+    /// Removed and isolated vertices get zero. Boundary vertices only see one side of the surface, so their
+    /// values are rough.
+    pub fn curvatures(&self) -> Result<Vec<VertexCurvature>, CurvatureError> {
+        if self.faces().any(|f| self.face_degree(f) != 3) {
+            return Err(CurvatureError::NotTriangleMesh);
+        }
+        Ok((0..self.vertex_count())
+            .map(VertexId::new)
+            .map(|v| {
+                if self.is_removed_vertex(v) || self.is_isolated_vertex(v) {
+                    VertexCurvature::default()
+                } else {
+                    self.vertex_curvature(v)
+                }
+            })
+            .collect())
+    }
+
+    fn vertex_curvature(&self, v: VertexId) -> VertexCurvature {
+        let mean = self.mean_curvature(v);
+        let gaussian = self.gaussian_curvature(v);
+        let spread = (mean * mean - gaussian).max(0.0).sqrt();
+        let (min, max) = (mean - spread, mean + spread);
+
+        // Fit the second fundamental form [[l, m], [m, n]] to the normal curvature along each edge, in a basis
+        // of the tangent plane, by weighted least squares. Its eigenvectors are the principal directions.
+        let normal = self.vertex_normal(v, NormalWeighting::Area);
+        let (t1, t2) = normal.any_orthonormal_pair();
+        let p = self.position(v);
+        let mut lhs = Mat3::ZERO;
+        let mut rhs = Vec3::ZERO;
+        for h in self.vertex_outgoing_halfedges(v) {
+            let d = self.position(self.dest(h)) - p;
+            let tangent = (d - normal * normal.dot(d)).normalize_or_zero();
+            let kappa = -2.0 * normal.dot(d) / d.length_squared().max(1e-12);
+            let weight: f32 = [h, self.twin(h)]
+                .into_iter()
+                .filter_map(|h| self.face(h))
+                .map(|f| self.face_area(f))
+                .sum();
+            let (x, y) = (tangent.dot(t1), tangent.dot(t2));
+            let row = Vec3::new(x * x, 2.0 * x * y, y * y);
+            lhs += Mat3::from_cols(row * row.x, row * row.y, row * row.z) * weight;
+            rhs += row * (weight * kappa);
+        }
+        let form = if lhs.determinant().abs() > 1e-12 {
+            lhs.inverse() * rhs
+        } else {
+            Vec3::ZERO
+        };
+        let (l, m, n) = (form.x, form.y, form.z);
+        // The eigenvector of the larger eigenvalue is the maximum direction.
+        let angle = 0.5 * (2.0 * m).atan2(l - n);
+        let max_direction = t1 * angle.cos() + t2 * angle.sin();
+        let min_direction = normal.cross(max_direction);
+
+        VertexCurvature {
+            mean,
+            gaussian,
+            min,
+            max,
+            min_direction,
+            max_direction,
+        }
+    }
+}
@@ -0,0 +1,577 @@
+//! Geodesic distances with the heat method of Crane, Weischedel and Wardetzky, "Geodesics in Heat".
+//!
+//! Heat is diffused from the sources for a short time, the normalised gradient of the result gives the
+//! direction distance grows in, and a Poisson solve recovers the distance whose gradient best matches it. Both
+//! systems only depend on the mesh, so [`HeatMethod`] factors them once and then answers each query with two
+//! back-substitutions. Boundaries get Neumann conditions. On badly shaped meshes the systems can instead be
+//! built on the intrinsic Delaunay triangulation, whose cotangent weights are never negative.
+//!
+//! Paths are traced afterwards by walking down the distance field from the target, straight across each
+//! triangle along the steepest descent of the interpolated distance, until a source is reached.
+
+use core::fmt;
+
+use glam::{DVec3, Vec2, Vec3};
+
+use crate::{
+    intrinsic::{IntrinsicError, IntrinsicTriangulation},
+    mesh::{FaceId, HalfEdgeId, HalfEdgeMesh, VertexId},
+    sparse::{Cholesky, MassMatrix, SolveError, TripletMatrix},
+};
+
+/// Settings for [`HeatMethod::new`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeatMethodConfig {
+    /// The diffusion time as a multiple of the squared mean edge length. Larger values give smoother but less
+    /// accurate distances.
+    pub time_scale: f64,
+    /// Diffuse and integrate on the intrinsic Delaunay triangulation of the mesh instead of its own
+    /// triangles, which keeps the distances accurate on slivers and obtuse triangles without moving any
+    /// vertex.
+    pub intrinsic_delaunay: bool,
+}
+
+impl Default for HeatMethodConfig {
+    fn default() -> Self {
+        Self {
+            time_scale: 1.0,
+            intrinsic_delaunay: false,
+        }
+    }
+}
+
+/// Why geodesic distances could not be computed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeodesicError {
+    /// The heat method is only defined here for triangle meshes.
+    NotTriangleMesh,
+    /// The mesh has removed elements; garbage collect it first.
+    HasGarbage,
+    /// No source vertices were given.
+    NoSources,
+    /// A source or target vertex does not exist in the mesh.
+    InvalidVertex { vertex: VertexId },
+    /// One of the linear systems could not be solved.
+    Solve(SolveError),
+}
+
+impl fmt::Display for GeodesicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTriangleMesh => f.write_str("the heat method requires a triangle mesh"),
+            Self::HasGarbage => f.write_str("the mesh has removed elements"),
+            Self::NoSources => f.write_str("no source vertices"),
+            Self::InvalidVertex { vertex } => write!(f, "vertex {vertex} does not exist"),
+            Self::Solve(err) => write!(f, "linear solve failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for GeodesicError {}
+
+impl From<SolveError> for GeodesicError {
+    fn from(err: SolveError) -> Self {
+        Self::Solve(err)
+    }
+}
+
+impl From<IntrinsicError> for GeodesicError {
+    fn from(err: IntrinsicError) -> Self {
+        match err {
+            IntrinsicError::NotTriangleMesh => Self::NotTriangleMesh,
+        }
+    }
+}
+
+/// Prefactored heat method systems for one mesh.
+#[derive(Clone, Debug)]
+pub struct HeatMethod<'a> {
+    mesh: &'a HalfEdgeMesh,
+    /// The triangulation the systems were built on, if not the mesh's own.
+    intrinsic: Option<IntrinsicTriangulation<'a>>,
+    /// `M + tL`, for diffusing heat.
+    heat: Cholesky,
+    /// `L + εM`, for recovering distance. The small mass term pins down the constant the Laplacian ignores.
+    poisson: Cholesky,
+    /// The connected component of every vertex, since distances only make sense within one.
+    components: Vec<usize>,
+}
+
+/// Where a traced path currently is: at a vertex, or on the half-edge it is about to leave its face through,
+/// a fraction `t` of the way from origin to destination.
+#[derive(Clone, Copy, Debug)]
+enum Location {
+    Vertex(VertexId),
+    Edge(HalfEdgeId, f32),
+}
+
+impl<'a> HeatMethod<'a> {
+    pub fn new(mesh: &'a HalfEdgeMesh, config: &HeatMethodConfig) -> Result<Self, GeodesicError> {
+        if mesh.has_garbage() {
+            return Err(GeodesicError::HasGarbage);
+        }
+        if mesh.faces().any(|f| mesh.face_degree(f) != 3) {
+            return Err(GeodesicError::NotTriangleMesh);
+        }
+
+        let (sum, count) = mesh.edges().fold((0.0, 0), |(sum, count), e| {
+            (sum + f64::from(mesh.edge_length(e)), count + 1)
+        });
+        let h = if count > 0 { sum / count as f64 } else { 1.0 };
+        let t = config.time_scale * h * h;
+
+        // Isolated vertices have empty rows; give them a unit diagonal so the systems stay definite.
+        let n = mesh.vertex_count();
+        let mut isolated = TripletMatrix::new(n, n);
+        for v in mesh.vertices().filter(|&v| mesh.is_isolated_vertex(v)) {
+            isolated.add(v.index(), v.index(), 1.0);
+        }
+        let isolated = isolated.to_csr();
+        let intrinsic = if config.intrinsic_delaunay {
+            let mut intrinsic = IntrinsicTriangulation::new(mesh)?;
+            intrinsic.flip_to_delaunay();
+            Some(intrinsic)
+        } else {
+            None
+        };
+        let (laplacian, mass) = match &intrinsic {
+            Some(intrinsic) => (intrinsic.cotan_laplacian(), intrinsic.mass_matrix()),
+            None => (
+                mesh.cotan_laplacian(),
+                mesh.mass_matrix(MassMatrix::Barycentric),
+            ),
+        };
+        let mass = mass.linear_combination(1.0, &isolated, 1.0);
+        let heat = Cholesky::new(&mass.linear_combination(1.0, &laplacian, t))?;
+        let poisson = Cholesky::new(&laplacian.linear_combination(1.0, &mass, 1e-8 / (h * h)))?;
+
+        Ok(Self {
+            mesh,
+            intrinsic,
+            heat,
+            poisson,
+            components: components(mesh),
+        })
+    }
+
+    /// The geodesic distance from every vertex to the nearest of `sources`, indexed by vertex. Vertices in
+    /// components without a source are infinitely far away.
+    pub fn distances(&self, sources: &[VertexId]) -> Result<Vec<f32>, GeodesicError> {
+        let mesh = self.mesh;
+        if sources.is_empty() {
+            return Err(GeodesicError::NoSources);
+        }
+        let n = mesh.vertex_count();
+        let mut initial = vec![0.0; n];
+        for &vertex in sources {
+            if vertex.index() >= n {
+                return Err(GeodesicError::InvalidVertex { vertex });
+            }
+            initial[vertex.index()] = 1.0;
+        }
+        let heat = self.heat.solve(&initial)?;
+
+        // The unit vector field pointing away from the sources, integrated against each vertex's dual cell.
+        let mut divergence = vec![0.0; n];
+        for f in mesh.faces() {
+            let (corners, points) = self.triangle(f);
+            let gradient = triangle_gradient(points, corners.map(|v| heat[v.index()]));
+            let field = -gradient.normalize_or_zero();
+            for i in 0..3 {
+                // Corner i is followed by j and k counterclockwise.
+                let (j, k) = ((i + 1) % 3, (i + 2) % 3);
+                let [pi, pj, pk] = [points[i], points[j], points[k]];
+                let cot_k = cotangent(pi - pk, pj - pk);
+                let cot_j = cotangent(pi - pj, pk - pj);
+                divergence[corners[i].index()] +=
+                    0.5 * (cot_k * (pj - pi).dot(field) + cot_j * (pk - pi).dot(field));
+            }
+        }
+        // With `L` positive semi-definite, the Laplacian of the distance is `-L φ = ∇·X`.
+        let rhs: Vec<f64> = divergence.iter().map(|d| -d).collect();
+        let phi = self.poisson.solve(&rhs)?;
+
+        // Shift each component so its sources sit at zero distance.
+        let component_count = self.components.iter().max().map_or(0, |c| c + 1);
+        let mut offset = vec![f64::INFINITY; component_count];
+        for &s in sources {
+            let c = self.components[s.index()];
+            offset[c] = offset[c].min(phi[s.index()]);
+        }
+        let mut distances: Vec<f32> = (0..n)
+            .map(|v| {
+                let offset = offset[self.components[v]];
+                if offset.is_finite() {
+                    (phi[v] - offset).max(0.0) as f32
+                } else {
+                    f32::INFINITY
+                }
+            })
+            .collect();
+        for &s in sources {
+            distances[s.index()] = 0.0;
+        }
+        Ok(distances)
+    }
+
+    /// The corners of triangle `f` of the triangulation the systems were built on, counterclockwise, and
+    /// where they are: in space for the mesh's own triangles, laid out in the plane for intrinsic ones.
+    fn triangle(&self, f: FaceId) -> ([VertexId; 3], [DVec3; 3]) {
+        match &self.intrinsic {
+            Some(intrinsic) => (
+                corners(intrinsic.mesh(), f),
+                intrinsic.face_layout(f).map(|p| p.extend(0.0)),
+            ),
+            None => {
+                let corners = corners(self.mesh, f);
+                (corners, corners.map(|v| self.mesh.position(v).as_dvec3()))
+            }
+        }
+    }
+
+    /// The geodesic between two vertices, as a polyline from `target` to `source` that runs straight across
+    /// each triangle it passes through.
+    pub fn geodesic_path(
+        &self,
+        source: VertexId,
+        target: VertexId,
+    ) -> Result<Vec<Vec3>, GeodesicError> {
+        if target.index() >= self.mesh.vertex_count() {
+            return Err(GeodesicError::InvalidVertex { vertex: target });
+        }
+        let distances = self.distances(&[source])?;
+        Ok(self.trace_path(&distances, target))
+    }
+
+    /// Walk down `distances` from `start` until reaching a vertex at distance zero, returning the points
+    /// passed through. Stops early if the walk gets stuck in a local minimum.
+    pub fn trace_path(&self, distances: &[f32], start: VertexId) -> Vec<Vec3> {
+        let mesh = self.mesh;
+        let mut path = vec![mesh.position(start)];
+        if !distances[start.index()].is_finite() {
+            return path;
+        }
+        let mut location = Location::Vertex(start);
+        for _ in 0..4 * mesh.face_count() + 16 {
+            // Finish with a straight segment once a source is a corner of the current face or a neighbour of
+            // the current vertex.
+            let nearby: Vec<VertexId> = match location {
+                Location::Vertex(v) => std::iter::once(v).chain(mesh.vertex_vertices(v)).collect(),
+                Location::Edge(h, _) => {
+                    let t = mesh.twin(h);
+                    match mesh.face(t) {
+                        Some(f) => mesh.face_vertices(f).collect(),
+                        None => Vec::new(),
+                    }
+                }
+            };
+            if let Some(&source) = nearby.iter().find(|v| distances[v.index()] == 0.0) {
+                if path.last() != Some(&mesh.position(source)) {
+                    path.push(mesh.position(source));
+                }
+                break;
+            }
+
+            let Some(next) = (match location {
+                Location::Vertex(v) => self.step_from_vertex(distances, v),
+                Location::Edge(h, t) => self.step_across_face(distances, h, t),
+            }) else {
+                break;
+            };
+            path.push(match next {
+                Location::Vertex(v) => mesh.position(v),
+                Location::Edge(h, t) => point_on(mesh, h, t),
+            });
+            location = next;
+        }
+        path
+    }
+
+    /// Leave vertex `v` into whichever face the steepest descent points into, or along the edge to its lowest
+    /// neighbour if it points into none.
+    fn step_from_vertex(&self, distances: &[f32], v: VertexId) -> Option<Location> {
+        let mesh = self.mesh;
+        let p = mesh.position(v);
+        for h in mesh.vertex_outgoing_halfedges(v) {
+            let Some(f) = mesh.face(h) else {
+                continue;
+            };
+            let descent = -face_gradient(mesh, f, |v| f64::from(distances[v.index()])).as_vec3();
+            // Write the descent as a combination of the two edges leaving `v` within `f`.
+            let (b, c) = (mesh.dest(h), mesh.origin(mesh.prev(h)));
+            let (eb, ec) = (mesh.position(b) - p, mesh.position(c) - p);
+            let Some([alpha, beta]) = decompose(descent, eb, ec) else {
+                continue;
+            };
+            if alpha > 0.0 && beta > 0.0 {
+                // The ray crosses the opposite edge b -> c where the weights are in proportion.
+                return Some(Location::Edge(mesh.next(h), beta / (alpha + beta)));
+            }
+        }
+        mesh.vertex_vertices(v)
+            .filter(|n| distances[n.index()] < distances[v.index()])
+            .min_by(|a, b| distances[a.index()].total_cmp(&distances[b.index()]))
+            .map(Location::Vertex)
+    }
+
+    /// Cross from the point on half-edge `h` into the face on its other side, following the steepest descent
+    /// there until it leaves through another edge.
+    fn step_across_face(&self, distances: &[f32], h: HalfEdgeId, t: f32) -> Option<Location> {
+        let mesh = self.mesh;
+        let lower_end = || {
+            let (a, b) = (mesh.origin(h), mesh.dest(h));
+            Some(Location::Vertex(
+                if distances[a.index()] < distances[b.index()] {
+                    a
+                } else {
+                    b
+                },
+            ))
+        };
+        let entry = mesh.twin(h);
+        let Some(f) = mesh.face(entry) else {
+            return lower_end();
+        };
+        let p = point_on(mesh, h, t);
+        let descent = -face_gradient(mesh, f, |v| f64::from(distances[v.index()])).as_vec3();
+
+        // Work in the plane of the face, with x along the entry edge and y pointing into the face.
+        let (a, b) = (mesh.origin(entry), mesh.dest(entry));
+        let c = mesh.dest(mesh.next(entry));
+        let x = (mesh.position(b) - mesh.position(a)).normalize_or_zero();
+        let y = mesh.face_normal(f).cross(x);
+        let flat = |q: Vec3| Vec2::new((q - p).dot(x), (q - p).dot(y));
+        let direction = Vec2::new(descent.dot(x), descent.dot(y));
+        if direction.y <= 1e-6 * direction.length() {
+            return lower_end();
+        }
+
+        for exit in [mesh.next(entry), mesh.prev(entry)] {
+            let (from, to) = (
+                flat(mesh.position(mesh.origin(exit))),
+                flat(mesh.position(mesh.dest(exit))),
+            );
+            let Some((s, u)) = ray_segment(direction, from, to) else {
+                continue;
+            };
+            if s <= 0.0 || !(-1e-5..=1.0 + 1e-5).contains(&u) {
+                continue;
+            }
+            let near_corner = if exit == mesh.next(entry) {
+                u > 1.0 - 1e-4
+            } else {
+                u < 1e-4
+            };
+            return Some(if near_corner {
+                Location::Vertex(c)
+            } else {
+                Location::Edge(exit, u.clamp(0.0, 1.0))
+            });
+        }
+        lower_end()
+    }
+}
+
+impl HalfEdgeMesh {
+    /// Geodesic distances from `sources` with the heat method. Factors the systems afresh on every call; use
+    /// [`HeatMethod`] directly to reuse them across queries.
+    pub fn geodesic_distances(&self, sources: &[VertexId]) -> Result<Vec<f32>, GeodesicError> {
+        HeatMethod::new(self, &HeatMethodConfig::default())?.distances(sources)
+    }
+}
+
+fn cotangent(u: DVec3, v: DVec3) -> f64 {
+    u.dot(v) / u.cross(v).length().max(1e-300)
+}
+
+/// The gradient within triangle `f` of the linear interpolation of `value` at its corners.
+fn face_gradient(mesh: &HalfEdgeMesh, f: FaceId, value: impl Fn(VertexId) -> f64) -> DVec3 {
+    let corners = corners(mesh, f);
+    triangle_gradient(
+        corners.map(|v| mesh.position(v).as_dvec3()),
+        corners.map(value),
+    )
+}
+
+/// The gradient within the counterclockwise triangle `points` of the linear interpolation of `values` at its
+/// corners.
+fn triangle_gradient(points: [DVec3; 3], values: [f64; 3]) -> DVec3 {
+    let normal = (points[1] - points[0]).cross(points[2] - points[0]);
+    let double_area = normal.length();
+    if double_area == 0.0 {
+        return DVec3::ZERO;
+    }
+    let normal = normal / double_area;
+    let mut gradient = DVec3::ZERO;
+    for i in 0..3 {
+        // The edge opposite corner i runs between the other two.
+        let opposite = points[(i + 2) % 3] - points[(i + 1) % 3];
+        gradient += values[i] * normal.cross(opposite);
+    }
+    gradient / double_area
+}
+
+/// The corners of triangle `f`, counterclockwise.
+fn corners(mesh: &HalfEdgeMesh, f: FaceId) -> [VertexId; 3] {
+    let mut corners = mesh.face_vertices(f);
+    [(); 3].map(|_| corners.next().expect("faces are triangles"))
+}
+
+fn point_on(mesh: &HalfEdgeMesh, h: HalfEdgeId, t: f32) -> Vec3 {
+    mesh.position(mesh.origin(h))
+        .lerp(mesh.position(mesh.dest(h)), t)
+}
+
+/// Coefficients `[α, β]` with `α u + β v` closest to `d`, if `u` and `v` are not parallel.
+fn decompose(d: Vec3, u: Vec3, v: Vec3) -> Option<[f32; 2]> {
+    let (uu, uv, vv) = (u.dot(u), u.dot(v), v.dot(v));
+    let det = uu * vv - uv * uv;
+    if det.abs() <= 1e-12 * uu * vv {
+        return None;
+    }
+    let (du, dv) = (d.dot(u), d.dot(v));
+    Some([(du * vv - dv * uv) / det, (dv * uu - du * uv) / det])
+}
+
+/// Where the ray from the origin along `direction` meets the segment `from -> to`: the distance along the ray
+/// and the fraction along the segment.
+fn ray_segment(direction: Vec2, from: Vec2, to: Vec2) -> Option<(f32, f32)> {
+    let edge = to - from;
+    let denominator = direction.perp_dot(edge);
+    if denominator.abs() <= f32::EPSILON * direction.length() * edge.length() {
+        return None;
+    }
+    let s = from.perp_dot(edge) / denominator;
+    let u = from.perp_dot(direction) / denominator;
+    Some((s, u))
+}
+
+/// Label every vertex with the index of its connected component.
+fn components(mesh: &HalfEdgeMesh) -> Vec<usize> {
+    let n = mesh.vertex_count();
+    let mut label = vec![usize::MAX; n];
+    let mut next = 0;
+    let mut stack = Vec::new();
+    for v in mesh.vertices() {
+        if label[v.index()] != usize::MAX {
+            continue;
+        }
+        label[v.index()] = next;
+        stack.push(v);
+        while let Some(v) = stack.pop() {
+            for w in mesh.vertex_vertices(v) {
+                if label[w.index()] == usize::MAX {
+                    label[w.index()] = next;
+                    stack.push(w);
+                }
+            }
+        }
+        next += 1;
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subdivision::SubdivisionConfig;
+
+    /// An octahedron refined by Loop subdivision and pushed out onto the unit sphere. Vertex 4 is the north
+    /// pole.
+    fn sphere(levels: u32) -> HalfEdgeMesh {
+        let positions = vec![Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+        let triangles = [
+            0, 2, 4, 2, 1, 4, 1, 3, 4, 3, 0, 4, 2, 0, 5, 1, 2, 5, 3, 1, 5, 0, 3, 5,
+        ];
+        let config = SubdivisionConfig {
+            levels,
+            ..Default::default()
+        };
+        let mut mesh = HalfEdgeMesh::from_triangles(positions, &triangles)
+            .unwrap()
+            .loop_subdivision(&config)
+            .unwrap();
+        for v in mesh.vertices().collect::<Vec<_>>() {
+            mesh.set_position(v, mesh.position(v).normalize());
+        }
+        mesh
+    }
+
+    /// The largest difference between `distances` from the north pole and the great-circle distances.
+    fn max_error(mesh: &HalfEdgeMesh, distances: &[f32]) -> f32 {
+        mesh.vertices()
+            .map(|v| (distances[v.index()] - mesh.position(v).z.clamp(-1.0, 1.0).acos()).abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn distances_on_a_sphere_follow_great_circles() {
+        let mesh = sphere(4);
+        let pole = VertexId::new(4);
+        let distances = mesh.geodesic_distances(&[pole]).unwrap();
+        assert_eq!(distances[pole.index()], 0.0);
+        assert!(max_error(&mesh, &distances) < 0.05);
+    }
+
+    #[test]
+    fn intrinsic_delaunay_keeps_distances_accurate_on_obtuse_triangles() {
+        // A grid over the unit square, sheared so that every triangle has an angle of about 146°.
+        let n = 10;
+        let positions: Vec<Vec3> = (0..=n)
+            .flat_map(|j| (0..=n).map(move |i| (i as f32 / n as f32, j as f32 / n as f32)))
+            .map(|(x, y)| Vec3::new(x + 1.5 * y, y, 0.0))
+            .collect();
+        let mut triangles = Vec::new();
+        for j in 0..n {
+            for i in 0..n {
+                let v = j * (n + 1) + i;
+                triangles.extend([v, v + 1, v + n + 2, v, v + n + 2, v + n + 1]);
+            }
+        }
+        let mesh = HalfEdgeMesh::from_triangles(positions, &triangles).unwrap();
+        let source = VertexId::new(0);
+        let error = |config: &HeatMethodConfig| {
+            let distances = HeatMethod::new(&mesh, config)
+                .unwrap()
+                .distances(&[source])
+                .unwrap();
+            mesh.vertices()
+                .map(|v| (distances[v.index()] - mesh.position(v).length()).abs())
+                .fold(0.0, f32::max)
+        };
+        let intrinsic = error(&HeatMethodConfig {
+            intrinsic_delaunay: true,
+            ..Default::default()
+        });
+        let extrinsic = error(&Default::default());
+        assert!(intrinsic < 0.1, "intrinsic error {intrinsic}");
+        assert!(extrinsic > 5.0 * intrinsic, "extrinsic error {extrinsic}");
+    }
+
+    #[test]
+    fn paths_run_from_the_target_down_to_the_source() {
+        let mesh = sphere(4);
+        let heat = HeatMethod::new(&mesh, &Default::default()).unwrap();
+        let (pole, equator) = (VertexId::new(4), VertexId::new(0));
+        let path = heat.geodesic_path(pole, equator).unwrap();
+        assert_eq!(path.first(), Some(&mesh.position(equator)));
+        assert_eq!(path.last(), Some(&mesh.position(pole)));
+        let length: f32 = path.windows(2).map(|w| w[0].distance(w[1])).sum();
+        assert!(
+            (length - core::f32::consts::FRAC_PI_2).abs() < 0.01,
+            "length {length}"
+        );
+    }
+
+    #[test]
+    fn bad_queries_are_refused() {
+        let mesh = sphere(1);
+        let heat = HeatMethod::new(&mesh, &Default::default()).unwrap();
+        assert_eq!(heat.distances(&[]).unwrap_err(), GeodesicError::NoSources);
+        let missing = VertexId::new(mesh.vertex_count());
+        assert_eq!(
+            heat.geodesic_path(VertexId::new(0), missing).unwrap_err(),
+            GeodesicError::InvalidVertex { vertex: missing }
+        );
+    }
+}
@@ -0,0 +1,621 @@
+//! Hole filling after Liepa, "Filling Holes in Meshes".
+//!
+//! Each hole is first triangulated using only its boundary vertices, by dynamic programming over the loop:
+//! the triangulation with the smallest maximum dihedral angle wins, ties going to the smaller area. The patch
+//! is then refined by splitting triangles that are large compared to the edges around the hole and flipping
+//! edges towards a Delaunay triangulation, and finally faired by solving the bi-Laplace (thin plate) equation
+//! for the new vertices, so the patch continues the surrounding surface smoothly.
+
+use core::fmt;
+use std::collections::HashMap;
+
+use glam::Vec3;
+
+use crate::{
+    mesh::{FaceId, HalfEdgeId, HalfEdgeMesh, TopologyError, VertexId},
+    predicates::delaunay_edge,
+    sparse::{Cholesky, SolveError, TripletMatrix},
+};
+
+/// Settings for [`HalfEdgeMesh::fill_hole`] and [`HalfEdgeMesh::fill_holes`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HoleFillingConfig {
+    /// Leave holes with more boundary edges than this alone; zero fills every hole. Triangulation takes time
+    /// cubic in the number of edges, and the outer border of an open surface is usually not a hole.
+    pub max_hole_edges: usize,
+    /// Add vertices inside the patch so that its triangles match the size of the surrounding ones.
+    pub refine: bool,
+    /// Move the added vertices so the patch blends smoothly into its surroundings. Only applies when
+    /// refining.
+    pub fair: bool,
+}
+
+impl Default for HoleFillingConfig {
+    fn default() -> Self {
+        Self {
+            max_hole_edges: 0,
+            refine: true,
+            fair: true,
+        }
+    }
+}
+
+/// A hole in the mesh: one boundary loop.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hole {
+    /// A boundary half-edge of the loop; walk it with [`HalfEdgeMesh::boundary_loop`].
+    pub halfedge: HalfEdgeId,
+    /// The number of boundary edges around the hole.
+    pub edges: usize,
+    /// The length of the boundary loop.
+    pub perimeter: f32,
+}
+
+/// The elements added to close one hole.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HoleFilling {
+    pub faces: Vec<FaceId>,
+    pub vertices: Vec<VertexId>,
+}
+
+/// Why a hole could not be filled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HoleFillingError {
+    /// The half-edge is not on a boundary, or has been removed.
+    Topology(TopologyError),
+    /// The boundary loop passes through the same vertex more than once.
+    NonSimpleLoop,
+    /// Every triangulation of the loop would duplicate an edge the mesh already has.
+    NoTriangulation,
+    /// The fairing system could not be solved.
+    Solve(SolveError),
+}
+
+impl fmt::Display for HoleFillingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Topology(err) => write!(f, "cannot fill hole: {err}"),
+            Self::NonSimpleLoop => f.write_str("boundary loop visits a vertex more than once"),
+            Self::NoTriangulation => {
+                f.write_str("no triangulation of the hole avoids existing edges")
+            }
+            Self::Solve(err) => write!(f, "fairing failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for HoleFillingError {}
+
+impl From<TopologyError> for HoleFillingError {
+    fn from(err: TopologyError) -> Self {
+        Self::Topology(err)
+    }
+}
+
+impl From<SolveError> for HoleFillingError {
+    fn from(err: SolveError) -> Self {
+        Self::Solve(err)
+    }
+}
+
+/// The cost of a (partial) triangulation: its largest dihedral angle, then its area.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Weight {
+    angle: f32,
+    area: f32,
+}
+
+impl Weight {
+    const ZERO: Self = Self {
+        angle: 0.0,
+        area: 0.0,
+    };
+    const INFINITE: Self = Self {
+        angle: f32::INFINITY,
+        area: f32::INFINITY,
+    };
+
+    fn combine(self, other: Self) -> Self {
+        Self {
+            angle: self.angle.max(other.angle),
+            area: self.area + other.area,
+        }
+    }
+
+    fn is_better_than(self, other: Self) -> bool {
+        if (self.angle - other.angle).abs() > 1e-4 {
+            self.angle < other.angle
+        } else {
+            self.area < other.area
+        }
+    }
+}
+
+fn triangle_normal(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    (b - a).cross(c - a).normalize_or_zero()
+}
+
+fn dihedral(n: Vec3, m: Vec3) -> f32 {
+    n.dot(m).clamp(-1.0, 1.0).acos()
+}
+
+impl HalfEdgeMesh {
+    /// Every hole in the mesh, largest first.
+    pub fn holes(&self) -> Vec<Hole> {
+        let mut holes: Vec<Hole> = self
+            .boundary_loops()
+            .into_iter()
+            .map(|h| Hole {
+                halfedge: h,
+                edges: self.boundary_loop(h).count(),
+                perimeter: self
+                    .boundary_loop(h)
+                    .map(|b| self.halfedge_vector(b).length())
+                    .sum(),
+            })
+            .collect();
+        holes.sort_by_key(|hole| std::cmp::Reverse(hole.edges));
+        holes
+    }
+
+    /// Fill every hole that [`HoleFillingConfig::max_hole_edges`] allows. Holes that cannot be filled are
+    /// skipped; only fairing failures are reported as errors.
+    pub fn fill_holes(
+        &mut self,
+        config: &HoleFillingConfig,
+    ) -> Result<Vec<HoleFilling>, HoleFillingError> {
+        let mut filled = Vec::new();
+        for hole in self.holes() {
+            if config.max_hole_edges > 0 && hole.edges > config.max_hole_edges {
+                continue;
+            }
+            match self.fill_hole(hole.halfedge, config) {
+                Ok(filling) => filled.push(filling),
+                Err(HoleFillingError::Solve(err)) => return Err(err.into()),
+                Err(_) => {}
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Fill the hole bounded by the boundary loop through `h`.
+    pub fn fill_hole(
+        &mut self,
+        h: HalfEdgeId,
+        config: &HoleFillingConfig,
+    ) -> Result<HoleFilling, HoleFillingError> {
+        if self.is_removed_halfedge(h) {
+            return Err(TopologyError::RemovedElement.into());
+        }
+        if !self.is_boundary_halfedge(h) {
+            return Err(TopologyError::NotBoundary.into());
+        }
+        let ring: Vec<HalfEdgeId> = self.boundary_loop(h).collect();
+        let corners: Vec<VertexId> = ring.iter().map(|&b| self.origin(b)).collect();
+        let mut sorted = corners.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != corners.len() {
+            return Err(HoleFillingError::NonSimpleLoop);
+        }
+
+        let choice = self.optimal_triangulation(&ring)?;
+        // The average length of the edges around each boundary vertex sets the density of the refined patch.
+        let density: Vec<f32> = corners
+            .iter()
+            .map(|&v| {
+                let (sum, count) = self.vertex_edges(v).fold((0.0, 0.0), |(sum, count), e| {
+                    (sum + self.edge_length(e), count + 1.0)
+                });
+                sum / count
+            })
+            .collect();
+
+        let face = self.fill_boundary_loop(h)?;
+        let mut filling = HoleFilling {
+            faces: vec![face],
+            vertices: Vec::new(),
+        };
+        self.apply_triangulation(
+            face,
+            &corners,
+            &choice,
+            0,
+            corners.len() - 1,
+            &mut filling.faces,
+        );
+
+        if config.refine {
+            let mut sigma: HashMap<VertexId, f32> = corners.iter().copied().zip(density).collect();
+            self.refine_patch(&mut filling, &mut sigma);
+            if config.fair && !filling.vertices.is_empty() {
+                self.fair_patch(&filling.vertices)?;
+            }
+        }
+        Ok(filling)
+    }
+
+    /// Liepa's dynamic program over the loop. `choice[i][k]` is the apex of the triangle on the chord from
+    /// corner `i` to corner `k` in the best triangulation of the corners `i..=k`.
+    fn optimal_triangulation(
+        &self,
+        ring: &[HalfEdgeId],
+    ) -> Result<Vec<Vec<usize>>, HoleFillingError> {
+        let n = ring.len();
+        let corner = |i: usize| self.origin(ring[i]);
+        let point = |i: usize| self.position(corner(i));
+        // The normal of the existing face across boundary edge `i -> i + 1`.
+        let outside: Vec<Vec3> = ring
+            .iter()
+            .map(|&b| {
+                self.face(self.twin(b))
+                    .map_or(Vec3::ZERO, |f| self.face_normal(f))
+            })
+            .collect();
+
+        let mut weight = vec![vec![Weight::INFINITE; n]; n];
+        let mut choice = vec![vec![usize::MAX; n]; n];
+        for i in 0..n - 1 {
+            weight[i][i + 1] = Weight::ZERO;
+        }
+        // The normal of the triangle on chord `i -> k` in the current best solution, or of the mesh face
+        // outside the loop edge when `k = i + 1`.
+        let normal_across = |choice: &Vec<Vec<usize>>, i: usize, k: usize| {
+            if k == i + 1 {
+                outside[i]
+            } else {
+                triangle_normal(point(i), point(choice[i][k]), point(k))
+            }
+        };
+
+        for gap in 2..n {
+            for i in 0..n - gap {
+                let k = i + gap;
+                // A chord that is already an edge elsewhere in the mesh would become a double edge.
+                let chord_ok =
+                    (i == 0 && k == n - 1) || self.find_edge(corner(i), corner(k)).is_none();
+                if !chord_ok {
+                    continue;
+                }
+                for m in i + 1..k {
+                    let inner = weight[i][m].combine(weight[m][k]);
+                    if inner.angle.is_infinite() {
+                        continue;
+                    }
+                    let normal = triangle_normal(point(i), point(m), point(k));
+                    let mut angle = dihedral(normal, normal_across(&choice, i, m))
+                        .max(dihedral(normal, normal_across(&choice, m, k)));
+                    if i == 0 && k == n - 1 {
+                        angle = angle.max(dihedral(normal, outside[n - 1]));
+                    }
+                    let area = 0.5 * (point(m) - point(i)).cross(point(k) - point(i)).length();
+                    let candidate = inner.combine(Weight { angle, area });
+                    if candidate.is_better_than(weight[i][k]) {
+                        weight[i][k] = candidate;
+                        choice[i][k] = m;
+                    }
+                }
+            }
+        }
+        if weight[0][n - 1].angle.is_infinite() {
+            return Err(HoleFillingError::NoTriangulation);
+        }
+        Ok(choice)
+    }
+
+    /// Split `face`, whose corners are `corners[i..=k]` in order, into the triangles chosen by the dynamic
+    /// program, collecting the new faces.
+    fn apply_triangulation(
+        &mut self,
+        face: FaceId,
+        corners: &[VertexId],
+        choice: &[Vec<usize>],
+        i: usize,
+        k: usize,
+        faces: &mut Vec<FaceId>,
+    ) {
+        if k - i < 2 {
+            return;
+        }
+        let m = choice[i][k];
+        // `face` keeps the corners from `i` round to `m`; the rest, `m..=k` and `i`, move to a new face.
+        let mut rest = face;
+        if m > i + 1 {
+            let h = self
+                .split_face(face, corners[i], corners[m])
+                .expect("chords were checked");
+            rest = self.face(h).expect("the new half-edge has a face");
+            faces.push(rest);
+            self.apply_triangulation(face, corners, choice, i, m, faces);
+        }
+        if k > m + 1 {
+            let h = self
+                .split_face(rest, corners[m], corners[k])
+                .expect("chords were checked");
+            faces.push(self.face(h).expect("the new half-edge has a face"));
+            self.apply_triangulation(rest, corners, choice, m, k, faces);
+        }
+    }
+
+    /// Split patch triangles whose centroid is far from all their corners, relative to the local density, and
+    /// keep the patch Delaunay, until nothing more needs splitting.
+    fn refine_patch(&mut self, filling: &mut HoleFilling, sigma: &mut HashMap<VertexId, f32>) {
+        let alpha = std::f32::consts::SQRT_2;
+        let mut in_patch = vec![false; self.face_count()];
+        for f in &filling.faces {
+            in_patch[f.index()] = true;
+        }
+        for _ in 0..32 {
+            let mut split_any = false;
+            for f in filling.faces.clone() {
+                let corners: Vec<VertexId> = self.face_vertices(f).collect();
+                let centroid = self.face_centroid(f);
+                let density = corners.iter().map(|v| sigma[v]).sum::<f32>() / 3.0;
+                let large = corners.iter().all(|v| {
+                    let d = alpha * centroid.distance(self.position(*v));
+                    d > density && d > sigma[v]
+                });
+                if !large {
+                    continue;
+                }
+                let v = self.insert_vertex(f, centroid).expect("patch faces exist");
+                sigma.insert(v, density);
+                filling.vertices.push(v);
+                for g in self.vertex_faces(v).collect::<Vec<_>>() {
+                    if g.index() >= in_patch.len() {
+                        in_patch.resize(g.index() + 1, false);
+                    }
+                    if !in_patch[g.index()] {
+                        in_patch[g.index()] = true;
+                        filling.faces.push(g);
+                    }
+                }
+                split_any = true;
+            }
+            self.relax_patch(&filling.faces, &in_patch);
+            if !split_any {
+                break;
+            }
+        }
+    }
+
+    /// Flip edges inside the patch until every one is locally Delaunay.
+    fn relax_patch(&mut self, faces: &[FaceId], in_patch: &[bool]) {
+        let inside = |f: Option<FaceId>| f.is_some_and(|f| in_patch[f.index()]);
+        for _ in 0..64 {
+            let mut flipped = false;
+            let edges: Vec<_> = faces.iter().flat_map(|&f| self.face_edges(f)).collect();
+            for e in edges {
+                let h = self.edge_halfedge(e);
+                let t = self.twin(h);
+                if !inside(self.face(h)) || !inside(self.face(t)) {
+                    continue;
+                }
+                let [a, b, c, d] = [h, t, self.prev(h), self.prev(t)]
+                    .map(|h| self.position(self.origin(h)).as_dvec3());
+                if delaunay_edge(a, b, c, d) < 0.0 && self.flip_edge(e).is_ok() {
+                    flipped = true;
+                }
+            }
+            if !flipped {
+                break;
+            }
+        }
+    }
+
+    /// Solve the umbrella bi-Laplace equation `Δ²x = 0` for the positions of `free` vertices, holding every
+    /// other vertex in place.
+    fn fair_patch(&mut self, free: &[VertexId]) -> Result<(), SolveError> {
+        let index: HashMap<VertexId, usize> =
+            free.iter().enumerate().map(|(i, &v)| (v, i)).collect();
+        // Rows of the Laplacian that touch a free vertex: the free vertices and their neighbours.
+        let mut rows: Vec<VertexId> = free.to_vec();
+        for &v in free {
+            rows.extend(self.vertex_vertices(v).filter(|w| !index.contains_key(w)));
+        }
+        rows.sort_unstable();
+        rows.dedup();
+
+        let n = free.len();
+        let mut matrix = TripletMatrix::new(n, n);
+        let mut rhs = vec![[0.0f64; 3]; n];
+        for &k in &rows {
+            // Row k of the umbrella Laplacian: `deg(k) x_k - Σ x_j`.
+            let mut row: Vec<(VertexId, f64)> = vec![(k, self.vertex_valence(k) as f64)];
+            row.extend(self.vertex_vertices(k).map(|j| (j, -1.0)));
+            let weight = 1.0 / self.vertex_valence(k) as f64;
+            let fixed = row.iter().filter(|(j, _)| !index.contains_key(j)).fold(
+                [0.0; 3],
+                |acc, &(j, c)| {
+                    let p = self.position(j).as_dvec3().to_array();
+                    [0, 1, 2].map(|d| acc[d] + c * p[d])
+                },
+            );
+            for &(u, cu) in &row {
+                let Some(&iu) = index.get(&u) else {
+                    continue;
+                };
+                for &(v, cv) in &row {
+                    if let Some(&iv) = index.get(&v) {
+                        matrix.add(iu, iv, weight * cu * cv);
+                    }
+                }
+                for d in 0..3 {
+                    rhs[iu][d] -= weight * cu * fixed[d];
+                }
+            }
+        }
+        let factor = Cholesky::new(&matrix.to_csr())?;
+        let solution: Vec<Vec<f64>> = (0..3)
+            .map(|d| factor.solve(&rhs.iter().map(|r| r[d]).collect::<Vec<_>>()))
+            .collect::<Result<_, _>>()?;
+        for (i, &v) in free.iter().enumerate() {
+            let p = Vec3::new(
+                solution[0][i] as f32,
+                solution[1][i] as f32,
+                solution[2][i] as f32,
+            );
+            self.set_position(v, p);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subdivision::SubdivisionConfig;
+
+    /// A subdivided octahedron projected onto the unit sphere.
+    fn sphere(levels: u32) -> HalfEdgeMesh {
+        let positions = vec![Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+        let triangles = [
+            0, 2, 4, 2, 1, 4, 1, 3, 4, 3, 0, 4, 2, 0, 5, 1, 2, 5, 3, 1, 5, 0, 3, 5,
+        ];
+        let config = SubdivisionConfig {
+            levels,
+            ..Default::default()
+        };
+        let mut mesh = HalfEdgeMesh::from_triangles(positions, &triangles)
+            .unwrap()
+            .loop_subdivision(&config)
+            .unwrap();
+        for v in mesh.vertices().collect::<Vec<_>>() {
+            mesh.set_position(v, mesh.position(v).normalize());
+        }
+        mesh
+    }
+
+    /// `mesh` without the faces `cut` selects. Vertices left without faces stay, isolated.
+    fn cut(mesh: &HalfEdgeMesh, cut: impl Fn(FaceId) -> bool) -> HalfEdgeMesh {
+        let triangles: Vec<u32> = mesh
+            .faces()
+            .filter(|&f| !cut(f))
+            .flat_map(|f| mesh.face_vertices(f).map(|v| v.index() as u32))
+            .collect();
+        HalfEdgeMesh::from_triangles(mesh.positions().to_vec(), &triangles).unwrap()
+    }
+
+    /// A sphere with three small holes: one triangle at the top, two triangles sharing an edge at the bottom,
+    /// and three in a row on the equator.
+    fn holed_sphere() -> HalfEdgeMesh {
+        let sphere = sphere(2);
+        let nearest = |p: Vec3| {
+            sphere
+                .faces()
+                .min_by(|&f, &g| {
+                    let distance = |f| sphere.face_centroid(f).distance(p);
+                    distance(f).total_cmp(&distance(g))
+                })
+                .unwrap()
+        };
+        let across = |f: FaceId, skip: &[FaceId]| {
+            sphere
+                .face_halfedges(f)
+                .filter_map(|h| sphere.face(sphere.twin(h)))
+                .find(|g| !skip.contains(g))
+                .unwrap()
+        };
+        let top = nearest(Vec3::Z);
+        let bottom = nearest(-Vec3::Z);
+        let middle = nearest(Vec3::X);
+        let before = across(middle, &[]);
+        let removed = [
+            top,
+            bottom,
+            across(bottom, &[]),
+            middle,
+            before,
+            across(middle, &[before]),
+        ];
+        cut(&sphere, |f| removed.contains(&f))
+    }
+
+    fn check_closed(mesh: &HalfEdgeMesh) {
+        assert!(mesh.validate().is_ok(), "{:?}", mesh.validate());
+        assert!(mesh.boundary_loops().is_empty());
+        let statistics = mesh.statistics(1);
+        assert_eq!(statistics.euler_characteristic, 2);
+        assert_eq!(statistics.genus, 0);
+        assert_eq!(statistics.boundary_loops, 0);
+        // The patches keep the sphere's outward orientation.
+        assert!(mesh
+            .faces()
+            .all(|f| mesh.face_normal(f).dot(mesh.face_centroid(f)) > 0.0));
+    }
+
+    #[test]
+    fn small_holes_are_closed_with_the_loop_alone() {
+        let mut mesh = holed_sphere();
+        let mut edges: Vec<usize> = mesh.holes().iter().map(|hole| hole.edges).collect();
+        edges.sort_unstable();
+        assert_eq!(edges, [3, 4, 5]);
+        let statistics = mesh.statistics(1);
+        assert_eq!(statistics.euler_characteristic, 2 - 3);
+        assert_eq!(statistics.boundary_loops, 3);
+
+        let config = HoleFillingConfig {
+            refine: false,
+            ..Default::default()
+        };
+        let fillings = mesh.fill_holes(&config).unwrap();
+        let mut faces: Vec<usize> = fillings.iter().map(|filling| filling.faces.len()).collect();
+        faces.sort_unstable();
+        assert_eq!(faces, [1, 2, 3]);
+        assert!(fillings.iter().all(|filling| filling.vertices.is_empty()));
+        check_closed(&mesh);
+    }
+
+    #[test]
+    fn a_cap_is_refined_and_faired() {
+        let sphere = sphere(3);
+        let mut mesh = cut(&sphere, |f| sphere.face_centroid(f).z > 0.7);
+        let rim = mesh
+            .holes()
+            .first()
+            .map(|hole| mesh.boundary_loop(hole.halfedge).count())
+            .unwrap();
+        let fillings = mesh.fill_holes(&HoleFillingConfig::default()).unwrap();
+        assert_eq!(fillings.len(), 1);
+        let filling = &fillings[0];
+        assert!(!filling.vertices.is_empty());
+        assert!(filling.faces.len() > rim - 2);
+        // The vertices the cut left behind do not count towards the Euler characteristic.
+        check_closed(&mesh);
+        // Fairing bulges the patch out of the rim's plane, towards where the cap was.
+        let top = filling
+            .vertices
+            .iter()
+            .map(|&v| mesh.position(v).z)
+            .fold(f32::MIN, f32::max);
+        let rim_z = mesh
+            .vertices()
+            .filter(|&v| !mesh.is_isolated_vertex(v) && !filling.vertices.contains(&v))
+            .map(|v| mesh.position(v).z)
+            .fold(f32::MIN, f32::max);
+        assert!(top > rim_z, "{top} <= {rim_z}");
+    }
+
+    #[test]
+    fn holes_beyond_the_limit_are_left_open() {
+        let mut mesh = holed_sphere();
+        let config = HoleFillingConfig {
+            max_hole_edges: 4,
+            ..Default::default()
+        };
+        assert_eq!(mesh.fill_holes(&config).unwrap().len(), 2);
+        assert!(mesh.validate().is_ok());
+        assert_eq!(mesh.holes().len(), 1);
+        assert_eq!(mesh.holes()[0].edges, 5);
+        assert_eq!(mesh.statistics(1).euler_characteristic, 1);
+
+        let interior = mesh
+            .halfedges()
+            .find(|&h| !mesh.is_boundary_halfedge(h))
+            .unwrap();
+        assert_eq!(
+            mesh.fill_hole(interior, &config).unwrap_err(),
+            HoleFillingError::Topology(TopologyError::NotBoundary)
+        );
+    }
+}
@@ -0,0 +1,619 @@
+//! Intrinsic triangulations, after Sharp, Soliman and Crane, "Navigating Intrinsic Triangulations".
+//!
+//! An intrinsic triangulation keeps the vertices of a triangle mesh but describes its triangles by their edge
+//! lengths alone, so its edges are free to run across the faces of the mesh as straight geodesics instead of
+//! along its edges. Flipping an edge changes the triangulation without changing the surface. Flipping to the
+//! intrinsic Delaunay triangulation (Bobenko and Springborn) makes every interior cotangent weight
+//! non-negative however badly shaped the input triangles are, which gives Laplacians with a maximum principle
+//! and well conditioned systems on scanned meshes while every vertex stays exactly where it is.
+//!
+//! Every half-edge also stores a signpost: the direction it leaves its origin in, as an angle measured
+//! counterclockwise around the vertex from one of the vertex's edges in the input mesh. Together with the
+//! edge lengths this is enough to trace intrinsic edges and points back onto the faces of the input mesh.
+
+use core::fmt;
+
+use glam::{DVec2, DVec3, Vec3};
+
+use crate::{
+    mesh::{EdgeId, FaceId, HalfEdgeId, HalfEdgeMesh, TopologyError, VertexId},
+    sparse::{CsrMatrix, TripletMatrix},
+};
+
+/// Cotangent weights above minus this count as Delaunay, so that round-off cannot make flips cycle.
+const DELAUNAY_TOLERANCE: f64 = 1e-12;
+
+/// Why an intrinsic triangulation could not be built.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntrinsicError {
+    /// Intrinsic triangulations are only built for triangle meshes.
+    NotTriangleMesh,
+}
+
+impl fmt::Display for IntrinsicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTriangleMesh => {
+                f.write_str("intrinsic triangulations require a triangle mesh")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntrinsicError {}
+
+/// A point on the surface of the input mesh of an [`IntrinsicTriangulation`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SurfacePoint {
+    Vertex(VertexId),
+    /// A fraction `t` of the way from the origin of `halfedge` to its destination.
+    Edge {
+        halfedge: HalfEdgeId,
+        t: f64,
+    },
+    /// Inside `face`, with barycentric coordinates for its corners in [`HalfEdgeMesh::face_vertices`] order.
+    Face {
+        face: FaceId,
+        barycentric: [f64; 3],
+    },
+}
+
+impl SurfacePoint {
+    /// Where the point is in space, given the input mesh it lies on.
+    pub fn position(&self, mesh: &HalfEdgeMesh) -> Vec3 {
+        match *self {
+            Self::Vertex(v) => mesh.position(v),
+            Self::Edge { halfedge, t } => {
+                let a = mesh.position(mesh.origin(halfedge)).as_dvec3();
+                let b = mesh.position(mesh.dest(halfedge)).as_dvec3();
+                a.lerp(b, t).as_vec3()
+            }
+            Self::Face { face, barycentric } => mesh
+                .face_positions(face)
+                .zip(barycentric)
+                .map(|(p, w)| w * p.as_dvec3())
+                .sum::<DVec3>()
+                .as_vec3(),
+        }
+    }
+}
+
+/// An intrinsic triangulation of a triangle mesh.
+///
+/// It starts out with the same triangles as the mesh; [`Self::flip_to_delaunay`] then changes them. Vertex,
+/// edge and face ids are shared with the input mesh, but after flipping an edge id names a different pair of
+/// vertices. Removed elements of the input stay removed.
+#[derive(Clone, Debug)]
+pub struct IntrinsicTriangulation<'a> {
+    input: &'a HalfEdgeMesh,
+    /// The intrinsic connectivity. Its positions are those of the input and are not used.
+    mesh: HalfEdgeMesh,
+    /// The length of every edge, indexed by edge.
+    lengths: Vec<f64>,
+    /// The direction of every half-edge at its origin, indexed by half-edge.
+    signposts: Vec<f64>,
+    /// The signposts of the input mesh's half-edges, which tracing starts from.
+    input_signposts: Vec<f64>,
+    /// The sum of the corner angles around every vertex, indexed by vertex.
+    angle_sums: Vec<f64>,
+}
+
+impl<'a> IntrinsicTriangulation<'a> {
+    /// The intrinsic triangulation with the same triangles as `input`.
+    pub fn new(input: &'a HalfEdgeMesh) -> Result<Self, IntrinsicError> {
+        if input.faces().any(|f| input.face_degree(f) != 3) {
+            return Err(IntrinsicError::NotTriangleMesh);
+        }
+        let lengths = (0..input.edge_count())
+            .map(EdgeId::new)
+            .map(|e| {
+                if input.is_removed_edge(e) {
+                    0.0
+                } else {
+                    let [a, b] = input.edge_vertices(e).map(|v| input.position(v).as_dvec3());
+                    a.distance(b)
+                }
+            })
+            .collect();
+        let mut triangulation = Self {
+            input,
+            mesh: input.clone(),
+            lengths,
+            signposts: vec![0.0; input.halfedge_count()],
+            input_signposts: Vec::new(),
+            angle_sums: vec![0.0; input.vertex_count()],
+        };
+        for v in input.vertices() {
+            triangulation.place_signposts(v);
+        }
+        triangulation.input_signposts = triangulation.signposts.clone();
+        Ok(triangulation)
+    }
+
+    /// Give the half-edges around `v` their directions by adding up corner angles counterclockwise, starting
+    /// just after the boundary at boundary vertices.
+    fn place_signposts(&mut self, v: VertexId) {
+        let mesh = &self.mesh;
+        let Some(first) = mesh
+            .vertex_outgoing_halfedges(v)
+            .find(|&h| mesh.is_boundary_halfedge(mesh.twin(h)))
+            .or(mesh.vertex_halfedge(v))
+        else {
+            return;
+        };
+        let mut angle = 0.0;
+        let mut directions = Vec::new();
+        let mut h = first;
+        loop {
+            directions.push((h, angle));
+            if mesh.is_boundary_halfedge(h) {
+                break;
+            }
+            angle += self.corner_angle(h);
+            h = mesh.twin(mesh.prev(h));
+            if h == first {
+                break;
+            }
+        }
+        for (h, angle) in directions {
+            self.signposts[h.index()] = angle;
+        }
+        self.angle_sums[v.index()] = angle;
+    }
+
+    /// The mesh the triangulation lies on.
+    pub fn input(&self) -> &'a HalfEdgeMesh {
+        self.input
+    }
+
+    /// The connectivity of the intrinsic triangles. Its positions are those of the input, so anything
+    /// computed from them rather than from [`Self::edge_length`] is meaningless once edges have been flipped.
+    pub fn mesh(&self) -> &HalfEdgeMesh {
+        &self.mesh
+    }
+
+    /// The length of intrinsic edge `e`, measured along the surface.
+    pub fn edge_length(&self, e: EdgeId) -> f64 {
+        self.lengths[e.index()]
+    }
+
+    /// The direction `h` leaves its origin in, as an angle counterclockwise from the first edge around the
+    /// vertex in the input mesh. Angles around a vertex add up to its angle sum rather than a full turn.
+    pub fn signpost(&self, h: HalfEdgeId) -> f64 {
+        self.signposts[h.index()]
+    }
+
+    /// The lengths of `h` and the next two half-edges around its triangle.
+    fn triangle_lengths(&self, h: HalfEdgeId) -> [f64; 3] {
+        let mesh = &self.mesh;
+        [h, mesh.next(h), mesh.prev(h)].map(|g| self.lengths[mesh.edge(g).index()])
+    }
+
+    /// The angle at the origin of `h` inside its triangle. Zero for boundary half-edges.
+    pub fn corner_angle(&self, h: HalfEdgeId) -> f64 {
+        if self.mesh.is_boundary_halfedge(h) {
+            return 0.0;
+        }
+        let [a, b, c] = self.triangle_lengths(h);
+        angle_between(a, c, b)
+    }
+
+    /// The area of intrinsic triangle `f`.
+    pub fn face_area(&self, f: FaceId) -> f64 {
+        triangle_area(self.triangle_lengths(self.mesh.face_halfedge(f)))
+    }
+
+    /// The corners of triangle `f` laid out in the plane, in [`HalfEdgeMesh::face_vertices`] order: the first
+    /// at the origin, the second on the positive x axis and the third above it.
+    pub fn face_layout(&self, f: FaceId) -> [DVec2; 3] {
+        let [a, b, c] = self.triangle_lengths(self.mesh.face_halfedge(f));
+        [DVec2::ZERO, DVec2::new(a, 0.0), apex(a, c, b)]
+    }
+
+    /// Half the sum of the cotangents of the angles opposite edge `e`, as in
+    /// [`HalfEdgeMesh::cotan_weight`] but from the intrinsic triangles.
+    pub fn cotan_weight(&self, e: EdgeId) -> f64 {
+        let h = self.mesh.edge_halfedge(e);
+        [h, self.mesh.twin(h)]
+            .into_iter()
+            .filter(|&h| !self.mesh.is_boundary_halfedge(h))
+            .map(|h| {
+                let [a, b, c] = self.triangle_lengths(h);
+                0.5 * (b * b + c * c - a * a) / (4.0 * triangle_area([a, b, c])).max(1e-300)
+            })
+            .sum()
+    }
+
+    /// Whether the angles opposite `e` add up to at most a half turn. Boundary edges always are.
+    pub fn is_delaunay(&self, e: EdgeId) -> bool {
+        self.mesh.is_boundary_edge(e) || self.cotan_weight(e) >= -DELAUNAY_TOLERANCE
+    }
+
+    /// Flip edges until every edge is Delaunay and return the number of flips.
+    ///
+    /// Flips that would join two vertices already joined by an edge are skipped, since the connectivity
+    /// cannot hold both, so a few edges may stay slightly short of Delaunay around vertices of very low
+    /// valence.
+    pub fn flip_to_delaunay(&mut self) -> usize {
+        let mut queued = vec![false; self.mesh.edge_count()];
+        let mut stack: Vec<EdgeId> = self.mesh.edges().collect();
+        for e in &stack {
+            queued[e.index()] = true;
+        }
+        let mut flips = 0;
+        while let Some(e) = stack.pop() {
+            queued[e.index()] = false;
+            if self.is_delaunay(e) || self.flip_edge(e).is_err() {
+                continue;
+            }
+            flips += 1;
+            let h = self.mesh.edge_halfedge(e);
+            let t = self.mesh.twin(h);
+            for g in [
+                self.mesh.next(h),
+                self.mesh.prev(h),
+                self.mesh.next(t),
+                self.mesh.prev(t),
+            ] {
+                let e = self.mesh.edge(g);
+                if !queued[e.index()] {
+                    queued[e.index()] = true;
+                    stack.push(e);
+                }
+            }
+        }
+        flips
+    }
+
+    /// Flip `e` to join the opposite corners of its two triangles, which must form a convex quadrilateral
+    /// when laid out in the plane, as they do whenever `e` is not Delaunay.
+    fn flip_edge(&mut self, e: EdgeId) -> Result<(), TopologyError> {
+        self.mesh.is_flip_ok(e)?;
+        // Lay out a -> b along the x axis with c above it and d below it.
+        let h = self.mesh.edge_halfedge(e);
+        let t = self.mesh.twin(h);
+        let [ab, bc, ca] = self.triangle_lengths(h);
+        let [_, ad, db] = self.triangle_lengths(t);
+        let c = apex(ab, ca, bc);
+        let d = apex(ab, ad, db) * DVec2::new(1.0, -1.0);
+        self.mesh.flip_edge(e)?;
+        self.lengths[e.index()] = c.distance(d);
+
+        // Now h runs d -> c and t c -> d. Each leaves its origin counterclockwise of the half-edge after its
+        // twin, by that half-edge's corner angle.
+        for (new, next) in [(h, self.mesh.next(t)), (t, self.mesh.next(h))] {
+            let angle = self.signposts[next.index()] + self.corner_angle(next);
+            self.signposts[new.index()] = self.wrap(self.mesh.origin(new), angle);
+        }
+        Ok(())
+    }
+
+    /// Bring a direction at `v` that went past its angle sum back round. Directions at boundary vertices end
+    /// at the boundary and never go round.
+    fn wrap(&self, v: VertexId, angle: f64) -> f64 {
+        let sum = self.angle_sums[v.index()];
+        if angle >= sum && !self.mesh.is_boundary_vertex(v) {
+            angle - sum
+        } else {
+            angle
+        }
+    }
+
+    /// The intrinsic cotangent Laplacian, laid out like [`HalfEdgeMesh::cotan_laplacian`]. On the Delaunay
+    /// triangulation every off-diagonal entry is at most zero, except along boundary edges opposite an obtuse
+    /// angle, which cannot be flipped.
+    pub fn cotan_laplacian(&self) -> CsrMatrix {
+        let n = self.mesh.vertex_count();
+        let mut triplets = TripletMatrix::new(n, n);
+        for e in self.mesh.edges() {
+            let [i, j] = self.mesh.edge_vertices(e).map(VertexId::index);
+            let w = self.cotan_weight(e);
+            triplets.add(i, j, -w);
+            triplets.add(j, i, -w);
+            triplets.add(i, i, w);
+            triplets.add(j, j, w);
+        }
+        triplets.to_csr()
+    }
+
+    /// The diagonal mass matrix with a third of the area of every adjacent intrinsic triangle per vertex.
+    pub fn mass_matrix(&self) -> CsrMatrix {
+        let mut areas = vec![0.0; self.mesh.vertex_count()];
+        for f in self.mesh.faces() {
+            let share = self.face_area(f) / 3.0;
+            for v in self.mesh.face_vertices(f) {
+                areas[v.index()] += share;
+            }
+        }
+        CsrMatrix::from_diagonal(&areas)
+    }
+
+    /// The path of intrinsic half-edge `h` over the input mesh: its origin, every input edge it crosses in
+    /// order, and its destination.
+    pub fn trace_halfedge(&self, h: HalfEdgeId) -> Vec<SurfacePoint> {
+        let origin = self.mesh.origin(h);
+        let length = self.lengths[self.mesh.edge(h).index()];
+        let (crossings, _) = self.trace(origin, self.signposts[h.index()], length);
+        let mut path = Vec::with_capacity(crossings.len() + 2);
+        path.push(SurfacePoint::Vertex(origin));
+        path.extend(crossings);
+        path.push(SurfacePoint::Vertex(self.mesh.dest(h)));
+        path
+    }
+
+    /// The point of the input mesh at `barycentric` in intrinsic triangle `f`, with coordinates summing to
+    /// one for its corners in [`HalfEdgeMesh::face_vertices`] order.
+    pub fn locate(&self, f: FaceId, barycentric: [f64; 3]) -> SurfacePoint {
+        let h = self.mesh.face_halfedge(f);
+        let v = self.mesh.origin(h);
+        let point: DVec2 = self
+            .face_layout(f)
+            .into_iter()
+            .zip(barycentric)
+            .map(|(p, w)| w * p)
+            .sum();
+        let length = point.length();
+        if length == 0.0 {
+            return SurfacePoint::Vertex(v);
+        }
+        let angle = self.wrap(v, self.signposts[h.index()] + point.y.atan2(point.x));
+        self.trace(v, angle, length).1
+    }
+
+    /// Walk straight over the input mesh from `v` in direction `angle` for `length`, unfolding each face into
+    /// the plane of the last. Returns the input edges crossed and the point reached, which is on the boundary
+    /// if the walk ran into it first.
+    fn trace(&self, v: VertexId, angle: f64, length: f64) -> (Vec<SurfacePoint>, SurfacePoint) {
+        let input = self.input;
+        let position = |v: VertexId| input.position(v).as_dvec3();
+        let normal = |f: FaceId| {
+            let h = input.face_halfedge(f);
+            let [a, b, c] =
+                [input.origin(h), input.dest(h), input.origin(input.prev(h))].map(position);
+            (b - a).cross(c - a).normalize_or_zero()
+        };
+
+        // Start into the corner at `v` whose angular range holds the direction.
+        let corners = input
+            .vertex_outgoing_halfedges(v)
+            .filter(|&h| !input.is_boundary_halfedge(h));
+        let direction_of = |h: &HalfEdgeId| self.input_signposts[h.index()];
+        let Some(start) = corners
+            .clone()
+            .filter(|h| direction_of(h) <= angle)
+            .max_by(|a, b| direction_of(a).total_cmp(&direction_of(b)))
+            .or_else(|| corners.min_by(|a, b| direction_of(a).total_cmp(&direction_of(b))))
+        else {
+            return (Vec::new(), SurfacePoint::Vertex(v));
+        };
+        let mut face = input.face(start).expect("corners have faces");
+        let along = (position(input.dest(start)) - position(v)).normalize_or_zero();
+        let turn = angle - direction_of(&start);
+        let mut direction = turn.cos() * along + turn.sin() * normal(face).cross(along);
+        let mut point = position(v);
+        let mut remaining = length;
+        let mut entry = None;
+        let tolerance = 1e-9 * length;
+
+        let mut crossings = Vec::new();
+        for _ in 0..4 * input.face_count() + 16 {
+            let n = normal(face);
+            // Leave through the first edge the ray meets among those it points out of. From the start vertex
+            // that can only be the opposite edge.
+            let exits: Vec<HalfEdgeId> = match entry {
+                None => vec![input.next(start)],
+                Some(entry) => input.face_halfedges(face).filter(|&g| g != entry).collect(),
+            };
+            let mut best: Option<(f64, HalfEdgeId, f64)> = None;
+            for g in exits {
+                let (a, b) = (position(input.origin(g)), position(input.dest(g)));
+                let edge = b - a;
+                let denominator = direction.cross(edge).dot(n);
+                if denominator <= 0.0 {
+                    continue;
+                }
+                let s = (a - point).cross(edge).dot(n) / denominator;
+                let t = (a - point).cross(direction).dot(n) / denominator;
+                if best.is_none_or(|(best_s, ..)| s < best_s) {
+                    best = Some((s, g, t));
+                }
+            }
+            let Some((s, exit, t)) = best else {
+                break;
+            };
+            if s >= remaining - tolerance {
+                point += remaining * direction;
+                break;
+            }
+            let crossing = SurfacePoint::Edge {
+                halfedge: exit,
+                t: t.clamp(0.0, 1.0),
+            };
+            let twin = input.twin(exit);
+            let Some(next) = input.face(twin) else {
+                return (crossings, crossing);
+            };
+            crossings.push(crossing);
+
+            // Keep the component along the shared edge and carry the one across it over to the next face.
+            let (a, b) = (position(input.origin(exit)), position(input.dest(exit)));
+            let edge = (b - a).normalize_or_zero();
+            let across = direction.dot(n.cross(edge));
+            direction = direction.dot(edge) * edge + across * normal(next).cross(edge);
+            point = a.lerp(b, t.clamp(0.0, 1.0));
+            remaining -= s;
+            face = next;
+            entry = Some(twin);
+        }
+        let barycentric = self.barycentric(face, point);
+        (crossings, SurfacePoint::Face { face, barycentric })
+    }
+
+    /// Barycentric coordinates of `p` in input face `f`, clamped into the triangle.
+    fn barycentric(&self, f: FaceId, p: DVec3) -> [f64; 3] {
+        let input = self.input;
+        let h = input.face_halfedge(f);
+        let [a, b, c] = [input.origin(h), input.dest(h), input.origin(input.prev(h))]
+            .map(|v| input.position(v).as_dvec3());
+        let n = (b - a).cross(c - a);
+        let weights = [(b, c), (c, a), (a, b)].map(|(q, r)| (q - p).cross(r - p).dot(n).max(0.0));
+        let total: f64 = weights.iter().sum();
+        if total > 0.0 {
+            weights.map(|w| w / total)
+        } else {
+            [1.0 / 3.0; 3]
+        }
+    }
+}
+
+/// The angle between sides `a` and `b` of a triangle whose third side is `c`.
+fn angle_between(a: f64, b: f64, c: f64) -> f64 {
+    let denominator = 2.0 * a * b;
+    if denominator <= 0.0 {
+        return 0.0;
+    }
+    ((a * a + b * b - c * c) / denominator)
+        .clamp(-1.0, 1.0)
+        .acos()
+}
+
+/// The area of a triangle from its side lengths, with Kahan's rearrangement of Heron's formula that stays
+/// accurate for needles.
+fn triangle_area(sides: [f64; 3]) -> f64 {
+    let mut sides = sides;
+    sides.sort_by(|a, b| b.total_cmp(a));
+    let [a, b, c] = sides;
+    0.25 * ((a + (b + c)) * (c - (a - b)) * (c + (a - b)) * (a + (b - c)))
+        .max(0.0)
+        .sqrt()
+}
+
+/// The third corner of a triangle over the base from the origin to `(base, 0)`, above the x axis, at
+/// distance `left` from the origin and `right` from the other end of the base.
+fn apex(base: f64, left: f64, right: f64) -> DVec2 {
+    let x = (base * base + left * left - right * right) / (2.0 * base).max(1e-300);
+    DVec2::new(x, (left * left - x * x).max(0.0).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An n × n grid over the unit square, sheared so that its diagonals are long and far from Delaunay,
+    /// then lifted by `height`.
+    fn sheared_grid(n: u32, height: impl Fn(f32, f32) -> f32) -> HalfEdgeMesh {
+        let positions: Vec<Vec3> = (0..=n)
+            .flat_map(|j| (0..=n).map(move |i| (i as f32 / n as f32, j as f32 / n as f32)))
+            .map(|(x, y)| Vec3::new(x + 1.5 * y, y, height(x, y)))
+            .collect();
+        let mut triangles = Vec::new();
+        for j in 0..n {
+            for i in 0..n {
+                let v = j * (n + 1) + i;
+                triangles.extend([v, v + 1, v + n + 2, v, v + n + 2, v + n + 1]);
+            }
+        }
+        HalfEdgeMesh::from_triangles(positions, &triangles).unwrap()
+    }
+
+    fn total_area(intrinsic: &IntrinsicTriangulation) -> f64 {
+        intrinsic
+            .mesh()
+            .faces()
+            .map(|f| intrinsic.face_area(f))
+            .sum()
+    }
+
+    #[test]
+    fn flipping_to_delaunay_keeps_the_area_and_clears_negative_weights() {
+        let mesh = sheared_grid(6, |x, y| 0.3 * (x * x - y * y));
+        let mut intrinsic = IntrinsicTriangulation::new(&mesh).unwrap();
+        let area = total_area(&intrinsic);
+        assert!(
+            (area
+                - mesh
+                    .faces()
+                    .map(|f| f64::from(mesh.face_area(f)))
+                    .sum::<f64>())
+            .abs()
+                < 1e-5
+        );
+        assert!(mesh.edges().any(|e| !intrinsic.is_delaunay(e)));
+
+        assert!(intrinsic.flip_to_delaunay() > 0);
+        assert!(intrinsic.mesh().validate().is_ok());
+        assert!((total_area(&intrinsic) - area).abs() < 1e-9);
+        // Boundary edges cannot be flipped, so only the interior ones are sure to have non-negative weights.
+        assert!(mesh.edges().all(|e| intrinsic.is_delaunay(e)));
+        let interior = mesh.edges().filter(|&e| !mesh.is_boundary_edge(e));
+        assert!(interior.clone().all(|e| intrinsic.cotan_weight(e) >= -1e-9));
+        // Flips move edges across the surface but leave the cone angle at every vertex alone.
+        for v in mesh.vertices() {
+            let before: f32 = mesh
+                .vertex_outgoing_halfedges(v)
+                .map(|h| mesh.corner_angle(h))
+                .sum();
+            let after: f64 = intrinsic
+                .mesh()
+                .vertex_outgoing_halfedges(v)
+                .map(|h| intrinsic.corner_angle(h))
+                .sum();
+            assert!((after - f64::from(before)).abs() < 1e-4, "vertex {v}");
+        }
+        let laplacian = intrinsic.cotan_laplacian();
+        for e in interior {
+            let [i, j] = intrinsic.mesh().edge_vertices(e).map(VertexId::index);
+            assert!(laplacian.get(i, j) <= 1e-9);
+        }
+    }
+
+    #[test]
+    fn traced_edges_are_as_long_as_their_intrinsic_length() {
+        let mesh = sheared_grid(6, |x, y| 0.3 * (x * x - y * y));
+        let mut intrinsic = IntrinsicTriangulation::new(&mesh).unwrap();
+        intrinsic.flip_to_delaunay();
+        let mut crossed = 0;
+        for e in intrinsic.mesh().edges() {
+            let h = intrinsic.mesh().edge_halfedge(e);
+            let path = intrinsic.trace_halfedge(h);
+            crossed += path.len() - 2;
+            let length: f64 = path
+                .windows(2)
+                .map(|w| f64::from(w[0].position(&mesh).distance(w[1].position(&mesh))))
+                .sum();
+            assert!((length - intrinsic.edge_length(e)).abs() < 1e-4, "edge {e}");
+        }
+        assert!(crossed > 0);
+    }
+
+    #[test]
+    fn located_points_match_the_flat_layout() {
+        let mesh = sheared_grid(4, |_, _| 0.0);
+        let mut intrinsic = IntrinsicTriangulation::new(&mesh).unwrap();
+        intrinsic.flip_to_delaunay();
+        let barycentric = [0.2, 0.3, 0.5];
+        for f in intrinsic.mesh().faces() {
+            let expected: Vec3 = intrinsic
+                .mesh()
+                .face_vertices(f)
+                .zip(barycentric)
+                .map(|(v, w)| w as f32 * mesh.position(v))
+                .sum();
+            let point = intrinsic.locate(f, barycentric);
+            assert!(point.position(&mesh).distance(expected) < 1e-5, "face {f}");
+        }
+    }
+
+    #[test]
+    fn polygons_are_refused() {
+        let mesh = HalfEdgeMesh::from_polygons(
+            vec![Vec3::ZERO, Vec3::X, Vec3::ONE, Vec3::Y],
+            [[0, 1, 2, 3]],
+        )
+        .unwrap();
+        assert_eq!(
+            IntrinsicTriangulation::new(&mesh).unwrap_err(),
+            IntrinsicError::NotTriangleMesh
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! glTF 2.0 import, from either a `.gltf` JSON document with embedded (`data:`) buffers or a binary `.glb`.
+//!
+//! Every triangle primitive becomes its own half-edge mesh. glTF splits vertices wherever a UV seam or hard
+//! edge runs, so positions are welded back together to recover the topology, and the original per-vertex
+//! texture coordinates and normals become per-corner data. Node transforms are not applied; meshes come out in
+//! their local space.
+
+use base64::Engine;
+use glam::{Vec2, Vec3};
+
+use super::{ImportError, MeshData};
+use crate::{mesh::HalfEdgeMesh, weld::weld_vertices};
+
+/// One primitive of a glTF mesh.
+#[derive(Clone, Debug)]
+pub struct GltfPrimitive {
+    /// Index of the mesh in the document's `meshes` array.
+    pub mesh_index: usize,
+    /// Name of the mesh, if it has one.
+    pub mesh_name: Option<String>,
+    /// Index of the primitive within its mesh.
+    pub primitive_index: usize,
+    /// Index of the primitive's material in the document's `materials` array.
+    pub material: Option<usize>,
+    pub data: MeshData,
+}
+
+/// Parse a glTF or GLB buffer into one mesh per triangle primitive. Point and line primitives are skipped.
+/// Buffers referring to external files are not supported, since there is no file system to load them from in
+/// the browser; convert such assets to GLB first.
+pub fn read(bytes: &[u8]) -> Result<Vec<GltfPrimitive>, ImportError> {
+    let document =
+        ::gltf::Gltf::from_slice(bytes).map_err(|err| ImportError::Malformed(err.to_string()))?;
+
+    let mut buffers = Vec::new();
+    for buffer in document.buffers() {
+        let data = match buffer.source() {
+            ::gltf::buffer::Source::Bin => document
+                .blob
+                .clone()
+                .ok_or_else(|| ImportError::Malformed("GLB has no binary chunk".to_string()))?,
+            ::gltf::buffer::Source::Uri(uri) => {
+                let encoded = uri
+                    .strip_prefix("data:")
+                    .and_then(|rest| rest.split_once(";base64,"))
+                    .map(|(_, encoded)| encoded)
+                    .ok_or_else(|| {
+                        ImportError::Malformed(format!("external buffer `{uri}` is not supported"))
+                    })?;
+                base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .map_err(|err| {
+                        ImportError::Malformed(format!("invalid base64 buffer: {err}"))
+                    })?
+            }
+        };
+        buffers.push(data);
+    }
+
+    let mut primitives = Vec::new();
+    for mesh in document.meshes() {
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+            let positions: Vec<Vec3> = positions.map(Vec3::from).collect();
+            let uvs: Option<Vec<Vec2>> = reader
+                .read_tex_coords(0)
+                .map(|uvs| uvs.into_f32().map(Vec2::from).collect());
+            let normals: Option<Vec<Vec3>> = reader
+                .read_normals()
+                .map(|normals| normals.map(Vec3::from).collect());
+            for (attribute, count) in [
+                ("TEXCOORD_0", uvs.as_ref().map(Vec::len)),
+                ("NORMAL", normals.as_ref().map(Vec::len)),
+            ] {
+                if let Some(count) = count.filter(|&count| count != positions.len()) {
+                    return Err(ImportError::Malformed(format!(
+                        "primitive {} of mesh {} has {count} {attribute} values for {} positions",
+                        primitive.index(),
+                        mesh.index(),
+                        positions.len()
+                    )));
+                }
+            }
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..positions.len() as u32).collect(),
+            };
+            if let Some(&index) = indices.iter().find(|&&i| i as usize >= positions.len()) {
+                return Err(ImportError::Malformed(format!(
+                    "primitive {} of mesh {} refers to vertex {index}, but only has {}",
+                    primitive.index(),
+                    mesh.index(),
+                    positions.len()
+                )));
+            }
+            let Some(triangles) = triangulate(primitive.mode(), &indices) else {
+                continue;
+            };
+
+            let (welded, remap) = weld_vertices(&positions, 0.0);
+            let mut polygons = Vec::with_capacity(triangles.len());
+            let mut corners = Vec::with_capacity(triangles.len() * 3);
+            for triangle in triangles {
+                let [a, b, c] = triangle.map(|i| remap[i as usize]);
+                if a != b && b != c && c != a {
+                    polygons.push([a, b, c]);
+                    corners.extend(triangle);
+                }
+            }
+            let half_edge_mesh = HalfEdgeMesh::from_polygons(welded, &polygons)?;
+
+            let mut data = MeshData::new(half_edge_mesh);
+            if let Some(uvs) = uvs {
+                let corner_uvs: Vec<Vec2> = corners.iter().map(|&i| uvs[i as usize]).collect();
+                let uvs = MeshData::corners_to_halfedges(&data.mesh, &corner_uvs);
+                data.set_uvs(&uvs).expect("a fresh mesh has no attributes");
+            }
+            if let Some(normals) = normals {
+                let corner_normals: Vec<Vec3> =
+                    corners.iter().map(|&i| normals[i as usize]).collect();
+                let normals = MeshData::corners_to_halfedges(&data.mesh, &corner_normals);
+                data.set_normals(&normals)
+                    .expect("a fresh mesh has no attributes");
+            }
+
+            primitives.push(GltfPrimitive {
+                mesh_index: mesh.index(),
+                mesh_name: mesh.name().map(str::to_string),
+                primitive_index: primitive.index(),
+                material: primitive.material().index(),
+                data,
+            });
+        }
+    }
+    Ok(primitives)
+}
+
+/// Expand strips and fans into a plain triangle list, keeping every triangle counter-clockwise. Returns `None`
+/// for point and line primitives.
+fn triangulate(mode: ::gltf::mesh::Mode, indices: &[u32]) -> Option<Vec<[u32; 3]>> {
+    use ::gltf::mesh::Mode;
+
+    Some(match mode {
+        Mode::Triangles => indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect(),
+        Mode::TriangleStrip => (0..indices.len().saturating_sub(2))
+            .map(|i| {
+                if i % 2 == 0 {
+                    [indices[i], indices[i + 1], indices[i + 2]]
+                } else {
+                    [indices[i + 1], indices[i], indices[i + 2]]
+                }
+            })
+            .collect(),
+        Mode::TriangleFan => (1..indices.len().saturating_sub(1))
+            .map(|i| [indices[0], indices[i], indices[i + 1]])
+            .collect(),
+        Mode::Points | Mode::Lines | Mode::LineLoop | Mode::LineStrip => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A GLB holding `json` and the binary chunk `bin`, both padded to four bytes.
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let mut bin = bin.to_vec();
+        bin.resize(bin.len().next_multiple_of(4), 0);
+        let mut bytes = Vec::new();
+        bytes.extend(b"glTF");
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+        bytes.extend((json.len() as u32).to_le_bytes());
+        bytes.extend(b"JSON");
+        bytes.extend(json);
+        bytes.extend((bin.len() as u32).to_le_bytes());
+        bytes.extend(b"BIN\0");
+        bytes.extend(bin);
+        bytes
+    }
+
+    /// The JSON of a one-primitive GLB whose binary chunk holds `positions` three-component floats, then
+    /// `normals` more, then `indices` `u16` indices if any. The primitive has `mode`.
+    fn document(positions: usize, normals: usize, indices: usize, mode: u32) -> String {
+        let normal_offset = 12 * positions;
+        let index_offset = normal_offset + 12 * normals;
+        let mut views = vec![format!(
+            r#"{{"buffer":0,"byteOffset":0,"byteLength":{normal_offset}}}"#
+        )];
+        let mut accessors = vec![format!(
+            r#"{{"bufferView":0,"componentType":5126,"count":{positions},"type":"VEC3",
+                "min":[-10,-10,-10],"max":[10,10,10]}}"#
+        )];
+        let mut attributes = r#""POSITION":0"#.to_string();
+        if normals > 0 {
+            views.push(format!(
+                r#"{{"buffer":0,"byteOffset":{normal_offset},"byteLength":{}}}"#,
+                12 * normals
+            ));
+            accessors.push(format!(
+                r#"{{"bufferView":1,"componentType":5126,"count":{normals},"type":"VEC3"}}"#
+            ));
+            attributes.push_str(r#","NORMAL":1"#);
+        }
+        let mut primitive = format!(r#""attributes":{{{attributes}}},"mode":{mode}"#);
+        if indices > 0 {
+            views.push(format!(
+                r#"{{"buffer":0,"byteOffset":{index_offset},"byteLength":{}}}"#,
+                2 * indices
+            ));
+            accessors.push(format!(
+                r#"{{"bufferView":{},"componentType":5123,"count":{indices},"type":"SCALAR"}}"#,
+                views.len() - 1
+            ));
+            primitive.push_str(&format!(r#","indices":{}"#, accessors.len() - 1));
+        }
+        let length = (index_offset + 2 * indices).next_multiple_of(4);
+        format!(
+            r#"{{"asset":{{"version":"2.0"}},"buffers":[{{"byteLength":{length}}}],
+                "bufferViews":[{}],"accessors":[{}],"meshes":[{{"primitives":[{{{primitive}}}]}}]}}"#,
+            views.join(","),
+            accessors.join(",")
+        )
+    }
+
+    /// A GLB with one primitive of `mode` holding `positions`, optional `normals` and optional `indices`.
+    fn primitive(positions: &[Vec3], normals: &[Vec3], indices: &[u16], mode: u32) -> Vec<u8> {
+        let json = document(positions.len(), normals.len(), indices.len(), mode);
+        glb(&json, &buffer(positions, normals, indices))
+    }
+
+    fn buffer(positions: &[Vec3], normals: &[Vec3], indices: &[u16]) -> Vec<u8> {
+        let floats = positions.iter().chain(normals).flat_map(|v| v.to_array());
+        floats
+            .flat_map(f32::to_le_bytes)
+            .chain(indices.iter().flat_map(|i| i.to_le_bytes()))
+            .collect()
+    }
+
+    /// The mesh of the only primitive in `bytes`, checked for validity.
+    fn only_mesh(bytes: &[u8]) -> MeshData {
+        let mut primitives = read(bytes).unwrap();
+        assert_eq!(primitives.len(), 1);
+        let data = primitives.pop().unwrap().data;
+        assert!(data.mesh.validate().is_ok());
+        data
+    }
+
+    /// A row of `n` points along x at `y`.
+    fn row(n: usize, y: f32) -> impl Iterator<Item = Vec3> {
+        (0..n).map(move |i| Vec3::new(i as f32, y, 0.0))
+    }
+
+    #[test]
+    fn indexed_triangles_are_welded_into_one_surface() {
+        // A quad whose two triangles have their own copies of the diagonal, with differing normals.
+        let positions = [
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::ONE.with_z(0.0),
+            Vec3::ZERO,
+            Vec3::ONE.with_z(0.0),
+            Vec3::Y,
+        ];
+        let normals = [Vec3::Z, Vec3::Z, Vec3::Z, Vec3::X, Vec3::X, Vec3::X];
+        let data = only_mesh(&primitive(&positions, &normals, &[0, 1, 2, 3, 4, 5], 4));
+        let mesh = &data.mesh;
+        assert_eq!((mesh.vertex_count(), mesh.face_count()), (4, 2));
+        assert_eq!(
+            mesh.edges().filter(|&e| !mesh.is_boundary_edge(e)).count(),
+            1
+        );
+        // Each triangle keeps its own corner normals across the weld.
+        for f in mesh.faces() {
+            let corners: Vec<Vec3> = mesh
+                .face_halfedges(f)
+                .map(|h| data.normal(h).unwrap())
+                .collect();
+            assert!(
+                corners == [Vec3::Z; 3] || corners == [Vec3::X; 3],
+                "{corners:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn strips_and_fans_become_consistently_wound_triangles() {
+        // A strip zigzagging between two rows, and a fan around the centre of a hexagon.
+        let strip: Vec<Vec3> = row(4, 1.0)
+            .zip(row(4, 0.0))
+            .flat_map(|(a, b)| [a, b])
+            .collect();
+        let indices: Vec<u16> = (0..strip.len() as u16).collect();
+        let fan: Vec<Vec3> = std::iter::once(Vec3::ZERO)
+            .chain((0..=6).map(|i| {
+                let angle = (i % 6) as f32 * std::f32::consts::TAU / 6.0;
+                Vec3::new(angle.cos(), angle.sin(), 0.0)
+            }))
+            .collect();
+        for (positions, indices, mode, faces) in [
+            (&strip, &indices[..], 5, 6),
+            (&strip, &[][..], 5, 6),
+            (&fan, &[][..], 6, 6),
+        ] {
+            let data = only_mesh(&primitive(positions, &[], indices, mode));
+            let mesh = &data.mesh;
+            assert_eq!(mesh.face_count(), faces, "mode {mode}");
+            for f in mesh.faces() {
+                assert!(
+                    mesh.face_normal(f).abs_diff_eq(Vec3::Z, 1e-6),
+                    "mode {mode}"
+                );
+            }
+        }
+        // The fan's last corner repeats its first, so the weld closes it into a disk.
+        let mesh = only_mesh(&primitive(&fan, &[], &[], 6)).mesh;
+        assert_eq!(mesh.vertex_count(), 7);
+        assert_eq!(
+            mesh.edges().filter(|&e| mesh.is_boundary_edge(e)).count(),
+            6
+        );
+    }
+
+    #[test]
+    fn embedded_buffers_are_decoded() {
+        let positions = [Vec3::ZERO, Vec3::X, Vec3::Y];
+        let bytes = buffer(&positions, &[], &[]);
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        let json = document(3, 0, 0, 4).replacen(
+            r#""byteLength":36"#,
+            &format!(r#""byteLength":36,"uri":"data:application/octet-stream;base64,{encoded}""#),
+            1,
+        );
+        assert_eq!(only_mesh(json.as_bytes()).mesh.positions(), positions);
+
+        let external = document(3, 0, 0, 4).replacen(
+            r#""byteLength":36"#,
+            r#""byteLength":36,"uri":"triangle.bin""#,
+            1,
+        );
+        assert!(matches!(
+            read(external.as_bytes()),
+            Err(ImportError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_files_are_refused() {
+        let positions = [Vec3::ZERO, Vec3::X, Vec3::Y];
+        assert!(matches!(
+            read(b"not a model"),
+            Err(ImportError::Malformed(_))
+        ));
+        assert!(matches!(
+            read(&primitive(&positions, &[], &[0, 1, 3], 4)),
+            Err(ImportError::Malformed(message)) if message.contains("vertex 3")
+        ));
+        // Points and lines are skipped rather than refused.
+        assert!(read(&primitive(&positions, &[], &[], 0))
+            .unwrap()
+            .is_empty());
+        assert!(read(&primitive(&positions, &[], &[], 1))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn normal_counts_must_match_the_positions() {
+        let positions = [Vec3::ZERO, Vec3::X, Vec3::Y];
+        assert!(matches!(
+            read(&primitive(&positions, &[Vec3::Z; 2], &[], 4)),
+            Err(ImportError::Malformed(message)) if message.contains("NORMAL")
+        ));
+
+        let data = only_mesh(&primitive(&positions, &[Vec3::Z; 3], &[], 4));
+        assert_eq!(data.mesh.face_count(), 1);
+        let h = data
+            .mesh
+            .face_halfedges(data.mesh.faces().next().unwrap())
+            .next()
+            .unwrap();
+        assert_eq!(data.normal(h), Some(Vec3::Z));
+    }
+}
@@ -0,0 +1,313 @@
+//! Reading and writing mesh file formats.
+//!
+//! Each format lives in its own module with a `read` function that parses a byte buffer (so it works the same
+//! natively and on bytes handed over from JavaScript) and a `write` function that targets any
+//! [`std::io::Write`]. Writers expect a mesh without removed elements; call
+//! [`HalfEdgeMesh::garbage_collect`] after editing with the Euler operators.
+
+use core::fmt;
+
+use glam::{Vec2, Vec3};
+
+use crate::mesh::{
+    blend_linear, blend_nearest, Attribute, AttributeError, Blend, BuildError, Element,
+    ElementKind, FaceId, HalfEdgeId, HalfEdgeMesh, VertexId,
+};
+
+pub mod gltf;
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+/// The name of the half-edge attribute holding imported texture coordinates, one per corner.
+pub const UV_ATTRIBUTE: &str = "uv";
+/// The name of the half-edge attribute holding imported normals, one per corner.
+pub const NORMAL_ATTRIBUTE: &str = "normal";
+/// The name of the face attribute holding each face's index into [`MeshData::groups`].
+pub const GROUP_ATTRIBUTE: &str = "group";
+
+/// A mesh together with the data file formats carry alongside the topology.
+///
+/// Texture coordinates, normals, face groups and extra vertex channels are stored as
+/// [attributes](crate::mesh::Attribute) of the mesh, so Euler operators, subdivision, simplification and
+/// garbage collection carry them along like any other attribute. Per-corner values live on half-edges: the
+/// corner of face `f` at vertex `v` is the half-edge of `f` leaving `v`. Boundary half-edges have no corner
+/// and hold zero.
+#[derive(Clone, Debug, Default)]
+pub struct MeshData {
+    pub mesh: HalfEdgeMesh,
+    /// Names of the groups faces can belong to, indexed by the [`GROUP_ATTRIBUTE`] face attribute. Empty if
+    /// the file had no grouping.
+    pub groups: Vec<String>,
+}
+
+/// Blend normals linearly and renormalize the result.
+fn blend_normals(sources: &[(&Vec3, f32)]) -> Vec3 {
+    blend_linear(sources).normalize_or_zero()
+}
+
+impl MeshData {
+    /// Wrap a bare mesh with no extra data.
+    #[must_use]
+    pub fn new(mesh: HalfEdgeMesh) -> Self {
+        Self {
+            mesh,
+            ..Default::default()
+        }
+    }
+
+    /// The texture coordinate attribute, if the mesh has one.
+    pub fn uvs(&self) -> Option<Attribute<HalfEdgeId, Vec2>> {
+        self.mesh.find_attribute(UV_ATTRIBUTE).ok()
+    }
+
+    /// The normal attribute, if the mesh has one.
+    pub fn normals(&self) -> Option<Attribute<HalfEdgeId, Vec3>> {
+        self.mesh.find_attribute(NORMAL_ATTRIBUTE).ok()
+    }
+
+    /// The face group attribute, if the mesh is grouped.
+    pub fn face_groups(&self) -> Option<Attribute<FaceId, u32>> {
+        self.mesh.find_attribute(GROUP_ATTRIBUTE).ok()
+    }
+
+    /// The texture coordinate at corner `h`, if the mesh has any.
+    pub fn uv(&self, h: HalfEdgeId) -> Option<Vec2> {
+        self.uvs().map(|uvs| *self.mesh.attribute(uvs, h))
+    }
+
+    /// The normal at corner `h`, if the mesh has any.
+    pub fn normal(&self, h: HalfEdgeId) -> Option<Vec3> {
+        self.normals()
+            .map(|normals| *self.mesh.attribute(normals, h))
+    }
+
+    /// The name of the group `f` belongs to, if the mesh is grouped.
+    pub fn group(&self, f: FaceId) -> Option<&str> {
+        let group = *self.mesh.attribute(self.face_groups()?, f);
+        self.groups.get(group as usize).map(String::as_str)
+    }
+
+    /// Set the texture coordinate of every corner, indexed by half-edge, adding the attribute if needed.
+    /// New corners blend the coordinates of the corners they come from. Fails if an attribute of another
+    /// type already has the name.
+    pub fn set_uvs(&mut self, uvs: &[Vec2]) -> Result<(), AttributeError> {
+        set_values::<HalfEdgeId, _>(&mut self.mesh, UV_ATTRIBUTE, uvs, blend_linear)
+    }
+
+    /// Set the normal of every corner, indexed by half-edge, adding the attribute if needed. New corners
+    /// blend the normals of the corners they come from and renormalize.
+    pub fn set_normals(&mut self, normals: &[Vec3]) -> Result<(), AttributeError> {
+        set_values::<HalfEdgeId, _>(&mut self.mesh, NORMAL_ATTRIBUTE, normals, blend_normals)
+    }
+
+    /// Set the group of every face, indexed by face, and the group names the indices refer to. New faces take
+    /// the group of the face they come from.
+    pub fn set_face_groups(
+        &mut self,
+        groups: Vec<String>,
+        face_groups: &[u32],
+    ) -> Result<(), AttributeError> {
+        set_values::<FaceId, _>(&mut self.mesh, GROUP_ATTRIBUTE, face_groups, blend_nearest)?;
+        self.groups = groups;
+        Ok(())
+    }
+
+    /// Add a named per-vertex scalar channel that has no dedicated attribute, such as PLY colours or scanner
+    /// confidence, or replace the values of an existing one. New vertices blend the values they come from.
+    pub fn set_vertex_property(
+        &mut self,
+        name: &str,
+        values: &[f32],
+    ) -> Result<(), AttributeError> {
+        set_values::<VertexId, _>(&mut self.mesh, name, values, blend_linear)
+    }
+
+    /// Every per-vertex scalar channel: the vertex attributes holding `f32`, with their names.
+    pub fn vertex_properties(&self) -> impl Iterator<Item = (&str, &[f32])> + '_ {
+        self.mesh
+            .attribute_names(ElementKind::Vertex)
+            .filter_map(|name| {
+                let attribute = self.mesh.find_attribute::<VertexId, f32>(name).ok()?;
+                Some((name, self.mesh.attribute_values(attribute)))
+            })
+    }
+
+    /// Scatter per-polygon-corner values (in the order the polygons were given to
+    /// [`HalfEdgeMesh::from_polygons`]) into a per-half-edge array.
+    pub(crate) fn corners_to_halfedges<T: Copy + Default>(
+        mesh: &HalfEdgeMesh,
+        corners: &[T],
+    ) -> Vec<T> {
+        let mut values = vec![T::default(); mesh.halfedge_count()];
+        let mut corner = corners.iter();
+        for f in mesh.faces() {
+            for h in mesh.face_halfedges(f) {
+                values[h.index()] = *corner.next().expect("one value per polygon corner");
+            }
+        }
+        values
+    }
+
+    /// Copy per-vertex values onto every corner of each vertex.
+    pub(crate) fn vertices_to_halfedges<T: Copy + Default>(
+        mesh: &HalfEdgeMesh,
+        values: &[T],
+    ) -> Vec<T> {
+        mesh.halfedges()
+            .map(|h| {
+                if mesh.is_boundary_halfedge(h) {
+                    T::default()
+                } else {
+                    values[mesh.origin(h).index()]
+                }
+            })
+            .collect()
+    }
+}
+
+/// Overwrite every value of the attribute `name` on elements of kind `H`, adding it with `blend` if it is
+/// missing.
+fn set_values<H: Element, T: Copy + Default + 'static>(
+    mesh: &mut HalfEdgeMesh,
+    name: &str,
+    values: &[T],
+    blend: Blend<T>,
+) -> Result<(), AttributeError> {
+    let attribute = match mesh.find_attribute::<H, T>(name) {
+        Err(AttributeError::NotFound { .. }) => mesh.add_attribute(name, T::default(), blend)?,
+        found => found?,
+    };
+    mesh.attribute_values_mut(attribute).copy_from_slice(values);
+    Ok(())
+}
+
+/// Why a file could not be imported.
+#[derive(Debug)]
+pub enum ImportError {
+    /// A line of a text format could not be understood. Lines are numbered from 1.
+    Parse { line: usize, message: String },
+    /// A binary file (or the data section of a text file) is truncated or inconsistent with its header.
+    Malformed(String),
+    /// The file parsed, but its polygons do not form a valid half-edge mesh.
+    Build(BuildError),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { line, message } => write!(f, "line {line}: {message}"),
+            Self::Malformed(message) => f.write_str(message),
+            Self::Build(err) => write!(f, "invalid mesh: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Build(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<BuildError> for ImportError {
+    fn from(err: BuildError) -> Self {
+        Self::Build(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+    use crate::{
+        hole_filling::HoleFillingConfig, remeshing::RemeshingConfig, subdivision::SubdivisionConfig,
+    };
+
+    /// A unit grid, missing the quad at `(hole, hole)` if given, textured by its positions.
+    fn textured_grid(n: u32, hole: Option<u32>) -> MeshData {
+        let positions: Vec<Vec3> = (0..=n)
+            .flat_map(|j| (0..=n).map(move |i| Vec3::new(i as f32, j as f32, 0.0) / n as f32))
+            .collect();
+        let mut triangles = Vec::new();
+        for j in 0..n {
+            for i in (0..n).filter(|&i| Some((i, j)) != hole.map(|k| (k, k))) {
+                let v = j * (n + 1) + i;
+                triangles.extend([v, v + 1, v + n + 2, v, v + n + 2, v + n + 1]);
+            }
+        }
+        let mesh = HalfEdgeMesh::from_triangles(positions, &triangles).unwrap();
+        let uvs: Vec<Vec2> = mesh
+            .halfedges()
+            .map(|h| match mesh.face(h) {
+                Some(_) => mesh.position(mesh.origin(h)).truncate(),
+                None => Vec2::ZERO,
+            })
+            .collect();
+        let mut data = MeshData::new(mesh);
+        data.set_uvs(&uvs).unwrap();
+        data
+    }
+
+    /// Every corner's texture coordinate, which must lie in the unit square like the grid's.
+    fn corner_uvs(data: &MeshData) -> Vec<Vec2> {
+        let mesh = &data.mesh;
+        let uvs: Vec<Vec2> = mesh
+            .faces()
+            .flat_map(|f| mesh.face_halfedges(f))
+            .map(|h| data.uv(h).unwrap())
+            .collect();
+        for uv in &uvs {
+            assert!(
+                uv.cmpge(Vec2::ZERO).all() && uv.cmple(Vec2::ONE).all(),
+                "{uv}"
+            );
+        }
+        uvs
+    }
+
+    #[test]
+    fn texture_coordinates_survive_subdivision() {
+        let data = textured_grid(4, None);
+        let fine = data
+            .mesh
+            .loop_subdivision(&SubdivisionConfig::default())
+            .unwrap();
+        let fine = MeshData::new(fine);
+        assert_eq!(corner_uvs(&fine).len(), 3 * fine.mesh.face_count());
+        // Corners of the original vertices keep their coordinates.
+        assert!(corner_uvs(&fine).contains(&Vec2::new(0.5, 0.5)));
+    }
+
+    #[test]
+    fn texture_coordinates_survive_remeshing() {
+        let mut data = textured_grid(4, None);
+        let config = RemeshingConfig {
+            target_edge_length: 0.1,
+            ..Default::default()
+        };
+        data.mesh.remesh(&config).unwrap();
+        data.mesh.garbage_collect();
+        assert!(data.mesh.face_count() > 32);
+        assert!(corner_uvs(&data).iter().any(|&uv| uv != Vec2::ZERO));
+    }
+
+    #[test]
+    fn filled_holes_take_the_texture_coordinates_around_them() {
+        let mut data = textured_grid(4, Some(1));
+        let config = HoleFillingConfig {
+            max_hole_edges: 4,
+            refine: false,
+            ..Default::default()
+        };
+        assert_eq!(data.mesh.fill_holes(&config).unwrap().len(), 1);
+        let mesh = &data.mesh;
+        for h in mesh.halfedges().filter(|&h| !mesh.is_boundary_halfedge(h)) {
+            let p = mesh.position(mesh.origin(h)).truncate();
+            assert_eq!(data.uv(h), Some(p), "corner {h}");
+        }
+    }
+}
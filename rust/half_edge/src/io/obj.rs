@@ -0,0 +1,265 @@
+//! Wavefront OBJ.
+//!
+//! Reads positions, texture coordinates, normals, polygon faces of any size and `g`/`o` groups. Materials,
+//! smoothing groups, lines and free-form geometry are ignored. Faces are kept as n-gons.
+
+use std::{collections::HashMap, hash::Hash, io::Write};
+
+use glam::{Vec2, Vec3};
+
+use super::{ImportError, MeshData};
+use crate::mesh::HalfEdgeMesh;
+
+/// Parse an OBJ file. If any face corner has a texture coordinate or normal, the result carries that
+/// attribute for every corner, with zero for corners that didn't specify one.
+pub fn read(bytes: &[u8]) -> Result<MeshData, ImportError> {
+    let text = String::from_utf8_lossy(bytes);
+
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+    let mut polygons: Vec<Vec<u32>> = Vec::new();
+    let mut corner_uvs: Vec<Option<Vec2>> = Vec::new();
+    let mut corner_normals: Vec<Option<Vec3>> = Vec::new();
+    let mut groups: Vec<String> = Vec::new();
+    let mut face_groups = Vec::new();
+    let mut current_group: Option<usize> = None;
+
+    let mut pending = String::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let raw_line = raw_line.split('#').next().unwrap_or_default();
+        // A trailing backslash joins the next line onto this one.
+        if let Some(stripped) = raw_line.trim_end().strip_suffix('\\') {
+            pending.push_str(stripped);
+            pending.push(' ');
+            continue;
+        }
+        pending.push_str(raw_line);
+        let line = std::mem::take(&mut pending);
+
+        let parse_error = |message: String| ImportError::Parse {
+            line: line_number,
+            message,
+        };
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        match keyword {
+            "v" => positions.push(parse_vec3(&mut tokens).map_err(parse_error)?),
+            "vn" => normals.push(parse_vec3(&mut tokens).map_err(parse_error)?),
+            "vt" => {
+                let u = parse_float(tokens.next(), "u").map_err(parse_error)?;
+                // `v` is optional for 1D textures.
+                let v = match tokens.next() {
+                    Some(token) => parse_float(Some(token), "v").map_err(parse_error)?,
+                    None => 0.0,
+                };
+                uvs.push(Vec2::new(u, v));
+            }
+            "f" => {
+                let mut polygon = Vec::new();
+                for corner in tokens {
+                    let mut parts = corner.split('/');
+                    let v = resolve_index(parts.next(), positions.len(), "vertex")
+                        .map_err(parse_error)?
+                        .ok_or_else(|| {
+                            parse_error(format!("face corner `{corner}` has no vertex"))
+                        })?;
+                    let vt = resolve_index(parts.next(), uvs.len(), "texture coordinate")
+                        .map_err(parse_error)?;
+                    let vn = resolve_index(parts.next(), normals.len(), "normal")
+                        .map_err(parse_error)?;
+                    polygon.push(v as u32);
+                    corner_uvs.push(vt.map(|i| uvs[i]));
+                    corner_normals.push(vn.map(|i| normals[i]));
+                }
+                polygons.push(polygon);
+                let group = *current_group.get_or_insert_with(|| {
+                    groups.push("default".to_string());
+                    groups.len() - 1
+                });
+                face_groups.push(group as u32);
+            }
+            "g" | "o" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                let name = if name.is_empty() {
+                    "default".to_string()
+                } else {
+                    name
+                };
+                current_group = Some(match groups.iter().position(|g| *g == name) {
+                    Some(existing) => existing,
+                    None => {
+                        groups.push(name);
+                        groups.len() - 1
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let mesh = HalfEdgeMesh::from_polygons(positions, &polygons)?;
+    let has_uvs = corner_uvs.iter().any(Option::is_some);
+    let has_normals = corner_normals.iter().any(Option::is_some);
+    let corner_uvs: Vec<_> = corner_uvs.iter().map(|uv| uv.unwrap_or_default()).collect();
+    let corner_normals: Vec<_> = corner_normals
+        .iter()
+        .map(|n| n.unwrap_or_default())
+        .collect();
+    let mut data = MeshData::new(mesh);
+    if has_uvs {
+        let uvs = MeshData::corners_to_halfedges(&data.mesh, &corner_uvs);
+        data.set_uvs(&uvs).expect("a fresh mesh has no attributes");
+    }
+    if has_normals {
+        let normals = MeshData::corners_to_halfedges(&data.mesh, &corner_normals);
+        data.set_normals(&normals)
+            .expect("a fresh mesh has no attributes");
+    }
+    data.set_face_groups(groups, &face_groups)
+        .expect("a fresh mesh has no attributes");
+    Ok(data)
+}
+
+/// Write `data` as an OBJ file. Texture coordinates and normals are deduplicated, and a `g` statement is
+/// emitted whenever the group changes between consecutive faces.
+pub fn write(writer: &mut impl Write, data: &MeshData) -> std::io::Result<()> {
+    let mesh = &data.mesh;
+    for p in mesh.positions() {
+        writeln!(writer, "v {} {} {}", p.x, p.y, p.z)?;
+    }
+
+    // Index of each corner's texture coordinate / normal in the deduplicated `vt` / `vn` lists.
+    let uvs = data.uvs().map(|uvs| mesh.attribute_values(uvs));
+    let normals = data.normals().map(|normals| mesh.attribute_values(normals));
+    let uv_index = match uvs {
+        Some(uvs) => {
+            let (unique, index) = dedup_corners(mesh, uvs, |uv| uv.to_array().map(f32::to_bits));
+            for uv in unique {
+                writeln!(writer, "vt {} {}", uv.x, uv.y)?;
+            }
+            index
+        }
+        None => Vec::new(),
+    };
+    let normal_index = match normals {
+        Some(normals) => {
+            let (unique, index) = dedup_corners(mesh, normals, |n| n.to_array().map(f32::to_bits));
+            for n in unique {
+                writeln!(writer, "vn {} {} {}", n.x, n.y, n.z)?;
+            }
+            index
+        }
+        None => Vec::new(),
+    };
+
+    let mut current_group = None;
+    for f in mesh.faces() {
+        if let Some(group) = data.group(f) {
+            if current_group != Some(group) {
+                writeln!(writer, "g {group}")?;
+                current_group = Some(group);
+            }
+        }
+        write!(writer, "f")?;
+        for h in mesh.face_halfedges(f) {
+            let v = mesh.origin(h).index() + 1;
+            match (uvs.is_some(), normals.is_some()) {
+                (false, false) => write!(writer, " {v}")?,
+                (true, false) => write!(writer, " {v}/{}", uv_index[h.index()])?,
+                (false, true) => write!(writer, " {v}//{}", normal_index[h.index()])?,
+                (true, true) => write!(
+                    writer,
+                    " {v}/{}/{}",
+                    uv_index[h.index()],
+                    normal_index[h.index()]
+                )?,
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Deduplicate per-corner values, returning the unique values and the 1-based index of each corner's value.
+fn dedup_corners<T: Copy, K: Hash + Eq>(
+    mesh: &HalfEdgeMesh,
+    values: &[T],
+    key: impl Fn(T) -> K,
+) -> (Vec<T>, Vec<usize>) {
+    let mut unique = Vec::new();
+    let mut seen = HashMap::new();
+    let mut index = vec![0; mesh.halfedge_count()];
+    for f in mesh.faces() {
+        for h in mesh.face_halfedges(f) {
+            let value = values[h.index()];
+            index[h.index()] = *seen.entry(key(value)).or_insert_with(|| {
+                unique.push(value);
+                unique.len()
+            });
+        }
+    }
+    (unique, index)
+}
+
+fn parse_float(token: Option<&str>, what: &str) -> Result<f32, String> {
+    let token = token.ok_or_else(|| format!("missing {what} component"))?;
+    token
+        .parse()
+        .map_err(|_| format!("`{token}` is not a number"))
+}
+
+fn parse_vec3<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Vec3, String> {
+    Ok(Vec3::new(
+        parse_float(tokens.next(), "x")?,
+        parse_float(tokens.next(), "y")?,
+        parse_float(tokens.next(), "z")?,
+    ))
+}
+
+/// Turn a 1-based (or negative, relative) OBJ index into a 0-based one. Empty tokens, as in `1//3`, are `None`.
+fn resolve_index(token: Option<&str>, count: usize, what: &str) -> Result<Option<usize>, String> {
+    let Some(token) = token.filter(|t| !t.is_empty()) else {
+        return Ok(None);
+    };
+    let index: i64 = token
+        .parse()
+        .map_err(|_| format!("`{token}` is not a valid {what} index"))?;
+    let resolved = match index {
+        1.. => index - 1,
+        ..=-1 => count as i64 + index,
+        0 => -1,
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!(
+            "{what} index {index} is out of range ({count} defined so far)"
+        ));
+    }
+    Ok(Some(resolved as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unused_texture_coordinates_and_normals_are_not_attached() {
+        let data = read(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5 0.5\nvn 0 0 1\nf 1 2 3\n").unwrap();
+        let h = data.mesh.halfedges().next().unwrap();
+        assert_eq!(data.uv(h), None);
+        assert_eq!(data.normal(h), None);
+    }
+
+    #[test]
+    fn used_texture_coordinates_are_attached_to_every_corner() {
+        let data = read(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5 0.25\nf 1/1 2/1 3/1\n").unwrap();
+        for f in data.mesh.faces() {
+            for h in data.mesh.face_halfedges(f) {
+                assert_eq!(data.uv(h), Some(Vec2::new(0.5, 0.25)));
+            }
+        }
+    }
+}
@@ -0,0 +1,692 @@
+//! Stanford PLY, in ASCII and both binary byte orders.
+//!
+//! Vertex positions, normals (`nx ny nz`) and texture coordinates (`u v`, `s t` or `texture_u texture_v`) are
+//! recognised by name. Every other scalar vertex property (colours, confidence, ...) is kept as a named channel
+//! in [`MeshData::vertex_properties`]. Faces are read from the `vertex_indices` (or `vertex_index`) list of the
+//! `face` element; other elements are skipped.
+
+use std::io::Write;
+
+use glam::{Vec2, Vec3};
+
+use super::{ImportError, MeshData};
+use crate::mesh::{HalfEdgeId, HalfEdgeMesh, VertexId};
+
+/// Which encoding to write the element data in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlyFormat {
+    Ascii,
+    #[default]
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar {
+        name: String,
+        ty: ScalarType,
+    },
+    List {
+        name: String,
+        count: ScalarType,
+        item: ScalarType,
+    },
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads values from the element data following the header.
+enum Body<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary {
+        bytes: &'a [u8],
+        pos: usize,
+        big_endian: bool,
+    },
+}
+
+impl Body<'_> {
+    fn read(&mut self, ty: ScalarType) -> Result<f64, ImportError> {
+        match self {
+            Self::Ascii(tokens) => {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| ImportError::Malformed("PLY data ends early".to_string()))?;
+                token
+                    .parse()
+                    .map_err(|_| ImportError::Malformed(format!("`{token}` is not a number")))
+            }
+            Self::Binary {
+                bytes,
+                pos,
+                big_endian,
+            } => {
+                let size = ty.size();
+                let Some(raw) = bytes.get(*pos..*pos + size) else {
+                    return Err(ImportError::Malformed("PLY data ends early".to_string()));
+                };
+                *pos += size;
+                let mut buf = [0u8; 8];
+                buf[..size].copy_from_slice(raw);
+                if *big_endian {
+                    buf[..size].reverse();
+                }
+                Ok(match ty {
+                    ScalarType::I8 => buf[0] as i8 as f64,
+                    ScalarType::U8 => buf[0] as f64,
+                    ScalarType::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+                    ScalarType::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+                    ScalarType::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                    ScalarType::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                    ScalarType::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                    ScalarType::F64 => f64::from_le_bytes(buf),
+                })
+            }
+        }
+    }
+}
+
+/// Parse a PLY file.
+pub fn read(bytes: &[u8]) -> Result<MeshData, ImportError> {
+    let (format, elements, body_start) = read_header(bytes)?;
+    check_counts(&elements, format, bytes.len() - body_start)?;
+    let mut body = match format {
+        PlyFormat::Ascii => {
+            let text = std::str::from_utf8(&bytes[body_start..]).map_err(|_| {
+                ImportError::Malformed("ASCII PLY data is not valid UTF-8".to_string())
+            })?;
+            Body::Ascii(text.split_whitespace())
+        }
+        PlyFormat::BinaryLittleEndian | PlyFormat::BinaryBigEndian => Body::Binary {
+            bytes: &bytes[body_start..],
+            pos: 0,
+            big_endian: format == PlyFormat::BinaryBigEndian,
+        },
+    };
+
+    let mut columns: Vec<(String, Vec<f32>)> = Vec::new();
+    let mut polygons: Vec<Vec<u32>> = Vec::new();
+    for element in &elements {
+        let is_vertex = element.name == "vertex";
+        let is_face = element.name == "face";
+        if is_vertex {
+            columns = element
+                .properties
+                .iter()
+                .filter_map(|p| match p {
+                    Property::Scalar { name, .. } => Some((name.clone(), Vec::new())),
+                    Property::List { .. } => None,
+                })
+                .collect();
+        }
+        if element.properties.is_empty() {
+            continue;
+        }
+        for _ in 0..element.count {
+            let mut column = 0;
+            for property in &element.properties {
+                match *property {
+                    Property::Scalar { ty, .. } => {
+                        let value = body.read(ty)?;
+                        if is_vertex {
+                            columns[column].1.push(value as f32);
+                            column += 1;
+                        }
+                    }
+                    Property::List {
+                        ref name,
+                        count,
+                        item,
+                    } => {
+                        let len = body.read(count)? as usize;
+                        let wanted =
+                            is_face && (name == "vertex_indices" || name == "vertex_index");
+                        let mut polygon = Vec::new();
+                        for _ in 0..len {
+                            let value = body.read(item)?;
+                            if wanted {
+                                if value < 0.0 {
+                                    return Err(ImportError::Malformed(format!(
+                                        "negative vertex index {value}"
+                                    )));
+                                }
+                                polygon.push(value as u32);
+                            }
+                        }
+                        if wanted {
+                            polygons.push(polygon);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut take = |name: &str| {
+        columns
+            .iter()
+            .position(|(n, _)| n == name)
+            .map(|i| columns.remove(i).1)
+    };
+    let (Some(x), Some(y), Some(z)) = (take("x"), take("y"), take("z")) else {
+        return Err(ImportError::Malformed(
+            "PLY vertices have no x, y, z properties".to_string(),
+        ));
+    };
+    let positions: Vec<Vec3> = (0..x.len()).map(|i| Vec3::new(x[i], y[i], z[i])).collect();
+    let vertex_normals = match (take("nx"), take("ny"), take("nz")) {
+        (Some(nx), Some(ny), Some(nz)) => Some(
+            (0..nx.len())
+                .map(|i| Vec3::new(nx[i], ny[i], nz[i]))
+                .collect::<Vec<_>>(),
+        ),
+        _ => None,
+    };
+    let mut vertex_uvs = None;
+    for (u, v) in [
+        ("u", "v"),
+        ("s", "t"),
+        ("texture_u", "texture_v"),
+        ("texture_s", "texture_t"),
+    ] {
+        if let (Some(u), Some(v)) = (take(u), take(v)) {
+            vertex_uvs = Some(
+                (0..u.len())
+                    .map(|i| Vec2::new(u[i], v[i]))
+                    .collect::<Vec<_>>(),
+            );
+            break;
+        }
+    }
+
+    let mut data = MeshData::new(HalfEdgeMesh::from_polygons(positions, &polygons)?);
+    if let Some(values) = vertex_normals {
+        let normals = MeshData::vertices_to_halfedges(&data.mesh, &values);
+        data.set_normals(&normals)
+            .expect("a fresh mesh has no attributes");
+    }
+    if let Some(values) = vertex_uvs {
+        let uvs = MeshData::vertices_to_halfedges(&data.mesh, &values);
+        data.set_uvs(&uvs).expect("a fresh mesh has no attributes");
+    }
+    for (name, values) in &columns {
+        data.set_vertex_property(name, values)
+            .expect("vertex properties are the only f32 vertex attributes");
+    }
+    Ok(data)
+}
+
+/// Check that the body is long enough for the element counts in the header, so that absurd counts are
+/// refused before anything is allocated or looped over for them. Every binary record takes at least its
+/// scalars and list counts, and every ASCII value at least one byte.
+fn check_counts(
+    elements: &[Element],
+    format: PlyFormat,
+    body_len: usize,
+) -> Result<(), ImportError> {
+    let mut needed = 0usize;
+    for element in elements {
+        let record: usize = element
+            .properties
+            .iter()
+            .map(|property| match (format, property) {
+                (PlyFormat::Ascii, _) => 1,
+                (_, Property::Scalar { ty, .. }) => ty.size(),
+                (_, Property::List { count, .. }) => count.size(),
+            })
+            .sum();
+        needed = element
+            .count
+            .checked_mul(record)
+            .and_then(|bytes| needed.checked_add(bytes))
+            .filter(|&needed| needed <= body_len)
+            .ok_or_else(|| {
+                ImportError::Malformed(format!(
+                    "PLY header declares {} {} elements, more than the data holds",
+                    element.count, element.name
+                ))
+            })?;
+    }
+    Ok(())
+}
+
+fn read_header(bytes: &[u8]) -> Result<(PlyFormat, Vec<Element>, usize), ImportError> {
+    const END: &[u8] = b"end_header";
+    let end = bytes
+        .windows(END.len())
+        .position(|w| w == END)
+        .ok_or_else(|| ImportError::Malformed("PLY header has no end_header".to_string()))?;
+    let body_start = bytes[end..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |i| end + i + 1);
+    let header = String::from_utf8_lossy(&bytes[..end]);
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for (index, line) in header.lines().enumerate() {
+        let parse_error = |message: String| ImportError::Parse {
+            line: index + 1,
+            message,
+        };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["ply"] if index == 0 => {}
+            _ if index == 0 => return Err(parse_error("not a PLY file".to_string())),
+            ["format", kind, _version] => {
+                format = Some(match *kind {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    other => return Err(parse_error(format!("unknown PLY format `{other}`"))),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| parse_error(format!("`{count}` is not an element count")))?,
+                properties: Vec::new(),
+            }),
+            ["property", rest @ ..] => {
+                let element = elements.last_mut().ok_or_else(|| {
+                    parse_error("property declared before any element".to_string())
+                })?;
+                let ty = |name: &str| {
+                    ScalarType::parse(name)
+                        .ok_or_else(|| parse_error(format!("unknown property type `{name}`")))
+                };
+                element.properties.push(match rest {
+                    ["list", count, item, name] => Property::List {
+                        name: name.to_string(),
+                        count: ty(count)?,
+                        item: ty(item)?,
+                    },
+                    [scalar, name] => Property::Scalar {
+                        name: name.to_string(),
+                        ty: ty(scalar)?,
+                    },
+                    _ => return Err(parse_error("malformed property declaration".to_string())),
+                });
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(parse_error(format!("unexpected header line `{line}`"))),
+        }
+    }
+    let format = format
+        .ok_or_else(|| ImportError::Malformed("PLY header has no format line".to_string()))?;
+    Ok((format, elements, body_start))
+}
+
+/// Write `data` as a PLY file. Per-corner normals and texture coordinates are written per vertex, taken from
+/// one of the faces around each vertex; vertex properties are written as `float`.
+pub fn write(writer: &mut impl Write, data: &MeshData, format: PlyFormat) -> std::io::Result<()> {
+    let mesh = &data.mesh;
+    // The corner each vertex takes its normal and texture coordinate from.
+    let corner = |v: VertexId| -> Option<HalfEdgeId> {
+        mesh.vertex_outgoing_halfedges(v)
+            .find(|&h| !mesh.is_boundary_halfedge(h))
+    };
+
+    writeln!(writer, "ply")?;
+    writeln!(
+        writer,
+        "format {} 1.0",
+        match format {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+            PlyFormat::BinaryBigEndian => "binary_big_endian",
+        }
+    )?;
+    writeln!(writer, "element vertex {}", mesh.vertex_count())?;
+    let normals = data.normals().map(|normals| mesh.attribute_values(normals));
+    let uvs = data.uvs().map(|uvs| mesh.attribute_values(uvs));
+    let properties: Vec<_> = data.vertex_properties().collect();
+    let mut names = vec!["x", "y", "z"];
+    if normals.is_some() {
+        names.extend(["nx", "ny", "nz"]);
+    }
+    if uvs.is_some() {
+        names.extend(["u", "v"]);
+    }
+    names.extend(properties.iter().map(|(name, _)| *name));
+    for name in &names {
+        writeln!(writer, "property float {name}")?;
+    }
+    writeln!(writer, "element face {}", mesh.face_count())?;
+    writeln!(writer, "property list uchar int vertex_indices")?;
+    writeln!(writer, "end_header")?;
+
+    let mut row = Vec::with_capacity(names.len());
+    for v in mesh.vertices() {
+        row.clear();
+        row.extend(mesh.position(v).to_array());
+        let h = corner(v);
+        if let Some(normals) = normals {
+            row.extend(h.map_or(Vec3::ZERO, |h| normals[h.index()]).to_array());
+        }
+        if let Some(uvs) = uvs {
+            row.extend(h.map_or(Vec2::ZERO, |h| uvs[h.index()]).to_array());
+        }
+        row.extend(properties.iter().map(|(_, values)| values[v.index()]));
+        match format {
+            PlyFormat::Ascii => {
+                let text: Vec<String> = row.iter().map(f32::to_string).collect();
+                writeln!(writer, "{}", text.join(" "))?;
+            }
+            PlyFormat::BinaryLittleEndian => row
+                .iter()
+                .try_for_each(|x| writer.write_all(&x.to_le_bytes()))?,
+            PlyFormat::BinaryBigEndian => row
+                .iter()
+                .try_for_each(|x| writer.write_all(&x.to_be_bytes()))?,
+        }
+    }
+
+    for f in mesh.faces() {
+        let indices: Vec<i32> = mesh.face_vertices(f).map(|v| v.index() as i32).collect();
+        let degree = u8::try_from(indices.len()).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "face has more than 255 corners",
+            )
+        })?;
+        match format {
+            PlyFormat::Ascii => {
+                let text: Vec<String> = indices.iter().map(i32::to_string).collect();
+                writeln!(writer, "{degree} {}", text.join(" "))?;
+            }
+            PlyFormat::BinaryLittleEndian => {
+                writer.write_all(&[degree])?;
+                indices
+                    .iter()
+                    .try_for_each(|i| writer.write_all(&i.to_le_bytes()))?;
+            }
+            PlyFormat::BinaryBigEndian => {
+                writer.write_all(&[degree])?;
+                indices
+                    .iter()
+                    .try_for_each(|i| writer.write_all(&i.to_be_bytes()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIANGLE_HEADER: &str = "ply\nformat binary_little_endian 1.0\nelement vertex 3\n\
+        property float x\nproperty float y\nproperty float z\n\
+        element face 1\nproperty list uint int vertex_indices\nend_header\n";
+
+    fn triangle_vertices() -> Vec<u8> {
+        [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect()
+    }
+
+    /// A closed square pyramid with a quad base, carrying per-vertex normals, texture coordinates and two
+    /// extra channels.
+    fn pyramid() -> MeshData {
+        let positions = vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.5, 0.5, 1.0),
+        ];
+        let polygons: [&[u32]; 5] = [
+            &[0, 3, 2, 1],
+            &[0, 1, 4],
+            &[1, 2, 4],
+            &[2, 3, 4],
+            &[3, 0, 4],
+        ];
+        let mut data = MeshData::new(HalfEdgeMesh::from_polygons(positions, polygons).unwrap());
+        let mesh = &data.mesh;
+        let normals: Vec<Vec3> = mesh
+            .positions()
+            .iter()
+            .map(|p| (*p - 0.5).normalize())
+            .collect();
+        let uvs: Vec<Vec2> = mesh.positions().iter().map(|p| p.truncate()).collect();
+        let normals = MeshData::vertices_to_halfedges(mesh, &normals);
+        let uvs = MeshData::vertices_to_halfedges(mesh, &uvs);
+        data.set_normals(&normals).unwrap();
+        data.set_uvs(&uvs).unwrap();
+        data.set_vertex_property("confidence", &[0.0, 0.25, 0.5, 0.75, 1.0])
+            .unwrap();
+        data.set_vertex_property("red", &[255.0, 0.0, 128.0, 3.0, 7.0])
+            .unwrap();
+        data
+    }
+
+    fn polygons(mesh: &HalfEdgeMesh) -> Vec<Vec<VertexId>> {
+        mesh.faces()
+            .map(|f| mesh.face_vertices(f).collect())
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_in_every_format() {
+        let data = pyramid();
+        for format in [
+            PlyFormat::Ascii,
+            PlyFormat::BinaryLittleEndian,
+            PlyFormat::BinaryBigEndian,
+        ] {
+            let mut bytes = Vec::new();
+            write(&mut bytes, &data, format).unwrap();
+            let read = read(&bytes).unwrap();
+            assert_eq!(read.mesh.positions(), data.mesh.positions(), "{format:?}");
+            assert_eq!(polygons(&read.mesh), polygons(&data.mesh), "{format:?}");
+            for f in read.mesh.faces() {
+                for h in read.mesh.face_halfedges(f) {
+                    assert_eq!(read.normal(h), data.normal(h), "{format:?}");
+                    assert_eq!(read.uv(h), data.uv(h), "{format:?}");
+                }
+            }
+            let properties: Vec<_> = read.vertex_properties().collect();
+            assert_eq!(
+                properties,
+                data.vertex_properties().collect::<Vec<_>>(),
+                "{format:?}"
+            );
+        }
+    }
+
+    /// Append `value` as a `ty` scalar in `format`.
+    fn scalar(bytes: &mut Vec<u8>, format: PlyFormat, ty: &str, value: f64) {
+        let little: Vec<u8> = match ty {
+            "uchar" => vec![value as u8],
+            "short" => (value as i16).to_le_bytes().to_vec(),
+            "int" => (value as i32).to_le_bytes().to_vec(),
+            "float" => (value as f32).to_le_bytes().to_vec(),
+            "double" => value.to_le_bytes().to_vec(),
+            _ => unreachable!("no {ty} scalars in these tests"),
+        };
+        match format {
+            PlyFormat::Ascii => bytes.extend(format!("{value}\n").bytes()),
+            PlyFormat::BinaryLittleEndian => bytes.extend(little),
+            PlyFormat::BinaryBigEndian => bytes.extend(little.into_iter().rev()),
+        }
+    }
+
+    /// A triangle whose vertices carry a `uchar` and a `double` besides their position, whose face has a
+    /// property after its index list, followed by an `edge` element, all in `format`.
+    fn annotated_triangle(format: PlyFormat) -> Vec<u8> {
+        let name = match format {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+            PlyFormat::BinaryBigEndian => "binary_big_endian",
+        };
+        let mut bytes = format!(
+            "ply\nformat {name} 1.0\ncomment made by hand\nelement vertex 3\n\
+             property uchar red\nproperty float x\nproperty float y\nproperty float z\n\
+             property double quality\nelement face 1\nproperty list uchar int vertex_indices\n\
+             property short flags\nelement edge 1\nproperty int vertex1\nproperty int vertex2\n\
+             end_header\n"
+        )
+        .into_bytes();
+        for (red, [x, y, z], quality) in [
+            (255.0, [0.0, 0.0, 0.0], 0.5),
+            (128.0, [1.0, 0.0, 0.0], 0.25),
+            (0.0, [0.0, 1.0, 0.0], 1.0),
+        ] {
+            scalar(&mut bytes, format, "uchar", red);
+            for coordinate in [x, y, z] {
+                scalar(&mut bytes, format, "float", coordinate);
+            }
+            scalar(&mut bytes, format, "double", quality);
+        }
+        scalar(&mut bytes, format, "uchar", 3.0);
+        for index in [0.0, 1.0, 2.0] {
+            scalar(&mut bytes, format, "int", index);
+        }
+        scalar(&mut bytes, format, "short", -7.0);
+        scalar(&mut bytes, format, "int", 0.0);
+        scalar(&mut bytes, format, "int", 1.0);
+        bytes
+    }
+
+    #[test]
+    fn extra_properties_of_any_type_are_kept() {
+        for format in [
+            PlyFormat::Ascii,
+            PlyFormat::BinaryLittleEndian,
+            PlyFormat::BinaryBigEndian,
+        ] {
+            let data = read(&annotated_triangle(format)).unwrap();
+            assert_eq!(
+                data.mesh.positions(),
+                [Vec3::ZERO, Vec3::X, Vec3::Y],
+                "{format:?}"
+            );
+            assert_eq!(data.mesh.face_count(), 1, "{format:?}");
+            let properties: Vec<_> = data.vertex_properties().collect();
+            assert_eq!(
+                properties,
+                [
+                    ("red", &[255.0, 128.0, 0.0][..]),
+                    ("quality", &[0.5, 0.25, 1.0][..])
+                ],
+                "{format:?}"
+            );
+            assert!(data.normals().is_none() && data.uvs().is_none());
+        }
+    }
+
+    #[test]
+    fn malformed_files_are_refused() {
+        let ascii = |body: &str| {
+            format!(
+                "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\n\
+                 property float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n\
+                 {body}"
+            )
+        };
+        let triangle = "0 0 0\n1 0 0\n0 1 0\n";
+        assert!(read(ascii(&format!("{triangle}3 0 1 2\n")).as_bytes()).is_ok());
+
+        let parse = |bytes: &[u8]| matches!(read(bytes), Err(ImportError::Parse { .. }));
+        let malformed = |bytes: &[u8]| matches!(read(bytes), Err(ImportError::Malformed(_)));
+        assert!(parse(b"obj\nend_header\n"));
+        assert!(parse(b"ply\nformat text 1.0\nend_header\n"));
+        assert!(parse(
+            b"ply\nformat ascii 1.0\nproperty float x\nend_header\n"
+        ));
+        assert!(parse(
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\nend_header\n"
+        ));
+        assert!(malformed(b"ply\nformat ascii 1.0\nelement vertex 0\n"));
+        assert!(malformed(b"ply\nelement vertex 0\nend_header\n"));
+        assert!(malformed(
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float w\nend_header\n1\n"
+        ));
+        // Data that ends early, is not a number or has negative indices.
+        assert!(malformed(ascii(&format!("{triangle}3 0 1")).as_bytes()));
+        assert!(malformed(
+            ascii(&format!("{triangle}3 0 one 2\n")).as_bytes()
+        ));
+        assert!(malformed(
+            ascii(&format!("{triangle}3 0 -1 2\n")).as_bytes()
+        ));
+        let mut truncated = TRIANGLE_HEADER.as_bytes().to_vec();
+        truncated.extend(triangle_vertices());
+        truncated.extend(3u32.to_le_bytes());
+        truncated.extend([0, 1].iter().flat_map(|i: &i32| i.to_le_bytes()));
+        assert!(malformed(&truncated));
+        // Indices past the vertices parse, but do not make a mesh.
+        assert!(matches!(
+            read(ascii(&format!("{triangle}3 0 1 3\n")).as_bytes()),
+            Err(ImportError::Build(_))
+        ));
+    }
+
+    #[test]
+    fn huge_element_counts_are_refused() {
+        let header = TRIANGLE_HEADER.replace("element vertex 3", "element vertex 4000000000000");
+        let mut bytes = header.into_bytes();
+        bytes.extend(triangle_vertices());
+        assert!(matches!(read(&bytes), Err(ImportError::Malformed(_))));
+
+        let ascii = b"ply\nformat ascii 1.0\nelement vertex 4000000000000\nproperty float x\nend_header\n0\n";
+        assert!(matches!(read(ascii), Err(ImportError::Malformed(_))));
+    }
+
+    #[test]
+    fn huge_list_counts_are_refused() {
+        let mut bytes = TRIANGLE_HEADER.as_bytes().to_vec();
+        bytes.extend(triangle_vertices());
+        bytes.extend(4_000_000_000u32.to_le_bytes());
+        bytes.extend([0, 1, 2].iter().flat_map(|i: &i32| i.to_le_bytes()));
+        assert!(matches!(read(&bytes), Err(ImportError::Malformed(_))));
+    }
+}
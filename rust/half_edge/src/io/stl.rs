@@ -0,0 +1,298 @@
+//! STL, in both its ASCII and binary forms.
+//!
+//! STL stores every triangle with its own copy of its corners, so reading welds coincident corners back into
+//! shared vertices before building the half-edge mesh. Triangles that collapse during welding are dropped.
+
+use std::io::Write;
+
+use glam::Vec3;
+
+use super::{ImportError, MeshData};
+use crate::{mesh::HalfEdgeMesh, weld::weld_vertices};
+
+/// Which flavour of STL to write.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StlFormat {
+    Ascii,
+    #[default]
+    Binary,
+}
+
+/// Parse an ASCII or binary STL file, merging corners closer than `weld_tolerance`. A tolerance of zero only
+/// merges bit-identical corners, which is usually enough for files written by CAD tools.
+pub fn read(bytes: &[u8], weld_tolerance: f32) -> Result<MeshData, ImportError> {
+    let corners = if is_binary(bytes) {
+        read_binary(bytes)?
+    } else {
+        read_ascii(bytes)?
+    };
+
+    let (positions, remap) = weld_vertices(&corners, weld_tolerance);
+    let triangles: Vec<[u32; 3]> = remap
+        .chunks_exact(3)
+        .map(|t| [t[0], t[1], t[2]])
+        .filter(|[a, b, c]| a != b && b != c && c != a)
+        .collect();
+    Ok(MeshData::new(HalfEdgeMesh::from_polygons(
+        positions, &triangles,
+    )?))
+}
+
+/// Write the mesh as STL. Polygons with more than three corners are split into triangle fans, and facet
+/// normals are recomputed from the geometry.
+pub fn write(
+    writer: &mut impl Write,
+    mesh: &HalfEdgeMesh,
+    format: StlFormat,
+) -> std::io::Result<()> {
+    let triangles: Vec<[Vec3; 3]> = mesh
+        .faces()
+        .flat_map(|f| {
+            let corners: Vec<Vec3> = mesh.face_positions(f).collect();
+            (1..corners.len() - 1)
+                .map(|i| [corners[0], corners[i], corners[i + 1]])
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let normal = |[a, b, c]: &[Vec3; 3]| (*b - *a).cross(*c - *a).normalize_or_zero();
+
+    match format {
+        StlFormat::Ascii => {
+            writeln!(writer, "solid half_edge")?;
+            for triangle in &triangles {
+                let n = normal(triangle);
+                writeln!(writer, "  facet normal {} {} {}", n.x, n.y, n.z)?;
+                writeln!(writer, "    outer loop")?;
+                for p in triangle {
+                    writeln!(writer, "      vertex {} {} {}", p.x, p.y, p.z)?;
+                }
+                writeln!(writer, "    endloop")?;
+                writeln!(writer, "  endfacet")?;
+            }
+            writeln!(writer, "endsolid half_edge")?;
+        }
+        StlFormat::Binary => {
+            let mut header = [0u8; 80];
+            header[..9].copy_from_slice(b"half_edge");
+            writer.write_all(&header)?;
+            writer.write_all(&(triangles.len() as u32).to_le_bytes())?;
+            for triangle in &triangles {
+                for v in std::iter::once(normal(triangle)).chain(triangle.iter().copied()) {
+                    for component in v.to_array() {
+                        writer.write_all(&component.to_le_bytes())?;
+                    }
+                }
+                writer.write_all(&0u16.to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Binary files may also start with `solid`, so trust the size recorded in the header when it matches.
+fn is_binary(bytes: &[u8]) -> bool {
+    if let Some(count) = triangle_count(bytes) {
+        let size = count.checked_mul(50).and_then(|body| body.checked_add(84));
+        if size == Some(bytes.len()) {
+            return true;
+        }
+    }
+    !bytes.trim_ascii_start().starts_with(b"solid")
+}
+
+/// The number of triangles a binary header declares, if there is a header.
+fn triangle_count(bytes: &[u8]) -> Option<usize> {
+    let count = bytes.get(80..84)?;
+    usize::try_from(u32::from_le_bytes([count[0], count[1], count[2], count[3]])).ok()
+}
+
+fn read_binary(bytes: &[u8]) -> Result<Vec<Vec3>, ImportError> {
+    let Some(count) = triangle_count(bytes) else {
+        return Err(ImportError::Malformed(
+            "binary STL is shorter than its header".to_string(),
+        ));
+    };
+    let body = &bytes[84..];
+    if count.checked_mul(50).is_none_or(|size| body.len() < size) {
+        return Err(ImportError::Malformed(format!(
+            "binary STL declares {count} triangles but only has room for {}",
+            body.len() / 50
+        )));
+    }
+
+    let float =
+        |at: usize| f32::from_le_bytes([body[at], body[at + 1], body[at + 2], body[at + 3]]);
+    // The body holds 50 bytes per triangle, so this cannot overflow.
+    let mut corners = Vec::with_capacity(count * 3);
+    for triangle in 0..count {
+        // Skip the 12-byte facet normal; it is recomputed from the winding when needed.
+        let start = triangle * 50 + 12;
+        for corner in 0..3 {
+            let at = start + corner * 12;
+            corners.push(Vec3::new(float(at), float(at + 4), float(at + 8)));
+        }
+    }
+    Ok(corners)
+}
+
+fn read_ascii(bytes: &[u8]) -> Result<Vec<Vec3>, ImportError> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut corners = Vec::new();
+    let mut loop_corners = 0;
+    for (index, line) in text.lines().enumerate() {
+        let parse_error = |message: String| ImportError::Parse {
+            line: index + 1,
+            message,
+        };
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("vertex") => {
+                let mut component = || {
+                    let token = tokens.next().ok_or_else(|| {
+                        parse_error("vertex has fewer than 3 components".to_string())
+                    })?;
+                    token
+                        .parse::<f32>()
+                        .map_err(|_| parse_error(format!("`{token}` is not a number")))
+                };
+                corners.push(Vec3::new(component()?, component()?, component()?));
+                loop_corners += 1;
+            }
+            Some("outer") => loop_corners = 0,
+            Some("endloop") if loop_corners != 3 => {
+                return Err(parse_error(format!(
+                    "facet has {loop_corners} vertices, expected 3"
+                )));
+            }
+            _ => {}
+        }
+    }
+    Ok(corners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A unit cube made of quads, which the writers fan into triangles.
+    fn cube() -> HalfEdgeMesh {
+        let positions: Vec<Vec3> = (0..8)
+            .map(|i| Vec3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2) as f32))
+            .collect();
+        let quads: [&[u32]; 6] = [
+            &[0, 2, 3, 1],
+            &[4, 5, 7, 6],
+            &[0, 1, 5, 4],
+            &[2, 6, 7, 3],
+            &[0, 4, 6, 2],
+            &[1, 3, 7, 5],
+        ];
+        HalfEdgeMesh::from_polygons(positions, quads).unwrap()
+    }
+
+    #[test]
+    fn round_trips_in_both_formats() {
+        let cube = cube();
+        for format in [StlFormat::Ascii, StlFormat::Binary] {
+            let mut bytes = Vec::new();
+            write(&mut bytes, &cube, format).unwrap();
+            assert_eq!(is_binary(&bytes), format == StlFormat::Binary);
+            let mesh = read(&bytes, 0.0).unwrap().mesh;
+            // Welding brings the 36 written corners back to the cube's 8 vertices.
+            assert_eq!(mesh.vertex_count(), 8, "{format:?}");
+            assert_eq!(mesh.face_count(), 12, "{format:?}");
+            assert!(mesh.validate().is_ok(), "{format:?}");
+            assert!(
+                mesh.edges().all(|e| !mesh.is_boundary_edge(e)),
+                "{format:?}"
+            );
+            let mut positions = mesh.positions().to_vec();
+            positions.sort_by(|a, b| a.to_array().partial_cmp(&b.to_array()).unwrap());
+            let mut expected = cube.positions().to_vec();
+            expected.sort_by(|a, b| a.to_array().partial_cmp(&b.to_array()).unwrap());
+            assert_eq!(positions, expected, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn binary_files_may_start_with_solid() {
+        let mut bytes = Vec::new();
+        write(&mut bytes, &cube(), StlFormat::Binary).unwrap();
+        bytes[..9].copy_from_slice(b"solid cad");
+        assert_eq!(read(&bytes, 0.0).unwrap().mesh.face_count(), 12);
+    }
+
+    #[test]
+    fn welding_merges_nearby_corners() {
+        // Two triangles sharing an edge whose copies differ by 1e-5, and a sliver that welding collapses.
+        let facet = |corners: [[f32; 3]; 3]| {
+            let mut text = "facet normal 0 0 1\nouter loop\n".to_string();
+            for [x, y, z] in corners {
+                text.push_str(&format!("vertex {x} {y} {z}\n"));
+            }
+            text + "endloop\nendfacet\n"
+        };
+        let text = [
+            "solid pair\n".to_string(),
+            facet([[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]),
+            facet([[1.00001, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.00001, 0.0]]),
+            facet([[3.0, 0.0, 0.0], [3.00001, 0.0, 0.0], [3.0, 1.0, 0.0]]),
+            "endsolid pair\n".to_string(),
+        ]
+        .concat();
+
+        let exact = read(text.as_bytes(), 0.0).unwrap().mesh;
+        assert_eq!((exact.vertex_count(), exact.face_count()), (9, 3));
+        assert_eq!(
+            exact
+                .edges()
+                .filter(|&e| !exact.is_boundary_edge(e))
+                .count(),
+            0
+        );
+        let welded = read(text.as_bytes(), 1e-4).unwrap().mesh;
+        assert_eq!(welded.face_count(), 2);
+        assert_eq!(
+            welded
+                .edges()
+                .filter(|&e| !welded.is_boundary_edge(e))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn malformed_files_are_refused() {
+        let parse =
+            |text: &str| matches!(read(text.as_bytes(), 0.0), Err(ImportError::Parse { .. }));
+        assert!(parse(
+            "solid s\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\n"
+        ));
+        assert!(parse(
+            "solid s\nfacet normal 0 0 1\nouter loop\nvertex 0 0\n"
+        ));
+        assert!(parse(
+            "solid s\nfacet normal 0 0 1\nouter loop\nvertex 0 zero 0\n"
+        ));
+
+        let mut truncated = Vec::new();
+        write(&mut truncated, &cube(), StlFormat::Binary).unwrap();
+        truncated.pop();
+        assert!(matches!(
+            read(&truncated, 0.0),
+            Err(ImportError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn triangle_counts_beyond_the_data_are_refused() {
+        let mut bytes = vec![0u8; 80];
+        bytes.extend(u32::MAX.to_le_bytes());
+        bytes.extend([0u8; 50]);
+        assert!(matches!(read(&bytes, 0.0), Err(ImportError::Malformed(_))));
+        assert!(matches!(
+            read_binary(&bytes[..83]),
+            Err(ImportError::Malformed(_))
+        ));
+    }
+}
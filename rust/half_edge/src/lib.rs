@@ -1,17 +1,30 @@
-use wasm_bindgen::prelude::*;
+pub mod boolean;
+pub mod bvh;
+pub mod differential;
+pub mod geodesics;
+pub mod hole_filling;
+pub mod intrinsic;
+pub mod io;
+pub mod mesh;
+pub mod parameterization;
+pub mod predicates;
+pub mod remeshing;
+pub mod repair;
+pub mod simplification;
+pub mod smoothing;
+pub mod sparse;
+pub mod statistics;
+pub mod subdivision;
+pub mod triangulation;
+mod union_find;
+pub mod validation;
+pub mod wasm;
+pub mod weld;
 
-#[wasm_bindgen]
-extern "C" {
-    pub fn alert(s: &str);
-}
-
-#[wasm_bindgen]
-pub fn greet(name: &str) {
-    alert(&format!("Heyooooo, {}!", name));
-}
-
-// #[derive(Serialize, Deserialize, Debug)]
-// pub struct TestStruct {
-//     name: String,
-//     size: i32;
-// }
+pub use glam::{Vec2, Vec3};
+pub use mesh::{
+    blend_linear, blend_nearest, Attribute, AttributeError, Blend, BuildError, EdgeId, EdgeSplit,
+    Element, ElementKind, FaceId, GarbageCollection, HalfEdgeId, HalfEdgeLoop, HalfEdgeMesh,
+    TopologyError, VertexId, VertexRing,
+};
+pub use validation::{validate_polygons, ValidationError};
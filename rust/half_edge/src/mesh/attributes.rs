@@ -0,0 +1,463 @@
+//! Typed attributes attached to mesh elements.
+//!
+//! An attribute stores one value per vertex, half-edge (corner), edge or face, in an array that the mesh keeps
+//! in step with its arenas. Elements created by an Euler operator or by subdivision get values blended from
+//! the elements they came from, using the attribute's [`Blend`] rule, and garbage collection compacts
+//! attributes along with everything else.
+//!
+//! Half-edge attributes are per-corner data: the value of `h` belongs to the corner of `face(h)` at
+//! `origin(h)`, which is what texture coordinates and split normals need.
+
+use core::{any::Any, fmt, marker::PhantomData, ops::Add, ops::Mul};
+
+use super::{EdgeId, FaceId, HalfEdgeId, HalfEdgeMesh, VertexId};
+
+/// The kinds of element an attribute can be attached to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ElementKind {
+    Vertex,
+    HalfEdge,
+    Edge,
+    Face,
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A handle type that attributes can be attached to: [`VertexId`], [`HalfEdgeId`], [`EdgeId`] or [`FaceId`].
+pub trait Element: Copy + sealed::Sealed + 'static {
+    const KIND: ElementKind;
+
+    fn index(self) -> usize;
+}
+
+macro_rules! impl_element {
+    ($handle:ident, $kind:ident) => {
+        impl sealed::Sealed for $handle {}
+
+        impl Element for $handle {
+            const KIND: ElementKind = ElementKind::$kind;
+
+            #[inline(always)]
+            fn index(self) -> usize {
+                $handle::index(self)
+            }
+        }
+    };
+}
+
+impl_element!(VertexId, Vertex);
+impl_element!(HalfEdgeId, HalfEdge);
+impl_element!(EdgeId, Edge);
+impl_element!(FaceId, Face);
+
+/// How an attribute combines the values of existing elements into the value of a new one. It receives each
+/// source value with its weight; the weights are positive and sum to one.
+pub type Blend<T> = fn(&[(&T, f32)]) -> T;
+
+/// Blend by weighted sum, for numeric values such as `f32`, [`glam::Vec2`] or [`glam::Vec3`].
+pub fn blend_linear<T>(sources: &[(&T, f32)]) -> T
+where
+    T: Copy + Default + Add<Output = T> + Mul<f32, Output = T>,
+{
+    sources
+        .iter()
+        .fold(T::default(), |sum, &(&value, weight)| sum + value * weight)
+}
+
+/// Blend by copying the value with the largest weight, for values that cannot be mixed such as flags, ids or
+/// materials.
+pub fn blend_nearest<T: Clone>(sources: &[(&T, f32)]) -> T {
+    sources
+        .iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|&(value, _)| value.clone())
+        .expect("blending needs at least one source")
+}
+
+/// A typed key for an attribute of a [`HalfEdgeMesh`], from [`HalfEdgeMesh::add_attribute`] or
+/// [`HalfEdgeMesh::find_attribute`]. It stays valid until the attribute is removed.
+pub struct Attribute<H, T> {
+    slot: usize,
+    marker: PhantomData<fn() -> (H, T)>,
+}
+
+impl<H, T> Clone for Attribute<H, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<H, T> Copy for Attribute<H, T> {}
+
+impl<H, T> PartialEq for Attribute<H, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.slot == other.slot
+    }
+}
+
+impl<H, T> Eq for Attribute<H, T> {}
+
+impl<H, T> fmt::Debug for Attribute<H, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Attribute({})", self.slot)
+    }
+}
+
+/// Why an attribute could not be added or found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttributeError {
+    /// An attribute of that name is already attached to the same kind of element.
+    NameTaken { name: String },
+    /// No attribute of that name is attached to that kind of element.
+    NotFound { name: String },
+    /// The attribute exists, but holds values of another type.
+    TypeMismatch { name: String },
+}
+
+impl fmt::Display for AttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NameTaken { name } => write!(f, "attribute \"{name}\" already exists"),
+            Self::NotFound { name } => write!(f, "attribute \"{name}\" does not exist"),
+            Self::TypeMismatch { name } => {
+                write!(f, "attribute \"{name}\" holds values of another type")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AttributeError {}
+
+/// The values of one attribute.
+struct Column<T> {
+    values: Vec<T>,
+    default: T,
+    blend: Blend<T>,
+}
+
+/// A [`Column`] with its value type erased, so that columns of every type can live in one list.
+trait AnyColumn {
+    fn clone_box(&self) -> Box<dyn AnyColumn>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn type_name(&self) -> &'static str;
+    fn push_default(&mut self);
+    fn blend(&mut self, target: usize, sources: &[(usize, f32)]);
+    fn compact(&mut self, live: &[bool]);
+    fn gather(&self, sources: &[Vec<(usize, f32)>]) -> Box<dyn AnyColumn>;
+}
+
+impl<T: Clone + 'static> Column<T> {
+    fn blended(&self, sources: &[(usize, f32)]) -> T {
+        let total: f32 = sources.iter().map(|&(_, w)| w).sum();
+        if sources.is_empty() || total <= 0.0 {
+            return self.default.clone();
+        }
+        let weighted: Vec<(&T, f32)> = sources
+            .iter()
+            .map(|&(i, w)| (&self.values[i], w / total))
+            .collect();
+        (self.blend)(&weighted)
+    }
+}
+
+impl<T: Clone + 'static> AnyColumn for Column<T> {
+    fn clone_box(&self) -> Box<dyn AnyColumn> {
+        Box::new(Self {
+            values: self.values.clone(),
+            default: self.default.clone(),
+            blend: self.blend,
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    fn push_default(&mut self) {
+        self.values.push(self.default.clone());
+    }
+
+    fn blend(&mut self, target: usize, sources: &[(usize, f32)]) {
+        self.values[target] = self.blended(sources);
+    }
+
+    fn compact(&mut self, live: &[bool]) {
+        let mut live = live.iter();
+        self.values.retain(|_| *live.next().unwrap_or(&false));
+    }
+
+    fn gather(&self, sources: &[Vec<(usize, f32)>]) -> Box<dyn AnyColumn> {
+        Box::new(Self {
+            values: sources.iter().map(|s| self.blended(s)).collect(),
+            default: self.default.clone(),
+            blend: self.blend,
+        })
+    }
+}
+
+/// The attributes attached to one kind of element. Removed attributes leave an empty slot, so that the keys
+/// of the others stay valid.
+#[derive(Default)]
+struct AttributeSet {
+    slots: Vec<Option<(String, Box<dyn AnyColumn>)>>,
+}
+
+impl Clone for AttributeSet {
+    fn clone(&self) -> Self {
+        Self {
+            slots: self
+                .slots
+                .iter()
+                .map(|slot| {
+                    slot.as_ref()
+                        .map(|(name, column)| (name.clone(), column.clone_box()))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl AttributeSet {
+    fn columns_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn AnyColumn>> {
+        self.slots.iter_mut().flatten().map(|(_, column)| column)
+    }
+}
+
+/// Every attribute of a mesh, by element kind.
+#[derive(Clone, Default)]
+pub(crate) struct Attributes {
+    sets: [AttributeSet; 4],
+}
+
+impl fmt::Debug for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_map();
+        for (kind, set) in [
+            ElementKind::Vertex,
+            ElementKind::HalfEdge,
+            ElementKind::Edge,
+            ElementKind::Face,
+        ]
+        .iter()
+        .zip(&self.sets)
+        {
+            for (name, column) in set.slots.iter().flatten() {
+                list.entry(&format_args!("{kind:?} {name}"), &column.type_name());
+            }
+        }
+        list.finish()
+    }
+}
+
+impl Attributes {
+    fn set(&self, kind: ElementKind) -> &AttributeSet {
+        &self.sets[kind as usize]
+    }
+
+    fn set_mut(&mut self, kind: ElementKind) -> &mut AttributeSet {
+        &mut self.sets[kind as usize]
+    }
+
+    /// Give every attribute of `kind` a default value for a newly allocated element.
+    pub(crate) fn push_default(&mut self, kind: ElementKind) {
+        self.set_mut(kind)
+            .columns_mut()
+            .for_each(|column| column.push_default());
+    }
+
+    /// Set every attribute of element `target` to the blend of the `sources`, given as indices and weights.
+    pub(crate) fn blend(&mut self, kind: ElementKind, target: usize, sources: &[(usize, f32)]) {
+        self.set_mut(kind)
+            .columns_mut()
+            .for_each(|column| column.blend(target, sources));
+    }
+
+    /// Drop the values of elements that are not `live`.
+    pub(crate) fn compact(&mut self, kind: ElementKind, live: &[bool]) {
+        self.set_mut(kind)
+            .columns_mut()
+            .for_each(|column| column.compact(live));
+    }
+
+    /// The attributes of `kind` for a rebuilt mesh, whose element `i` blends the old elements `sources[i]`.
+    pub(crate) fn gather(&mut self, kind: ElementKind, sources: &[Vec<(usize, f32)>]) {
+        for (_, column) in self.set_mut(kind).slots.iter_mut().flatten() {
+            *column = column.gather(sources);
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.sets
+            .iter()
+            .all(|set| set.slots.iter().all(Option::is_none))
+    }
+}
+
+impl HalfEdgeMesh {
+    fn arena_len(&self, kind: ElementKind) -> usize {
+        match kind {
+            ElementKind::Vertex => self.vertex_count(),
+            ElementKind::HalfEdge => self.halfedge_count(),
+            ElementKind::Edge => self.edge_count(),
+            ElementKind::Face => self.face_count(),
+        }
+    }
+
+    fn column<H: Element, T: 'static>(&self, attribute: Attribute<H, T>) -> &Column<T> {
+        let (_, column) = self.attributes.set(H::KIND).slots[attribute.slot]
+            .as_ref()
+            .expect("attribute has been removed");
+        column
+            .as_any()
+            .downcast_ref()
+            .expect("attribute key belongs to another mesh")
+    }
+
+    fn column_mut<H: Element, T: 'static>(&mut self, attribute: Attribute<H, T>) -> &mut Column<T> {
+        let (_, column) = self.attributes.set_mut(H::KIND).slots[attribute.slot]
+            .as_mut()
+            .expect("attribute has been removed");
+        column
+            .as_any_mut()
+            .downcast_mut()
+            .expect("attribute key belongs to another mesh")
+    }
+
+    /// Attach a new attribute called `name` to every element of kind `H`, initialised to `default`. Elements
+    /// created later start from `default` too, unless an operator blends them from existing elements with
+    /// `blend`.
+    pub fn add_attribute<H: Element, T: Clone + 'static>(
+        &mut self,
+        name: &str,
+        default: T,
+        blend: Blend<T>,
+    ) -> Result<Attribute<H, T>, AttributeError> {
+        let len = self.arena_len(H::KIND);
+        let set = self.attributes.set_mut(H::KIND);
+        if set.slots.iter().flatten().any(|(taken, _)| taken == name) {
+            return Err(AttributeError::NameTaken {
+                name: name.to_owned(),
+            });
+        }
+        let column = Column {
+            values: vec![default.clone(); len],
+            default,
+            blend,
+        };
+        // Slots are never reused, so a key to a removed attribute cannot reach a newer one.
+        set.slots.push(Some((name.to_owned(), Box::new(column))));
+        Ok(Attribute {
+            slot: set.slots.len() - 1,
+            marker: PhantomData,
+        })
+    }
+
+    /// Look up the attribute called `name` on elements of kind `H`, checking that it holds values of type `T`.
+    pub fn find_attribute<H: Element, T: 'static>(
+        &self,
+        name: &str,
+    ) -> Result<Attribute<H, T>, AttributeError> {
+        let set = self.attributes.set(H::KIND);
+        let (slot, (_, column)) = set
+            .slots
+            .iter()
+            .enumerate()
+            .find_map(|(slot, entry)| {
+                entry
+                    .as_ref()
+                    .filter(|(taken, _)| taken == name)
+                    .map(|entry| (slot, entry))
+            })
+            .ok_or_else(|| AttributeError::NotFound {
+                name: name.to_owned(),
+            })?;
+        if !column.as_any().is::<Column<T>>() {
+            return Err(AttributeError::TypeMismatch {
+                name: name.to_owned(),
+            });
+        }
+        Ok(Attribute {
+            slot,
+            marker: PhantomData,
+        })
+    }
+
+    /// Detach an attribute and drop its values.
+    pub fn remove_attribute<H: Element, T: 'static>(&mut self, attribute: Attribute<H, T>) {
+        self.attributes.set_mut(H::KIND).slots[attribute.slot] = None;
+    }
+
+    /// The names of the attributes attached to elements of `kind`.
+    pub fn attribute_names(&self, kind: ElementKind) -> impl Iterator<Item = &str> + '_ {
+        self.attributes
+            .set(kind)
+            .slots
+            .iter()
+            .flatten()
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// The value of `attribute` at element `h`.
+    #[inline]
+    pub fn attribute<H: Element, T: 'static>(&self, attribute: Attribute<H, T>, h: H) -> &T {
+        &self.column(attribute).values[h.index()]
+    }
+
+    #[inline]
+    pub fn attribute_mut<H: Element, T: 'static>(
+        &mut self,
+        attribute: Attribute<H, T>,
+        h: H,
+    ) -> &mut T {
+        &mut self.column_mut(attribute).values[h.index()]
+    }
+
+    #[inline]
+    pub fn set_attribute<H: Element, T: 'static>(
+        &mut self,
+        attribute: Attribute<H, T>,
+        h: H,
+        value: T,
+    ) {
+        self.column_mut(attribute).values[h.index()] = value;
+    }
+
+    /// Every value of `attribute`, indexed like the arena of `H` (removed elements included).
+    pub fn attribute_values<H: Element, T: 'static>(&self, attribute: Attribute<H, T>) -> &[T] {
+        &self.column(attribute).values
+    }
+
+    pub fn attribute_values_mut<H: Element, T: 'static>(
+        &mut self,
+        attribute: Attribute<H, T>,
+    ) -> &mut [T] {
+        &mut self.column_mut(attribute).values
+    }
+
+    /// Set every attribute of `target` to the blend of the `sources` with their weights, which are normalized
+    /// to sum to one. Operators do this for the elements they create; call it after moving or merging elements
+    /// by other means. The sources may include `target` itself.
+    pub fn blend_attributes<H: Element>(&mut self, target: H, sources: &[(H, f32)]) {
+        if self.attributes.is_empty() {
+            return;
+        }
+        let sources: Vec<(usize, f32)> = sources.iter().map(|&(h, w)| (h.index(), w)).collect();
+        self.attributes.blend(H::KIND, target.index(), &sources);
+    }
+
+    /// Copy every attribute of `source` onto `target`.
+    pub(crate) fn copy_attributes<H: Element>(&mut self, target: H, source: H) {
+        self.blend_attributes(target, &[(source, 1.0)]);
+    }
+}
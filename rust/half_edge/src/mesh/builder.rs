@@ -0,0 +1,281 @@
+use core::fmt;
+use std::collections::HashMap;
+
+use glam::Vec3;
+
+use super::{
+    Edge, EdgeId, ElementKind, Face, FaceId, HalfEdge, HalfEdgeId, HalfEdgeMesh, Vertex, VertexId,
+};
+
+/// Why a polygon list could not be turned into a [`HalfEdgeMesh`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// A face has fewer than three corners.
+    FaceTooSmall { face: usize, corners: usize },
+    /// A face refers to a vertex index past the end of the position list.
+    IndexOutOfRange { face: usize, index: u32 },
+    /// A face visits the same vertex more than once.
+    DegenerateFace { face: usize, vertex: u32 },
+    /// The triangle index list is not a multiple of three long.
+    TruncatedIndices { len: usize },
+    /// More than two faces share the edge `a`-`b`.
+    NonManifoldEdge { face: usize, a: u32, b: u32 },
+    /// Two faces traverse the edge `a`->`b` in the same direction, so their windings disagree.
+    InconsistentOrientation { face: usize, a: u32, b: u32 },
+    /// The faces around this vertex do not form a single fan (e.g. two cones touching at their tips).
+    NonManifoldVertex { vertex: u32 },
+    /// A face uses exactly the same vertices as an earlier one.
+    DuplicateFace { face: usize, duplicate_of: usize },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::FaceTooSmall { face, corners } => {
+                write!(
+                    f,
+                    "face {face} has {corners} corners, at least 3 are required"
+                )
+            }
+            Self::IndexOutOfRange { face, index } => {
+                write!(
+                    f,
+                    "face {face} refers to vertex {index}, which does not exist"
+                )
+            }
+            Self::DegenerateFace { face, vertex } => {
+                write!(f, "face {face} visits vertex {vertex} more than once")
+            }
+            Self::TruncatedIndices { len } => {
+                write!(
+                    f,
+                    "triangle index list has length {len}, which is not a multiple of 3"
+                )
+            }
+            Self::NonManifoldEdge { face, a, b } => {
+                write!(f, "face {face} is the third face on edge {a}-{b}")
+            }
+            Self::InconsistentOrientation { face, a, b } => {
+                write!(
+                    f,
+                    "face {face} traverses edge {a}->{b} in the same direction as a neighbour"
+                )
+            }
+            Self::NonManifoldVertex { vertex } => {
+                write!(
+                    f,
+                    "the faces around vertex {vertex} do not form a single fan"
+                )
+            }
+            Self::DuplicateFace { face, duplicate_of } => {
+                write!(f, "face {face} duplicates face {duplicate_of}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl HalfEdgeMesh {
+    /// Build a mesh from a flat triangle index list, three indices per face.
+    pub fn from_triangles(
+        positions: impl Into<Vec<Vec3>>,
+        indices: &[u32],
+    ) -> Result<Self, BuildError> {
+        if !indices.len().is_multiple_of(3) {
+            return Err(BuildError::TruncatedIndices { len: indices.len() });
+        }
+        Self::from_polygons(positions, indices.chunks_exact(3))
+    }
+
+    /// Build a mesh from a list of polygons, each given as a counter-clockwise loop of vertex indices. Polygons
+    /// may have any number of corners; they are kept as n-gons.
+    ///
+    /// Construction stops at the first problem. Use [`crate::validate_polygons`] to list all of them.
+    pub fn from_polygons<P: AsRef<[u32]>>(
+        positions: impl Into<Vec<Vec3>>,
+        polygons: impl IntoIterator<Item = P>,
+    ) -> Result<Self, BuildError> {
+        let positions = positions.into();
+        let mut mesh = HalfEdgeMesh {
+            vertices: vec![Vertex::default(); positions.len()],
+            positions,
+            ..Default::default()
+        };
+
+        // Directed vertex pair -> half-edge. Both directions are inserted as soon as the edge is first seen.
+        let mut directed: HashMap<(u32, u32), HalfEdgeId> = HashMap::new();
+        let mut outgoing_count = vec![0u32; mesh.vertices.len()];
+        // Sorted corners -> the first face with them. Two faces on the same vertices in opposite directions
+        // would otherwise close up into a flat pillow.
+        let mut corner_sets: HashMap<Vec<u32>, usize> = HashMap::new();
+
+        for (face_index, polygon) in polygons.into_iter().enumerate() {
+            let polygon = polygon.as_ref();
+            mesh.check_polygon(face_index, polygon)?;
+            let mut corners = polygon.to_vec();
+            corners.sort_unstable();
+            if let Some(&duplicate_of) = corner_sets.get(&corners) {
+                return Err(BuildError::DuplicateFace {
+                    face: face_index,
+                    duplicate_of,
+                });
+            }
+            corner_sets.insert(corners, face_index);
+
+            let face = FaceId::new(mesh.faces.len());
+            let mut loop_halfedges = Vec::with_capacity(polygon.len());
+            for (i, &a) in polygon.iter().enumerate() {
+                let b = polygon[(i + 1) % polygon.len()];
+                let h = match directed.get(&(a, b)) {
+                    Some(&h) => h,
+                    None => {
+                        let h = mesh
+                            .add_edge_pair(VertexId::new(a as usize), VertexId::new(b as usize));
+                        directed.insert((a, b), h);
+                        directed.insert((b, a), mesh.twin(h));
+                        h
+                    }
+                };
+                if mesh.face(h).is_some() {
+                    return Err(if mesh.face(mesh.twin(h)).is_some() {
+                        BuildError::NonManifoldEdge {
+                            face: face_index,
+                            a,
+                            b,
+                        }
+                    } else {
+                        BuildError::InconsistentOrientation {
+                            face: face_index,
+                            a,
+                            b,
+                        }
+                    });
+                }
+                loop_halfedges.push(h);
+            }
+
+            // Only claim the half-edges once the whole loop is known to be free, so a later error in this
+            // polygon doesn't leave earlier corners half-linked.
+            let n = loop_halfedges.len();
+            for (i, &h) in loop_halfedges.iter().enumerate() {
+                let he = &mut mesh.halfedges[h.index()];
+                he.face = Some(face);
+                he.next = loop_halfedges[(i + 1) % n];
+                he.prev = loop_halfedges[(i + n - 1) % n];
+                outgoing_count[he.origin.index()] += 1;
+            }
+            mesh.faces.push(Face {
+                halfedge: loop_halfedges[0],
+                removed: false,
+            });
+        }
+
+        mesh.link_boundary(&mut outgoing_count)?;
+
+        // Every half-edge leaving a vertex must be reachable by circulating around it, otherwise the vertex
+        // joins several disconnected fans.
+        for v in mesh.vertices() {
+            let Some(start) = mesh.vertex_halfedge(v) else {
+                continue;
+            };
+            let mut count = 0;
+            let mut h = start;
+            loop {
+                count += 1;
+                h = mesh.next(mesh.twin(h));
+                if h == start || count > outgoing_count[v.index()] {
+                    break;
+                }
+            }
+            if count != outgoing_count[v.index()] {
+                return Err(BuildError::NonManifoldVertex {
+                    vertex: v.index() as u32,
+                });
+            }
+        }
+
+        Ok(mesh)
+    }
+
+    fn check_polygon(&self, face: usize, polygon: &[u32]) -> Result<(), BuildError> {
+        if polygon.len() < 3 {
+            return Err(BuildError::FaceTooSmall {
+                face,
+                corners: polygon.len(),
+            });
+        }
+        for (i, &index) in polygon.iter().enumerate() {
+            if index as usize >= self.vertices.len() {
+                return Err(BuildError::IndexOutOfRange { face, index });
+            }
+            if polygon[..i].contains(&index) {
+                return Err(BuildError::DegenerateFace {
+                    face,
+                    vertex: index,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Allocate an unlinked edge and its two half-edges, returning the half-edge `a`->`b`. Both half-edges start
+    /// out as boundary half-edges whose `next`/`prev` point at themselves.
+    pub(crate) fn add_edge_pair(&mut self, a: VertexId, b: VertexId) -> HalfEdgeId {
+        let edge = EdgeId::new(self.edges.len());
+        let h = HalfEdgeId::new(self.halfedges.len());
+        let t = HalfEdgeId::new(self.halfedges.len() + 1);
+        for (id, origin, twin) in [(h, a, t), (t, b, h)] {
+            self.halfedges.push(HalfEdge {
+                origin,
+                twin,
+                next: id,
+                prev: id,
+                face: None,
+                edge,
+            });
+        }
+        self.edges.push(Edge {
+            halfedge: h,
+            removed: false,
+        });
+        self.attributes.push_default(ElementKind::HalfEdge);
+        self.attributes.push_default(ElementKind::HalfEdge);
+        self.attributes.push_default(ElementKind::Edge);
+        h
+    }
+
+    /// Link the faceless half-edges into boundary loops and pick each vertex's outgoing half-edge.
+    fn link_boundary(&mut self, outgoing_count: &mut [u32]) -> Result<(), BuildError> {
+        for h in (0..self.halfedges.len()).map(HalfEdgeId::new) {
+            if self.is_boundary_halfedge(h) {
+                let v = self.origin(h);
+                if self.vertices[v.index()]
+                    .halfedge
+                    .is_some_and(|other| self.is_boundary_halfedge(other))
+                {
+                    // Two boundary half-edges leave the same vertex: a bow-tie.
+                    return Err(BuildError::NonManifoldVertex {
+                        vertex: v.index() as u32,
+                    });
+                }
+                self.vertices[v.index()].halfedge = Some(h);
+                outgoing_count[v.index()] += 1;
+            } else {
+                let v = self.origin(h);
+                self.vertices[v.index()].halfedge.get_or_insert(h);
+            }
+        }
+
+        for h in (0..self.halfedges.len()).map(HalfEdgeId::new) {
+            if self.is_boundary_halfedge(h) {
+                let next = self.vertices[self.dest(h).index()].halfedge.expect(
+                    "destination of a boundary half-edge has an outgoing boundary half-edge",
+                );
+                self.halfedges[h.index()].next = next;
+                self.halfedges[next.index()].prev = h;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+//! Zero-allocation walks over the local neighbourhood of mesh elements.
+//!
+//! All of these are built from two primitive walks: [`HalfEdgeLoop`], which follows `next` around a face or a
+//! boundary loop, and [`VertexRing`], which rotates counter-clockwise through the half-edges leaving a vertex.
+//! Both assume the mesh is valid; run [`HalfEdgeMesh::validate`] first on untrusted input.
+
+use super::{EdgeId, FaceId, HalfEdgeId, HalfEdgeMesh, VertexId};
+
+/// Follows `next` from a starting half-edge until it comes back around.
+#[derive(Clone)]
+pub struct HalfEdgeLoop<'a> {
+    mesh: &'a HalfEdgeMesh,
+    start: HalfEdgeId,
+    current: Option<HalfEdgeId>,
+}
+
+impl Iterator for HalfEdgeLoop<'_> {
+    type Item = HalfEdgeId;
+
+    #[inline]
+    fn next(&mut self) -> Option<HalfEdgeId> {
+        let h = self.current?;
+        let next = self.mesh.next(h);
+        self.current = (next != self.start).then_some(next);
+        Some(h)
+    }
+}
+
+/// Rotates counter-clockwise through the outgoing half-edges of a vertex. Boundary half-edges are included.
+#[derive(Clone)]
+pub struct VertexRing<'a> {
+    mesh: &'a HalfEdgeMesh,
+    start: Option<HalfEdgeId>,
+    current: Option<HalfEdgeId>,
+}
+
+impl Iterator for VertexRing<'_> {
+    type Item = HalfEdgeId;
+
+    #[inline]
+    fn next(&mut self) -> Option<HalfEdgeId> {
+        let h = self.current?;
+        let next = self.mesh.twin(self.mesh.prev(h));
+        self.current = (Some(next) != self.start).then_some(next);
+        Some(h)
+    }
+}
+
+impl HalfEdgeMesh {
+    /// Walk `next` pointers starting at `h`. For an interior half-edge this visits its face; for a boundary
+    /// half-edge it visits the whole boundary loop.
+    pub fn halfedge_loop(&self, h: HalfEdgeId) -> HalfEdgeLoop<'_> {
+        HalfEdgeLoop {
+            mesh: self,
+            start: h,
+            current: Some(h),
+        }
+    }
+
+    /// Half-edges leaving `v`, in counter-clockwise order. Starts at the boundary half-edge for boundary
+    /// vertices, and is empty for isolated vertices.
+    pub fn vertex_outgoing_halfedges(&self, v: VertexId) -> VertexRing<'_> {
+        let start = self.vertex_halfedge(v);
+        VertexRing {
+            mesh: self,
+            start,
+            current: start,
+        }
+    }
+
+    /// Half-edges pointing at `v`, in counter-clockwise order.
+    pub fn vertex_incoming_halfedges(
+        &self,
+        v: VertexId,
+    ) -> impl Iterator<Item = HalfEdgeId> + Clone + '_ {
+        self.vertex_outgoing_halfedges(v).map(|h| self.twin(h))
+    }
+
+    /// The one-ring of `v`: every vertex sharing an edge with it, in counter-clockwise order.
+    pub fn vertex_vertices(&self, v: VertexId) -> impl Iterator<Item = VertexId> + Clone + '_ {
+        self.vertex_outgoing_halfedges(v).map(|h| self.dest(h))
+    }
+
+    /// Edges incident to `v`, in counter-clockwise order.
+    pub fn vertex_edges(&self, v: VertexId) -> impl Iterator<Item = EdgeId> + Clone + '_ {
+        self.vertex_outgoing_halfedges(v).map(|h| self.edge(h))
+    }
+
+    /// Faces incident to `v`, in counter-clockwise order. Holes are skipped.
+    pub fn vertex_faces(&self, v: VertexId) -> impl Iterator<Item = FaceId> + Clone + '_ {
+        self.vertex_outgoing_halfedges(v)
+            .filter_map(|h| self.face(h))
+    }
+
+    /// Number of edges incident to `v`.
+    pub fn vertex_valence(&self, v: VertexId) -> usize {
+        self.vertex_outgoing_halfedges(v).count()
+    }
+
+    /// Half-edges around `f`, in counter-clockwise order.
+    pub fn face_halfedges(&self, f: FaceId) -> HalfEdgeLoop<'_> {
+        self.halfedge_loop(self.face_halfedge(f))
+    }
+
+    /// Corners of `f`, in counter-clockwise order.
+    pub fn face_vertices(&self, f: FaceId) -> impl Iterator<Item = VertexId> + Clone + '_ {
+        self.face_halfedges(f).map(|h| self.origin(h))
+    }
+
+    /// Edges around `f`, in counter-clockwise order.
+    pub fn face_edges(&self, f: FaceId) -> impl Iterator<Item = EdgeId> + Clone + '_ {
+        self.face_halfedges(f).map(|h| self.edge(h))
+    }
+
+    /// Faces sharing an edge with `f`. Boundary edges contribute nothing.
+    pub fn face_faces(&self, f: FaceId) -> impl Iterator<Item = FaceId> + Clone + '_ {
+        self.face_halfedges(f)
+            .filter_map(|h| self.face(self.twin(h)))
+    }
+
+    /// Number of corners of `f`.
+    pub fn face_degree(&self, f: FaceId) -> usize {
+        self.face_halfedges(f).count()
+    }
+
+    /// Positions of the corners of `f`, in counter-clockwise order.
+    pub fn face_positions(&self, f: FaceId) -> impl Iterator<Item = glam::Vec3> + Clone + '_ {
+        self.face_vertices(f).map(|v| self.position(v))
+    }
+
+    /// The half-edges of the boundary loop containing the boundary half-edge `h`.
+    pub fn boundary_loop(&self, h: HalfEdgeId) -> HalfEdgeLoop<'_> {
+        debug_assert!(self.is_boundary_halfedge(h));
+        self.halfedge_loop(h)
+    }
+
+    /// One boundary half-edge per hole in the mesh. Walk each with [`Self::boundary_loop`].
+    pub fn boundary_loops(&self) -> Vec<HalfEdgeId> {
+        let mut visited = vec![false; self.halfedge_count()];
+        let mut loops = Vec::new();
+        for h in self.halfedges() {
+            if visited[h.index()] || !self.is_boundary_halfedge(h) {
+                continue;
+            }
+            for b in self.boundary_loop(h) {
+                visited[b.index()] = true;
+            }
+            loops.push(h);
+        }
+        loops
+    }
+
+    /// The half-edge from `a` to `b`, if they are connected.
+    pub fn find_halfedge(&self, a: VertexId, b: VertexId) -> Option<HalfEdgeId> {
+        self.vertex_outgoing_halfedges(a)
+            .find(|&h| self.dest(h) == b)
+    }
+
+    /// The edge between `a` and `b`, if they are connected.
+    pub fn find_edge(&self, a: VertexId, b: VertexId) -> Option<EdgeId> {
+        self.find_halfedge(a, b).map(|h| self.edge(h))
+    }
+}
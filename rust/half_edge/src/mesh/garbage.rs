@@ -0,0 +1,122 @@
+use super::{EdgeId, ElementKind, FaceId, HalfEdgeId, HalfEdgeMesh, VertexId};
+
+/// Where every element ended up after [`HalfEdgeMesh::garbage_collect`], indexed by the old handle's index.
+/// Removed elements map to `None`.
+#[derive(Clone, Debug, Default)]
+pub struct GarbageCollection {
+    pub vertices: Vec<Option<VertexId>>,
+    pub halfedges: Vec<Option<HalfEdgeId>>,
+    pub edges: Vec<Option<EdgeId>>,
+    pub faces: Vec<Option<FaceId>>,
+}
+
+impl GarbageCollection {
+    /// Drop the entries of a per-element array that belong to removed elements. Since collection preserves
+    /// order, what is left lines up with the new handles.
+    pub fn compact<H, T: Clone>(map: &[Option<H>], values: &[T]) -> Vec<T> {
+        map.iter()
+            .zip(values)
+            .filter(|(new, _)| new.is_some())
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+}
+
+impl HalfEdgeMesh {
+    /// Drop removed elements and renumber the rest, preserving their relative order. Invalidates every handle
+    /// held outside the mesh; the returned maps translate old handles into new ones.
+    pub fn garbage_collect(&mut self) -> GarbageCollection {
+        fn renumber<T: Copy>(
+            removed: impl Iterator<Item = bool>,
+            handle: fn(usize) -> T,
+        ) -> Vec<Option<T>> {
+            let mut next = 0;
+            removed
+                .map(|removed| {
+                    (!removed).then(|| {
+                        next += 1;
+                        handle(next - 1)
+                    })
+                })
+                .collect()
+        }
+
+        let map = GarbageCollection {
+            vertices: renumber(self.vertices.iter().map(|v| v.removed), VertexId::new),
+            halfedges: renumber(
+                self.halfedges
+                    .iter()
+                    .map(|h| self.edges[h.edge.index()].removed),
+                HalfEdgeId::new,
+            ),
+            edges: renumber(self.edges.iter().map(|e| e.removed), EdgeId::new),
+            faces: renumber(self.faces.iter().map(|f| f.removed), FaceId::new),
+        };
+        let vertex =
+            |v: VertexId| map.vertices[v.index()].expect("live element refers to a removed vertex");
+        let halfedge = |h: HalfEdgeId| {
+            map.halfedges[h.index()].expect("live element refers to a removed half-edge")
+        };
+        let edge = |e: EdgeId| map.edges[e.index()].expect("live element refers to a removed edge");
+        let face = |f: FaceId| map.faces[f.index()].expect("live element refers to a removed face");
+
+        let mut positions = Vec::new();
+        let mut vertices = Vec::new();
+        for (i, (v, p)) in self.vertices.iter().zip(&self.positions).enumerate() {
+            if map.vertices[i].is_some() {
+                let mut v = v.clone();
+                v.halfedge = v.halfedge.map(halfedge);
+                vertices.push(v);
+                positions.push(*p);
+            }
+        }
+        let mut halfedges = Vec::new();
+        for (i, h) in self.halfedges.iter().enumerate() {
+            if map.halfedges[i].is_some() {
+                let mut h = h.clone();
+                h.origin = vertex(h.origin);
+                h.twin = halfedge(h.twin);
+                h.next = halfedge(h.next);
+                h.prev = halfedge(h.prev);
+                h.face = h.face.map(face);
+                h.edge = edge(h.edge);
+                halfedges.push(h);
+            }
+        }
+        let mut edges = Vec::new();
+        for (i, e) in self.edges.iter().enumerate() {
+            if map.edges[i].is_some() {
+                let mut e = e.clone();
+                e.halfedge = halfedge(e.halfedge);
+                edges.push(e);
+            }
+        }
+        let mut faces = Vec::new();
+        for (i, f) in self.faces.iter().enumerate() {
+            if map.faces[i].is_some() {
+                let mut f = f.clone();
+                f.halfedge = halfedge(f.halfedge);
+                faces.push(f);
+            }
+        }
+
+        fn live<T>(map: &[Option<T>]) -> Vec<bool> {
+            map.iter().map(Option::is_some).collect()
+        }
+        self.attributes
+            .compact(ElementKind::Vertex, &live(&map.vertices));
+        self.attributes
+            .compact(ElementKind::HalfEdge, &live(&map.halfedges));
+        self.attributes
+            .compact(ElementKind::Edge, &live(&map.edges));
+        self.attributes
+            .compact(ElementKind::Face, &live(&map.faces));
+
+        self.positions = positions;
+        self.vertices = vertices;
+        self.halfedges = halfedges;
+        self.edges = edges;
+        self.faces = faces;
+        map
+    }
+}
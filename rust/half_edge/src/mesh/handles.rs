@@ -0,0 +1,58 @@
+use core::fmt;
+
+macro_rules! define_handle {
+    ($(#[$meta:meta])* $name:ident, $prefix:literal) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(u32);
+
+        impl $name {
+            /// Create a handle from a raw arena index.
+            #[inline(always)]
+            #[must_use]
+            pub const fn new(index: usize) -> Self {
+                Self(index as u32)
+            }
+
+            /// The raw arena index of this handle.
+            #[inline(always)]
+            #[must_use]
+            pub const fn index(self) -> usize {
+                self.0 as usize
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, concat!($prefix, "{}"), self.0)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(self, f)
+            }
+        }
+    };
+}
+
+define_handle!(
+    /// Index of a vertex in a [`HalfEdgeMesh`](super::HalfEdgeMesh).
+    VertexId,
+    "v"
+);
+define_handle!(
+    /// Index of a half-edge in a [`HalfEdgeMesh`](super::HalfEdgeMesh).
+    HalfEdgeId,
+    "h"
+);
+define_handle!(
+    /// Index of an undirected edge (a pair of twin half-edges) in a [`HalfEdgeMesh`](super::HalfEdgeMesh).
+    EdgeId,
+    "e"
+);
+define_handle!(
+    /// Index of a face in a [`HalfEdgeMesh`](super::HalfEdgeMesh).
+    FaceId,
+    "f"
+);
@@ -0,0 +1,276 @@
+use glam::Vec3;
+
+use attributes::Attributes;
+
+mod attributes;
+mod builder;
+mod circulators;
+mod garbage;
+mod handles;
+mod operators;
+
+pub use attributes::{
+    blend_linear, blend_nearest, Attribute, AttributeError, Blend, Element, ElementKind,
+};
+pub use builder::BuildError;
+pub use circulators::{HalfEdgeLoop, VertexRing};
+pub use garbage::GarbageCollection;
+pub use handles::{EdgeId, FaceId, HalfEdgeId, VertexId};
+pub use operators::{EdgeSplit, TopologyError};
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Vertex {
+    /// One outgoing half-edge. For boundary vertices this is always the outgoing boundary half-edge, so that
+    /// circulation starts (and ends) on the boundary. `None` for isolated vertices.
+    pub halfedge: Option<HalfEdgeId>,
+    pub removed: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct HalfEdge {
+    pub origin: VertexId,
+    pub twin: HalfEdgeId,
+    pub next: HalfEdgeId,
+    pub prev: HalfEdgeId,
+    /// `None` for boundary half-edges, which link up into loops around each hole.
+    pub face: Option<FaceId>,
+    pub edge: EdgeId,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Edge {
+    pub halfedge: HalfEdgeId,
+    /// Removing an edge removes both of its half-edges.
+    pub removed: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Face {
+    pub halfedge: HalfEdgeId,
+    pub removed: bool,
+}
+
+/// An index-based half-edge mesh.
+///
+/// Every undirected edge is stored as a pair of twin half-edges. Edges on the boundary of the surface still get
+/// both half-edges; the one without a face is a *boundary half-edge*, and boundary half-edges are linked with
+/// `next`/`prev` into a loop around each hole. This keeps every traversal total: `next`, `prev` and `twin` are
+/// always defined.
+///
+/// Elements are addressed by index handles. Operations that delete elements (such as
+/// [`collapse_edge`](Self::collapse_edge)) only mark them as removed, so that every other handle stays valid;
+/// the iterators skip removed elements, while the `*_count` methods report the size of the underlying arenas.
+/// [`garbage_collect`](Self::garbage_collect) compacts the arenas and renumbers what is left.
+#[derive(Clone, Debug, Default)]
+pub struct HalfEdgeMesh {
+    pub(crate) positions: Vec<Vec3>,
+    pub(crate) vertices: Vec<Vertex>,
+    pub(crate) halfedges: Vec<HalfEdge>,
+    pub(crate) edges: Vec<Edge>,
+    pub(crate) faces: Vec<Face>,
+    pub(crate) attributes: Attributes,
+}
+
+impl HalfEdgeMesh {
+    /// An empty mesh.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Size of the vertex arena, including removed vertices. Per-vertex arrays indexed by
+    /// [`VertexId::index`] should be this long.
+    #[inline]
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Size of the half-edge arena, including removed half-edges.
+    #[inline]
+    pub fn halfedge_count(&self) -> usize {
+        self.halfedges.len()
+    }
+
+    /// Size of the edge arena, including removed edges.
+    #[inline]
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Size of the face arena, including removed faces.
+    #[inline]
+    pub fn face_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    /// Live (not removed) vertices.
+    pub fn vertices(&self) -> impl Iterator<Item = VertexId> + Clone + '_ {
+        (0..self.vertices.len())
+            .filter(|&i| !self.vertices[i].removed)
+            .map(VertexId::new)
+    }
+
+    /// Live (not removed) half-edges.
+    pub fn halfedges(&self) -> impl Iterator<Item = HalfEdgeId> + Clone + '_ {
+        (0..self.halfedges.len())
+            .filter(|&i| !self.edges[self.halfedges[i].edge.index()].removed)
+            .map(HalfEdgeId::new)
+    }
+
+    /// Live (not removed) edges.
+    pub fn edges(&self) -> impl Iterator<Item = EdgeId> + Clone + '_ {
+        (0..self.edges.len())
+            .filter(|&i| !self.edges[i].removed)
+            .map(EdgeId::new)
+    }
+
+    /// Live (not removed) faces.
+    pub fn faces(&self) -> impl Iterator<Item = FaceId> + Clone + '_ {
+        (0..self.faces.len())
+            .filter(|&i| !self.faces[i].removed)
+            .map(FaceId::new)
+    }
+
+    #[inline]
+    pub fn is_removed_vertex(&self, v: VertexId) -> bool {
+        self.vertices[v.index()].removed
+    }
+
+    #[inline]
+    pub fn is_removed_halfedge(&self, h: HalfEdgeId) -> bool {
+        self.is_removed_edge(self.edge(h))
+    }
+
+    #[inline]
+    pub fn is_removed_edge(&self, e: EdgeId) -> bool {
+        self.edges[e.index()].removed
+    }
+
+    #[inline]
+    pub fn is_removed_face(&self, f: FaceId) -> bool {
+        self.faces[f.index()].removed
+    }
+
+    /// Whether any element has been removed since the last [`garbage_collect`](Self::garbage_collect).
+    pub fn has_garbage(&self) -> bool {
+        self.vertices.iter().any(|v| v.removed)
+            || self.edges.iter().any(|e| e.removed)
+            || self.faces.iter().any(|f| f.removed)
+    }
+
+    #[inline]
+    pub fn position(&self, v: VertexId) -> Vec3 {
+        self.positions[v.index()]
+    }
+
+    #[inline]
+    pub fn set_position(&mut self, v: VertexId, position: Vec3) {
+        self.positions[v.index()] = position;
+    }
+
+    /// All vertex positions, indexed by [`VertexId::index`].
+    #[inline]
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    #[inline]
+    pub fn positions_mut(&mut self) -> &mut [Vec3] {
+        &mut self.positions
+    }
+
+    /// One outgoing half-edge of `v`, or `None` if the vertex is isolated. For boundary vertices this is the
+    /// outgoing boundary half-edge.
+    #[inline]
+    pub fn vertex_halfedge(&self, v: VertexId) -> Option<HalfEdgeId> {
+        self.vertices[v.index()].halfedge
+    }
+
+    #[inline]
+    pub fn edge_halfedge(&self, e: EdgeId) -> HalfEdgeId {
+        self.edges[e.index()].halfedge
+    }
+
+    #[inline]
+    pub fn face_halfedge(&self, f: FaceId) -> HalfEdgeId {
+        self.faces[f.index()].halfedge
+    }
+
+    #[inline]
+    pub fn twin(&self, h: HalfEdgeId) -> HalfEdgeId {
+        self.halfedges[h.index()].twin
+    }
+
+    #[inline]
+    pub fn next(&self, h: HalfEdgeId) -> HalfEdgeId {
+        self.halfedges[h.index()].next
+    }
+
+    #[inline]
+    pub fn prev(&self, h: HalfEdgeId) -> HalfEdgeId {
+        self.halfedges[h.index()].prev
+    }
+
+    /// The vertex `h` points away from.
+    #[inline]
+    pub fn origin(&self, h: HalfEdgeId) -> VertexId {
+        self.halfedges[h.index()].origin
+    }
+
+    /// The vertex `h` points to.
+    #[inline]
+    pub fn dest(&self, h: HalfEdgeId) -> VertexId {
+        self.origin(self.twin(h))
+    }
+
+    /// The face to the left of `h`, or `None` for boundary half-edges.
+    #[inline]
+    pub fn face(&self, h: HalfEdgeId) -> Option<FaceId> {
+        self.halfedges[h.index()].face
+    }
+
+    #[inline]
+    pub fn edge(&self, h: HalfEdgeId) -> EdgeId {
+        self.halfedges[h.index()].edge
+    }
+
+    /// Both vertices of `e`, in the direction of its primary half-edge.
+    #[inline]
+    pub fn edge_vertices(&self, e: EdgeId) -> [VertexId; 2] {
+        let h = self.edge_halfedge(e);
+        [self.origin(h), self.dest(h)]
+    }
+
+    #[inline]
+    pub fn is_boundary_halfedge(&self, h: HalfEdgeId) -> bool {
+        self.face(h).is_none()
+    }
+
+    #[inline]
+    pub fn is_boundary_edge(&self, e: EdgeId) -> bool {
+        let h = self.edge_halfedge(e);
+        self.is_boundary_halfedge(h) || self.is_boundary_halfedge(self.twin(h))
+    }
+
+    #[inline]
+    pub fn is_boundary_vertex(&self, v: VertexId) -> bool {
+        self.vertex_halfedge(v)
+            .is_some_and(|h| self.is_boundary_halfedge(h))
+    }
+
+    #[inline]
+    pub fn is_isolated_vertex(&self, v: VertexId) -> bool {
+        self.vertex_halfedge(v).is_none()
+    }
+
+    /// The vector from the origin to the destination of `h`.
+    #[inline]
+    pub fn halfedge_vector(&self, h: HalfEdgeId) -> Vec3 {
+        self.position(self.dest(h)) - self.position(self.origin(h))
+    }
+
+    #[inline]
+    pub fn edge_length(&self, e: EdgeId) -> f32 {
+        self.halfedge_vector(self.edge_halfedge(e)).length()
+    }
+}
@@ -0,0 +1,549 @@
+//! Local topological (Euler) operators.
+//!
+//! Each operator either succeeds and leaves a valid mesh behind, or refuses with a [`TopologyError`] and leaves
+//! the mesh untouched. Operators that delete elements only mark them as removed; see
+//! [`HalfEdgeMesh::garbage_collect`].
+
+use core::fmt;
+
+use glam::Vec3;
+
+use super::{EdgeId, ElementKind, Face, FaceId, HalfEdgeId, HalfEdgeMesh, Vertex, VertexId};
+
+/// Why a local operator refused to run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopologyError {
+    /// One of the arguments has already been removed.
+    RemovedElement,
+    /// Boundary edges cannot be flipped.
+    BoundaryEdge,
+    /// The operation is only defined for triangles.
+    NotTriangle,
+    /// The operation would create an edge that already exists.
+    EdgeExists,
+    /// Collapsing would make the mesh non-manifold (the link condition does not hold).
+    LinkCondition,
+    /// The vertex is not a corner of the face.
+    NotInFace { vertex: VertexId },
+    /// The two corners are already joined by an edge of the face.
+    AdjacentCorners,
+    /// The half-edge is not on the boundary.
+    NotBoundary,
+}
+
+impl fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RemovedElement => f.write_str("element has been removed"),
+            Self::BoundaryEdge => f.write_str("boundary edges cannot be flipped"),
+            Self::NotTriangle => f.write_str("operation requires triangles"),
+            Self::EdgeExists => f.write_str("the new edge already exists"),
+            Self::LinkCondition => f.write_str("collapse would make the mesh non-manifold"),
+            Self::NotInFace { vertex } => write!(f, "{vertex} is not a corner of the face"),
+            Self::AdjacentCorners => {
+                f.write_str("corners are already connected by an edge of the face")
+            }
+            Self::NotBoundary => f.write_str("half-edge is not on the boundary"),
+        }
+    }
+}
+
+impl std::error::Error for TopologyError {}
+
+/// The elements created by [`HalfEdgeMesh::split_edge`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EdgeSplit {
+    /// The vertex inserted on the edge.
+    pub vertex: VertexId,
+    /// The new half-edge from the inserted vertex to the original destination. The original half-edge now ends
+    /// at the inserted vertex.
+    pub halfedge: HalfEdgeId,
+    /// The edge `halfedge` belongs to.
+    pub edge: EdgeId,
+}
+
+impl HalfEdgeMesh {
+    /// Add a vertex that is not connected to anything.
+    pub fn add_vertex(&mut self, position: Vec3) -> VertexId {
+        self.positions.push(position);
+        self.vertices.push(Vertex::default());
+        self.attributes.push_default(ElementKind::Vertex);
+        VertexId::new(self.vertices.len() - 1)
+    }
+
+    fn add_face(&mut self, halfedge: HalfEdgeId) -> FaceId {
+        self.faces.push(Face {
+            halfedge,
+            removed: false,
+        });
+        self.attributes.push_default(ElementKind::Face);
+        FaceId::new(self.faces.len() - 1)
+    }
+
+    #[inline]
+    fn link(&mut self, h: HalfEdgeId, next: HalfEdgeId) {
+        self.halfedges[h.index()].next = next;
+        self.halfedges[next.index()].prev = h;
+    }
+
+    /// Restore the invariant that boundary vertices reference their outgoing boundary half-edge.
+    pub(crate) fn adjust_outgoing_halfedge(&mut self, v: VertexId) {
+        if let Some(h) = self
+            .vertex_outgoing_halfedges(v)
+            .find(|&h| self.is_boundary_halfedge(h))
+        {
+            self.vertices[v.index()].halfedge = Some(h);
+        }
+    }
+
+    /// Insert a new vertex at `position` in the middle of edge `e`. The faces on either side gain a corner;
+    /// nothing is triangulated. The new vertex and corners blend the attributes at the ends of the edge by how
+    /// far along it `position` lies, and both halves of the edge keep its attributes.
+    pub fn split_edge(&mut self, e: EdgeId, position: Vec3) -> Result<EdgeSplit, TopologyError> {
+        if self.is_removed_edge(e) {
+            return Err(TopologyError::RemovedElement);
+        }
+        let h = self.edge_halfedge(e);
+        let t = self.twin(h);
+        let b = self.dest(h);
+        let h_next = self.next(h);
+        let t_prev = self.prev(t);
+
+        let m = self.add_vertex(position);
+        let n = self.add_edge_pair(m, b);
+        let nt = self.twin(n);
+
+        self.halfedges[t.index()].origin = m;
+        self.halfedges[n.index()].face = self.face(h);
+        self.halfedges[nt.index()].face = self.face(t);
+        self.link(h, n);
+        self.link(n, h_next);
+        self.link(t_prev, nt);
+        self.link(nt, t);
+
+        if self.vertices[b.index()].halfedge == Some(t) {
+            self.vertices[b.index()].halfedge = Some(nt);
+        }
+        self.vertices[m.index()].halfedge = Some(if self.is_boundary_halfedge(t) { t } else { n });
+
+        if !self.attributes.is_empty() {
+            let a = self.origin(h);
+            let along = self.position(b) - self.position(a);
+            let s = (position - self.position(a)).dot(along) / along.length_squared();
+            let s = if s.is_finite() {
+                s.clamp(0.0, 1.0)
+            } else {
+                0.5
+            };
+            self.blend_attributes(m, &[(a, 1.0 - s), (b, s)]);
+            self.copy_attributes(self.edge(n), e);
+            self.blend_attributes(n, &[(h, 1.0 - s), (h_next, s)]);
+            // `t` used to hold the corner at `b`, which `nt` now is; `t` itself has become the corner at `m`.
+            self.copy_attributes(nt, t);
+            self.blend_attributes(t, &[(self.next(t), 1.0 - s), (nt, s)]);
+        }
+
+        Ok(EdgeSplit {
+            vertex: m,
+            halfedge: n,
+            edge: self.edge(n),
+        })
+    }
+
+    /// Split face `f` in two by connecting its corners `a` and `b` with a new edge. Returns the new half-edge
+    /// from `a` to `b`, which borders the newly created face; `f` keeps the half-edges from `a` round to `b`.
+    /// Refuses if `a` and `b` are already connected elsewhere, since that would create a double edge. The new
+    /// face copies the attributes of `f`, and the new corners those of the corners at `a` and `b`.
+    pub fn split_face(
+        &mut self,
+        f: FaceId,
+        a: VertexId,
+        b: VertexId,
+    ) -> Result<HalfEdgeId, TopologyError> {
+        if self.is_removed_face(f) {
+            return Err(TopologyError::RemovedElement);
+        }
+        let corner = |v: VertexId| {
+            self.face_halfedges(f)
+                .find(|&h| self.origin(h) == v)
+                .ok_or(TopologyError::NotInFace { vertex: v })
+        };
+        let ha = corner(a)?;
+        let hb = corner(b)?;
+        if ha == hb || self.next(ha) == hb || self.next(hb) == ha {
+            return Err(TopologyError::AdjacentCorners);
+        }
+        if self.find_halfedge(a, b).is_some() {
+            return Err(TopologyError::EdgeExists);
+        }
+
+        let pa = self.prev(ha);
+        let pb = self.prev(hb);
+        let ab = self.add_edge_pair(a, b);
+        let ba = self.twin(ab);
+
+        self.link(pb, ba);
+        self.link(ba, ha);
+        self.link(pa, ab);
+        self.link(ab, hb);
+
+        self.halfedges[ba.index()].face = Some(f);
+        self.faces[f.index()].halfedge = ha;
+        let g = self.add_face(ab);
+        let mut h = ab;
+        loop {
+            self.halfedges[h.index()].face = Some(g);
+            h = self.next(h);
+            if h == ab {
+                break;
+            }
+        }
+        self.copy_attributes(g, f);
+        self.copy_attributes(ab, ha);
+        self.copy_attributes(ba, hb);
+        Ok(ab)
+    }
+
+    /// Close the hole bounded by the boundary loop through `h` with a single new face, whose corners are the
+    /// loop's vertices in order. The new face copies the attributes of the face across `h`, and each new
+    /// corner those of the corner at the same vertex across its edge.
+    pub fn fill_boundary_loop(&mut self, h: HalfEdgeId) -> Result<FaceId, TopologyError> {
+        if self.is_removed_halfedge(h) {
+            return Err(TopologyError::RemovedElement);
+        }
+        if !self.is_boundary_halfedge(h) {
+            return Err(TopologyError::NotBoundary);
+        }
+        let ring: Vec<HalfEdgeId> = self.boundary_loop(h).collect();
+        let f = self.add_face(h);
+        if let Some(neighbour) = self.face(self.twin(h)) {
+            self.copy_attributes(f, neighbour);
+        }
+        for &b in &ring {
+            self.halfedges[b.index()].face = Some(f);
+            let across = self.next(self.twin(b));
+            self.copy_attributes(b, across);
+        }
+        for &b in &ring {
+            self.adjust_outgoing_halfedge(self.origin(b));
+        }
+        Ok(f)
+    }
+
+    /// Insert a new vertex at `position` inside face `f` and connect it to every corner, replacing `f` with a
+    /// fan of triangles. The new faces copy the attributes of `f`. The new vertex, and its corners, blend those
+    /// of the face's corners: by the barycentric coordinates of `position` in a triangle, equally otherwise.
+    pub fn insert_vertex(&mut self, f: FaceId, position: Vec3) -> Result<VertexId, TopologyError> {
+        if self.is_removed_face(f) {
+            return Err(TopologyError::RemovedElement);
+        }
+        let ring: Vec<HalfEdgeId> = self.face_halfedges(f).collect();
+        let m = self.add_vertex(position);
+
+        // `spokes[i]` runs from the new vertex to corner `i`.
+        let spokes: Vec<HalfEdgeId> = ring
+            .iter()
+            .map(|&h| {
+                let corner = self.origin(h);
+                self.add_edge_pair(m, corner)
+            })
+            .collect();
+        let k = ring.len();
+        for i in 0..k {
+            let h = ring[i];
+            let inward = self.twin(spokes[(i + 1) % k]);
+            let outward = spokes[i];
+            let face = if i == 0 { f } else { self.add_face(h) };
+            self.faces[face.index()].halfedge = h;
+            for x in [h, inward, outward] {
+                self.halfedges[x.index()].face = Some(face);
+            }
+            self.link(h, inward);
+            self.link(inward, outward);
+            self.link(outward, h);
+        }
+        self.vertices[m.index()].halfedge = Some(spokes[0]);
+
+        if !self.attributes.is_empty() {
+            let weights = self.corner_weights(&ring, position);
+            let corners: Vec<(HalfEdgeId, f32)> = ring.iter().copied().zip(weights).collect();
+            let vertices: Vec<(VertexId, f32)> =
+                corners.iter().map(|&(h, w)| (self.origin(h), w)).collect();
+            self.blend_attributes(m, &vertices);
+            for i in 0..k {
+                let face = self.face(ring[i]).expect("fan triangles have faces");
+                if face != f {
+                    self.copy_attributes(face, f);
+                }
+                self.blend_attributes(spokes[i], &corners);
+                self.copy_attributes(self.twin(spokes[(i + 1) % k]), ring[(i + 1) % k]);
+            }
+        }
+        Ok(m)
+    }
+
+    /// Weights of the corners of a face for interpolating at `position`: barycentric coordinates in a
+    /// triangle, clamped to the triangle, and equal weights for other polygons.
+    fn corner_weights(&self, ring: &[HalfEdgeId], position: Vec3) -> Vec<f32> {
+        let uniform = vec![1.0 / ring.len() as f32; ring.len()];
+        let [a, b, c] = match ring {
+            &[a, b, c] => [a, b, c].map(|h| self.position(self.origin(h))),
+            _ => return uniform,
+        };
+        let normal = (b - a).cross(c - a);
+        let area = normal.length_squared();
+        if area <= f32::EPSILON * f32::EPSILON {
+            return uniform;
+        }
+        let weights = [(b, c), (c, a), (a, b)]
+            .map(|(p, q)| ((p - position).cross(q - position).dot(normal) / area).max(0.0));
+        let total: f32 = weights.iter().sum();
+        if total > 0.0 {
+            weights.iter().map(|w| w / total).collect()
+        } else {
+            uniform
+        }
+    }
+
+    /// Whether [`Self::flip_edge`] would succeed.
+    pub fn is_flip_ok(&self, e: EdgeId) -> Result<(), TopologyError> {
+        if self.is_removed_edge(e) {
+            return Err(TopologyError::RemovedElement);
+        }
+        if self.is_boundary_edge(e) {
+            return Err(TopologyError::BoundaryEdge);
+        }
+        let h = self.edge_halfedge(e);
+        let t = self.twin(h);
+        if self.next(self.next(self.next(h))) != h || self.next(self.next(self.next(t))) != t {
+            return Err(TopologyError::NotTriangle);
+        }
+        let c = self.dest(self.next(h));
+        let d = self.dest(self.next(t));
+        if c == d || self.find_halfedge(c, d).is_some() {
+            return Err(TopologyError::EdgeExists);
+        }
+        Ok(())
+    }
+
+    /// Rotate the edge shared by two triangles so that it connects their opposite corners instead. The edge
+    /// keeps its attributes, and its half-edges take over those of the corners they now start from.
+    pub fn flip_edge(&mut self, e: EdgeId) -> Result<(), TopologyError> {
+        self.is_flip_ok(e)?;
+
+        // Before: h = a->b, h1 = b->c, h2 = c->a and t = b->a, t1 = a->d, t2 = d->b.
+        // After:  h = d->c, h2, t1 and t = c->d, t2, h1.
+        let h = self.edge_halfedge(e);
+        let t = self.twin(h);
+        let (h1, h2) = (self.next(h), self.prev(h));
+        let (t1, t2) = (self.next(t), self.prev(t));
+        let (a, b) = (self.origin(h), self.origin(t));
+        let (c, d) = (self.origin(h2), self.origin(t2));
+        let (fh, ft) = (self.face(h), self.face(t));
+
+        self.halfedges[h.index()].origin = d;
+        self.halfedges[t.index()].origin = c;
+        self.link(h, h2);
+        self.link(h2, t1);
+        self.link(t1, h);
+        self.link(t, t2);
+        self.link(t2, h1);
+        self.link(h1, t);
+        self.halfedges[t1.index()].face = fh;
+        self.halfedges[h1.index()].face = ft;
+        if let Some(fh) = fh {
+            self.faces[fh.index()].halfedge = h;
+        }
+        if let Some(ft) = ft {
+            self.faces[ft.index()].halfedge = t;
+        }
+
+        if self.vertices[a.index()].halfedge == Some(h) {
+            self.vertices[a.index()].halfedge = Some(t1);
+        }
+        if self.vertices[b.index()].halfedge == Some(t) {
+            self.vertices[b.index()].halfedge = Some(h1);
+        }
+        self.copy_attributes(h, t2);
+        self.copy_attributes(t, h2);
+        Ok(())
+    }
+
+    fn loop_length(&self, h: HalfEdgeId) -> usize {
+        self.halfedge_loop(h).count()
+    }
+
+    /// Whether [`Self::collapse_edge`] would succeed, i.e. whether collapsing `h` keeps the mesh manifold.
+    ///
+    /// This is the link condition: the only vertices adjacent to both ends of the edge must be the opposite
+    /// corners of the triangles on either side, and no other pair of faces may become duplicates.
+    pub fn is_collapse_ok(&self, h: HalfEdgeId) -> Result<(), TopologyError> {
+        if self.is_removed_halfedge(h) {
+            return Err(TopologyError::RemovedElement);
+        }
+        let t = self.twin(h);
+        let a = self.origin(h);
+        let b = self.dest(h);
+
+        // Joining two separate parts of the boundary through the interior would pinch the surface.
+        if self.is_boundary_vertex(a)
+            && self.is_boundary_vertex(b)
+            && !self.is_boundary_edge(self.edge(h))
+        {
+            return Err(TopologyError::LinkCondition);
+        }
+
+        let mut opposite = Vec::with_capacity(2);
+        for side in [h, t] {
+            if self.loop_length(side) == 3 {
+                // Collapsing merges the other two sides of this triangle; if neither has a face beyond it,
+                // the result would be a dangling edge.
+                let (n, p) = (self.next(side), self.prev(side));
+                if self.is_boundary_halfedge(self.twin(n))
+                    && self.is_boundary_halfedge(self.twin(p))
+                {
+                    return Err(TopologyError::LinkCondition);
+                }
+                opposite.push(self.dest(n));
+            }
+        }
+        if opposite.len() == 2 && opposite[0] == opposite[1] {
+            return Err(TopologyError::LinkCondition);
+        }
+        for c in self.vertex_vertices(a) {
+            if c != b && !opposite.contains(&c) && self.vertex_vertices(b).any(|x| x == c) {
+                return Err(TopologyError::LinkCondition);
+            }
+        }
+
+        // Faces around `a` other than the two on the edge must not touch `b`, or become copies of faces
+        // around `b` once `a` is replaced by `b`.
+        let adjacent = [self.face(h), self.face(t)];
+        let sorted_corners = |f: FaceId| {
+            let mut corners: Vec<VertexId> = self
+                .face_vertices(f)
+                .map(|v| if v == a { b } else { v })
+                .collect();
+            corners.sort_unstable();
+            corners
+        };
+        let around_b: Vec<Vec<VertexId>> = self
+            .vertex_faces(b)
+            .filter(|f| !adjacent.contains(&Some(*f)))
+            .map(sorted_corners)
+            .collect();
+        for f in self.vertex_faces(a) {
+            if adjacent.contains(&Some(f)) {
+                continue;
+            }
+            if self.face_vertices(f).any(|v| v == b) || around_b.contains(&sorted_corners(f)) {
+                return Err(TopologyError::LinkCondition);
+            }
+        }
+        Ok(())
+    }
+
+    /// Collapse half-edge `h`, merging its origin into its destination. The destination keeps its position;
+    /// move it afterwards if needed. Triangles on either side of the edge disappear and their remaining two
+    /// edges are merged. Returns the surviving vertex.
+    ///
+    /// The destination keeps its attributes, as do the corners that move over from the origin. Use
+    /// [`Self::blend_attributes`] to mix in the origin's values.
+    pub fn collapse_edge(&mut self, h: HalfEdgeId) -> Result<VertexId, TopologyError> {
+        self.is_collapse_ok(h)?;
+
+        let t = self.twin(h);
+        let a = self.origin(h);
+        let b = self.dest(h);
+        let (h_next, h_prev) = (self.next(h), self.prev(h));
+        let (t_next, t_prev) = (self.next(t), self.prev(t));
+
+        let outgoing: Vec<HalfEdgeId> = self.vertex_outgoing_halfedges(a).collect();
+        for o in outgoing {
+            self.halfedges[o.index()].origin = b;
+        }
+        self.link(h_prev, h_next);
+        self.link(t_prev, t_next);
+        if let Some(f) = self.face(h) {
+            self.faces[f.index()].halfedge = h_next;
+        }
+        if let Some(f) = self.face(t) {
+            self.faces[f.index()].halfedge = t_next;
+        }
+        self.vertices[b.index()].halfedge = Some(h_next);
+        let e = self.edge(h);
+        self.edges[e.index()].removed = true;
+        self.vertices[a.index()].removed = true;
+        self.vertices[a.index()].halfedge = None;
+
+        for side in [h_next, t_next] {
+            if !self.is_removed_halfedge(side) && self.next(self.next(side)) == side {
+                self.merge_digon(side);
+            }
+        }
+        self.adjust_outgoing_halfedge(b);
+        Ok(b)
+    }
+
+    /// Remove a two-sided loop left behind by a collapse, gluing the twins of its two half-edges together.
+    fn merge_digon(&mut self, p: HalfEdgeId) {
+        let n = self.next(p);
+        let (tp, tn) = (self.twin(p), self.twin(n));
+        let (kept, dropped) = (self.edge(tp), self.edge(tn));
+        let (x, y) = (self.origin(p), self.origin(n));
+
+        self.halfedges[tp.index()].twin = tn;
+        self.halfedges[tn.index()].twin = tp;
+        self.halfedges[tn.index()].edge = kept;
+        self.halfedges[p.index()].edge = dropped;
+        self.edges[kept.index()].halfedge = tp;
+        self.edges[dropped.index()].halfedge = p;
+        self.edges[dropped.index()].removed = true;
+        if let Some(f) = self.face(p) {
+            self.faces[f.index()].removed = true;
+        }
+
+        if matches!(self.vertices[x.index()].halfedge, Some(h) if h == p || h == n) {
+            self.vertices[x.index()].halfedge = Some(tn);
+        }
+        if matches!(self.vertices[y.index()].halfedge, Some(h) if h == p || h == n) {
+            self.vertices[y.index()].halfedge = Some(tp);
+        }
+        self.adjust_outgoing_halfedge(x);
+        self.adjust_outgoing_halfedge(y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad() -> HalfEdgeMesh {
+        let positions = vec![Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0), Vec3::Y];
+        HalfEdgeMesh::from_triangles(positions, &[0, 1, 2, 0, 2, 3]).unwrap()
+    }
+
+    #[test]
+    fn split_edge_refuses_removed_edges() {
+        let mut mesh = quad();
+        let e = mesh.find_edge(VertexId::new(0), VertexId::new(1)).unwrap();
+        let h = mesh.edge_halfedge(e);
+        mesh.collapse_edge(h).unwrap();
+        assert!(mesh.is_removed_edge(e));
+        assert_eq!(
+            mesh.split_edge(e, Vec3::ZERO).unwrap_err(),
+            TopologyError::RemovedElement
+        );
+        assert!(mesh.validate().is_ok());
+    }
+
+    #[test]
+    fn split_edge_inserts_a_vertex_on_both_sides() {
+        let mut mesh = quad();
+        let e = mesh.find_edge(VertexId::new(0), VertexId::new(2)).unwrap();
+        let split = mesh.split_edge(e, Vec3::new(0.5, 0.5, 0.0)).unwrap();
+        assert_eq!(mesh.vertex_valence(split.vertex), 2);
+        assert!(mesh.faces().all(|f| mesh.face_degree(f) == 4));
+        assert!(mesh.validate().is_ok());
+    }
+}
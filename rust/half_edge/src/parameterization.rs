@@ -0,0 +1,833 @@
+//! Least squares conformal maps (Lévy et al., "Least Squares Conformal Maps for Automatic Texture Atlas
+//! Generation").
+//!
+//! Surfaces that are not topological disks are first cut open along seams. Each handle contributes a pair of
+//! loops found with Eppstein's tree-cotree construction, closed surfaces without handles are cut along a single
+//! path, and separate boundaries (and the seams themselves) are joined up with shortest paths, so that every
+//! chart ends up with one boundary. Corners on either side of a seam get their own texture coordinates, which
+//! is why the result is per corner.
+//!
+//! Every chart is then flattened by minimising the conformal energy with two of its vertices pinned, scaled to
+//! its surface area, and packed into the unit square. The energy is measured either on the chart's own
+//! triangles or, as the Dirichlet energy minus the signed area of the map, on their intrinsic Delaunay
+//! triangulation.
+
+use core::fmt;
+use std::collections::{HashMap, VecDeque};
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    intrinsic::IntrinsicTriangulation,
+    io::MeshData,
+    mesh::{AttributeError, EdgeId, HalfEdgeId, HalfEdgeMesh, VertexId},
+    sparse::{Cholesky, SolveError, TripletMatrix},
+    union_find::UnionFind,
+};
+
+/// Settings for [`HalfEdgeMesh::lscm`].
+#[derive(Clone, Debug)]
+pub struct ParameterizationConfig {
+    /// Edges to cut along in any case.
+    pub seams: Vec<EdgeId>,
+    /// Add seams wherever needed to make every chart a disk.
+    pub cut_automatically: bool,
+    /// Space left between packed charts, as a fraction of the atlas size.
+    pub margin: f32,
+    /// Measure the conformal energy on the intrinsic Delaunay triangulation of each chart instead of its own
+    /// triangles, so that slivers and obtuse triangles contribute no negative cotangent weights.
+    pub intrinsic_delaunay: bool,
+}
+
+impl Default for ParameterizationConfig {
+    fn default() -> Self {
+        Self {
+            seams: Vec::new(),
+            cut_automatically: true,
+            margin: 0.01,
+            intrinsic_delaunay: false,
+        }
+    }
+}
+
+/// The result of [`HalfEdgeMesh::lscm`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Parameterization {
+    /// A texture coordinate per corner, indexed by [`HalfEdgeId::index`], inside the unit square. Boundary
+    /// half-edges hold zero.
+    pub uvs: Vec<Vec2>,
+    /// Every edge the mesh was cut along, including the requested seams.
+    pub seams: Vec<EdgeId>,
+    /// The number of separately flattened pieces.
+    pub charts: usize,
+}
+
+/// Why a mesh could not be parameterized.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParameterizationError {
+    /// Conformal maps are only computed for triangle meshes.
+    NotTriangleMesh,
+    /// The mesh has removed elements; garbage collect it first.
+    HasGarbage,
+    /// A seam edge does not exist in the mesh.
+    InvalidSeam { edge: EdgeId },
+    /// A chart's system could not be solved, typically because all its triangles are degenerate.
+    Solve(SolveError),
+    /// The texture coordinates could not be stored, because another attribute took their name.
+    Attribute(AttributeError),
+}
+
+impl fmt::Display for ParameterizationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTriangleMesh => f.write_str("parameterization requires a triangle mesh"),
+            Self::HasGarbage => f.write_str("the mesh has removed elements"),
+            Self::InvalidSeam { edge } => write!(f, "seam edge {edge} does not exist"),
+            Self::Solve(err) => write!(f, "chart could not be flattened: {err}"),
+            Self::Attribute(err) => write!(f, "texture coordinates could not be stored: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ParameterizationError {}
+
+impl From<SolveError> for ParameterizationError {
+    fn from(err: SolveError) -> Self {
+        Self::Solve(err)
+    }
+}
+
+impl From<AttributeError> for ParameterizationError {
+    fn from(err: AttributeError) -> Self {
+        Self::Attribute(err)
+    }
+}
+
+impl HalfEdgeMesh {
+    /// Cut the mesh into disk-like charts and flatten each with a least squares conformal map.
+    pub fn lscm(
+        &self,
+        config: &ParameterizationConfig,
+    ) -> Result<Parameterization, ParameterizationError> {
+        if self.has_garbage() {
+            return Err(ParameterizationError::HasGarbage);
+        }
+        if self.faces().any(|f| self.face_degree(f) != 3) {
+            return Err(ParameterizationError::NotTriangleMesh);
+        }
+        let mut cut = vec![false; self.edge_count()];
+        for &edge in &config.seams {
+            if edge.index() >= self.edge_count() {
+                return Err(ParameterizationError::InvalidSeam { edge });
+            }
+            cut[edge.index()] = true;
+        }
+        if config.cut_automatically {
+            self.cut_to_disks(&mut cut);
+        }
+
+        let (wedges, wedge_vertex) = self.wedges(&cut);
+        let charts = self.charts(&cut);
+        let chart_count = charts.iter().max().map_or(0, |c| c + 1);
+        let mut chart_faces = vec![Vec::new(); chart_count];
+        for f in self.faces() {
+            chart_faces[charts[f.index()]].push(f);
+        }
+
+        let mut wedge_uv = vec![Vec2::ZERO; wedge_vertex.len()];
+        let mut bounds = Vec::with_capacity(chart_count);
+        for faces in &chart_faces {
+            let mut chart_wedges: Vec<usize> = faces
+                .iter()
+                .flat_map(|&f| self.face_halfedges(f))
+                .map(|h| wedges[h.index()])
+                .collect();
+            chart_wedges.sort_unstable();
+            chart_wedges.dedup();
+            let corners: Vec<[usize; 3]> = faces
+                .iter()
+                .map(|&f| {
+                    let mut h = self.face_halfedges(f).map(|h| wedges[h.index()]);
+                    [(); 3].map(|_| h.next().expect("a triangle"))
+                })
+                .collect();
+            let positions = |w: usize| self.position(wedge_vertex[w]);
+            let uv = flatten_chart(
+                &chart_wedges,
+                &corners,
+                positions,
+                config.intrinsic_delaunay,
+            )?;
+
+            // Scale the chart so its texture area matches its surface area, then move it to the origin.
+            let surface: f32 = faces.iter().map(|&f| self.face_area(f)).sum();
+            let flat: f32 = corners
+                .iter()
+                .map(|c| 0.5 * (uv[&c[1]] - uv[&c[0]]).perp_dot(uv[&c[2]] - uv[&c[0]]))
+                .sum::<f32>()
+                .abs();
+            let scale = if flat > 0.0 {
+                (surface / flat).sqrt()
+            } else {
+                1.0
+            };
+            let min = uv
+                .values()
+                .fold(Vec2::splat(f32::INFINITY), |m, &p| m.min(p));
+            let mut max = Vec2::ZERO;
+            for (&w, &p) in &uv {
+                wedge_uv[w] = (p - min) * scale;
+                max = max.max(wedge_uv[w]);
+            }
+            bounds.push(max);
+        }
+
+        let offsets = pack(&bounds, config.margin);
+        let mut uvs = vec![Vec2::ZERO; self.halfedge_count()];
+        for h in self.halfedges() {
+            if let Some(f) = self.face(h) {
+                let (offset, scale) = offsets[charts[f.index()]];
+                uvs[h.index()] = (wedge_uv[wedges[h.index()]] + offset) * scale;
+            }
+        }
+        Ok(Parameterization {
+            uvs,
+            seams: self.edges().filter(|e| cut[e.index()]).collect(),
+            charts: chart_count,
+        })
+    }
+
+    /// Mark edges in `cut` until every connected component becomes a disk once cut open along them.
+    fn cut_to_disks(&self, cut: &mut [bool]) {
+        // Tree-cotree on the surface with every hole closed by a virtual face: a spanning tree of vertices, a
+        // spanning tree of faces across the remaining edges, and whatever is left over closes one loop per
+        // handle generator.
+        let mut in_tree = vec![false; self.edge_count()];
+        let mut seen = vec![false; self.vertex_count()];
+        for root in self.vertices() {
+            if seen[root.index()] {
+                continue;
+            }
+            seen[root.index()] = true;
+            let mut queue = VecDeque::from([root]);
+            while let Some(v) = queue.pop_front() {
+                for h in self.vertex_outgoing_halfedges(v) {
+                    let w = self.dest(h);
+                    if !seen[w.index()] {
+                        seen[w.index()] = true;
+                        in_tree[self.edge(h).index()] = true;
+                        queue.push_back(w);
+                    }
+                }
+            }
+        }
+
+        let loops = self.boundary_loops();
+        let mut hole = vec![usize::MAX; self.halfedge_count()];
+        for (i, &start) in loops.iter().enumerate() {
+            for h in self.boundary_loop(start) {
+                hole[h.index()] = i;
+            }
+        }
+        let faces = self.face_count();
+        let dual_node = |h: HalfEdgeId| match self.face(h) {
+            Some(f) => f.index(),
+            None => faces + hole[h.index()],
+        };
+        let mut dual = UnionFind::new(faces + loops.len());
+        let mut graph = Vec::new();
+        for e in self.edges() {
+            if in_tree[e.index()] {
+                graph.push(e);
+                continue;
+            }
+            let h = self.edge_halfedge(e);
+            if !dual.union(dual_node(h), dual_node(self.twin(h))) {
+                graph.push(e);
+            }
+        }
+
+        // Prune the dangling branches of the vertex tree, leaving just the loops.
+        let mut degree = vec![0usize; self.vertex_count()];
+        let mut alive = vec![false; self.edge_count()];
+        for &e in &graph {
+            alive[e.index()] = true;
+            for v in self.edge_vertices(e) {
+                degree[v.index()] += 1;
+            }
+        }
+        let mut leaves: Vec<VertexId> =
+            self.vertices().filter(|v| degree[v.index()] == 1).collect();
+        while let Some(v) = leaves.pop() {
+            let Some(e) = self.vertex_edges(v).find(|e| alive[e.index()]) else {
+                continue;
+            };
+            alive[e.index()] = false;
+            for w in self.edge_vertices(e) {
+                degree[w.index()] -= 1;
+                if degree[w.index()] == 1 {
+                    leaves.push(w);
+                }
+            }
+        }
+        for e in self.edges().filter(|e| alive[e.index()]) {
+            cut[e.index()] = true;
+        }
+
+        // Every component now needs exactly one connected boundary. Group the holes and cut edges into
+        // connected pieces, then join the pieces of each component with shortest paths.
+        let mut pieces = UnionFind::new(self.vertex_count());
+        let mut on_piece = vec![false; self.vertex_count()];
+        for h in self.halfedges() {
+            let e = self.edge(h);
+            if self.is_boundary_halfedge(h) || cut[e.index()] {
+                let [a, b] = self.edge_vertices(e);
+                pieces.union(a.index(), b.index());
+                on_piece[a.index()] = true;
+                on_piece[b.index()] = true;
+            }
+        }
+        let components = vertex_components(self);
+        let component_count = components.iter().max().map_or(0, |c| c + 1);
+        let mut reached = vec![false; self.vertex_count()];
+        for component in 0..component_count {
+            let members: Vec<VertexId> = self
+                .vertices()
+                .filter(|v| components[v.index()] == component && !self.is_isolated_vertex(*v))
+                .collect();
+            let Some(&first) = members.iter().find(|v| on_piece[v.index()]) else {
+                // A closed surface without handles: cut along a path between two far apart vertices.
+                if let Some(&start) = members.first() {
+                    let (parents, last) = breadth_first(self, &[start], |_| false);
+                    for e in path_edges(self, &parents, last) {
+                        cut[e.index()] = true;
+                    }
+                }
+                continue;
+            };
+            let root = pieces.find(first.index());
+            for v in &members {
+                reached[v.index()] = on_piece[v.index()] && pieces.find(v.index()) == root;
+            }
+            loop {
+                let sources: Vec<VertexId> = members
+                    .iter()
+                    .copied()
+                    .filter(|v| reached[v.index()])
+                    .collect();
+                let unreached = |v: VertexId| on_piece[v.index()] && !reached[v.index()];
+                let (parents, last) = breadth_first(self, &sources, unreached);
+                if !unreached(last) {
+                    break;
+                }
+                for e in path_edges(self, &parents, last) {
+                    cut[e.index()] = true;
+                    for v in self.edge_vertices(e) {
+                        reached[v.index()] = true;
+                    }
+                }
+                let joined = pieces.find(last.index());
+                for v in &members {
+                    if on_piece[v.index()] && pieces.find(v.index()) == joined {
+                        reached[v.index()] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Group the corners around every vertex into wedges separated by cut edges. Returns the wedge of every
+    /// corner (indexed by half-edge, `usize::MAX` for boundary half-edges) and the vertex of every wedge.
+    fn wedges(&self, cut: &[bool]) -> (Vec<usize>, Vec<VertexId>) {
+        let mut wedge = vec![usize::MAX; self.halfedge_count()];
+        let mut wedge_vertex = Vec::new();
+        for v in self.vertices() {
+            let ring: Vec<HalfEdgeId> = self.vertex_outgoing_halfedges(v).collect();
+            // Start just after a boundary or a cut, so that the last wedge does not wrap around into the first.
+            let start = ring
+                .iter()
+                .position(|&h| cut[self.edge(h).index()] || self.is_boundary_halfedge(h))
+                .unwrap_or(0);
+            let mut current = None;
+            for i in 0..ring.len() {
+                let h = ring[(start + i) % ring.len()];
+                // Rotating onto `h` crosses its edge.
+                if self.is_boundary_halfedge(h) {
+                    current = None;
+                    continue;
+                }
+                if current.is_none() || (i > 0 && cut[self.edge(h).index()]) {
+                    current = Some(wedge_vertex.len());
+                    wedge_vertex.push(v);
+                }
+                wedge[h.index()] = current.expect("just assigned");
+            }
+        }
+        (wedge, wedge_vertex)
+    }
+
+    /// Label every face with its chart: faces connected across edges that are not cut.
+    fn charts(&self, cut: &[bool]) -> Vec<usize> {
+        let mut charts = UnionFind::new(self.face_count());
+        for e in self.edges() {
+            let h = self.edge_halfedge(e);
+            if let (false, Some(f), Some(g)) =
+                (cut[e.index()], self.face(h), self.face(self.twin(h)))
+            {
+                charts.union(f.index(), g.index());
+            }
+        }
+        let mut label = vec![usize::MAX; self.face_count()];
+        let mut root_label = HashMap::new();
+        for f in self.faces() {
+            let root = charts.find(f.index());
+            let next = root_label.len();
+            label[f.index()] = *root_label.entry(root).or_insert(next);
+        }
+        label
+    }
+}
+
+impl MeshData {
+    /// Replace the texture coordinates with a fresh least squares conformal map.
+    pub fn compute_uvs(
+        &mut self,
+        config: &ParameterizationConfig,
+    ) -> Result<Parameterization, ParameterizationError> {
+        let parameterization = self.mesh.lscm(config)?;
+        self.set_uvs(&parameterization.uvs)?;
+        Ok(parameterization)
+    }
+}
+
+/// One texture coordinate of a wedge: its `u` for axis 0, its `v` for axis 1.
+type Coordinate = (usize, usize);
+
+/// The linear system whose solution minimises a quadratic energy in the texture coordinates of a chart's free
+/// wedges, with those of the pinned wedges moved to the right-hand side.
+struct ChartSystem {
+    pins: [(usize, Vec2); 2],
+    /// The unknown of each free wedge's `u`; its `v` comes `index.len()` later.
+    index: HashMap<usize, usize>,
+    matrix: TripletMatrix,
+    rhs: Vec<f64>,
+}
+
+impl ChartSystem {
+    fn new(wedges: &[usize], pins: [(usize, Vec2); 2]) -> Self {
+        let mut index = HashMap::new();
+        for &w in wedges {
+            if pins.iter().all(|&(p, _)| p != w) {
+                let next = index.len();
+                index.insert(w, next);
+            }
+        }
+        let free = index.len();
+        Self {
+            pins,
+            index,
+            matrix: TripletMatrix::new(2 * free, 2 * free),
+            rhs: vec![0.0; 2 * free],
+        }
+    }
+
+    /// The unknown holding `coordinate`, or its value if the wedge is pinned.
+    fn unknown(&self, (w, axis): Coordinate) -> Result<usize, f64> {
+        match self.pins.iter().find(|&&(p, _)| p == w) {
+            Some(&(_, uv)) => Err(f64::from(uv[axis])),
+            None => Ok(self.index[&w] + axis * self.index.len()),
+        }
+    }
+
+    /// Add `coefficient * a * b` to the energy.
+    fn add(&mut self, a: Coordinate, b: Coordinate, coefficient: f64) {
+        let half = 0.5 * coefficient;
+        match (self.unknown(a), self.unknown(b)) {
+            (Ok(i), Ok(j)) => {
+                self.matrix.add(i, j, half);
+                self.matrix.add(j, i, half);
+            }
+            (Ok(i), Err(x)) | (Err(x), Ok(i)) => self.rhs[i] -= half * x,
+            (Err(_), Err(_)) => {}
+        }
+    }
+
+    fn solve(self) -> Result<HashMap<usize, Vec2>, SolveError> {
+        let mut uv: HashMap<usize, Vec2> = self.pins.into();
+        let free = self.index.len();
+        if free > 0 {
+            let solution = Cholesky::new(&self.matrix.to_csr())?.solve(&self.rhs)?;
+            for (&w, &i) in &self.index {
+                uv.insert(w, Vec2::new(solution[i] as f32, solution[free + i] as f32));
+            }
+        }
+        Ok(uv)
+    }
+}
+
+/// Minimise the conformal energy of one chart with its two furthest apart wedges pinned at `(0, 0)` and
+/// `(1, 0)`, over the chart's own triangles or, if `intrinsic`, over their intrinsic Delaunay triangulation.
+fn flatten_chart(
+    wedges: &[usize],
+    triangles: &[[usize; 3]],
+    position: impl Fn(usize) -> Vec3,
+    intrinsic: bool,
+) -> Result<HashMap<usize, Vec2>, SolveError> {
+    let farthest = |from: usize| {
+        let p = position(from);
+        *wedges
+            .iter()
+            .max_by(|&&a, &&b| {
+                p.distance_squared(position(a))
+                    .total_cmp(&p.distance_squared(position(b)))
+            })
+            .expect("a chart has wedges")
+    };
+    let first = farthest(wedges[0]);
+    let second = farthest(first);
+    let mut system = ChartSystem::new(wedges, [(first, Vec2::ZERO), (second, Vec2::X)]);
+
+    // The chart cut out as a mesh of its own, so that no flip crosses a seam. It only fails to build if the
+    // input has duplicate faces, and then the chart keeps its own triangles.
+    let local: HashMap<usize, u32> = wedges
+        .iter()
+        .enumerate()
+        .map(|(i, &w)| (w, i as u32))
+        .collect();
+    let chart = intrinsic
+        .then(|| {
+            let positions: Vec<Vec3> = wedges.iter().map(|&w| position(w)).collect();
+            let polygons: Vec<[u32; 3]> = triangles.iter().map(|c| c.map(|w| local[&w])).collect();
+            HalfEdgeMesh::from_polygons(positions, &polygons).ok()
+        })
+        .flatten();
+    match chart.as_ref().map(IntrinsicTriangulation::new) {
+        Some(Ok(mut intrinsic)) => {
+            intrinsic.flip_to_delaunay();
+            add_intrinsic_energy(&mut system, wedges, triangles, &intrinsic);
+        }
+        _ => add_extrinsic_energy(&mut system, triangles, position),
+    }
+    system.solve()
+}
+
+/// Each triangle contributes the squared complex residual `Σ W_j U_j` of Lévy et al., with `W_j` the
+/// triangle's edges in its own plane and `U_j = u_j + i v_j`.
+fn add_extrinsic_energy(
+    system: &mut ChartSystem,
+    triangles: &[[usize; 3]],
+    position: impl Fn(usize) -> Vec3,
+) {
+    for corners in triangles {
+        let [p0, p1, p2] = corners.map(&position);
+        let x_axis = (p1 - p0).normalize_or_zero();
+        let normal_vector = (p1 - p0).cross(p2 - p0);
+        let double_area = normal_vector.length();
+        if double_area <= f32::EPSILON * (p1 - p0).length_squared() {
+            continue;
+        }
+        let y_axis = normal_vector.cross(x_axis).normalize();
+        let flat = [p0, p1, p2].map(|p| Vec2::new((p - p0).dot(x_axis), (p - p0).dot(y_axis)));
+        let weight = 1.0 / f64::from(double_area).sqrt();
+        // Two real rows per triangle, as (wedge, coefficient of u, coefficient of v).
+        let mut rows = [Vec::new(), Vec::new()];
+        for j in 0..3 {
+            let w = flat[(j + 2) % 3] - flat[(j + 1) % 3];
+            let (a, b) = (f64::from(w.x) * weight, f64::from(w.y) * weight);
+            // Re(W U) = a u - b v, Im(W U) = b u + a v.
+            rows[0].push((corners[j], [a, -b]));
+            rows[1].push((corners[j], [b, a]));
+        }
+        for row in &rows {
+            for &(w, c) in row {
+                for &(x, d) in row {
+                    for (i, j) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                        system.add((w, i), (x, j), c[i] * d[j]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The conformal energy as the Dirichlet energy `½ Σ w_ij |U_i - U_j|²` with intrinsic cotangent weights,
+/// minus the signed area of the chart in the plane. Both are the same as the sum of Lévy et al.'s residuals
+/// on a mesh that is already Delaunay.
+fn add_intrinsic_energy(
+    system: &mut ChartSystem,
+    wedges: &[usize],
+    triangles: &[[usize; 3]],
+    intrinsic: &IntrinsicTriangulation,
+) {
+    let mesh = intrinsic.mesh();
+    for e in mesh.edges() {
+        let weight = 0.5 * intrinsic.cotan_weight(e);
+        // Edges of zero length have no angles to weigh them by.
+        if !weight.is_finite() {
+            continue;
+        }
+        let [i, j] = mesh.edge_vertices(e).map(|v| wedges[v.index()]);
+        for axis in 0..2 {
+            system.add((i, axis), (i, axis), weight);
+            system.add((j, axis), (j, axis), weight);
+            system.add((i, axis), (j, axis), -2.0 * weight);
+        }
+    }
+    // The signed area `½ Σ (u_i v_j - u_j v_i)` over the boundary, which is the sum over every triangle's
+    // edges since interior edges cancel. Flips do not change the boundary, so the input triangles serve.
+    for corners in triangles {
+        for k in 0..3 {
+            let (i, j) = (corners[k], corners[(k + 1) % 3]);
+            system.add((i, 0), (j, 1), -0.5);
+            system.add((j, 0), (i, 1), 0.5);
+        }
+    }
+}
+
+/// Shelf-pack boxes of the given sizes (anchored at the origin) into the unit square. Returns the offset of
+/// each box and the scale applied after offsetting.
+fn pack(sizes: &[Vec2], margin: f32) -> Vec<(Vec2, f32)> {
+    let area: f32 = sizes.iter().map(|s| s.x * s.y).sum();
+    let widest = sizes.iter().fold(0.0f32, |w, s| w.max(s.x));
+    let spacing = margin * area.sqrt();
+    let row_width = widest.max(area.sqrt());
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| sizes[b].y.total_cmp(&sizes[a].y));
+    let mut offsets = vec![Vec2::ZERO; sizes.len()];
+    let (mut cursor, mut row_height, mut extent) = (Vec2::ZERO, 0.0f32, Vec2::ZERO);
+    for i in order {
+        if cursor.x > 0.0 && cursor.x + sizes[i].x > row_width {
+            cursor = Vec2::new(0.0, cursor.y + row_height + spacing);
+            row_height = 0.0;
+        }
+        offsets[i] = cursor;
+        extent = extent.max(cursor + sizes[i]);
+        row_height = row_height.max(sizes[i].y);
+        cursor.x += sizes[i].x + spacing;
+    }
+    let scale = 1.0 / extent.max_element().max(f32::MIN_POSITIVE);
+    offsets.into_iter().map(|o| (o, scale)).collect()
+}
+
+/// Breadth-first search over edges from `sources` until reaching a vertex for which `stop` holds, or the whole
+/// component otherwise. Returns the edge each vertex was reached through and the last vertex visited.
+fn breadth_first(
+    mesh: &HalfEdgeMesh,
+    sources: &[VertexId],
+    stop: impl Fn(VertexId) -> bool,
+) -> (Vec<Option<HalfEdgeId>>, VertexId) {
+    let mut parent = vec![None; mesh.vertex_count()];
+    let mut seen = vec![false; mesh.vertex_count()];
+    let mut queue: VecDeque<VertexId> = sources.iter().copied().collect();
+    sources.iter().for_each(|v| seen[v.index()] = true);
+    let mut last = sources[0];
+    while let Some(v) = queue.pop_front() {
+        last = v;
+        if stop(v) {
+            break;
+        }
+        for h in mesh.vertex_outgoing_halfedges(v) {
+            let w = mesh.dest(h);
+            if !seen[w.index()] {
+                seen[w.index()] = true;
+                parent[w.index()] = Some(h);
+                queue.push_back(w);
+            }
+        }
+    }
+    (parent, last)
+}
+
+/// The edges on the way back from `end` along `parents`.
+fn path_edges(mesh: &HalfEdgeMesh, parents: &[Option<HalfEdgeId>], end: VertexId) -> Vec<EdgeId> {
+    let mut edges = Vec::new();
+    let mut v = end;
+    while let Some(h) = parents[v.index()] {
+        edges.push(mesh.edge(h));
+        v = mesh.origin(h);
+    }
+    edges
+}
+
+fn vertex_components(mesh: &HalfEdgeMesh) -> Vec<usize> {
+    let mut components = UnionFind::new(mesh.vertex_count());
+    for e in mesh.edges() {
+        let [a, b] = mesh.edge_vertices(e);
+        components.union(a.index(), b.index());
+    }
+    let mut label = vec![0; mesh.vertex_count()];
+    let mut root_label = HashMap::new();
+    for v in mesh.vertices() {
+        let root = components.find(v.index());
+        let next = root_label.len();
+        label[v.index()] = *root_label.entry(root).or_insert(next);
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An `n` by `n` grid of squares, each cut into two triangles, with `height` giving every vertex its z.
+    fn grid(n: u32, height: impl Fn(f32, f32) -> f32) -> HalfEdgeMesh {
+        let positions: Vec<Vec3> = (0..=n)
+            .flat_map(|j| (0..=n).map(move |i| (i as f32 / n as f32, j as f32 / n as f32)))
+            .map(|(x, y)| Vec3::new(x, y, height(x, y)))
+            .collect();
+        let mut triangles = Vec::new();
+        for j in 0..n {
+            for i in 0..n {
+                let v = j * (n + 1) + i;
+                triangles.extend([v, v + 1, v + n + 2, v, v + n + 2, v + n + 1]);
+            }
+        }
+        HalfEdgeMesh::from_triangles(positions, &triangles).unwrap()
+    }
+
+    /// The signed area of every face in texture space.
+    fn uv_areas(mesh: &HalfEdgeMesh, uvs: &[Vec2]) -> Vec<f32> {
+        mesh.faces()
+            .map(|f| {
+                let [a, b, c] = {
+                    let mut h = mesh.face_halfedges(f).map(|h| uvs[h.index()]);
+                    [(); 3].map(|_| h.next().unwrap())
+                };
+                0.5 * (b - a).perp_dot(c - a)
+            })
+            .collect()
+    }
+
+    /// An octahedron refined by Loop subdivision and pushed out onto the unit sphere.
+    fn sphere(levels: u32) -> HalfEdgeMesh {
+        let positions = vec![Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+        let triangles = [
+            0, 2, 4, 2, 1, 4, 1, 3, 4, 3, 0, 4, 2, 0, 5, 1, 2, 5, 3, 1, 5, 0, 3, 5,
+        ];
+        let config = crate::subdivision::SubdivisionConfig {
+            levels,
+            ..Default::default()
+        };
+        let mut mesh = HalfEdgeMesh::from_triangles(positions, &triangles)
+            .unwrap()
+            .loop_subdivision(&config)
+            .unwrap();
+        for v in mesh.vertices().collect::<Vec<_>>() {
+            mesh.set_position(v, mesh.position(v).normalize());
+        }
+        mesh
+    }
+
+    fn in_unit_square(uvs: &[Vec2]) -> bool {
+        uvs.iter()
+            .all(|uv| uv.cmpge(Vec2::ZERO).all() && uv.cmple(Vec2::ONE).all())
+    }
+
+    #[test]
+    fn a_saddle_maps_without_flips() {
+        let mesh = grid(8, |x, y| (x - 0.5) * (y - 0.5));
+        let parameterization = mesh.lscm(&Default::default()).unwrap();
+        assert_eq!(parameterization.charts, 1);
+        assert!(parameterization.seams.is_empty());
+        assert!(uv_areas(&mesh, &parameterization.uvs)
+            .iter()
+            .all(|&a| a > 0.0));
+        assert!(in_unit_square(&parameterization.uvs));
+    }
+
+    #[test]
+    fn a_sphere_is_cut_open_and_maps_without_flips() {
+        let mesh = sphere(2);
+        let parameterization = mesh.lscm(&Default::default()).unwrap();
+        assert!(!parameterization.seams.is_empty());
+        assert!(uv_areas(&mesh, &parameterization.uvs)
+            .iter()
+            .all(|&a| a > 0.0));
+        assert!(in_unit_square(&parameterization.uvs));
+        // Corners on either side of a seam get their own coordinates; elsewhere they agree.
+        let seams: std::collections::HashSet<EdgeId> =
+            parameterization.seams.iter().copied().collect();
+        let split = mesh.vertices().filter(|&v| {
+            let mut corners = mesh
+                .vertex_outgoing_halfedges(v)
+                .map(|h| parameterization.uvs[h.index()]);
+            let first = corners.next().unwrap();
+            corners.any(|uv| uv != first)
+        });
+        for v in split {
+            assert!(mesh
+                .vertex_outgoing_halfedges(v)
+                .any(|h| seams.contains(&mesh.edge(h))));
+        }
+    }
+
+    #[test]
+    fn missing_seams_are_refused() {
+        let mesh = grid(2, |_, _| 0.0);
+        let edge = EdgeId::new(mesh.edge_count());
+        let config = ParameterizationConfig {
+            seams: vec![edge],
+            ..Default::default()
+        };
+        assert_eq!(
+            mesh.lscm(&config).unwrap_err(),
+            ParameterizationError::InvalidSeam { edge }
+        );
+    }
+
+    #[test]
+    fn intrinsic_lscm_flattens_a_plane_exactly() {
+        // Shear the grid so that its diagonals are long and the intrinsic triangulation flips them.
+        let mut mesh = grid(4, |_, _| 0.0);
+        for v in mesh.vertices().collect::<Vec<_>>() {
+            let p = mesh.position(v);
+            mesh.set_position(v, Vec3::new(p.x + 1.5 * p.y, p.y, 0.0));
+        }
+        assert!(
+            IntrinsicTriangulation::new(&mesh)
+                .unwrap()
+                .flip_to_delaunay()
+                > 0
+        );
+        let config = ParameterizationConfig {
+            intrinsic_delaunay: true,
+            ..Default::default()
+        };
+        let intrinsic = mesh.lscm(&config).unwrap();
+        let extrinsic = mesh.lscm(&Default::default()).unwrap();
+        assert_eq!(intrinsic.charts, 1);
+        // A flat chart maps onto the plane by a similarity, whichever triangles measure the energy.
+        let scale = |uvs: &[Vec2]| {
+            let ratios: Vec<f32> = mesh
+                .halfedges()
+                .filter(|&h| !mesh.is_boundary_halfedge(h))
+                .map(|h| {
+                    let uv = uvs[mesh.next(h).index()] - uvs[h.index()];
+                    uv.length() / mesh.halfedge_vector(h).length()
+                })
+                .collect();
+            let (min, max) = ratios.iter().fold((f32::INFINITY, 0.0f32), |(lo, hi), &r| {
+                (lo.min(r), hi.max(r))
+            });
+            max / min
+        };
+        assert!(scale(&intrinsic.uvs) < 1.0 + 1e-3);
+        for (a, b) in intrinsic.uvs.iter().zip(&extrinsic.uvs) {
+            assert!(a.distance(*b) < 1e-3);
+        }
+    }
+
+    #[test]
+    fn intrinsic_lscm_keeps_every_triangle_facing_up() {
+        let mesh = grid(8, |x, y| 0.3 * (x * x - y * y));
+        let config = ParameterizationConfig {
+            intrinsic_delaunay: true,
+            ..Default::default()
+        };
+        let parameterization = mesh.lscm(&config).unwrap();
+        assert!(uv_areas(&mesh, &parameterization.uvs)
+            .iter()
+            .all(|&a| a > 0.0));
+    }
+}
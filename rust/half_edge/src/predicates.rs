@@ -0,0 +1,523 @@
+//! Geometric predicates whose sign is always right.
+//!
+//! Each predicate is first evaluated in plain floating point along with a bound on its rounding error,
+//! after Shewchuk, "Adaptive Precision Floating-Point Arithmetic and Fast Robust Geometric Predicates". Only
+//! when the result is too close to zero to trust is it recomputed exactly, with expansions: sums of
+//! non-overlapping doubles that represent a value without rounding.
+
+use glam::{DVec2, DVec3};
+
+/// The unit roundoff of `f64`.
+const EPSILON: f64 = f64::EPSILON * 0.5;
+
+/// Error bound factors for the floating-point evaluation of each predicate, from Shewchuk's paper.
+const ORIENT2D_BOUND: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+const ORIENT3D_BOUND: f64 = (7.0 + 56.0 * EPSILON) * EPSILON;
+const INCIRCLE_BOUND: f64 = (10.0 + 96.0 * EPSILON) * EPSILON;
+const INSPHERE_BOUND: f64 = (16.0 + 224.0 * EPSILON) * EPSILON;
+
+// The two predicates on triangle pairs are not in the paper; their bounds follow its method. Each rounded
+// operation multiplies its result by some 1 + δ with |δ| ≤ ε, so a value that has been through k roundings is
+// off by at most kε of its magnitude, to first order.
+//
+// normal_dot: a normal component `uy vz - uz vy` goes through four roundings on either product (two
+// differences of inputs, the product and the subtraction), so it is within 4ε of the matching permanent
+// component `|uy vz| + |uz vy|`. Multiplying two such components leaves an error of 8ε times the product of
+// their permanents; the multiplication and the two additions of the dot product add 3ε more. The error is
+// thus below 11ε times the dot product of the permanents.
+//
+// delaunay_edge: write s = |ca| |cb| |da| |db|. The dot product `ca · cb` is off by at most 5ε (two
+// differences, a product and two additions) times the sum of its terms' magnitudes, which is at most
+// |ca| |cb| by Cauchy-Schwarz. The components of `da × db` are off by at most 4ε of their permanents, a
+// vector no longer than √2 |da| |db|, and squaring, summing and taking the square root add 2.5ε of the
+// length, which is at most |da| |db|: 8.2ε |da| |db| in all. Each term is then off by (5 + 8.2 + 1)ε s, and
+// the final addition by ε of each, so the error is below 31ε s.
+//
+// Both bounds are rounded up to leave room for the second-order terms and for the roundings in the
+// permanents, `scale` and the comparisons themselves, each of which moves the threshold by a few ε of itself.
+const NORMAL_DOT_BOUND: f64 = 16.0 * EPSILON;
+const DELAUNAY_BOUND: f64 = 64.0 * EPSILON;
+
+/// `a + b` and its rounding error.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+    (x, (a - a_virtual) + (b - b_virtual))
+}
+
+/// `a * b` and its rounding error.
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    (x, a.mul_add(b, -x))
+}
+
+/// An exact value as a sum of non-overlapping doubles in order of increasing magnitude, with zeros left out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Expansion(Vec<f64>);
+
+impl Expansion {
+    /// `a - b`, exactly.
+    pub(crate) fn difference(a: f64, b: f64) -> Self {
+        let (x, error) = two_sum(a, -b);
+        Self([error, x].into_iter().filter(|&c| c != 0.0).collect())
+    }
+
+    /// Add a single double, with Shewchuk's Grow-Expansion.
+    fn grow(&mut self, b: f64) {
+        let mut sum = b;
+        let mut components = Vec::with_capacity(self.0.len() + 1);
+        for &c in &self.0 {
+            let (x, error) = two_sum(sum, c);
+            if error != 0.0 {
+                components.push(error);
+            }
+            sum = x;
+        }
+        if sum != 0.0 {
+            components.push(sum);
+        }
+        self.0 = components;
+    }
+
+    #[must_use]
+    pub(crate) fn add(&self, other: &Self) -> Self {
+        let mut sum = self.clone();
+        for &c in &other.0 {
+            sum.grow(c);
+        }
+        sum
+    }
+
+    #[must_use]
+    pub(crate) fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+
+    #[must_use]
+    pub(crate) fn neg(&self) -> Self {
+        Self(self.0.iter().map(|&c| -c).collect())
+    }
+
+    #[must_use]
+    pub(crate) fn mul(&self, other: &Self) -> Self {
+        let mut product = Self::default();
+        for &a in &self.0 {
+            for &b in &other.0 {
+                let (x, error) = two_product(a, b);
+                product.grow(error);
+                product.grow(x);
+            }
+        }
+        product
+    }
+
+    /// The sign of the value: -1, 0 or 1.
+    pub(crate) fn signum(&self) -> f64 {
+        self.0.last().map_or(0.0, |c| c.signum())
+    }
+
+    /// The value rounded to a double. Its sign is exact.
+    pub(crate) fn estimate(&self) -> f64 {
+        self.0.iter().sum::<f64>().abs() * self.signum()
+    }
+}
+
+/// The exact coordinate differences `a - b`.
+fn differences(a: DVec3, b: DVec3) -> [Expansion; 3] {
+    [0, 1, 2].map(|i| Expansion::difference(a[i], b[i]))
+}
+
+/// The exact coordinate differences `a - b` in the plane.
+fn differences2(a: DVec2, b: DVec2) -> [Expansion; 2] {
+    [0, 1].map(|i| Expansion::difference(a[i], b[i]))
+}
+
+/// The exact perp-dot product `u.x * v.y - u.y * v.x` of two planar vectors.
+fn perp_dot(u: &[Expansion; 2], v: &[Expansion; 2]) -> Expansion {
+    u[0].mul(&v[1]).sub(&u[1].mul(&v[0]))
+}
+
+/// The exact cross product `u × v` of two vectors given as expansions.
+fn cross(u: &[Expansion; 3], v: &[Expansion; 3]) -> [Expansion; 3] {
+    [0, 1, 2].map(|i| {
+        let (j, k) = ((i + 1) % 3, (i + 2) % 3);
+        u[j].mul(&v[k]).sub(&u[k].mul(&v[j]))
+    })
+}
+
+/// The exact dot product of two vectors given as expansions.
+fn dot<const N: usize>(u: &[Expansion; N], v: &[Expansion; N]) -> Expansion {
+    (0..N).fold(Expansion::default(), |sum, i| sum.add(&u[i].mul(&v[i])))
+}
+
+/// The exact triple product `u · (v × w)`.
+fn triple(u: &[Expansion; 3], v: &[Expansion; 3], w: &[Expansion; 3]) -> Expansion {
+    dot(u, &cross(v, w))
+}
+
+/// The orientation of the triangle `abc` in the plane: positive if it runs counter-clockwise, negative if
+/// clockwise and zero if the points are collinear. The sign is exact; the magnitude approximates twice the
+/// triangle's area.
+pub fn orient2d(a: DVec2, b: DVec2, c: DVec2) -> f64 {
+    let left = (a.x - c.x) * (b.y - c.y);
+    let right = (a.y - c.y) * (b.x - c.x);
+    let determinant = left - right;
+    if determinant.abs() > ORIENT2D_BOUND * (left.abs() + right.abs()) {
+        return determinant;
+    }
+
+    perp_dot(&differences2(b, a), &differences2(c, a)).estimate()
+}
+
+/// The orientation of `d` relative to the plane through `a`, `b` and `c`: positive if `d` lies on the side
+/// that `(b - a) × (c - a)` points to, negative on the other side and zero on the plane. The sign is exact;
+/// the magnitude approximates six times the volume of the tetrahedron `abcd`.
+pub fn orient3d(a: DVec3, b: DVec3, c: DVec3, d: DVec3) -> f64 {
+    let (ad, bd, cd) = (a - d, b - d, c - d);
+    let bc = bd.x * cd.y - cd.x * bd.y;
+    let ca = cd.x * ad.y - ad.x * cd.y;
+    let ab = ad.x * bd.y - bd.x * ad.y;
+    // This is Shewchuk's orient3d, which has the opposite sign convention.
+    let determinant = -(ad.z * bc + bd.z * ca + cd.z * ab);
+    let permanent = ((bd.x * cd.y).abs() + (cd.x * bd.y).abs()) * ad.z.abs()
+        + ((cd.x * ad.y).abs() + (ad.x * cd.y).abs()) * bd.z.abs()
+        + ((ad.x * bd.y).abs() + (bd.x * ad.y).abs()) * cd.z.abs();
+    if determinant.abs() > ORIENT3D_BOUND * permanent {
+        return determinant;
+    }
+
+    let u = differences(b, a);
+    let v = differences(c, a);
+    let w = differences(d, a);
+    triple(&w, &u, &v).estimate()
+}
+
+/// Whether `d` lies inside the circle through `a`, `b` and `c`, which must run counter-clockwise: positive
+/// inside, negative outside and zero on the circle. The sign is exact.
+pub fn incircle(a: DVec2, b: DVec2, c: DVec2, d: DVec2) -> f64 {
+    let (ad, bd, cd) = (a - d, b - d, c - d);
+    let (bdxcdy, cdxbdy) = (bd.x * cd.y, cd.x * bd.y);
+    let (cdxady, adxcdy) = (cd.x * ad.y, ad.x * cd.y);
+    let (adxbdy, bdxady) = (ad.x * bd.y, bd.x * ad.y);
+    let (alift, blift, clift) = (
+        ad.length_squared(),
+        bd.length_squared(),
+        cd.length_squared(),
+    );
+    let determinant =
+        alift * (bdxcdy - cdxbdy) + blift * (cdxady - adxcdy) + clift * (adxbdy - bdxady);
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * alift
+        + (cdxady.abs() + adxcdy.abs()) * blift
+        + (adxbdy.abs() + bdxady.abs()) * clift;
+    if determinant.abs() > INCIRCLE_BOUND * permanent {
+        return determinant;
+    }
+
+    let [u, v, w] = [a, b, c].map(|p| differences2(p, d));
+    let lifted = dot(&u, &u)
+        .mul(&perp_dot(&v, &w))
+        .add(&dot(&v, &v).mul(&perp_dot(&w, &u)))
+        .add(&dot(&w, &w).mul(&perp_dot(&u, &v)));
+    lifted.estimate()
+}
+
+/// Whether `e` lies inside the sphere through `a`, `b`, `c` and `d`, which must be positively oriented
+/// (`orient3d(a, b, c, d) > 0`): positive inside, negative outside and zero on the sphere. The sign is exact.
+pub fn insphere(a: DVec3, b: DVec3, c: DVec3, d: DVec3, e: DVec3) -> f64 {
+    let (ae, be, ce, de) = (a - e, b - e, c - e, d - e);
+    let minor = |p: DVec3, q: DVec3| (p.x * q.y, q.x * p.y);
+    let (aexbey, bexaey) = minor(ae, be);
+    let (bexcey, cexbey) = minor(be, ce);
+    let (cexdey, dexcey) = minor(ce, de);
+    let (dexaey, aexdey) = minor(de, ae);
+    let (aexcey, cexaey) = minor(ae, ce);
+    let (bexdey, dexbey) = minor(be, de);
+    let (ab, bc, cd) = (aexbey - bexaey, bexcey - cexbey, cexdey - dexcey);
+    let (da, ac, bd) = (dexaey - aexdey, aexcey - cexaey, bexdey - dexbey);
+    let abc = ae.z * bc - be.z * ac + ce.z * ab;
+    let bcd = be.z * cd - ce.z * bd + de.z * bc;
+    let cda = ce.z * da + de.z * ac + ae.z * cd;
+    let dab = de.z * ab + ae.z * bd + be.z * da;
+    let [alift, blift, clift, dlift] = [ae, be, ce, de].map(DVec3::length_squared);
+    // Shewchuk's insphere, which has the opposite sign convention.
+    let determinant = -((dlift * abc - clift * dab) + (blift * cda - alift * bcd));
+    let [az, bz, cz, dz] = [ae.z, be.z, ce.z, de.z].map(f64::abs);
+    let [ab, bc, cd, da, ac, bd] = [
+        aexbey.abs() + bexaey.abs(),
+        bexcey.abs() + cexbey.abs(),
+        cexdey.abs() + dexcey.abs(),
+        dexaey.abs() + aexdey.abs(),
+        aexcey.abs() + cexaey.abs(),
+        bexdey.abs() + dexbey.abs(),
+    ];
+    let permanent = (cd * bz + bd * cz + bc * dz) * alift
+        + (da * cz + ac * dz + cd * az) * blift
+        + (ab * dz + bd * az + da * bz) * clift
+        + (bc * az + ac * bz + ab * cz) * dlift;
+    if determinant.abs() > INSPHERE_BOUND * permanent {
+        return determinant;
+    }
+
+    let [u, v, w, x] = [a, b, c, d].map(|p| differences(p, e));
+    let lifted = dot(&x, &x)
+        .mul(&triple(&u, &v, &w))
+        .sub(&dot(&w, &w).mul(&triple(&x, &u, &v)))
+        .add(&dot(&v, &v).mul(&triple(&w, &x, &u)))
+        .sub(&dot(&u, &u).mul(&triple(&v, &w, &x)));
+    lifted.neg().estimate()
+}
+
+/// Whether the triangles `abc` and `def` face the same way: the sign of the dot product of their normals
+/// `(b - a) × (c - a)` and `(e - d) × (f - d)`, exactly. Zero if either is degenerate or they are
+/// perpendicular.
+pub fn normal_dot([a, b, c]: [DVec3; 3], [d, e, f]: [DVec3; 3]) -> f64 {
+    let normal = |a: DVec3, b: DVec3, c: DVec3| {
+        let (u, v) = (b - a, c - a);
+        let normal = u.cross(v);
+        let permanent = DVec3::new(
+            (u.y * v.z).abs() + (u.z * v.y).abs(),
+            (u.z * v.x).abs() + (u.x * v.z).abs(),
+            (u.x * v.y).abs() + (u.y * v.x).abs(),
+        );
+        (normal, permanent)
+    };
+    let (m, m_permanent) = normal(a, b, c);
+    let (n, n_permanent) = normal(d, e, f);
+    let product = m.dot(n);
+    if product.abs() > NORMAL_DOT_BOUND * m_permanent.dot(n_permanent) {
+        return product;
+    }
+
+    let m = cross(&differences(b, a), &differences(c, a));
+    let n = cross(&differences(e, d), &differences(f, d));
+    dot(&m, &n).estimate()
+}
+
+/// Whether the edge `ab` between the triangles `abc` and `bad` is locally Delaunay: positive if the angles
+/// at `c` and `d` sum to less than π, negative if to more and zero if to exactly π. The triangles need not
+/// lie in one plane; when they do, this agrees in sign with `-incircle(a, b, c, d)` for counter-clockwise
+/// `abc`. The sign is exact.
+pub fn delaunay_edge(a: DVec3, b: DVec3, c: DVec3, d: DVec3) -> f64 {
+    // The angle sum is below π exactly when cot(acb) + cot(adb) > 0. Multiplying through by both
+    // triangles' doubled areas turns the cotangents' sum into `x |da × db| + y |ca × cb|`.
+    let (ca, cb, da, db) = (a - c, b - c, a - d, b - d);
+    let (x, y) = (ca.dot(cb), da.dot(db));
+    let sum = x * da.cross(db).length() + y * ca.cross(cb).length();
+    let scale = ca.length() * cb.length() * da.length() * db.length();
+    if sum.abs() > DELAUNAY_BOUND * scale {
+        return sum;
+    }
+
+    // Square roots are not exact, so compare the two terms through their squares.
+    let [ca, cb, da, db] = [(a, c), (b, c), (a, d), (b, d)].map(|(p, q)| differences(p, q));
+    let (x, y) = (dot(&ca, &cb), dot(&da, &db));
+    let (cross_c, cross_d) = (cross(&ca, &cb), cross(&da, &db));
+    let (p, q) = (dot(&cross_c, &cross_c), dot(&cross_d, &cross_d));
+    let (sx, sy) = (x.signum(), y.signum());
+    if sx >= 0.0 && sy >= 0.0 || sx <= 0.0 && sy <= 0.0 {
+        // The terms do not have opposite signs, so their signs add up.
+        return sx * q.signum() + sy * p.signum();
+    }
+    sx * x.mul(&x).mul(&q).sub(&y.mul(&y).mul(&p)).signum()
+}
+
+/// The signs (-1, 0 or 1) of the components of `(b - a) × (d - c)`, exactly.
+pub(crate) fn cross_signs(a: DVec3, b: DVec3, c: DVec3, d: DVec3) -> [f64; 3] {
+    cross(&differences(b, a), &differences(d, c)).map(|component| component.signum())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cmp::Ordering;
+
+    use super::*;
+
+    /// Fibonacci numbers from `F(n)` on, as doubles. Consecutive ones make nearly parallel integer vectors
+    /// whose determinants are ±1, far below the rounding error of the products that form them.
+    fn fibonacci(n: usize) -> impl Iterator<Item = i128> {
+        std::iter::successors(Some((0i128, 1i128)), |&(a, b)| Some((b, a + b)))
+            .map(|(a, _)| a)
+            .skip(n)
+    }
+
+    /// The sign of `x`, with zero for both zeros.
+    fn sign(x: f64) -> Option<Ordering> {
+        x.partial_cmp(&0.0)
+    }
+
+    /// Check that `predicate` has the `exact` sign on every case, and that plain `f64` evaluation, `naive`,
+    /// gets some of them wrong, so that the exact fallback is what decided them.
+    fn check<T>(cases: &[(T, f64)], predicate: impl Fn(&T) -> f64, naive: impl Fn(&T) -> f64) {
+        let mut wrong = 0;
+        for (case, exact) in cases {
+            assert_eq!(sign(predicate(case)), sign(*exact));
+            wrong += usize::from(sign(naive(case)) != sign(*exact));
+        }
+        assert!(wrong > 0, "plain evaluation got every case right");
+    }
+
+    /// Integer points on the circle of radius 5⁸ around the origin, in counter-clockwise order: the products
+    /// (2 + i)ʲ (2 - i)¹⁶⁻ʲ, whose norms are all 5¹⁶, and their reflections.
+    fn circle_points() -> Vec<(i128, i128)> {
+        let mut points = Vec::new();
+        for j in 0..=16 {
+            let (mut x, mut y) = (1i128, 0i128);
+            for k in 0..16 {
+                let im = if k < j { 1 } else { -1 };
+                (x, y) = (2 * x - im * y, 2 * y + im * x);
+            }
+            for (sx, sy) in [(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+                points.extend([(sx * x, sy * y), (sx * y, sy * x)]);
+            }
+        }
+        points.sort_by(|p, q| {
+            (p.1 as f64)
+                .atan2(p.0 as f64)
+                .total_cmp(&(q.1 as f64).atan2(q.0 as f64))
+        });
+        points.dedup();
+        points
+    }
+
+    #[test]
+    fn orient2d_is_exact_for_nearly_collinear_points() {
+        let mut cases = Vec::new();
+        let f: Vec<i128> = fibonacci(30).take(45).collect();
+        for n in 0..f.len() - 2 {
+            // The determinant of (F(n), F(n + 1)) and (F(n + 1), F(n + 2)) is ±1; shifting the corner by a
+            // small offset leaves it so.
+            let sign = if n % 2 == 0 { -1.0 } else { 1.0 };
+            for offset in [0.0, 3.0, -7.0] {
+                let a = DVec2::splat(offset);
+                let b = a + DVec2::new(f[n] as f64, f[n + 1] as f64);
+                let c = a + DVec2::new(f[n + 1] as f64, f[n + 2] as f64);
+                cases.push(([a, b, c], sign));
+                cases.push(([a, c, b], -sign));
+            }
+        }
+        check(
+            &cases,
+            |&[a, b, c]| orient2d(a, b, c),
+            |&[a, b, c]| (b - a).perp_dot(c - a),
+        );
+    }
+
+    #[test]
+    fn orient3d_is_exact_for_nearly_coplanar_points() {
+        let mut cases = Vec::new();
+        let f: Vec<i128> = fibonacci(20).take(40).collect();
+        for n in 0..f.len() - 4 {
+            // Rows of consecutive Fibonacci numbers are dependent, so the third lies in the plane of the
+            // first two; raising its last entry by k makes the determinant k (F(n) F(n + 2) - F(n + 1)²).
+            let row =
+                |i: usize, k: i128| DVec3::new(f[i] as f64, f[i + 1] as f64, (f[i + 2] + k) as f64);
+            let minor = (f[n] * f[n + 2] - f[n + 1] * f[n + 1]) as f64;
+            for k in -2..=2 {
+                let points = [DVec3::ZERO, row(n, 0), row(n + 1, 0), row(n + 2, k)];
+                cases.push((points, k as f64 * minor));
+            }
+        }
+        check(
+            &cases,
+            |&[a, b, c, d]| orient3d(a, b, c, d),
+            |&[a, b, c, d]| (b - a).cross(c - a).dot(d - a),
+        );
+    }
+
+    #[test]
+    fn incircle_is_exact_for_nearly_cocircular_points() {
+        let points = circle_points();
+        let center = (12_345, -6_789);
+        let radius_squared = 5i128.pow(16);
+        let point = |(x, y): (i128, i128)| DVec2::new((x + center.0) as f64, (y + center.1) as f64);
+        let mut cases = Vec::new();
+        for i in 0..points.len() - 3 {
+            let [a, b, c] = [i, i + 1, i + 2].map(|j| point(points[j]));
+            for (dx, dy) in [(0, 0), (1, 0), (0, -1)] {
+                let (x, y) = points[(i + points.len() / 2) % points.len()];
+                let d = point((x + dx, y + dy));
+                let distance_squared = (x + dx).pow(2) + (y + dy).pow(2);
+                cases.push(([a, b, c, d], (radius_squared - distance_squared) as f64));
+            }
+        }
+        check(
+            &cases,
+            |&[a, b, c, d]| incircle(a, b, c, d),
+            |&[a, b, c, d]| {
+                let [u, v, w] = [a - d, b - d, c - d];
+                u.length_squared() * v.perp_dot(w)
+                    + v.length_squared() * w.perp_dot(u)
+                    + w.length_squared() * u.perp_dot(v)
+            },
+        );
+    }
+
+    #[test]
+    fn insphere_is_exact_for_nearly_cospherical_points() {
+        // Points of the circle, placed in the three coordinate planes, all lie on one sphere.
+        let circle = circle_points();
+        let center = (1_234, -5_678, 910);
+        let radius_squared = 5i128.pow(16);
+        let point = |(x, y, z): (i128, i128, i128)| {
+            DVec3::new(
+                (x + center.0) as f64,
+                (y + center.1) as f64,
+                (z + center.2) as f64,
+            )
+        };
+        let mut cases = Vec::new();
+        for i in 0..circle.len() - 2 {
+            let ((x0, y0), (x1, y1), (x2, y2)) = (circle[i], circle[i + 1], circle[i + 2]);
+            let sphere = [(x0, y0, 0), (x1, 0, y1), (0, x2, y2)];
+            let (x3, y3) = circle[(i + circle.len() / 3) % circle.len()];
+            let [mut a, mut b, c, d] = [sphere[0], sphere[1], sphere[2], (x3, y3, 0)].map(point);
+            if orient3d(a, b, c, d) < 0.0 {
+                (a, b) = (b, a);
+            }
+            for offset in [(0, 0, 0), (1, 0, 0), (0, 0, -1)] {
+                let (x, y, z) = (y3 + offset.0, offset.1, x3 + offset.2);
+                let e = point((x, y, z));
+                let distance_squared = x * x + y * y + z * z;
+                cases.push(([a, b, c, d, e], (radius_squared - distance_squared) as f64));
+            }
+        }
+        check(
+            &cases,
+            |&[a, b, c, d, e]| insphere(a, b, c, d, e),
+            |&[a, b, c, d, e]| {
+                let [u, v, w, x] = [a - e, b - e, c - e, d - e];
+                let triple = |u: DVec3, v: DVec3, w: DVec3| u.dot(v.cross(w));
+                -(x.length_squared() * triple(u, v, w) - w.length_squared() * triple(x, u, v)
+                    + v.length_squared() * triple(w, x, u)
+                    - u.length_squared() * triple(v, w, x))
+            },
+        );
+    }
+
+    #[test]
+    fn delaunay_edge_agrees_with_incircle_in_the_plane() {
+        let lift = |p: DVec2| p.extend(0.0);
+        let (a, b, c) = (DVec2::ZERO, DVec2::X, DVec2::new(0.3, 0.7));
+        // Points just inside, on and just outside the circle through a, b and c, below the edge ab.
+        let center_y: f64 = (0.09 + 0.49 - 0.3) / 1.4;
+        let radius = (0.25 + center_y * center_y).sqrt();
+        for offset in [-1e-12, 0.0, 1e-12] {
+            let d = DVec2::new(0.5, center_y - radius - offset);
+            let expected = -incircle(a, b, c, d).signum();
+            let actual = delaunay_edge(lift(a), lift(b), lift(c), lift(d)).signum();
+            assert_eq!(actual, expected, "offset {offset}");
+        }
+    }
+
+    #[test]
+    fn normal_dot_is_exact_for_nearly_perpendicular_triangles() {
+        let a = [DVec3::ZERO, DVec3::X, DVec3::Y];
+        let tilt = f64::EPSILON * f64::EPSILON;
+        let b = [DVec3::ZERO, DVec3::X, DVec3::new(0.0, tilt, 1.0)];
+        assert!(normal_dot(a, b) > 0.0);
+        let c = [DVec3::ZERO, DVec3::X, DVec3::new(0.0, -tilt, 1.0)];
+        assert!(normal_dot(a, c) < 0.0);
+        assert_eq!(normal_dot(a, [DVec3::ZERO, DVec3::X, DVec3::Z]), 0.0);
+    }
+}
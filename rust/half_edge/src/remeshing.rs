@@ -0,0 +1,370 @@
+//! Isotropic remeshing, after Botsch and Kobbelt's "A Remeshing Approach to Multiresolution Modeling".
+//!
+//! Each iteration splits edges longer than 4/3 of the target length, collapses edges shorter than 4/5 of it,
+//! flips edges to bring vertex valences towards 6 (4 on the boundary), and relaxes vertices towards the
+//! centroid of their neighbours within the tangent plane. Feature edges (boundary edges, explicitly listed
+//! edges and edges whose dihedral angle exceeds [`RemeshingConfig::feature_angle`]) are never flipped, are only
+//! split and collapsed along their own length, and their vertices do not relax, so sharp creases and outlines
+//! survive.
+
+use core::fmt;
+
+use glam::Vec3;
+
+use crate::{
+    mesh::{EdgeId, FaceId, HalfEdgeId, HalfEdgeMesh, VertexId},
+    predicates::normal_dot,
+};
+
+/// Settings for [`HalfEdgeMesh::remesh`].
+#[derive(Clone, Debug)]
+pub struct RemeshingConfig {
+    /// The edge length to aim for. Zero uses the mean edge length of the input.
+    pub target_edge_length: f32,
+    /// How many split, collapse, flip and relax rounds to run.
+    pub iterations: u32,
+    /// Edges whose faces meet at a dihedral angle larger than this (in radians) are kept as features.
+    pub feature_angle: f32,
+    /// Further edges to keep as features regardless of their angle.
+    pub feature_edges: Vec<EdgeId>,
+}
+
+impl Default for RemeshingConfig {
+    fn default() -> Self {
+        Self {
+            target_edge_length: 0.0,
+            iterations: 10,
+            feature_angle: 45f32.to_radians(),
+            feature_edges: Vec::new(),
+        }
+    }
+}
+
+/// Why a mesh could not be remeshed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RemeshingError {
+    /// Remeshing is only defined for triangle meshes.
+    NotTriangleMesh,
+    /// The target edge length is negative or not finite.
+    InvalidTargetLength { length: f32 },
+    /// A feature edge does not exist in the mesh.
+    InvalidFeature { edge: EdgeId },
+}
+
+impl fmt::Display for RemeshingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTriangleMesh => f.write_str("remeshing requires a triangle mesh"),
+            Self::InvalidTargetLength { length } => {
+                write!(f, "invalid target edge length {length}")
+            }
+            Self::InvalidFeature { edge } => write!(f, "feature edge {edge} does not exist"),
+        }
+    }
+}
+
+impl std::error::Error for RemeshingError {}
+
+/// The mesh being remeshed, plus which of its edges are features. Edges created while remeshing are only
+/// features if they are halves of a split feature edge.
+struct Remesher<'a> {
+    mesh: &'a mut HalfEdgeMesh,
+    features: Vec<bool>,
+}
+
+impl Remesher<'_> {
+    fn is_feature(&self, e: EdgeId) -> bool {
+        self.features.get(e.index()).copied().unwrap_or(false)
+    }
+
+    fn set_feature(&mut self, e: EdgeId, feature: bool) {
+        if self.features.len() <= e.index() {
+            self.features.resize(e.index() + 1, false);
+        }
+        self.features[e.index()] = feature;
+    }
+
+    fn feature_valence(&self, v: VertexId) -> usize {
+        self.mesh
+            .vertex_edges(v)
+            .filter(|&e| self.is_feature(e))
+            .count()
+    }
+
+    fn face_normal(&self, f: FaceId) -> Vec3 {
+        let mut p = self.mesh.face_positions(f);
+        let (a, b, c) = (p.next().unwrap(), p.next().unwrap(), p.next().unwrap());
+        (b - a).cross(c - a)
+    }
+
+    fn split_long_edges(&mut self, high: f32) {
+        let mut queue: Vec<EdgeId> = self.mesh.edges().collect();
+        while let Some(e) = queue.pop() {
+            if self.mesh.edge_length(e) <= high {
+                continue;
+            }
+            // Both triangles share their third corner when the edge sits on a two-sided sheet; splitting would
+            // need the same diagonal twice.
+            let h = self.mesh.edge_halfedge(e);
+            let t = self.mesh.twin(h);
+            if !self.mesh.is_boundary_edge(e)
+                && self.mesh.dest(self.mesh.next(h)) == self.mesh.dest(self.mesh.next(t))
+            {
+                continue;
+            }
+            let [a, b] = self.mesh.edge_vertices(e);
+            let midpoint = 0.5 * (self.mesh.position(a) + self.mesh.position(b));
+            let feature = self.is_feature(e);
+            let split = self.mesh.split_edge(e, midpoint).expect("edge is live");
+            self.set_feature(split.edge, feature);
+
+            // Triangulate the quads on either side by connecting the new vertex to their opposite corners.
+            let m = split.vertex;
+            let sides = [
+                split.halfedge,
+                self.mesh.twin(self.mesh.prev(split.halfedge)),
+            ];
+            for side in sides {
+                if let Some(f) = self.mesh.face(side) {
+                    let opposite = self.mesh.dest(self.mesh.next(side));
+                    let diagonal = self
+                        .mesh
+                        .split_face(f, m, opposite)
+                        .expect("a new vertex has no other edges");
+                    self.set_feature(self.mesh.edge(diagonal), false);
+                }
+            }
+            queue.extend([e, split.edge]);
+        }
+    }
+
+    /// Whether vertex `v` may be merged away along edge `e`: free vertices always, feature vertices only along
+    /// a feature line they lie in the middle of.
+    fn is_removable(&self, v: VertexId, e: EdgeId) -> bool {
+        let boundary = self.mesh.is_boundary_vertex(v);
+        match self.feature_valence(v) {
+            0 => !boundary,
+            2 => self.is_feature(e),
+            _ => false,
+        }
+    }
+
+    fn collapse_short_edges(&mut self, low: f32, high: f32) {
+        let edges: Vec<EdgeId> = self.mesh.edges().collect();
+        for e in edges {
+            if self.mesh.is_removed_edge(e) || self.mesh.edge_length(e) >= low {
+                continue;
+            }
+            let h = self.mesh.edge_halfedge(e);
+            let candidates = [h, self.mesh.twin(h)];
+            let Some(h) = candidates
+                .into_iter()
+                .find(|&h| self.is_collapse_acceptable(h, high))
+            else {
+                continue;
+            };
+
+            let (a, b) = (self.mesh.origin(h), self.mesh.dest(h));
+            // The collapse merges pairs of edges and keeps either one's handle, so note which neighbours are
+            // joined to either end by a feature and re-mark those edges afterwards.
+            let feature_neighbours: Vec<VertexId> = [a, b]
+                .into_iter()
+                .flat_map(|v| self.mesh.vertex_outgoing_halfedges(v))
+                .filter(|&o| self.is_feature(self.mesh.edge(o)))
+                .map(|o| self.mesh.dest(o))
+                .filter(|&c| c != a && c != b)
+                .collect();
+            self.mesh.collapse_edge(h).expect("collapse was checked");
+            for c in feature_neighbours {
+                if let Some(merged) = self.mesh.find_edge(b, c) {
+                    self.set_feature(merged, true);
+                }
+            }
+        }
+    }
+
+    /// Whether collapsing `h` keeps the mesh manifold, respects features, creates no long edges and turns no
+    /// triangle over.
+    fn is_collapse_acceptable(&self, h: HalfEdgeId, high: f32) -> bool {
+        let mesh = &*self.mesh;
+        let (a, b) = (mesh.origin(h), mesh.dest(h));
+        if !self.is_removable(a, mesh.edge(h)) || mesh.is_collapse_ok(h).is_err() {
+            return false;
+        }
+        let target = mesh.position(b);
+        if mesh
+            .vertex_vertices(a)
+            .any(|c| c != b && mesh.position(c).distance(target) > high)
+        {
+            return false;
+        }
+        let skip = [mesh.face(h), mesh.face(mesh.twin(h))];
+        mesh.vertex_faces(a)
+            .filter(|f| !skip.contains(&Some(*f)))
+            .all(|f| {
+                let corners: Vec<VertexId> = mesh.face_vertices(f).collect();
+                let before = [0, 1, 2].map(|i| mesh.position(corners[i]).as_dvec3());
+                let after = [0, 1, 2].map(|i| {
+                    if corners[i] == a {
+                        target.as_dvec3()
+                    } else {
+                        before[i]
+                    }
+                });
+                normal_dot(before, after) > 0.0
+            })
+    }
+
+    fn flip_edges(&mut self) {
+        let target_valence = |mesh: &HalfEdgeMesh, v: VertexId| -> i64 {
+            if mesh.is_boundary_vertex(v) {
+                4
+            } else {
+                6
+            }
+        };
+        let edges: Vec<EdgeId> = self.mesh.edges().collect();
+        for e in edges {
+            if self.is_feature(e) || self.mesh.is_flip_ok(e).is_err() {
+                continue;
+            }
+            let mesh = &*self.mesh;
+            let h = mesh.edge_halfedge(e);
+            let t = mesh.twin(h);
+            let (a, b) = (mesh.origin(h), mesh.dest(h));
+            let (c, d) = (mesh.dest(mesh.next(h)), mesh.dest(mesh.next(t)));
+            let deviation = |v: VertexId, change: i64| {
+                (mesh.vertex_valence(v) as i64 + change - target_valence(mesh, v)).pow(2)
+            };
+            let before = deviation(a, 0) + deviation(b, 0) + deviation(c, 0) + deviation(d, 0);
+            let after = deviation(a, -1) + deviation(b, -1) + deviation(c, 1) + deviation(d, 1);
+            if after >= before {
+                continue;
+            }
+            // Do not fold the surface over: each new triangle must face the same way as both old ones.
+            let [pa, pb, pc, pd] = [a, b, c, d].map(|v| mesh.position(v).as_dvec3());
+            let folds = [[pd, pc, pa], [pc, pd, pb]].into_iter().any(|new| {
+                [[pa, pb, pc], [pb, pa, pd]]
+                    .into_iter()
+                    .any(|old| normal_dot(new, old) <= 0.0)
+            });
+            if folds {
+                continue;
+            }
+            self.mesh.flip_edge(e).expect("flip was checked");
+        }
+    }
+
+    fn relax_vertices(&mut self) {
+        let mesh = &*self.mesh;
+        let moved: Vec<(VertexId, Vec3)> = mesh
+            .vertices()
+            .filter(|&v| {
+                !mesh.is_boundary_vertex(v)
+                    && !mesh.is_isolated_vertex(v)
+                    && self.feature_valence(v) == 0
+            })
+            .map(|v| {
+                let p = mesh.position(v);
+                let (sum, count) = mesh
+                    .vertex_vertices(v)
+                    .fold((Vec3::ZERO, 0.0), |(sum, count), n| {
+                        (sum + mesh.position(n), count + 1.0)
+                    });
+                let centroid = sum / count;
+                let normal = mesh
+                    .vertex_faces(v)
+                    .map(|f| self.face_normal(f))
+                    .sum::<Vec3>()
+                    .normalize_or_zero();
+                (v, centroid + normal * normal.dot(p - centroid))
+            })
+            .collect();
+        for (v, p) in moved {
+            self.mesh.set_position(v, p);
+        }
+    }
+}
+
+impl HalfEdgeMesh {
+    /// Remesh in place so that edges approach a uniform length and vertices approach valence 6. Removed
+    /// elements are garbage collected afterwards, so existing handles are invalidated.
+    pub fn remesh(&mut self, config: &RemeshingConfig) -> Result<(), RemeshingError> {
+        if self.faces().any(|f| self.face_degree(f) != 3) {
+            return Err(RemeshingError::NotTriangleMesh);
+        }
+        if let Some(&edge) = config
+            .feature_edges
+            .iter()
+            .find(|e| e.index() >= self.edge_count() || self.is_removed_edge(**e))
+        {
+            return Err(RemeshingError::InvalidFeature { edge });
+        }
+        let length = config.target_edge_length;
+        if !length.is_finite() || length < 0.0 {
+            return Err(RemeshingError::InvalidTargetLength { length });
+        }
+        let length = if length > 0.0 {
+            length
+        } else {
+            let (sum, count) = self.edges().fold((0.0, 0), |(sum, count), e| {
+                (sum + self.edge_length(e), count + 1)
+            });
+            if count == 0 {
+                return Ok(());
+            }
+            sum / count as f32
+        };
+
+        let mut remesher = Remesher {
+            features: vec![false; self.edge_count()],
+            mesh: self,
+        };
+        let cos_feature = config.feature_angle.cos();
+        for e in remesher.mesh.edges().collect::<Vec<_>>() {
+            let mesh = &*remesher.mesh;
+            let h = mesh.edge_halfedge(e);
+            let feature = match (mesh.face(h), mesh.face(mesh.twin(h))) {
+                (Some(f), Some(g)) => {
+                    let n = remesher.face_normal(f).normalize_or_zero();
+                    n.dot(remesher.face_normal(g).normalize_or_zero()) < cos_feature
+                }
+                _ => true,
+            };
+            remesher.set_feature(e, feature);
+        }
+        for &e in &config.feature_edges {
+            remesher.set_feature(e, true);
+        }
+
+        let (low, high) = (0.8 * length, 4.0 / 3.0 * length);
+        for _ in 0..config.iterations {
+            remesher.split_long_edges(high);
+            remesher.collapse_short_edges(low, high);
+            remesher.flip_edges();
+            remesher.relax_vertices();
+        }
+        self.garbage_collect();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remeshing_a_two_sided_triangle_does_not_panic() {
+        let positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+        let mut mesh = HalfEdgeMesh::from_triangles(positions, &[0, 1, 2]).unwrap();
+        let back = mesh.twin(mesh.face_halfedge(FaceId::new(0)));
+        mesh.fill_boundary_loop(back).unwrap();
+        let config = RemeshingConfig {
+            target_edge_length: 0.2,
+            ..Default::default()
+        };
+        // Every edge borders both sides of the sheet, so none can be split.
+        mesh.remesh(&config).unwrap();
+        assert_eq!((mesh.vertex_count(), mesh.face_count()), (3, 2));
+    }
+}
@@ -0,0 +1,444 @@
+//! Turning arbitrary polygon soup into something [`HalfEdgeMesh::from_polygons`] accepts.
+//!
+//! Repair runs in stages. Coincident vertices are welded, then faces that collapsed or duplicate another face
+//! are dropped. Faces are paired across edges that exactly two of them share, and flipped so that every pair
+//! agrees on orientation. Edges shared by more than two faces are cut open. Finally each vertex is split into
+//! one copy per fan of faces around it, so that bow-ties and the ends of cut edges become separate vertices.
+
+use std::collections::{HashMap, HashSet};
+
+use glam::Vec3;
+
+use crate::{mesh::HalfEdgeMesh, union_find::UnionFind, weld::weld_vertices};
+
+/// Settings for [`repair_polygons`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RepairConfig {
+    /// Vertices closer than this are merged before anything else. Zero merges only identical positions.
+    pub weld_tolerance: f32,
+    /// Faces with at most this much area are dropped as degenerate. Faces whose corners are collinear up to
+    /// rounding are dropped regardless.
+    pub min_area: f32,
+}
+
+impl Default for RepairConfig {
+    fn default() -> Self {
+        Self {
+            weld_tolerance: 0.0,
+            min_area: 0.0,
+        }
+    }
+}
+
+/// What [`repair_polygons`] changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Input vertices merged into another one.
+    pub welded_vertices: usize,
+    /// Faces dropped because they had fewer than three distinct corners, no area, or indices out of range.
+    pub degenerate_faces: usize,
+    /// Faces dropped because an earlier face has the same corners, in either orientation.
+    pub duplicate_faces: usize,
+    /// Faces whose winding was reversed to agree with their neighbours.
+    pub flipped_faces: usize,
+    /// Edges that were cut open because more than two faces met there, or their faces could not be oriented
+    /// consistently.
+    pub cut_edges: usize,
+    /// Vertex copies added to separate the fans around non-manifold vertices.
+    pub split_vertices: usize,
+}
+
+/// A polygon list that is guaranteed to build, from [`repair_polygons`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RepairedPolygons {
+    pub positions: Vec<Vec3>,
+    pub polygons: Vec<Vec<u32>>,
+    /// The input polygon each output polygon came from.
+    pub source_faces: Vec<usize>,
+    /// The output vertex each input vertex became. Where a vertex was split, this is its first copy.
+    pub vertex_map: Vec<u32>,
+    pub report: RepairReport,
+}
+
+impl RepairedPolygons {
+    /// Build the repaired polygons into a mesh.
+    pub fn into_mesh(self) -> HalfEdgeMesh {
+        HalfEdgeMesh::from_polygons(self.positions, &self.polygons)
+            .expect("repaired polygons always form a manifold mesh")
+    }
+}
+
+/// Two faces meeting across the undirected edge `edge`.
+#[derive(Clone, Copy, Debug)]
+struct Pair {
+    faces: [usize; 2],
+    edge: (u32, u32),
+}
+
+fn polygon_area(positions: &[Vec3], polygon: &[u32]) -> (f32, f32) {
+    let mut normal = Vec3::ZERO;
+    let mut longest = 0.0f32;
+    for (i, &a) in polygon.iter().enumerate() {
+        let (p, q) = (
+            positions[a as usize],
+            positions[polygon[(i + 1) % polygon.len()] as usize],
+        );
+        normal += p.cross(q);
+        longest = longest.max(p.distance_squared(q));
+    }
+    (0.5 * normal.length(), longest)
+}
+
+fn undirected(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+/// Repair a polygon soup so that it can be built into a half-edge mesh. Polygons are counter-clockwise loops
+/// of indices into `positions`, as for [`HalfEdgeMesh::from_polygons`]; anything may be wrong with them.
+pub fn repair_polygons<P: AsRef<[u32]>>(
+    positions: &[Vec3],
+    polygons: &[P],
+    config: &RepairConfig,
+) -> RepairedPolygons {
+    let (mut positions, vertex_map) = weld_vertices(positions, config.weld_tolerance);
+    let mut report = RepairReport {
+        welded_vertices: vertex_map.len() - positions.len(),
+        ..Default::default()
+    };
+
+    // Remap onto the welded vertices, dropping repeated corners and faces left without area.
+    let mut faces: Vec<Vec<u32>> = Vec::new();
+    let mut source_faces = Vec::new();
+    let mut seen: HashSet<Vec<u32>> = HashSet::new();
+    for (index, polygon) in polygons.iter().enumerate() {
+        let polygon = polygon.as_ref();
+        if polygon.iter().any(|&i| i as usize >= vertex_map.len()) {
+            report.degenerate_faces += 1;
+            continue;
+        }
+        let mut face: Vec<u32> = polygon.iter().map(|&i| vertex_map[i as usize]).collect();
+        face.dedup();
+        while face.len() > 1 && face.first() == face.last() {
+            face.pop();
+        }
+        let mut sorted = face.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        let (area, longest) = polygon_area(&positions, &face);
+        if sorted.len() < 3
+            || sorted.len() != face.len()
+            || area <= config.min_area
+            || area <= 1e-7 * longest
+        {
+            report.degenerate_faces += 1;
+            continue;
+        }
+        if !seen.insert(sorted) {
+            report.duplicate_faces += 1;
+            continue;
+        }
+        faces.push(face);
+        source_faces.push(index);
+    }
+
+    // Pair faces across edges that exactly two of them use; every other edge is cut.
+    let mut uses: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (f, face) in faces.iter().enumerate() {
+        for (i, &a) in face.iter().enumerate() {
+            uses.entry(undirected(a, face[(i + 1) % face.len()]))
+                .or_default()
+                .push(f);
+        }
+    }
+    let mut edges: Vec<_> = uses.into_iter().collect();
+    edges.sort_unstable_by_key(|(edge, _)| *edge);
+    let mut pairs = Vec::new();
+    for (edge, users) in edges {
+        match users[..] {
+            [f, g] => pairs.push(Pair {
+                faces: [f, g],
+                edge,
+            }),
+            [_] => {}
+            _ => report.cut_edges += 1,
+        }
+    }
+
+    // Orient each connected component from its first face, dropping pairs that disagree (as around a Möbius
+    // strip), then flip whole components that ended up mostly reversed.
+    let traverses = |face: &[u32], (a, b): (u32, u32)| {
+        let i = face
+            .iter()
+            .position(|&x| x == a)
+            .expect("face uses the edge");
+        face[(i + 1) % face.len()] == b
+    };
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); faces.len()];
+    for (p, pair) in pairs.iter().enumerate() {
+        adjacency[pair.faces[0]].push(p);
+        adjacency[pair.faces[1]].push(p);
+    }
+    let mut flip: Vec<Option<bool>> = vec![None; faces.len()];
+    let mut active = vec![true; pairs.len()];
+    for seed in 0..faces.len() {
+        if flip[seed].is_some() {
+            continue;
+        }
+        flip[seed] = Some(false);
+        let mut component = vec![seed];
+        let mut next = 0;
+        while next < component.len() {
+            let f = component[next];
+            next += 1;
+            for &p in &adjacency[f] {
+                let pair = pairs[p];
+                let g = pair.faces[usize::from(pair.faces[0] == f)];
+                let same_direction =
+                    traverses(&faces[f], pair.edge) == traverses(&faces[g], pair.edge);
+                let wanted = same_direction != flip[f].expect("visited");
+                match flip[g] {
+                    None => {
+                        flip[g] = Some(wanted);
+                        component.push(g);
+                    }
+                    Some(current) if current != wanted && active[p] => {
+                        active[p] = false;
+                        report.cut_edges += 1;
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        let flipped = component.iter().filter(|&&f| flip[f] == Some(true)).count();
+        let invert = 2 * flipped > component.len();
+        for &f in &component {
+            let reverse = flip[f] != Some(invert);
+            if reverse {
+                faces[f].reverse();
+                report.flipped_faces += 1;
+            }
+        }
+    }
+
+    // Corners of paired faces at a shared vertex are the same vertex; the sets of corners this leaves around
+    // a welded vertex are its fans. The two ends of a fan can still run along the same edge in the same
+    // direction, which no half-edge mesh can represent, so each such clash cuts the later face off the fan
+    // and the fans are recomputed.
+    let mut offsets = vec![0];
+    for face in &faces {
+        offsets.push(offsets[offsets.len() - 1] + face.len());
+    }
+    let corner = |f: usize, v: u32| {
+        offsets[f]
+            + faces[f]
+                .iter()
+                .position(|&x| x == v)
+                .expect("face uses the vertex")
+    };
+    let mut pair_of: HashMap<(usize, (u32, u32)), usize> = HashMap::new();
+    for (p, pair) in pairs.iter().enumerate() {
+        for f in pair.faces {
+            pair_of.insert((f, pair.edge), p);
+        }
+    }
+    let welded_count = positions.len();
+    let mut output: Vec<Vec<u32>>;
+    loop {
+        let mut fans = UnionFind::new(offsets[faces.len()]);
+        for (pair, _) in pairs.iter().zip(&active).filter(|(_, &active)| active) {
+            let [f, g] = pair.faces;
+            for v in [pair.edge.0, pair.edge.1] {
+                fans.union(corner(f, v), corner(g, v));
+            }
+        }
+        positions.truncate(welded_count);
+        let mut claimed = vec![false; welded_count];
+        let mut vertex_of_fan: HashMap<usize, u32> = HashMap::new();
+        output = faces
+            .iter()
+            .enumerate()
+            .map(|(f, face)| {
+                face.iter()
+                    .enumerate()
+                    .map(|(i, &v)| {
+                        *vertex_of_fan
+                            .entry(fans.find(offsets[f] + i))
+                            .or_insert_with(|| {
+                                if claimed[v as usize] {
+                                    positions.push(positions[v as usize]);
+                                    positions.len() as u32 - 1
+                                } else {
+                                    claimed[v as usize] = true;
+                                    v
+                                }
+                            })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut directed: HashSet<(u32, u32)> = HashSet::new();
+        let mut clash = None;
+        'faces: for (f, face) in output.iter().enumerate() {
+            for (i, &a) in face.iter().enumerate() {
+                if !directed.insert((a, face[(i + 1) % face.len()])) {
+                    clash = Some((f, i));
+                    break 'faces;
+                }
+            }
+        }
+        let Some((f, i)) = clash else {
+            break;
+        };
+        // Cut the face from its fan at the clashing edge's origin, by dropping the pair across its other edge
+        // there. If that is already cut, the face is paired across the clashing edge itself; drop that
+        // instead.
+        let face = &faces[f];
+        let previous = face[(i + face.len() - 1) % face.len()];
+        let p = pair_of
+            .get(&(f, undirected(previous, face[i])))
+            .copied()
+            .filter(|&p| active[p])
+            .or_else(|| {
+                pair_of
+                    .get(&(f, undirected(face[i], face[(i + 1) % face.len()])))
+                    .copied()
+            })
+            .expect("a face on a clashing edge is paired with a neighbour");
+        active[p] = false;
+        report.cut_edges += 1;
+    }
+    report.split_vertices = positions.len() - welded_count;
+
+    RepairedPolygons {
+        positions,
+        polygons: output,
+        source_faces,
+        vertex_map,
+        report,
+    }
+}
+
+impl HalfEdgeMesh {
+    /// Build a mesh from any polygon soup, repairing whatever stops [`HalfEdgeMesh::from_polygons`] from
+    /// accepting it. See [`repair_polygons`] for the steps taken.
+    pub fn from_polygon_soup<P: AsRef<[u32]>>(
+        positions: &[Vec3],
+        polygons: &[P],
+        config: &RepairConfig,
+    ) -> (Self, RepairReport) {
+        let repaired = repair_polygons(positions, polygons, config);
+        let report = repaired.report;
+        (repaired.into_mesh(), report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(repaired: &RepairedPolygons) -> HalfEdgeMesh {
+        let mesh = repaired.clone().into_mesh();
+        assert!(mesh.validate().is_ok());
+        assert_eq!(repaired.source_faces.len(), mesh.face_count());
+        mesh
+    }
+
+    #[test]
+    fn bow_ties_are_split_at_the_shared_vertex() {
+        let positions = [
+            Vec3::ZERO,
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(-1.0, -1.0, 0.0),
+        ];
+        let repaired = repair_polygons(&positions, &[[0, 1, 2], [0, 3, 4]], &Default::default());
+        assert_eq!(repaired.report.split_vertices, 1);
+        assert_eq!(repaired.positions.len(), 6);
+        let mesh = check(&repaired);
+        assert_eq!(mesh.face_count(), 2);
+    }
+
+    #[test]
+    fn a_mobius_strip_is_cut_where_it_cannot_be_oriented() {
+        let n = 12;
+        let mut positions = Vec::new();
+        for k in 0..n {
+            let angle = core::f32::consts::TAU * k as f32 / n as f32;
+            let center = Vec3::new(angle.cos(), angle.sin(), 0.0);
+            let (sin, cos) = (0.5 * angle).sin_cos();
+            let across = 0.2 * (cos * center + sin * Vec3::Z);
+            positions.extend([center + across, center - across]);
+        }
+        let mut quads: Vec<[u32; 4]> = (0..n - 1)
+            .map(|k| [2 * k, 2 * k + 2, 2 * k + 3, 2 * k + 1])
+            .collect();
+        // The half twist joins each side of the strip to the other one.
+        quads.push([2 * n - 2, 1, 0, 2 * n - 1]);
+        let repaired = repair_polygons(&positions, &quads, &Default::default());
+        assert!(repaired.report.cut_edges >= 1);
+        let mesh = check(&repaired);
+        assert_eq!(mesh.face_count(), n as usize);
+    }
+
+    #[test]
+    fn windings_are_made_to_agree() {
+        let positions = [Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0), Vec3::Y];
+        let repaired = repair_polygons(&positions, &[[0, 1, 2], [0, 3, 2]], &Default::default());
+        assert_eq!(repaired.report.flipped_faces, 1);
+        assert_eq!(repaired.report.cut_edges, 0);
+        check(&repaired);
+    }
+
+    #[test]
+    fn duplicate_degenerate_and_out_of_range_faces_are_dropped() {
+        let positions = [Vec3::ZERO, Vec3::X, Vec3::Y, 2.0 * Vec3::X];
+        let polygons = [
+            vec![0, 1, 2],
+            vec![2, 1, 0],
+            vec![1, 2, 0],
+            vec![0, 1, 3],
+            vec![0, 1, 7],
+            vec![2, 2, 1],
+        ];
+        let repaired = repair_polygons(&positions, &polygons, &Default::default());
+        assert_eq!(repaired.report.duplicate_faces, 2);
+        assert_eq!(repaired.report.degenerate_faces, 3);
+        assert_eq!(repaired.source_faces, [0]);
+        check(&repaired);
+    }
+
+    #[test]
+    fn edges_of_more_than_two_faces_are_cut() {
+        let positions = [Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::Z, -Vec3::Y];
+        let polygons = [[0, 1, 2], [1, 0, 3], [1, 0, 4]];
+        let repaired = repair_polygons(&positions, &polygons, &Default::default());
+        assert_eq!(repaired.report.cut_edges, 1);
+        let mesh = check(&repaired);
+        assert_eq!(mesh.face_count(), 3);
+    }
+
+    #[test]
+    fn nearby_vertices_are_welded() {
+        let positions = [
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::Y,
+            Vec3::new(1.0, 1e-4, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+        ];
+        let config = RepairConfig {
+            weld_tolerance: 1e-3,
+            ..Default::default()
+        };
+        let repaired = repair_polygons(&positions, &[[0, 1, 2], [2, 3, 4]], &config);
+        assert_eq!(repaired.report.welded_vertices, 1);
+        let mesh = check(&repaired);
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(
+            mesh.edges().filter(|&e| !mesh.is_boundary_edge(e)).count(),
+            1
+        );
+    }
+}
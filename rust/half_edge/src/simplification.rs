@@ -0,0 +1,712 @@
+//! Quadric error metric (Garland-Heckbert) decimation.
+//!
+//! Every vertex accumulates a quadric measuring squared distance to the planes of its original faces; edges
+//! are collapsed cheapest-first into the point that minimises the summed quadric of their two ends. With
+//! [`SimplificationConfig::attribute_weight`] set, the quadrics are the generalised ones from Garland and
+//! Heckbert's 1998 follow-up: positions, texture coordinates and normals are treated as one point in a higher
+//! dimensional space, so collapses that would smear attributes are penalised and new attribute values are
+//! optimised along with positions.
+
+use core::fmt;
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use glam::{DVec3, Vec2, Vec3};
+
+use crate::{
+    io::MeshData,
+    mesh::{EdgeId, HalfEdgeId, HalfEdgeMesh, VertexId},
+    predicates::normal_dot,
+};
+
+/// When to stop simplifying, and what to protect.
+#[derive(Clone, Debug)]
+pub struct SimplificationConfig {
+    /// Stop once the mesh has at most this many faces.
+    pub target_face_count: usize,
+    /// Never perform a collapse whose quadric error exceeds this.
+    pub max_error: f64,
+    /// Keep boundary vertices exactly where they are. When off, boundaries may still be simplified but are
+    /// held in shape by extra quadrics perpendicular to the boundary faces.
+    pub preserve_boundary: bool,
+    /// Weight of texture coordinates and normals relative to positions in the quadrics. Zero simplifies by
+    /// geometry alone. Only [`MeshData::simplify`] has attributes to weigh.
+    pub attribute_weight: f64,
+}
+
+impl Default for SimplificationConfig {
+    fn default() -> Self {
+        Self {
+            target_face_count: 0,
+            max_error: f64::INFINITY,
+            preserve_boundary: true,
+            attribute_weight: 0.0,
+        }
+    }
+}
+
+/// What a simplification run did.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SimplificationReport {
+    pub collapses: usize,
+    pub faces_before: usize,
+    pub faces_after: usize,
+    /// The largest quadric error of any collapse performed.
+    pub max_error: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimplificationError {
+    /// Quadric simplification is only defined for triangle meshes.
+    NotTriangleMesh,
+}
+
+impl fmt::Display for SimplificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTriangleMesh => f.write_str("simplification requires a triangle mesh"),
+        }
+    }
+}
+
+impl std::error::Error for SimplificationError {}
+
+/// A generalised quadric `Q(v) = vᵀAv + 2bᵀv + c` over points of dimension `n`.
+#[derive(Clone, Debug)]
+struct Quadric {
+    n: usize,
+    /// Row-major `n * n`.
+    a: Vec<f64>,
+    b: Vec<f64>,
+    c: f64,
+}
+
+impl Quadric {
+    fn zero(n: usize) -> Self {
+        Self {
+            n,
+            a: vec![0.0; n * n],
+            b: vec![0.0; n],
+            c: 0.0,
+        }
+    }
+
+    /// The quadric of squared distance to the plane (or, for `n > 3`, the 2D affine subspace) through the
+    /// triangle `p`, scaled by `weight`.
+    fn triangle(p: [&[f64]; 3], weight: f64) -> Self {
+        let n = p[0].len();
+        let sub =
+            |x: &[f64], y: &[f64]| -> Vec<f64> { x.iter().zip(y).map(|(x, y)| x - y).collect() };
+        let dot = |x: &[f64], y: &[f64]| -> f64 { x.iter().zip(y).map(|(x, y)| x * y).sum() };
+        let normalize = |x: &mut Vec<f64>| {
+            let len = dot(x, x).sqrt();
+            if len > 1e-300 {
+                x.iter_mut().for_each(|c| *c /= len);
+            }
+        };
+
+        let mut e1 = sub(p[1], p[0]);
+        normalize(&mut e1);
+        let mut e2 = sub(p[2], p[0]);
+        let along = dot(&e1, &e2);
+        e2.iter_mut().zip(&e1).for_each(|(c, e)| *c -= along * e);
+        normalize(&mut e2);
+
+        let mut q = Self::zero(n);
+        for i in 0..n {
+            for j in 0..n {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                q.a[i * n + j] = weight * (identity - e1[i] * e1[j] - e2[i] * e2[j]);
+            }
+        }
+        let (p_e1, p_e2) = (dot(p[0], &e1), dot(p[0], &e2));
+        for i in 0..n {
+            q.b[i] = weight * (p_e1 * e1[i] + p_e2 * e2[i] - p[0][i]);
+        }
+        q.c = weight * (dot(p[0], p[0]) - p_e1 * p_e1 - p_e2 * p_e2);
+        q
+    }
+
+    /// The quadric of squared distance to a plane in position space, ignoring any attribute dimensions.
+    fn plane(n: usize, normal: DVec3, point: DVec3, weight: f64) -> Self {
+        let mut q = Self::zero(n);
+        let normal = normal.to_array();
+        let d = -normal
+            .iter()
+            .zip(point.to_array())
+            .map(|(n, p)| n * p)
+            .sum::<f64>();
+        for i in 0..3 {
+            for j in 0..3 {
+                q.a[i * n + j] = weight * normal[i] * normal[j];
+            }
+            q.b[i] = weight * d * normal[i];
+        }
+        q.c = weight * d * d;
+        q
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.a.iter_mut().zip(&other.a).for_each(|(x, y)| *x += y);
+        self.b.iter_mut().zip(&other.b).for_each(|(x, y)| *x += y);
+        self.c += other.c;
+    }
+
+    fn error(&self, v: &[f64]) -> f64 {
+        let n = self.n;
+        let mut e = self.c;
+        for i in 0..n {
+            let row: f64 = (0..n).map(|j| self.a[i * n + j] * v[j]).sum();
+            e += v[i] * row + 2.0 * self.b[i] * v[i];
+        }
+        e.max(0.0)
+    }
+
+    /// The point minimising the quadric, if `A` is well conditioned enough to solve `Av = -b`.
+    fn minimizer(&self) -> Option<Vec<f64>> {
+        let n = self.n;
+        let mut m = self.a.clone();
+        let mut x: Vec<f64> = self.b.iter().map(|b| -b).collect();
+        let scale = m.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+        if scale == 0.0 {
+            return None;
+        }
+        for col in 0..n {
+            let pivot =
+                (col..n).max_by(|&i, &j| m[i * n + col].abs().total_cmp(&m[j * n + col].abs()))?;
+            if m[pivot * n + col].abs() < 1e-9 * scale {
+                return None;
+            }
+            if pivot != col {
+                for k in 0..n {
+                    m.swap(pivot * n + k, col * n + k);
+                }
+                x.swap(pivot, col);
+            }
+            for row in col + 1..n {
+                let factor = m[row * n + col] / m[col * n + col];
+                for k in col..n {
+                    m[row * n + k] -= factor * m[col * n + k];
+                }
+                x[row] -= factor * x[col];
+            }
+        }
+        for col in (0..n).rev() {
+            let tail: f64 = (col + 1..n).map(|k| m[col * n + k] * x[k]).sum();
+            x[col] = (x[col] - tail) / m[col * n + col];
+        }
+        Some(x)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Candidate {
+    error: f64,
+    edge: EdgeId,
+    /// The edge's endpoints and their versions when this candidate was computed; stale candidates are
+    /// skipped.
+    ends: [VertexId; 2],
+    stamp: [u32; 2],
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so that `BinaryHeap` pops the cheapest collapse first.
+        other.error.total_cmp(&self.error)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A planned collapse: which half-edge to collapse, and where the survivor ends up.
+struct Plan {
+    halfedge: HalfEdgeId,
+    target: Vec<f64>,
+    error: f64,
+}
+
+struct Simplifier<'a> {
+    data: &'a mut MeshData,
+    /// Each vertex's position followed by its weighted attributes.
+    points: Vec<Vec<f64>>,
+    quadrics: Vec<Quadric>,
+    locked: Vec<bool>,
+    version: Vec<u32>,
+    attribute_weight: f64,
+}
+
+impl Simplifier<'_> {
+    fn plan(&self, e: EdgeId) -> Option<Plan> {
+        let mesh = &self.data.mesh;
+        let h = mesh.edge_halfedge(e);
+        let t = mesh.twin(h);
+        let (a, b) = (mesh.origin(h), mesh.dest(h));
+        let (locked_a, locked_b) = (self.locked[a.index()], self.locked[b.index()]);
+        if locked_a && locked_b {
+            return None;
+        }
+
+        let mut q = self.quadrics[a.index()].clone();
+        q.add(&self.quadrics[b.index()]);
+        let (pa, pb) = (&self.points[a.index()], &self.points[b.index()]);
+
+        // Collapse towards a locked end, keeping it where it is. Otherwise take the optimum, falling back to
+        // the best of the endpoints and the midpoint when the quadric is singular.
+        let (halfedge, target) = if locked_b {
+            (h, pb.clone())
+        } else if locked_a {
+            (t, pa.clone())
+        } else {
+            let target = q.minimizer().unwrap_or_else(|| {
+                let mid: Vec<f64> = pa.iter().zip(pb).map(|(x, y)| 0.5 * (x + y)).collect();
+                [pa.clone(), pb.clone(), mid]
+                    .into_iter()
+                    .min_by(|x, y| q.error(x).total_cmp(&q.error(y)))
+                    .expect("three candidates")
+            });
+            (h, target)
+        };
+        Some(Plan {
+            error: q.error(&target),
+            halfedge,
+            target,
+        })
+    }
+
+    fn push(&self, heap: &mut BinaryHeap<Candidate>, e: EdgeId) {
+        if let Some(plan) = self.plan(e) {
+            let [a, b] = self.data.mesh.edge_vertices(e);
+            heap.push(Candidate {
+                error: plan.error,
+                edge: e,
+                ends: [a, b],
+                stamp: [self.version[a.index()], self.version[b.index()]],
+            });
+        }
+    }
+
+    /// Whether moving the collapsed vertex to `target` would turn any surrounding triangle upside down.
+    fn flips_faces(&self, h: HalfEdgeId, target: Vec3) -> bool {
+        let mesh = &self.data.mesh;
+        let t = mesh.twin(h);
+        let (a, b) = (mesh.origin(h), mesh.dest(h));
+        let skip = [mesh.face(h), mesh.face(t)];
+        for v in [a, b] {
+            for f in mesh.vertex_faces(v) {
+                if skip.contains(&Some(f)) {
+                    continue;
+                }
+                let corners: Vec<VertexId> = mesh.face_vertices(f).collect();
+                let before = [0, 1, 2].map(|i| mesh.position(corners[i]).as_dvec3());
+                let after = [0, 1, 2].map(|i| {
+                    let c = corners[i];
+                    if c == a || c == b {
+                        target.as_dvec3()
+                    } else {
+                        before[i]
+                    }
+                });
+                if normal_dot(before, after) <= 0.0 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn run(&mut self, config: &SimplificationConfig) -> SimplificationReport {
+        let mut report = SimplificationReport {
+            faces_before: self.data.mesh.faces().count(),
+            ..Default::default()
+        };
+        let mut faces = report.faces_before;
+
+        let mut heap = BinaryHeap::new();
+        for e in self.data.mesh.edges().collect::<Vec<_>>() {
+            self.push(&mut heap, e);
+        }
+
+        while faces > config.target_face_count {
+            let Some(candidate) = heap.pop() else {
+                break;
+            };
+            if candidate.error > config.max_error {
+                break;
+            }
+            let mesh = &self.data.mesh;
+            if mesh.is_removed_edge(candidate.edge) {
+                continue;
+            }
+            let [a, b] = mesh.edge_vertices(candidate.edge);
+            if candidate.ends != [a, b]
+                || candidate.stamp != [self.version[a.index()], self.version[b.index()]]
+            {
+                continue;
+            }
+            let Some(plan) = self.plan(candidate.edge) else {
+                continue;
+            };
+            let target_position = Vec3::new(
+                plan.target[0] as f32,
+                plan.target[1] as f32,
+                plan.target[2] as f32,
+            );
+            if mesh.is_collapse_ok(plan.halfedge).is_err()
+                || self.flips_faces(plan.halfedge, target_position)
+            {
+                continue;
+            }
+
+            let h = plan.halfedge;
+            let removed = mesh.origin(h);
+            let survivor = mesh.dest(h);
+            let removed_faces = [mesh.face(h), mesh.face(mesh.twin(h))]
+                .iter()
+                .flatten()
+                .count();
+            let moved_corners: Vec<HalfEdgeId> = mesh
+                .vertex_outgoing_halfedges(removed)
+                .filter(|&o| o != h)
+                .collect();
+            // The survivor's corner in the same wedge, for carrying attributes onto the moved corners.
+            let wedge = if mesh.face(h).is_some() {
+                mesh.next(h)
+            } else {
+                mesh.twin(h)
+            };
+            // The removed vertex's corner in that wedge, the counterpart for the survivor's own corners.
+            let removed_wedge = if mesh.face(h).is_some() {
+                h
+            } else {
+                mesh.next(mesh.twin(h))
+            };
+            let kept_corners: Vec<HalfEdgeId> = mesh
+                .vertex_outgoing_halfedges(survivor)
+                .filter(|&o| o != mesh.twin(h))
+                .collect();
+
+            self.data
+                .mesh
+                .collapse_edge(h)
+                .expect("collapse was checked");
+            // Attributes slide along the edge with the survivor, its corners pairing up across the wedge the
+            // collapsed edge lay in. A locked survivor keeps its own values.
+            let mesh = &mut self.data.mesh;
+            let (from, to) = (mesh.position(removed), mesh.position(survivor));
+            let s = (target_position - from).dot(to - from) / from.distance_squared(to);
+            let s = if s.is_finite() && !self.locked[survivor.index()] {
+                s.clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            mesh.blend_attributes(survivor, &[(removed, 1.0 - s), (survivor, s)]);
+            for &o in &moved_corners {
+                mesh.blend_attributes(o, &[(o, 1.0 - s), (wedge, s)]);
+            }
+            for &o in &kept_corners {
+                if !mesh.is_removed_halfedge(o) {
+                    mesh.blend_attributes(o, &[(removed_wedge, 1.0 - s), (o, s)]);
+                }
+            }
+            mesh.set_position(survivor, target_position);
+            let removed_quadric = self.quadrics[removed.index()].clone();
+            self.quadrics[survivor.index()].add(&removed_quadric);
+            self.points[survivor.index()] = plan.target;
+            self.version[survivor.index()] += 1;
+            self.update_corners(survivor);
+
+            faces -= removed_faces;
+            report.collapses += 1;
+            report.max_error = report.max_error.max(plan.error);
+            for e in self.data.mesh.vertex_edges(survivor).collect::<Vec<_>>() {
+                self.push(&mut heap, e);
+            }
+        }
+
+        report.faces_after = faces;
+        report
+    }
+
+    /// With an attribute weight, give the corners of an unlocked survivor its optimised texture coordinate
+    /// and normal instead of the blended ones.
+    fn update_corners(&mut self, survivor: VertexId) {
+        let w = self.attribute_weight;
+        if w <= 0.0 || self.locked[survivor.index()] {
+            return;
+        }
+        let mesh = &self.data.mesh;
+        let corners: Vec<HalfEdgeId> = mesh
+            .vertex_outgoing_halfedges(survivor)
+            .filter(|&h| !mesh.is_boundary_halfedge(h))
+            .collect();
+        let point = &self.points[survivor.index()];
+        let (uvs, normals) = (self.data.uvs(), self.data.normals());
+        let mesh = &mut self.data.mesh;
+
+        let mut offset = 3;
+        if let Some(uvs) = uvs {
+            let uv = Vec2::new((point[offset] / w) as f32, (point[offset + 1] / w) as f32);
+            corners.iter().for_each(|&h| mesh.set_attribute(uvs, h, uv));
+            offset += 2;
+        }
+        if let Some(normals) = normals {
+            let n = Vec3::new(
+                (point[offset] / w) as f32,
+                (point[offset + 1] / w) as f32,
+                (point[offset + 2] / w) as f32,
+            )
+            .normalize_or_zero();
+            corners
+                .iter()
+                .for_each(|&h| mesh.set_attribute(normals, h, n));
+        }
+    }
+}
+
+impl MeshData {
+    /// Simplify the mesh, carrying texture coordinates and normals along. Vertices on a texture or normal seam
+    /// (whose corners disagree) are kept in place so the seam survives. Removed elements are garbage collected.
+    pub fn simplify(
+        &mut self,
+        config: &SimplificationConfig,
+    ) -> Result<SimplificationReport, SimplificationError> {
+        let mesh = &self.mesh;
+        if mesh.faces().any(|f| mesh.face_degree(f) != 3) {
+            return Err(SimplificationError::NotTriangleMesh);
+        }
+        let w = config.attribute_weight;
+
+        let mut locked = vec![false; mesh.vertex_count()];
+        let mut points = Vec::with_capacity(mesh.vertex_count());
+        for v in 0..mesh.vertex_count() {
+            let v = VertexId::new(v);
+            let corners: Vec<HalfEdgeId> = mesh
+                .vertex_outgoing_halfedges(v)
+                .filter(|&h| !mesh.is_boundary_halfedge(h))
+                .collect();
+            let mut point = mesh.position(v).as_dvec3().to_array().to_vec();
+            locked[v.index()] = config.preserve_boundary && mesh.is_boundary_vertex(v);
+            if let Some(uvs) = self.uvs().map(|uvs| mesh.attribute_values(uvs)) {
+                let first = corners.first().map_or(Vec2::ZERO, |h| uvs[h.index()]);
+                locked[v.index()] |= corners.iter().any(|h| uvs[h.index()] != first);
+                if w > 0.0 {
+                    point.extend(first.as_dvec2().to_array().map(|x| x * w));
+                }
+            }
+            if let Some(normals) = self.normals().map(|normals| mesh.attribute_values(normals)) {
+                let first = corners.first().map_or(Vec3::ZERO, |h| normals[h.index()]);
+                locked[v.index()] |= corners.iter().any(|h| normals[h.index()] != first);
+                if w > 0.0 {
+                    point.extend(first.as_dvec3().to_array().map(|x| x * w));
+                }
+            }
+            points.push(point);
+        }
+        let n = points.first().map_or(3, Vec::len);
+
+        let mut quadrics = vec![Quadric::zero(n); mesh.vertex_count()];
+        for f in mesh.faces() {
+            let corners: Vec<VertexId> = mesh.face_vertices(f).collect();
+            let [p0, p1, p2] = [0, 1, 2].map(|i| mesh.position(corners[i]).as_dvec3());
+            let area = 0.5 * (p1 - p0).cross(p2 - p0).length();
+            let q = Quadric::triangle(
+                [0, 1, 2].map(|i| points[corners[i].index()].as_slice()),
+                area,
+            );
+            for c in corners {
+                quadrics[c.index()].add(&q);
+            }
+        }
+        // Hold boundaries in place with planes perpendicular to the boundary faces, weighted heavily so that
+        // sliding along the boundary is cheap but moving off it is not.
+        for h in mesh.halfedges() {
+            if !mesh.is_boundary_halfedge(h) {
+                continue;
+            }
+            let t = mesh.twin(h);
+            let (p0, p1) = (
+                mesh.position(mesh.origin(h)).as_dvec3(),
+                mesh.position(mesh.dest(h)).as_dvec3(),
+            );
+            let p2 = mesh.position(mesh.dest(mesh.next(t))).as_dvec3();
+            let face_normal = (p0 - p1).cross(p2 - p1);
+            let edge = p1 - p0;
+            let normal = edge.cross(face_normal).normalize_or_zero();
+            let q = Quadric::plane(n, normal, p0, 1000.0 * edge.length_squared());
+            for v in [mesh.origin(h), mesh.dest(h)] {
+                quadrics[v.index()].add(&q);
+            }
+        }
+
+        let version = vec![0; mesh.vertex_count()];
+        let report = Simplifier {
+            data: self,
+            points,
+            quadrics,
+            locked,
+            version,
+            attribute_weight: w,
+        }
+        .run(config);
+
+        self.mesh.garbage_collect();
+        Ok(report)
+    }
+}
+
+impl HalfEdgeMesh {
+    /// Simplify the mesh by geometry alone. Removed elements are garbage collected afterwards.
+    pub fn simplify(
+        &mut self,
+        config: &SimplificationConfig,
+    ) -> Result<SimplificationReport, SimplificationError> {
+        let mut data = MeshData::new(std::mem::take(self));
+        let result = data.simplify(config);
+        *self = data.mesh;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subdivision::SubdivisionConfig;
+
+    /// An octahedron refined by Loop subdivision and pushed out onto the unit sphere.
+    fn sphere(levels: u32) -> HalfEdgeMesh {
+        let positions = vec![Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+        let triangles = [
+            0, 2, 4, 2, 1, 4, 1, 3, 4, 3, 0, 4, 2, 0, 5, 1, 2, 5, 3, 1, 5, 0, 3, 5,
+        ];
+        let config = SubdivisionConfig {
+            levels,
+            ..Default::default()
+        };
+        let mut mesh = HalfEdgeMesh::from_triangles(positions, &triangles)
+            .unwrap()
+            .loop_subdivision(&config)
+            .unwrap();
+        for v in mesh.vertices().collect::<Vec<_>>() {
+            mesh.set_position(v, mesh.position(v).normalize());
+        }
+        mesh
+    }
+
+    fn grid(n: u32) -> HalfEdgeMesh {
+        let positions: Vec<Vec3> = (0..=n)
+            .flat_map(|j| (0..=n).map(move |i| Vec3::new(i as f32, j as f32, 0.0) / n as f32))
+            .collect();
+        let mut triangles = Vec::new();
+        for j in 0..n {
+            for i in 0..n {
+                let v = j * (n + 1) + i;
+                triangles.extend([v, v + 1, v + n + 2, v, v + n + 2, v + n + 1]);
+            }
+        }
+        HalfEdgeMesh::from_triangles(positions, &triangles).unwrap()
+    }
+
+    #[test]
+    fn stops_at_the_face_target() {
+        let mut mesh = sphere(3);
+        assert_eq!(mesh.face_count(), 512);
+        let config = SimplificationConfig {
+            target_face_count: 100,
+            ..Default::default()
+        };
+        let report = mesh.simplify(&config).unwrap();
+        assert_eq!(report.faces_before, 512);
+        assert!((98..=100).contains(&report.faces_after), "{report:?}");
+        assert_eq!(mesh.face_count(), report.faces_after);
+        assert!(!mesh.has_garbage());
+        assert!(mesh.validate().is_ok());
+        assert!(mesh.edges().all(|e| !mesh.is_boundary_edge(e)));
+        // Every vertex stays close to the sphere it was sampled from.
+        assert!(mesh
+            .vertices()
+            .all(|v| (mesh.position(v).length() - 1.0).abs() < 0.1));
+    }
+
+    #[test]
+    fn flat_regions_simplify_without_error() {
+        let mut mesh = grid(6);
+        let report = mesh.simplify(&Default::default()).unwrap();
+        assert!(report.max_error < 1e-9, "{report:?}");
+        assert!(report.faces_after < report.faces_before);
+        assert!(mesh.validate().is_ok());
+        assert!(mesh.vertices().all(|v| mesh.position(v).z == 0.0));
+        // The boundary is preserved, so its corners and edges' vertices all stay.
+        let boundary = mesh
+            .vertices()
+            .filter(|&v| mesh.is_boundary_vertex(v))
+            .count();
+        assert_eq!(boundary, 24);
+    }
+
+    #[test]
+    fn max_error_limits_collapses() {
+        let mut mesh = sphere(2);
+        let config = SimplificationConfig {
+            max_error: 0.0,
+            ..Default::default()
+        };
+        let report = mesh.simplify(&config).unwrap();
+        assert_eq!(report.collapses, 0);
+        assert_eq!(mesh.face_count(), 128);
+    }
+
+    /// A grid whose texture coordinates are its positions.
+    fn textured_grid(n: u32) -> MeshData {
+        let mesh = grid(n);
+        let corner_uvs: Vec<Vec2> = mesh
+            .halfedges()
+            .map(|h| match mesh.face(h) {
+                Some(_) => mesh.position(mesh.origin(h)).truncate(),
+                None => Vec2::ZERO,
+            })
+            .collect();
+        let mut data = MeshData::new(mesh);
+        data.set_uvs(&corner_uvs).unwrap();
+        data
+    }
+
+    /// Check that the texture coordinates are still the positions, which also means that the corners of
+    /// every vertex agree.
+    fn check_uvs(data: &MeshData) {
+        for h in data
+            .mesh
+            .halfedges()
+            .filter(|&h| !data.mesh.is_boundary_halfedge(h))
+        {
+            let p = data.mesh.position(data.mesh.origin(h)).truncate();
+            assert!(data.uv(h).unwrap().distance(p) < 1e-5, "corner {h}");
+        }
+    }
+
+    #[test]
+    fn corner_data_follows_the_mesh() {
+        let mut data = textured_grid(6);
+        data.simplify(&Default::default()).unwrap();
+        check_uvs(&data);
+    }
+
+    #[test]
+    fn corner_data_follows_interior_collapses() {
+        let mut data = textured_grid(10);
+        let config = SimplificationConfig {
+            target_face_count: 120,
+            ..Default::default()
+        };
+        data.simplify(&config).unwrap();
+        assert_eq!(data.mesh.face_count(), 120);
+        // Stopping early leaves interior vertices that took the place of their collapsed neighbours.
+        let mesh = &data.mesh;
+        assert!(mesh.vertices().any(|v| !mesh.is_boundary_vertex(v)));
+        check_uvs(&data);
+    }
+}
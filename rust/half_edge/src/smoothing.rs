@@ -0,0 +1,195 @@
+//! Laplacian and Taubin smoothing.
+//!
+//! Every step moves each free vertex a fraction of the way towards the weighted average of its neighbours.
+//! Plain Laplacian smoothing shrinks the mesh; Taubin's λ|μ scheme follows every shrinking step with a
+//! slightly larger inflating one, which removes noise without losing volume.
+
+use core::fmt;
+
+use glam::Vec3;
+
+use crate::{
+    intrinsic::{IntrinsicError, IntrinsicTriangulation},
+    mesh::{HalfEdgeMesh, VertexId},
+};
+
+/// How the neighbours of a vertex are weighted when averaging them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SmoothingWeights {
+    /// Every neighbour counts the same. This also evens out the triangulation.
+    #[default]
+    Uniform,
+    /// Neighbours are weighted by the cotangent weights of the connecting edges, clamped at zero. This moves
+    /// vertices along the surface normal and leaves the triangulation alone. Needs a triangle mesh.
+    Cotangent,
+    /// Cotangent weights of the intrinsic Delaunay triangulation. They are not negative even on slivers and
+    /// obtuse triangles, so badly shaped regions are smoothed instead of clamped away. Neighbours are the
+    /// vertices joined by intrinsic edges, which need not be neighbours in the mesh. Needs a triangle mesh.
+    ///
+    /// Moving the vertices changes the surface the intrinsic edges lie on, so the triangulation is built and
+    /// flipped to Delaunay afresh before every step (both steps of a Taubin iteration). That costs a pass
+    /// over every half-edge plus the flips, which on a badly shaped mesh can outweigh the smoothing itself.
+    IntrinsicCotangent,
+}
+
+/// Which smoothing scheme to run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmoothingMethod {
+    /// Move every vertex by `lambda` times its Laplacian each iteration.
+    Laplacian { lambda: f32 },
+    /// Alternate a step of `lambda` with a step of `mu`, which should be negative and slightly larger in
+    /// magnitude.
+    Taubin { lambda: f32, mu: f32 },
+}
+
+impl Default for SmoothingMethod {
+    fn default() -> Self {
+        Self::Taubin {
+            lambda: 0.5,
+            mu: -0.53,
+        }
+    }
+}
+
+/// Settings for [`HalfEdgeMesh::smooth`].
+#[derive(Clone, Debug)]
+pub struct SmoothingConfig {
+    pub iterations: u32,
+    pub method: SmoothingMethod,
+    pub weights: SmoothingWeights,
+    /// Keep boundary vertices in place. Otherwise they are smoothed along the boundary, using only their
+    /// boundary neighbours.
+    pub lock_boundary: bool,
+    /// Vertices that must not move.
+    pub pinned: Vec<VertexId>,
+}
+
+impl Default for SmoothingConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 10,
+            method: SmoothingMethod::default(),
+            weights: SmoothingWeights::default(),
+            lock_boundary: true,
+            pinned: Vec::new(),
+        }
+    }
+}
+
+/// Why a mesh could not be smoothed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmoothingError {
+    /// Cotangent weights are only defined for triangle meshes.
+    NotTriangleMesh,
+    /// A pinned vertex does not exist in the mesh.
+    InvalidPinned { vertex: VertexId },
+}
+
+impl fmt::Display for SmoothingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTriangleMesh => f.write_str("cotangent weights require a triangle mesh"),
+            Self::InvalidPinned { vertex } => write!(f, "pinned vertex {vertex} does not exist"),
+        }
+    }
+}
+
+impl std::error::Error for SmoothingError {}
+
+impl From<IntrinsicError> for SmoothingError {
+    fn from(err: IntrinsicError) -> Self {
+        match err {
+            IntrinsicError::NotTriangleMesh => Self::NotTriangleMesh,
+        }
+    }
+}
+
+impl HalfEdgeMesh {
+    /// Smooth vertex positions in place. The connectivity is not changed.
+    pub fn smooth(&mut self, config: &SmoothingConfig) -> Result<(), SmoothingError> {
+        if config.weights != SmoothingWeights::Uniform
+            && self.faces().any(|f| self.face_degree(f) != 3)
+        {
+            return Err(SmoothingError::NotTriangleMesh);
+        }
+        let mut fixed = vec![false; self.vertex_count()];
+        for &vertex in &config.pinned {
+            if vertex.index() >= self.vertex_count() || self.is_removed_vertex(vertex) {
+                return Err(SmoothingError::InvalidPinned { vertex });
+            }
+            fixed[vertex.index()] = true;
+        }
+        let free: Vec<VertexId> = self
+            .vertices()
+            .filter(|&v| {
+                let locked = config.lock_boundary && self.is_boundary_vertex(v);
+                !(fixed[v.index()] || locked || self.is_isolated_vertex(v))
+            })
+            .collect();
+
+        let steps: &[f32] = match &config.method {
+            SmoothingMethod::Laplacian { lambda } => &[*lambda],
+            SmoothingMethod::Taubin { lambda, mu } => &[*lambda, *mu],
+        };
+        for _ in 0..config.iterations {
+            for &factor in steps {
+                let intrinsic = if config.weights == SmoothingWeights::IntrinsicCotangent {
+                    let mut intrinsic = IntrinsicTriangulation::new(self)?;
+                    intrinsic.flip_to_delaunay();
+                    Some(intrinsic)
+                } else {
+                    None
+                };
+                let moved: Vec<Vec3> = free
+                    .iter()
+                    .map(|&v| {
+                        let laplacian =
+                            self.smoothing_laplacian(v, config.weights, intrinsic.as_ref());
+                        self.position(v) + factor * laplacian
+                    })
+                    .collect();
+                for (&v, p) in free.iter().zip(moved) {
+                    self.set_position(v, p);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The weighted average of the neighbours of `v`, minus its position. Boundary vertices only look along
+    /// the boundary, so that smoothing them does not pull the boundary inwards. Intrinsic weights come from
+    /// `intrinsic`, whose boundary edges are those of the mesh.
+    fn smoothing_laplacian(
+        &self,
+        v: VertexId,
+        weights: SmoothingWeights,
+        intrinsic: Option<&IntrinsicTriangulation>,
+    ) -> Vec3 {
+        let p = self.position(v);
+        let boundary = self.is_boundary_vertex(v);
+        let connectivity = intrinsic.map_or(self, IntrinsicTriangulation::mesh);
+        let (mut sum, mut total) = (Vec3::ZERO, 0.0);
+        for h in connectivity.vertex_outgoing_halfedges(v) {
+            let e = connectivity.edge(h);
+            if boundary && !connectivity.is_boundary_edge(e) {
+                continue;
+            }
+            let w = match (weights, intrinsic) {
+                (SmoothingWeights::Uniform, _) => 1.0,
+                _ if boundary => 1.0,
+                (SmoothingWeights::Cotangent, _) => self.cotan_weight(e).max(0.0),
+                (SmoothingWeights::IntrinsicCotangent, Some(intrinsic)) => {
+                    intrinsic.cotan_weight(e).max(0.0) as f32
+                }
+                (SmoothingWeights::IntrinsicCotangent, None) => 1.0,
+            };
+            sum += w * (self.position(connectivity.dest(h)) - p);
+            total += w;
+        }
+        if total > 0.0 {
+            sum / total
+        } else {
+            Vec3::ZERO
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use super::{CsrMatrix, SolveError};
+
+/// Settings for [`conjugate_gradient`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CgConfig {
+    /// Stop once the residual norm falls below this fraction of the right-hand side's norm.
+    pub tolerance: f64,
+    /// Give up after this many iterations. Zero means the matrix size, which is enough in exact arithmetic.
+    pub max_iterations: usize,
+}
+
+impl Default for CgConfig {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-10,
+            max_iterations: 0,
+        }
+    }
+}
+
+/// The result of a converged [`conjugate_gradient`] solve.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CgSolution {
+    pub x: Vec<f64>,
+    pub iterations: usize,
+    /// The final residual norm relative to the right-hand side's norm.
+    pub residual: f64,
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Solve `a x = b` for a symmetric positive definite `a` with the Jacobi-preconditioned conjugate gradient
+/// method, starting from `initial` if given and from zero otherwise.
+pub fn conjugate_gradient(
+    a: &CsrMatrix,
+    b: &[f64],
+    initial: Option<&[f64]>,
+    config: &CgConfig,
+) -> Result<CgSolution, SolveError> {
+    a.check_square()?;
+    a.check_len(b)?;
+    let n = a.rows();
+    let mut x = match initial {
+        Some(initial) => {
+            a.check_len(initial)?;
+            initial.to_vec()
+        }
+        None => vec![0.0; n],
+    };
+
+    let b_norm = dot(b, b).sqrt();
+    if b_norm == 0.0 {
+        return Ok(CgSolution {
+            x: vec![0.0; n],
+            iterations: 0,
+            residual: 0.0,
+        });
+    }
+    let inverse_diagonal: Vec<f64> = a
+        .diagonal()
+        .into_iter()
+        .map(|d| if d.abs() > 0.0 { 1.0 / d } else { 1.0 })
+        .collect();
+    let precondition = |r: &[f64]| -> Vec<f64> {
+        r.iter()
+            .zip(&inverse_diagonal)
+            .map(|(r, d)| r * d)
+            .collect()
+    };
+
+    let ax = a.mul_vec(&x);
+    let mut r: Vec<f64> = b.iter().zip(&ax).map(|(b, ax)| b - ax).collect();
+    let mut z = precondition(&r);
+    let mut p = z.clone();
+    let mut rz = dot(&r, &z);
+    let max_iterations = if config.max_iterations == 0 {
+        n.max(1)
+    } else {
+        config.max_iterations
+    };
+
+    let mut residual = dot(&r, &r).sqrt() / b_norm;
+    for iteration in 0..max_iterations {
+        if residual <= config.tolerance {
+            return Ok(CgSolution {
+                x,
+                iterations: iteration,
+                residual,
+            });
+        }
+        let ap = a.mul_vec(&p);
+        let pap = dot(&p, &ap);
+        if pap <= 0.0 {
+            return Err(SolveError::NotPositiveDefinite);
+        }
+        let alpha = rz / pap;
+        x.iter_mut().zip(&p).for_each(|(x, p)| *x += alpha * p);
+        r.iter_mut().zip(&ap).for_each(|(r, ap)| *r -= alpha * ap);
+        residual = dot(&r, &r).sqrt() / b_norm;
+
+        z = precondition(&r);
+        let rz_next = dot(&r, &z);
+        let beta = rz_next / rz;
+        rz = rz_next;
+        p.iter_mut().zip(&z).for_each(|(p, z)| *p = z + beta * *p);
+    }
+    if residual <= config.tolerance {
+        return Ok(CgSolution {
+            x,
+            iterations: max_iterations,
+            residual,
+        });
+    }
+    Err(SolveError::NotConverged {
+        iterations: max_iterations,
+        residual,
+    })
+}
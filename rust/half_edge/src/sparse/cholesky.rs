@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+
+use super::{CsrMatrix, SolveError};
+
+/// A sparse `L D Lᵀ` factorization of a symmetric positive definite matrix, the square-root free form of
+/// Cholesky. Rows are reordered by reverse Cuthill-McKee first to limit fill-in.
+///
+/// The factorization follows Davis' LDL package: a symbolic pass builds the elimination tree and counts the
+/// entries of each column of `L`, then a numeric pass computes one row of `L` at a time.
+#[derive(Clone, Debug)]
+pub struct Cholesky {
+    /// `permutation[k]` is the original row placed at position `k`.
+    permutation: Vec<usize>,
+    /// Column offsets, row indices and values of the strictly lower triangular `L`, by column.
+    l_offsets: Vec<usize>,
+    l_indices: Vec<usize>,
+    l_values: Vec<f64>,
+    d: Vec<f64>,
+}
+
+/// Reverse Cuthill-McKee: a breadth-first ordering from a low degree row, visiting neighbours by increasing
+/// degree, then reversed. Keeps entries close to the diagonal, which keeps the factor sparse.
+fn reverse_cuthill_mckee(a: &CsrMatrix) -> Vec<usize> {
+    let n = a.rows();
+    let degree: Vec<usize> = (0..n).map(|r| a.row(r).count()).collect();
+    let mut by_degree: Vec<usize> = (0..n).collect();
+    by_degree.sort_by_key(|&r| degree[r]);
+
+    let mut visited = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    let mut queue = VecDeque::new();
+    for start in by_degree {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        queue.push_back(start);
+        while let Some(r) = queue.pop_front() {
+            order.push(r);
+            let mut neighbours: Vec<usize> =
+                a.row(r).map(|(c, _)| c).filter(|&c| !visited[c]).collect();
+            neighbours.sort_by_key(|&c| degree[c]);
+            for c in neighbours {
+                visited[c] = true;
+                queue.push_back(c);
+            }
+        }
+    }
+    order.reverse();
+    order
+}
+
+impl Cholesky {
+    /// Factor the symmetric positive definite matrix `a`. Only the upper triangle is read.
+    pub fn new(a: &CsrMatrix) -> Result<Self, SolveError> {
+        a.check_square()?;
+        let n = a.rows();
+        let permutation = reverse_cuthill_mckee(a);
+        let mut inverse = vec![0; n];
+        for (k, &p) in permutation.iter().enumerate() {
+            inverse[p] = k;
+        }
+        // For a symmetric matrix, row `r` holds the same entries as column `r`.
+        let column = |k: usize| a.row(permutation[k]).map(|(i, v)| (inverse[i], v));
+
+        // Symbolic pass: the elimination tree, and the number of entries in each column of `L`.
+        let mut parent = vec![usize::MAX; n];
+        let mut flag = vec![usize::MAX; n];
+        let mut counts = vec![0; n];
+        for k in 0..n {
+            flag[k] = k;
+            for (mut i, _) in column(k) {
+                while i < k && flag[i] != k {
+                    if parent[i] == usize::MAX {
+                        parent[i] = k;
+                    }
+                    counts[i] += 1;
+                    flag[i] = k;
+                    i = parent[i];
+                }
+            }
+        }
+        let mut l_offsets = vec![0; n + 1];
+        for k in 0..n {
+            l_offsets[k + 1] = l_offsets[k] + counts[k];
+        }
+
+        // Numeric pass: row `k` of `L` comes from a sparse triangular solve whose pattern is the set of
+        // elimination tree paths from the row's entries up to `k`.
+        let total = l_offsets[n];
+        let mut l_indices = vec![0; total];
+        let mut l_values = vec![0.0; total];
+        let mut d = vec![0.0; n];
+        let mut y = vec![0.0; n];
+        let mut pattern = vec![0; n];
+        let mut filled = vec![0; n];
+        flag.fill(usize::MAX);
+        for k in 0..n {
+            let mut top = n;
+            flag[k] = k;
+            for (i, v) in column(k) {
+                if i > k {
+                    continue;
+                }
+                y[i] += v;
+                let mut len = 0;
+                let mut i = i;
+                while flag[i] != k {
+                    pattern[len] = i;
+                    len += 1;
+                    flag[i] = k;
+                    i = parent[i];
+                }
+                while len > 0 {
+                    len -= 1;
+                    top -= 1;
+                    pattern[top] = pattern[len];
+                }
+            }
+            let pivot = y[k];
+            d[k] = pivot;
+            y[k] = 0.0;
+            for &i in &pattern[top..n] {
+                let yi = y[i];
+                y[i] = 0.0;
+                let end = l_offsets[i] + filled[i];
+                for p in l_offsets[i]..end {
+                    y[l_indices[p]] -= l_values[p] * yi;
+                }
+                let l_ki = yi / d[i];
+                d[k] -= l_ki * yi;
+                l_indices[end] = k;
+                l_values[end] = l_ki;
+                filled[i] += 1;
+            }
+            // A pivot that cancels down to rounding noise means the matrix is singular, as with a Laplacian
+            // that has no boundary conditions.
+            if d[k] <= 1e-12 * pivot.abs() || !d[k].is_finite() {
+                return Err(SolveError::NotPositiveDefinite);
+            }
+        }
+
+        Ok(Self {
+            permutation,
+            l_offsets,
+            l_indices,
+            l_values,
+            d,
+        })
+    }
+
+    /// The size of the factored matrix.
+    pub fn dimension(&self) -> usize {
+        self.d.len()
+    }
+
+    /// Solve `a x = b` with the factored matrix.
+    pub fn solve(&self, b: &[f64]) -> Result<Vec<f64>, SolveError> {
+        let n = self.dimension();
+        if b.len() != n {
+            return Err(SolveError::DimensionMismatch {
+                expected: n,
+                found: b.len(),
+            });
+        }
+        let mut x: Vec<f64> = self.permutation.iter().map(|&p| b[p]).collect();
+        for j in 0..n {
+            for p in self.l_offsets[j]..self.l_offsets[j + 1] {
+                x[self.l_indices[p]] -= self.l_values[p] * x[j];
+            }
+        }
+        x.iter_mut().zip(&self.d).for_each(|(x, d)| *x /= d);
+        for j in (0..n).rev() {
+            for p in self.l_offsets[j]..self.l_offsets[j + 1] {
+                x[j] -= self.l_values[p] * x[self.l_indices[p]];
+            }
+        }
+        let mut result = vec![0.0; n];
+        for (k, &p) in self.permutation.iter().enumerate() {
+            result[p] = x[k];
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,142 @@
+use crate::mesh::{HalfEdgeMesh, VertexId};
+
+use super::{CsrMatrix, TripletMatrix};
+
+/// How vertex areas are lumped into the diagonal mass matrix.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MassMatrix {
+    /// A third of the area of every adjacent triangle (a fraction `1/n` of every adjacent polygon).
+    #[default]
+    Barycentric,
+    /// The mixed Voronoi area of [`HalfEdgeMesh::vertex_area`].
+    Voronoi,
+}
+
+impl HalfEdgeMesh {
+    /// The cotangent Laplacian as a symmetric positive semi-definite matrix, one row per vertex: off the
+    /// diagonal `L[i][j] = -w_ij` for the cotangent weight of edge `ij`, and on it `L[i][i] = Σ w_ij`. Its rows
+    /// sum to zero and `xᵀ L x` is the Dirichlet energy of `x`.
+    ///
+    /// Rows are indexed by [`VertexId::index`]; removed and isolated vertices get empty rows, so garbage
+    /// collect first when the matrix has to be factored. Faces are assumed to be triangles.
+    pub fn cotan_laplacian(&self) -> CsrMatrix {
+        let n = self.vertex_count();
+        let mut triplets = TripletMatrix::new(n, n);
+        for e in self.edges() {
+            let [i, j] = self.edge_vertices(e).map(VertexId::index);
+            let w = f64::from(self.cotan_weight(e));
+            triplets.add(i, j, -w);
+            triplets.add(j, i, -w);
+            triplets.add(i, i, w);
+            triplets.add(j, j, w);
+        }
+        triplets.to_csr()
+    }
+
+    /// The diagonal mass matrix holding each vertex's share of the surface area, indexed by
+    /// [`VertexId::index`].
+    pub fn mass_matrix(&self, kind: MassMatrix) -> CsrMatrix {
+        let mut areas = vec![0.0; self.vertex_count()];
+        match kind {
+            MassMatrix::Barycentric => {
+                for f in self.faces() {
+                    let share = f64::from(self.face_area(f)) / self.face_degree(f) as f64;
+                    for v in self.face_vertices(f) {
+                        areas[v.index()] += share;
+                    }
+                }
+            }
+            MassMatrix::Voronoi => {
+                for v in self.vertices() {
+                    areas[v.index()] = f64::from(self.vertex_area(v));
+                }
+            }
+        }
+        CsrMatrix::from_diagonal(&areas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    /// An octahedron with its vertices pushed off the unit sphere, so that no two weights are alike.
+    fn lopsided_octahedron() -> HalfEdgeMesh {
+        let positions = vec![
+            Vec3::new(1.3, 0.1, 0.0),
+            Vec3::new(-0.8, 0.0, 0.2),
+            Vec3::new(0.0, 1.1, -0.1),
+            Vec3::new(0.2, -0.9, 0.0),
+            Vec3::new(0.1, 0.0, 1.6),
+            Vec3::new(0.0, 0.2, -0.7),
+        ];
+        let triangles = [
+            0, 2, 4, 2, 1, 4, 1, 3, 4, 3, 0, 4, 2, 0, 5, 1, 2, 5, 3, 1, 5, 0, 3, 5,
+        ];
+        HalfEdgeMesh::from_triangles(positions, &triangles).unwrap()
+    }
+
+    #[test]
+    fn unit_square_has_the_known_laplacian() {
+        let square = HalfEdgeMesh::from_triangles(
+            vec![Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0), Vec3::Y],
+            &[0, 1, 2, 0, 2, 3],
+        )
+        .unwrap();
+        let laplacian = square.cotan_laplacian();
+        // The diagonal is opposite right angles, so only the sides, opposite 45° angles, have weight.
+        let expected = [
+            [1.0, -0.5, 0.0, -0.5],
+            [-0.5, 1.0, -0.5, 0.0],
+            [0.0, -0.5, 1.0, -0.5],
+            [-0.5, 0.0, -0.5, 1.0],
+        ];
+        for (i, row) in expected.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                assert!((laplacian.get(i, j) - value).abs() < 1e-6, "L[{i}][{j}]");
+            }
+        }
+        // The Dirichlet energy of a unit-slope linear function over a unit area.
+        let x: Vec<f64> = square.positions().iter().map(|p| f64::from(p.x)).collect();
+        let energy: f64 = x
+            .iter()
+            .zip(laplacian.mul_vec(&x))
+            .map(|(x, lx)| x * lx)
+            .sum();
+        assert!((energy - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn laplacian_is_symmetric_with_zero_row_sums() {
+        let mesh = lopsided_octahedron();
+        let laplacian = mesh.cotan_laplacian();
+        for i in 0..mesh.vertex_count() {
+            let sum: f64 = laplacian.row(i).map(|(_, value)| value).sum();
+            assert!(sum.abs() < 1e-6, "row {i} sums to {sum}");
+            assert!(laplacian.get(i, i) > 0.0);
+            for (j, value) in laplacian.row(i) {
+                assert_eq!(laplacian.get(j, i), value, "L[{i}][{j}]");
+            }
+        }
+        assert_eq!(laplacian, laplacian.transpose());
+        // Four neighbours and the vertex itself in every row.
+        assert_eq!(laplacian.nnz(), 6 * 5);
+    }
+
+    #[test]
+    fn mass_matrices_share_out_the_whole_area() {
+        let mesh = lopsided_octahedron();
+        let area: f64 = mesh.faces().map(|f| f64::from(mesh.face_area(f))).sum();
+        for kind in [MassMatrix::Barycentric, MassMatrix::Voronoi] {
+            let mass = mesh.mass_matrix(kind);
+            assert_eq!(mass.nnz(), mesh.vertex_count());
+            let total: f64 = mass.diagonal().iter().sum();
+            assert!(
+                (total - area).abs() < 1e-5 * area,
+                "{kind:?}: {total} vs {area}"
+            );
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! Sparse matrices and solvers for the linear systems of mesh processing.
+//!
+//! Matrices are assembled as lists of `(row, column, value)` triplets in a [`TripletMatrix`], where repeated
+//! entries add up as they do in finite element assembly, and then compressed into a [`CsrMatrix`] for
+//! multiplication and solving. Symmetric matrices are stored in full, both triangles. Everything is plain Rust
+//! in `f64`, so it runs the same natively and in WebAssembly.
+//!
+//! [`conjugate_gradient`] solves symmetric positive definite systems iteratively and needs nothing but
+//! matrix-vector products; [`Cholesky`] factors them once for fast repeated solves with different right-hand
+//! sides.
+
+use core::fmt;
+
+mod cg;
+mod cholesky;
+mod laplacian;
+
+pub use cg::{conjugate_gradient, CgConfig, CgSolution};
+pub use cholesky::Cholesky;
+pub use laplacian::MassMatrix;
+
+/// Why a linear system could not be solved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SolveError {
+    /// The matrix is not square.
+    NotSquare { rows: usize, cols: usize },
+    /// A vector does not have as many entries as the matrix has rows.
+    DimensionMismatch { expected: usize, found: usize },
+    /// The matrix turned out not to be positive definite: a pivot was zero or negative during factorization,
+    /// or the conjugate gradient method met a direction of non-positive curvature.
+    NotPositiveDefinite,
+    /// The iteration limit was reached before the residual fell below the tolerance.
+    NotConverged { iterations: usize, residual: f64 },
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotSquare { rows, cols } => write!(f, "matrix is {rows}x{cols}, not square"),
+            Self::DimensionMismatch { expected, found } => {
+                write!(f, "expected a vector of length {expected}, found {found}")
+            }
+            Self::NotPositiveDefinite => f.write_str("matrix is not positive definite"),
+            Self::NotConverged {
+                iterations,
+                residual,
+            } => write!(
+                f,
+                "did not converge within {iterations} iterations (residual {residual})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// A matrix under assembly, as a list of entries. Entries at the same position are summed on compression.
+#[derive(Clone, Debug, Default)]
+pub struct TripletMatrix {
+    rows: usize,
+    cols: usize,
+    entries: Vec<(usize, usize, f64)>,
+}
+
+impl TripletMatrix {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add `value` to the entry at `(row, col)`.
+    pub fn add(&mut self, row: usize, col: usize, value: f64) {
+        assert!(
+            row < self.rows && col < self.cols,
+            "entry ({row}, {col}) outside {}x{} matrix",
+            self.rows,
+            self.cols
+        );
+        self.entries.push((row, col, value));
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn to_csr(&self) -> CsrMatrix {
+        CsrMatrix::from_triplets(self)
+    }
+}
+
+/// A matrix in compressed sparse row form, with sorted column indices and no duplicate entries.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CsrMatrix {
+    rows: usize,
+    cols: usize,
+    /// `row_offsets[r]..row_offsets[r + 1]` indexes the entries of row `r`.
+    row_offsets: Vec<usize>,
+    col_indices: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl CsrMatrix {
+    pub fn from_triplets(triplets: &TripletMatrix) -> Self {
+        let mut entries = triplets.entries.clone();
+        entries.sort_unstable_by_key(|&(r, c, _)| (r, c));
+
+        let mut row_offsets = vec![0; triplets.rows + 1];
+        let mut col_indices = Vec::with_capacity(entries.len());
+        let mut values: Vec<f64> = Vec::with_capacity(entries.len());
+        let mut last = None;
+        for (r, c, v) in entries {
+            if last == Some((r, c)) {
+                *values.last_mut().expect("a previous entry") += v;
+                continue;
+            }
+            last = Some((r, c));
+            row_offsets[r + 1] += 1;
+            col_indices.push(c);
+            values.push(v);
+        }
+        for r in 0..triplets.rows {
+            row_offsets[r + 1] += row_offsets[r];
+        }
+        Self {
+            rows: triplets.rows,
+            cols: triplets.cols,
+            row_offsets,
+            col_indices,
+            values,
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        Self::from_diagonal(&vec![1.0; n])
+    }
+
+    pub fn from_diagonal(diagonal: &[f64]) -> Self {
+        let n = diagonal.len();
+        Self {
+            rows: n,
+            cols: n,
+            row_offsets: (0..=n).collect(),
+            col_indices: (0..n).collect(),
+            values: diagonal.to_vec(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The number of stored entries.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The stored entries of row `r`, as `(column, value)` pairs in column order.
+    pub fn row(&self, r: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let range = self.row_offsets[r]..self.row_offsets[r + 1];
+        self.col_indices[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.values[range].iter().copied())
+    }
+
+    /// The entry at `(r, c)`, which is zero if it is not stored.
+    pub fn get(&self, r: usize, c: usize) -> f64 {
+        let range = self.row_offsets[r]..self.row_offsets[r + 1];
+        match self.col_indices[range.clone()].binary_search(&c) {
+            Ok(i) => self.values[range.start + i],
+            Err(_) => 0.0,
+        }
+    }
+
+    pub fn diagonal(&self) -> Vec<f64> {
+        (0..self.rows.min(self.cols))
+            .map(|i| self.get(i, i))
+            .collect()
+    }
+
+    /// The product `self * x`.
+    pub fn mul_vec(&self, x: &[f64]) -> Vec<f64> {
+        assert_eq!(x.len(), self.cols, "vector length does not match matrix");
+        (0..self.rows)
+            .map(|r| self.row(r).map(|(c, v)| v * x[c]).sum())
+            .collect()
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut triplets = TripletMatrix::new(self.cols, self.rows);
+        for r in 0..self.rows {
+            for (c, v) in self.row(r) {
+                triplets.add(c, r, v);
+            }
+        }
+        triplets.to_csr()
+    }
+
+    /// The matrix `a * self + b * other`, as used for systems like `M + tL`.
+    pub fn linear_combination(&self, a: f64, other: &Self, b: f64) -> Self {
+        assert_eq!(
+            (self.rows, self.cols),
+            (other.rows, other.cols),
+            "matrix dimensions differ"
+        );
+        let mut triplets = TripletMatrix::new(self.rows, self.cols);
+        for r in 0..self.rows {
+            for (c, v) in self.row(r) {
+                triplets.add(r, c, a * v);
+            }
+            for (c, v) in other.row(r) {
+                triplets.add(r, c, b * v);
+            }
+        }
+        triplets.to_csr()
+    }
+
+    /// Scale every entry by `s`.
+    pub fn scale(&mut self, s: f64) {
+        self.values.iter_mut().for_each(|v| *v *= s);
+    }
+
+    fn check_square(&self) -> Result<(), SolveError> {
+        if self.rows != self.cols {
+            return Err(SolveError::NotSquare {
+                rows: self.rows,
+                cols: self.cols,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_len(&self, v: &[f64]) -> Result<(), SolveError> {
+        if v.len() != self.rows {
+            return Err(SolveError::DimensionMismatch {
+                expected: self.rows,
+                found: v.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(n: usize, entries: &[(usize, usize, f64)]) -> CsrMatrix {
+        let mut triplets = TripletMatrix::new(n, n);
+        for &(r, c, v) in entries {
+            triplets.add(r, c, v);
+        }
+        triplets.to_csr()
+    }
+
+    /// The second difference matrix `tridiag(-1, 2, -1)` of size `n`. With a right-hand side of ones, the
+    /// solution is the parabola `x_i = (i + 1)(n - i) / 2`, zero just beyond both ends.
+    fn second_difference(n: usize) -> (CsrMatrix, Vec<f64>) {
+        let mut triplets = TripletMatrix::new(n, n);
+        for i in 0..n {
+            triplets.add(i, i, 2.0);
+            if i + 1 < n {
+                triplets.add(i, i + 1, -1.0);
+                triplets.add(i + 1, i, -1.0);
+            }
+        }
+        let exact = (0..n).map(|i| ((i + 1) * (n - i)) as f64 / 2.0).collect();
+        (triplets.to_csr(), exact)
+    }
+
+    /// The five-point Laplacian of a `k` by `k` grid plus the identity, whose factor fills in unless the
+    /// rows are reordered, with a right-hand side chosen for a known solution.
+    fn shifted_grid(k: usize) -> (CsrMatrix, Vec<f64>, Vec<f64>) {
+        let mut triplets = TripletMatrix::new(k * k, k * k);
+        for i in 0..k {
+            for j in 0..k {
+                let r = i * k + j;
+                triplets.add(r, r, 1.0);
+                for (di, dj) in [(1, 0), (0, 1)] {
+                    let (ni, nj) = (i + di, j + dj);
+                    if ni < k && nj < k {
+                        let c = ni * k + nj;
+                        for (a, b) in [(r, c), (c, r)] {
+                            triplets.add(a, a, 1.0);
+                            triplets.add(a, b, -1.0);
+                        }
+                    }
+                }
+            }
+        }
+        let a = triplets.to_csr();
+        let exact: Vec<f64> = (0..k * k)
+            .map(|i| (i as f64 * 0.7).sin() + 0.1 * i as f64)
+            .collect();
+        let b = a.mul_vec(&exact);
+        (a, b, exact)
+    }
+
+    fn assert_close(x: &[f64], exact: &[f64]) {
+        assert_eq!(x.len(), exact.len());
+        for (x, e) in x.iter().zip(exact) {
+            assert!((x - e).abs() < 1e-8, "{x} vs {e}");
+        }
+    }
+
+    #[test]
+    fn triplets_add_up() {
+        let a = matrix(
+            3,
+            &[
+                (0, 0, 4.0),
+                (0, 1, 1.0),
+                (1, 0, 1.0),
+                (0, 0, -1.0),
+                (2, 2, 2.0),
+            ],
+        );
+        assert_eq!(a.nnz(), 4);
+        assert_eq!(a.get(0, 0), 3.0);
+        assert_eq!(a.get(1, 2), 0.0);
+        assert_eq!(a.diagonal(), [3.0, 0.0, 2.0]);
+        assert_eq!(a.row(0).collect::<Vec<_>>(), [(0, 3.0), (1, 1.0)]);
+        assert_eq!(a.mul_vec(&[1.0, 2.0, 3.0]), [5.0, 1.0, 6.0]);
+        assert_eq!(a.transpose(), a);
+    }
+
+    #[test]
+    fn known_systems_are_solved_exactly() {
+        // [4 1 0; 1 3 1; 0 1 2] (1, 2, 3) = (6, 10, 8).
+        let small = matrix(
+            3,
+            &[
+                (0, 0, 4.0),
+                (0, 1, 1.0),
+                (1, 0, 1.0),
+                (1, 1, 3.0),
+                (1, 2, 1.0),
+                (2, 1, 1.0),
+                (2, 2, 2.0),
+            ],
+        );
+        let (poisson, parabola) = second_difference(9);
+        let (grid, grid_b, grid_x) = shifted_grid(6);
+        for (a, b, exact) in [
+            (&small, vec![6.0, 10.0, 8.0], vec![1.0, 2.0, 3.0]),
+            (&poisson, vec![1.0; 9], parabola),
+            (&grid, grid_b, grid_x),
+        ] {
+            let solution = conjugate_gradient(a, &b, None, &CgConfig::default()).unwrap();
+            assert!(solution.iterations <= a.rows());
+            assert!(solution.residual <= 1e-10);
+            assert_close(&solution.x, &exact);
+            assert_close(&Cholesky::new(a).unwrap().solve(&b).unwrap(), &exact);
+        }
+    }
+
+    #[test]
+    fn unsolvable_systems_are_refused() {
+        let indefinite = matrix(2, &[(0, 0, 1.0), (0, 1, 2.0), (1, 0, 2.0), (1, 1, 1.0)]);
+        assert_eq!(
+            Cholesky::new(&indefinite).unwrap_err(),
+            SolveError::NotPositiveDefinite
+        );
+        let saddle = matrix(2, &[(0, 0, 1.0), (1, 1, -1.0)]);
+        assert_eq!(
+            conjugate_gradient(&saddle, &[1.0, 1.0], None, &CgConfig::default()).unwrap_err(),
+            SolveError::NotPositiveDefinite
+        );
+
+        let wide = TripletMatrix::new(2, 3).to_csr();
+        assert_eq!(
+            Cholesky::new(&wide).unwrap_err(),
+            SolveError::NotSquare { rows: 2, cols: 3 }
+        );
+        let (poisson, _) = second_difference(4);
+        assert_eq!(
+            conjugate_gradient(&poisson, &[1.0; 3], None, &CgConfig::default()).unwrap_err(),
+            SolveError::DimensionMismatch {
+                expected: 4,
+                found: 3
+            }
+        );
+        let config = CgConfig {
+            max_iterations: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            conjugate_gradient(&poisson, &[1.0; 4], None, &config),
+            Err(SolveError::NotConverged { iterations: 1, .. })
+        ));
+    }
+}
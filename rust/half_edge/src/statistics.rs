@@ -0,0 +1,315 @@
+//! Summary statistics for asset checks: element counts, topology, size and a picture of element quality.
+//!
+//! [`MeshStatistics`] serializes with serde, and [`MeshStatistics::to_json`] renders the report for tooling
+//! that checks assets automatically.
+
+use serde::Serialize;
+
+use crate::{mesh::HalfEdgeMesh, union_find::UnionFind};
+
+/// An axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Bounds {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+/// The distribution of a per-element quantity, in equal-width bins from `min` to `max`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Histogram {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// How many values fall in each bin. The last bin includes `max`.
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    fn new(values: &[f64], bins: usize) -> Self {
+        if values.is_empty() || bins == 0 {
+            return Self::default();
+        }
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let mut counts = vec![0; bins];
+        let width = (max - min) / bins as f64;
+        for &value in values {
+            let bin = if width > 0.0 {
+                ((value - min) / width) as usize
+            } else {
+                0
+            };
+            counts[bin.min(bins - 1)] += 1;
+        }
+        Self {
+            min,
+            max,
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            counts,
+        }
+    }
+}
+
+/// A report on the shape and quality of a mesh, from [`HalfEdgeMesh::statistics`]. Removed elements are not
+/// counted.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MeshStatistics {
+    pub vertices: usize,
+    pub edges: usize,
+    pub faces: usize,
+    /// Vertices that no face or edge uses. They are left out of the topological quantities below.
+    pub isolated_vertices: usize,
+    /// `V - E + F`, over the vertices that are part of the surface.
+    pub euler_characteristic: i64,
+    /// The total number of handles over all components, from `χ = 2c - 2g - b`.
+    pub genus: i64,
+    /// Connected pieces of the surface.
+    pub components: usize,
+    pub boundary_loops: usize,
+    pub boundary_edges: usize,
+    pub surface_area: f64,
+    /// The volume enclosed by the surface, if it is closed.
+    pub volume: Option<f64>,
+    /// The bounding box of the vertices, if there are any.
+    pub bounds: Option<Bounds>,
+    pub edge_lengths: Histogram,
+    /// The ratio of circumradius to twice the inradius of each triangle, which is 1 for an equilateral
+    /// triangle and grows as triangles get thinner. Polygons report their worst triangle when fanned from
+    /// their first corner. Faces without area are left out and counted in `degenerate_faces`.
+    pub aspect_ratios: Histogram,
+    pub degenerate_faces: usize,
+}
+
+impl MeshStatistics {
+    /// The report as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("statistics are always representable as JSON")
+    }
+}
+
+impl HalfEdgeMesh {
+    /// Count, measure and check the mesh, summarizing edge lengths and triangle shapes in histograms with
+    /// `histogram_bins` bins each.
+    pub fn statistics(&self, histogram_bins: usize) -> MeshStatistics {
+        let vertices = self.vertices().count();
+        let edges = self.edges().count();
+        let faces = self.faces().count();
+        let isolated_vertices = self
+            .vertices()
+            .filter(|&v| self.is_isolated_vertex(v))
+            .count();
+        let euler_characteristic =
+            (vertices - isolated_vertices) as i64 - edges as i64 + faces as i64;
+
+        let mut pieces = UnionFind::new(self.vertex_count());
+        let mut components = vertices - isolated_vertices;
+        for e in self.edges() {
+            let [a, b] = self.edge_vertices(e);
+            if pieces.union(a.index(), b.index()) {
+                components -= 1;
+            }
+        }
+        let boundary_loops = self.boundary_loops().len();
+        let genus = (2 * components as i64 - boundary_loops as i64 - euler_characteristic) / 2;
+
+        let mut surface_area = 0.0;
+        let mut volume = 0.0;
+        let mut aspect_ratios = Vec::with_capacity(faces);
+        let mut degenerate_faces = 0;
+        for f in self.faces() {
+            let corners: Vec<_> = self.face_vertices(f).map(|v| self.position(v)).collect();
+            surface_area += f64::from(self.face_area(f));
+            let mut worst = 0.0f64;
+            for i in 1..corners.len() - 1 {
+                let [a, b, c] = [corners[0], corners[i], corners[i + 1]].map(|p| p.as_dvec3());
+                volume += a.dot(b.cross(c)) / 6.0;
+                let (x, y, z) = (a.distance(b), b.distance(c), c.distance(a));
+                let area = 0.5 * (b - a).cross(c - a).length();
+                // R / 2r = abc s / (8 A²), with R = abc / 4A and r = A / s.
+                worst = worst.max(x * y * z * 0.5 * (x + y + z) / (8.0 * area * area));
+            }
+            if worst.is_finite() {
+                aspect_ratios.push(worst);
+            } else {
+                degenerate_faces += 1;
+            }
+        }
+        let edge_lengths: Vec<f64> = self
+            .edges()
+            .map(|e| f64::from(self.edge_length(e)))
+            .collect();
+
+        let bounds = self
+            .vertices()
+            .map(|v| self.position(v))
+            .fold(None, |bounds, p| {
+                let (min, max) = bounds.unwrap_or((p, p));
+                Some((min.min(p), max.max(p)))
+            });
+
+        MeshStatistics {
+            vertices,
+            edges,
+            faces,
+            isolated_vertices,
+            euler_characteristic,
+            genus,
+            components,
+            boundary_loops,
+            boundary_edges: self.edges().filter(|&e| self.is_boundary_edge(e)).count(),
+            surface_area,
+            volume: (boundary_loops == 0 && faces > 0).then_some(volume),
+            bounds: bounds.map(|(min, max)| Bounds {
+                min: min.to_array(),
+                max: max.to_array(),
+            }),
+            edge_lengths: Histogram::new(&edge_lengths, histogram_bins),
+            aspect_ratios: Histogram::new(&aspect_ratios, histogram_bins),
+            degenerate_faces,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f64::consts::PI;
+
+    use glam::Vec3;
+
+    use super::*;
+
+    /// A torus of `n` by `m` quads, each split in two, around the z axis.
+    fn torus(n: u32, m: u32, major: f32, minor: f32) -> HalfEdgeMesh {
+        let tau = std::f32::consts::TAU;
+        let positions: Vec<Vec3> = (0..n)
+            .flat_map(|i| {
+                (0..m).map(move |j| {
+                    let (u, v) = (tau * i as f32 / n as f32, tau * j as f32 / m as f32);
+                    let ring = major + minor * v.cos();
+                    Vec3::new(ring * u.cos(), ring * u.sin(), minor * v.sin())
+                })
+            })
+            .collect();
+        let index = |i: u32, j: u32| (i % n) * m + j % m;
+        let triangles: Vec<u32> = (0..n)
+            .flat_map(|i| {
+                (0..m).flat_map(move |j| {
+                    let [a, b, c, d] = [
+                        index(i, j),
+                        index(i + 1, j),
+                        index(i + 1, j + 1),
+                        index(i, j + 1),
+                    ];
+                    [a, b, c, a, c, d]
+                })
+            })
+            .collect();
+        HalfEdgeMesh::from_triangles(positions, &triangles).unwrap()
+    }
+
+    /// A unit cube made of quads.
+    fn cube() -> HalfEdgeMesh {
+        let positions: Vec<Vec3> = (0..8)
+            .map(|i| Vec3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2) as f32))
+            .collect();
+        let quads: [&[u32]; 6] = [
+            &[0, 2, 3, 1],
+            &[4, 5, 7, 6],
+            &[0, 1, 5, 4],
+            &[2, 6, 7, 3],
+            &[0, 4, 6, 2],
+            &[1, 3, 7, 5],
+        ];
+        HalfEdgeMesh::from_polygons(positions, quads).unwrap()
+    }
+
+    #[test]
+    fn torus_has_genus_one() {
+        let statistics = torus(12, 8, 2.0, 0.5).statistics(4);
+        assert_eq!(
+            (statistics.vertices, statistics.edges, statistics.faces),
+            (96, 288, 192)
+        );
+        assert_eq!(statistics.euler_characteristic, 0);
+        assert_eq!(statistics.genus, 1);
+        assert_eq!(statistics.components, 1);
+        assert_eq!(statistics.boundary_loops, 0);
+        assert_eq!(statistics.boundary_edges, 0);
+        // The inscribed polyhedron is a little smaller than the smooth torus.
+        let volume = statistics.volume.unwrap();
+        let smooth = 2.0 * PI * PI * 2.0 * 0.25;
+        assert!(volume < smooth && volume > 0.8 * smooth, "{volume}");
+    }
+
+    #[test]
+    fn disk_has_one_boundary_loop() {
+        let mut positions = vec![Vec3::ZERO];
+        positions.extend((0..6).map(|i| {
+            let angle = i as f32 * std::f32::consts::TAU / 6.0;
+            Vec3::new(angle.cos(), angle.sin(), 0.0)
+        }));
+        let triangles: Vec<u32> = (0..6).flat_map(|i| [0, 1 + i, 1 + (i + 1) % 6]).collect();
+        let disk = HalfEdgeMesh::from_triangles(positions, &triangles).unwrap();
+        let statistics = disk.statistics(4);
+        assert_eq!(statistics.euler_characteristic, 1);
+        assert_eq!(statistics.genus, 0);
+        assert_eq!(statistics.boundary_loops, 1);
+        assert_eq!(statistics.boundary_edges, 6);
+        assert_eq!(statistics.volume, None);
+        assert!((statistics.surface_area - 1.5 * 3f64.sqrt()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn cube_is_a_closed_sphere() {
+        let statistics = cube().statistics(2);
+        assert_eq!(
+            (statistics.vertices, statistics.edges, statistics.faces),
+            (8, 12, 6)
+        );
+        assert_eq!(statistics.euler_characteristic, 2);
+        assert_eq!(statistics.genus, 0);
+        assert_eq!(statistics.boundary_loops, 0);
+        assert!((statistics.volume.unwrap() - 1.0).abs() < 1e-6);
+        assert!((statistics.surface_area - 6.0).abs() < 1e-6);
+        assert_eq!(
+            statistics.bounds,
+            Some(Bounds {
+                min: [0.0; 3],
+                max: [1.0; 3]
+            })
+        );
+        assert_eq!(statistics.edge_lengths.counts, [12, 0]);
+        assert_eq!(statistics.edge_lengths.mean, 1.0);
+        // Each quad is judged by its worse half, a right isosceles triangle: R / 2r = (1 + √2) / 2.
+        let right = (1.0 + 2f64.sqrt()) / 2.0;
+        assert!((statistics.aspect_ratios.min - right).abs() < 1e-6);
+        assert!((statistics.aspect_ratios.max - right).abs() < 1e-6);
+        assert!(statistics.to_json().contains("\"genus\": 0"));
+    }
+
+    #[test]
+    fn equilateral_triangles_have_aspect_ratio_one() {
+        let positions = vec![
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::new(0.5, 3f32.sqrt() / 2.0, 0.0),
+            Vec3::new(5.0, 5.0, 5.0),
+        ];
+        let mesh = HalfEdgeMesh::from_triangles(positions, &[0, 1, 2]).unwrap();
+        let statistics = mesh.statistics(4);
+        assert_eq!(statistics.isolated_vertices, 1);
+        assert_eq!(statistics.euler_characteristic, 1);
+        let aspect = &statistics.aspect_ratios;
+        assert!((aspect.min - 1.0).abs() < 1e-6 && (aspect.max - 1.0).abs() < 1e-6);
+        assert!((aspect.mean - 1.0).abs() < 1e-6);
+        assert_eq!(aspect.counts, [1, 0, 0, 0]);
+        assert_eq!(statistics.degenerate_faces, 0);
+
+        let flat =
+            HalfEdgeMesh::from_triangles(vec![Vec3::ZERO, Vec3::X, 2.0 * Vec3::X], &[0, 1, 2])
+                .unwrap()
+                .statistics(4);
+        assert_eq!(flat.degenerate_faces, 1);
+        assert!(flat.aspect_ratios.counts.is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+use glam::Vec3;
+
+use super::{sharp_vertex_position, Creases, Level, Origin};
+use crate::mesh::HalfEdgeMesh;
+
+pub(super) fn step(mesh: &HalfEdgeMesh, creases: &Creases) -> Level {
+    let vertex_count = mesh.vertex_count();
+    let edge_offset = vertex_count;
+    let face_offset = vertex_count + mesh.edge_count();
+
+    let face_points: Vec<Vec3> = mesh
+        .faces()
+        .map(|f| mesh.face_positions(f).sum::<Vec3>() / mesh.face_degree(f) as f32)
+        .collect();
+
+    let mut positions = Vec::with_capacity(face_offset + mesh.face_count());
+    let mut origins = Vec::with_capacity(positions.capacity());
+    for v in mesh.vertices() {
+        let p = sharp_vertex_position(mesh, creases, v).unwrap_or_else(|| {
+            let n = mesh.vertex_valence(v);
+            if n == 0 {
+                return mesh.position(v);
+            }
+            let p = mesh.position(v);
+            let n = n as f32;
+            let faces = mesh
+                .vertex_faces(v)
+                .map(|f| face_points[f.index()])
+                .sum::<Vec3>()
+                / n;
+            let edge_midpoints = mesh
+                .vertex_vertices(v)
+                .map(|u| 0.5 * (p + mesh.position(u)))
+                .sum::<Vec3>()
+                / n;
+            (faces + 2.0 * edge_midpoints + (n - 3.0) * p) / n
+        });
+        positions.push(p);
+        origins.push(Origin::Vertex(v));
+    }
+
+    let mut new_creases = Creases::default();
+    for e in mesh.edges() {
+        let h = mesh.edge_halfedge(e);
+        let [a, b] = mesh.edge_vertices(e);
+        let midpoint = 0.5 * (mesh.position(a) + mesh.position(b));
+        let p = if creases.is_sharp(mesh, e) {
+            if !mesh.is_boundary_edge(e) {
+                let m = (edge_offset + e.index()) as u32;
+                new_creases.insert(a.index() as u32, m);
+                new_creases.insert(m, b.index() as u32);
+            }
+            midpoint
+        } else {
+            let f0 = mesh.face(h).expect("interior edge has two faces");
+            let f1 = mesh
+                .face(mesh.twin(h))
+                .expect("interior edge has two faces");
+            0.5 * midpoint + 0.25 * (face_points[f0.index()] + face_points[f1.index()])
+        };
+        positions.push(p);
+        origins.push(Origin::Edge(e));
+    }
+    positions.extend(&face_points);
+    origins.extend(mesh.faces().map(Origin::Face));
+
+    let mut polygons = Vec::new();
+    let mut parents = Vec::new();
+    for f in mesh.faces() {
+        let center = (face_offset + f.index()) as u32;
+        let halfedges: Vec<_> = mesh.face_halfedges(f).collect();
+        let k = halfedges.len();
+        for i in 0..k {
+            let h = halfedges[i];
+            let prev = halfedges[(i + k - 1) % k];
+            polygons.push(vec![
+                mesh.origin(h).index() as u32,
+                (edge_offset + mesh.edge(h).index()) as u32,
+                center,
+                (edge_offset + mesh.edge(prev).index()) as u32,
+            ]);
+            parents.push(f);
+        }
+    }
+
+    Level {
+        positions,
+        origins,
+        polygons,
+        parents,
+        creases: new_creases,
+    }
+}
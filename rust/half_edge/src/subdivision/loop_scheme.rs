@@ -0,0 +1,78 @@
+use std::f32::consts::TAU;
+
+use super::Origin;
+use super::{sharp_vertex_position, Creases, Level};
+use crate::mesh::HalfEdgeMesh;
+
+/// Loop's original weight for the one-ring of a smooth vertex of valence `n`.
+fn beta(n: usize) -> f32 {
+    let n = n as f32;
+    let c = 0.375 + 0.25 * (TAU / n).cos();
+    (0.625 - c * c) / n
+}
+
+pub(super) fn step(mesh: &HalfEdgeMesh, creases: &Creases) -> Level {
+    let vertex_count = mesh.vertex_count();
+    let mut positions = Vec::with_capacity(vertex_count + mesh.edge_count());
+    let mut origins = Vec::with_capacity(positions.capacity());
+
+    for v in mesh.vertices() {
+        let p = sharp_vertex_position(mesh, creases, v).unwrap_or_else(|| {
+            let n = mesh.vertex_valence(v);
+            if n == 0 {
+                return mesh.position(v);
+            }
+            let b = beta(n);
+            let ring: glam::Vec3 = mesh.vertex_vertices(v).map(|u| mesh.position(u)).sum();
+            (1.0 - n as f32 * b) * mesh.position(v) + b * ring
+        });
+        positions.push(p);
+        origins.push(Origin::Vertex(v));
+    }
+
+    let mut new_creases = Creases::default();
+    for e in mesh.edges() {
+        let h = mesh.edge_halfedge(e);
+        let t = mesh.twin(h);
+        let [a, b] = mesh.edge_vertices(e);
+        let ends = mesh.position(a) + mesh.position(b);
+        let p = if creases.is_sharp(mesh, e) {
+            if !mesh.is_boundary_edge(e) {
+                let m = (vertex_count + e.index()) as u32;
+                new_creases.insert(a.index() as u32, m);
+                new_creases.insert(m, b.index() as u32);
+            }
+            0.5 * ends
+        } else {
+            let c = mesh.dest(mesh.next(h));
+            let d = mesh.dest(mesh.next(t));
+            0.375 * ends + 0.125 * (mesh.position(c) + mesh.position(d))
+        };
+        positions.push(p);
+        origins.push(Origin::Edge(e));
+    }
+
+    let mut polygons = Vec::with_capacity(mesh.face_count() * 4);
+    let mut parents = Vec::with_capacity(polygons.capacity());
+    for f in mesh.faces() {
+        let corners: Vec<u32> = mesh.face_vertices(f).map(|v| v.index() as u32).collect();
+        let mids: Vec<u32> = mesh
+            .face_edges(f)
+            .map(|e| (vertex_count + e.index()) as u32)
+            .collect();
+        // mids[i] lies on the edge from corners[i] to corners[i + 1].
+        polygons.push(vec![corners[0], mids[0], mids[2]]);
+        polygons.push(vec![corners[1], mids[1], mids[0]]);
+        polygons.push(vec![corners[2], mids[2], mids[1]]);
+        polygons.push(vec![mids[0], mids[1], mids[2]]);
+        parents.extend([f; 4]);
+    }
+
+    Level {
+        positions,
+        origins,
+        polygons,
+        parents,
+        creases: new_creases,
+    }
+}
@@ -0,0 +1,387 @@
+//! Subdivision surfaces.
+//!
+//! Each scheme rebuilds the mesh once per level from freshly computed positions and polygons. Boundary edges,
+//! and any edges listed in [`SubdivisionConfig::crease_edges`], are treated as sharp: they are refined as
+//! curves of their own, and vertices where more than two sharp edges meet are kept as corners.
+//!
+//! Attributes are carried over linearly, whatever the scheme does with positions: vertices and corners at old
+//! vertices keep their values, those on an old edge average its ends, and those at a face point average the
+//! face's corners. Faces inherit from the face they were cut from, and the halves of old edges from that edge.
+
+use core::fmt;
+use std::collections::HashSet;
+
+use glam::Vec3;
+
+use crate::mesh::{BuildError, EdgeId, ElementKind, FaceId, HalfEdgeId, HalfEdgeMesh, VertexId};
+
+mod catmull_clark;
+mod loop_scheme;
+mod sqrt3;
+
+/// Settings shared by all subdivision schemes.
+#[derive(Clone, Debug)]
+pub struct SubdivisionConfig {
+    /// How many times to apply the scheme.
+    pub levels: u32,
+    /// Interior edges of the input mesh to keep sharp. Their descendants stay sharp at every level.
+    pub crease_edges: Vec<EdgeId>,
+}
+
+impl Default for SubdivisionConfig {
+    fn default() -> Self {
+        Self {
+            levels: 1,
+            crease_edges: Vec::new(),
+        }
+    }
+}
+
+/// Why a mesh could not be subdivided.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubdivisionError {
+    /// The scheme only applies to triangle meshes, and the mesh has other polygons.
+    NotTriangleMesh,
+    /// A crease edge does not exist in the mesh.
+    InvalidCrease { edge: EdgeId },
+    /// The refined polygons did not form a valid mesh. This only happens if the input was invalid.
+    Build(BuildError),
+}
+
+impl fmt::Display for SubdivisionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTriangleMesh => f.write_str("the scheme requires a triangle mesh"),
+            Self::InvalidCrease { edge } => write!(f, "crease edge {edge} does not exist"),
+            Self::Build(err) => write!(f, "refined mesh is invalid: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SubdivisionError {}
+
+impl From<BuildError> for SubdivisionError {
+    fn from(err: BuildError) -> Self {
+        Self::Build(err)
+    }
+}
+
+/// Sharp edges of one level, keyed by their (sorted) vertex indices so they survive rebuilding the mesh.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Creases(HashSet<(u32, u32)>);
+
+impl Creases {
+    fn key(a: VertexId, b: VertexId) -> (u32, u32) {
+        let (a, b) = (a.index() as u32, b.index() as u32);
+        (a.min(b), a.max(b))
+    }
+
+    fn insert(&mut self, a: u32, b: u32) {
+        self.0.insert((a.min(b), a.max(b)));
+    }
+
+    /// Whether edge `e` is sharp: either a crease or on the boundary.
+    fn is_sharp(&self, mesh: &HalfEdgeMesh, e: EdgeId) -> bool {
+        let [a, b] = mesh.edge_vertices(e);
+        mesh.is_boundary_edge(e) || self.0.contains(&Self::key(a, b))
+    }
+}
+
+/// The element of the coarser mesh a new vertex was placed on.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Origin {
+    Vertex(VertexId),
+    Edge(EdgeId),
+    Face(FaceId),
+}
+
+/// The result of refining one level: new positions, new polygons and the sharp edges among them, with where
+/// each new vertex came from and the old face each new polygon lies in.
+pub(crate) struct Level {
+    positions: Vec<Vec3>,
+    origins: Vec<Origin>,
+    polygons: Vec<Vec<u32>>,
+    parents: Vec<FaceId>,
+    creases: Creases,
+}
+
+/// Give the refined mesh `fine` the attributes of `coarse`, following the provenance recorded in `level`.
+fn carry_attributes(coarse: &HalfEdgeMesh, fine: &mut HalfEdgeMesh, level: &Level) {
+    let vertex_sources = |origin: Origin| -> Vec<(usize, f32)> {
+        match origin {
+            Origin::Vertex(v) => vec![(v.index(), 1.0)],
+            Origin::Edge(e) => coarse.edge_vertices(e).map(|v| (v.index(), 0.5)).to_vec(),
+            Origin::Face(f) => coarse.face_vertices(f).map(|v| (v.index(), 1.0)).collect(),
+        }
+    };
+    let corner_sources = |origin: Origin, parent: FaceId| -> Vec<(usize, f32)> {
+        let in_parent = |h: &HalfEdgeId| coarse.face(*h) == Some(parent);
+        match origin {
+            Origin::Vertex(v) => coarse
+                .vertex_outgoing_halfedges(v)
+                .find(in_parent)
+                .map(|h| vec![(h.index(), 1.0)])
+                .unwrap_or_default(),
+            Origin::Edge(e) => {
+                let h = coarse.edge_halfedge(e);
+                [h, coarse.twin(h)]
+                    .into_iter()
+                    .find(in_parent)
+                    .map(|h| vec![(h.index(), 0.5), (coarse.next(h).index(), 0.5)])
+                    .unwrap_or_default()
+            }
+            Origin::Face(f) => coarse.face_halfedges(f).map(|h| (h.index(), 1.0)).collect(),
+        }
+    };
+    let edge_sources = |x: Origin, y: Origin| -> Vec<(usize, f32)> {
+        let old = match (x, y) {
+            (Origin::Vertex(a), Origin::Vertex(b)) => coarse.find_edge(a, b),
+            (Origin::Edge(d), Origin::Edge(e)) => (d == e).then_some(e),
+            (Origin::Vertex(v), Origin::Edge(e)) | (Origin::Edge(e), Origin::Vertex(v)) => {
+                coarse.edge_vertices(e).contains(&v).then_some(e)
+            }
+            _ => None,
+        };
+        old.map(|e| vec![(e.index(), 1.0)]).unwrap_or_default()
+    };
+
+    let origin = |v: VertexId| level.origins[v.index()];
+    let vertices: Vec<_> = level.origins.iter().map(|&o| vertex_sources(o)).collect();
+    let mut corners = vec![Vec::new(); fine.halfedge_count()];
+    for f in fine.faces() {
+        for h in fine.face_halfedges(f) {
+            corners[h.index()] = corner_sources(origin(fine.origin(h)), level.parents[f.index()]);
+        }
+    }
+    let edges: Vec<_> = fine
+        .edges()
+        .map(|e| {
+            let [a, b] = fine.edge_vertices(e);
+            edge_sources(origin(a), origin(b))
+        })
+        .collect();
+    let faces: Vec<_> = level
+        .parents
+        .iter()
+        .map(|f| vec![(f.index(), 1.0)])
+        .collect();
+
+    fine.attributes = coarse.attributes.clone();
+    fine.attributes.gather(ElementKind::Vertex, &vertices);
+    fine.attributes.gather(ElementKind::HalfEdge, &corners);
+    fine.attributes.gather(ElementKind::Edge, &edges);
+    fine.attributes.gather(ElementKind::Face, &faces);
+}
+
+/// The position of an old vertex under the sharp-vertex rules, or `None` if the vertex is smooth and the
+/// scheme's own rule applies. Vertices on a single sharp curve move along it with the cubic B-spline mask;
+/// vertices where more than two sharp edges meet are corners and stay put.
+fn sharp_vertex_position(mesh: &HalfEdgeMesh, creases: &Creases, v: VertexId) -> Option<Vec3> {
+    let sharp_neighbours = sharp_neighbours(mesh, creases, v);
+    let p = mesh.position(v);
+    match sharp_neighbours.len() {
+        0 | 1 => None,
+        2 => Some(
+            0.75 * p
+                + 0.125 * (mesh.position(sharp_neighbours[0]) + mesh.position(sharp_neighbours[1])),
+        ),
+        _ => Some(p),
+    }
+}
+
+/// The vertices joined to `v` by sharp edges.
+fn sharp_neighbours(mesh: &HalfEdgeMesh, creases: &Creases, v: VertexId) -> Vec<VertexId> {
+    mesh.vertex_outgoing_halfedges(v)
+        .filter(|&h| creases.is_sharp(mesh, mesh.edge(h)))
+        .map(|h| mesh.dest(h))
+        .collect()
+}
+
+impl HalfEdgeMesh {
+    /// Apply `step`, which is given the index of the level it computes, `config.levels` times.
+    fn subdivide(
+        &self,
+        config: &SubdivisionConfig,
+        triangles_only: bool,
+        step: fn(&HalfEdgeMesh, &Creases, u32) -> Level,
+    ) -> Result<HalfEdgeMesh, SubdivisionError> {
+        let mut mesh = self.clone();
+        let gc = mesh.garbage_collect();
+        let mut creases = Creases::default();
+        for &e in &config.crease_edges {
+            let Some(Some(e)) = gc.edges.get(e.index()) else {
+                return Err(SubdivisionError::InvalidCrease { edge: e });
+            };
+            let [a, b] = mesh.edge_vertices(*e);
+            creases.0.insert(Creases::key(a, b));
+        }
+        if triangles_only && mesh.faces().any(|f| mesh.face_degree(f) != 3) {
+            return Err(SubdivisionError::NotTriangleMesh);
+        }
+
+        for index in 0..config.levels {
+            let mut level = step(&mesh, &creases, index);
+            let positions = std::mem::take(&mut level.positions);
+            let mut fine = HalfEdgeMesh::from_polygons(positions, &level.polygons)?;
+            if !mesh.attributes.is_empty() {
+                carry_attributes(&mesh, &mut fine, &level);
+            }
+            mesh = fine;
+            creases = level.creases;
+        }
+        Ok(mesh)
+    }
+
+    /// Loop subdivision: each triangle is split into four and the surface converges to a C2 box-spline
+    /// surface (C1 at extraordinary vertices). Requires a triangle mesh.
+    pub fn loop_subdivision(
+        &self,
+        config: &SubdivisionConfig,
+    ) -> Result<HalfEdgeMesh, SubdivisionError> {
+        self.subdivide(config, true, |mesh, creases, _| {
+            loop_scheme::step(mesh, creases)
+        })
+    }
+
+    /// Catmull-Clark subdivision: every n-gon is split into n quads and the surface converges to a bicubic
+    /// B-spline surface away from extraordinary vertices. Works on any polygon mesh.
+    pub fn catmull_clark_subdivision(
+        &self,
+        config: &SubdivisionConfig,
+    ) -> Result<HalfEdgeMesh, SubdivisionError> {
+        self.subdivide(config, false, |mesh, creases, _| {
+            catmull_clark::step(mesh, creases)
+        })
+    }
+
+    /// Kobbelt's sqrt(3) subdivision: a vertex is inserted in every triangle and the old edges are flipped,
+    /// tripling the face count per level (rather than quadrupling it as Loop does). Boundary and crease edges
+    /// follow Kobbelt's boundary rule: on the first level and every other one after it they are cut in three
+    /// with the ternary cubic B-spline masks, and on the levels in between they are left alone, so that every
+    /// two levels cut them in three just as they cut interior edges. Requires a triangle mesh.
+    pub fn sqrt3_subdivision(
+        &self,
+        config: &SubdivisionConfig,
+    ) -> Result<HalfEdgeMesh, SubdivisionError> {
+        self.subdivide(config, true, sqrt3::step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn octahedron() -> HalfEdgeMesh {
+        let positions = vec![Vec3::Z, Vec3::X, Vec3::Y, -Vec3::X, -Vec3::Y, -Vec3::Z];
+        let polygons = [
+            [0, 1, 2],
+            [0, 2, 3],
+            [0, 3, 4],
+            [0, 4, 1],
+            [5, 2, 1],
+            [5, 3, 2],
+            [5, 4, 3],
+            [5, 1, 4],
+        ];
+        HalfEdgeMesh::from_polygons(positions, polygons).unwrap()
+    }
+
+    fn cube() -> HalfEdgeMesh {
+        let positions: Vec<Vec3> = (0..8)
+            .map(|i| {
+                Vec3::new(
+                    [-1.0, 1.0][i & 1],
+                    [-1.0, 1.0][(i >> 1) & 1],
+                    [-1.0, 1.0][i >> 2],
+                )
+            })
+            .collect();
+        let polygons = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ];
+        HalfEdgeMesh::from_polygons(positions, polygons).unwrap()
+    }
+
+    /// A flat `n` by `n` grid of squares over the unit square, each cut into two triangles.
+    fn grid(n: u32) -> HalfEdgeMesh {
+        let positions: Vec<Vec3> = (0..=n)
+            .flat_map(|j| (0..=n).map(move |i| Vec3::new(i as f32, j as f32, 0.0) / n as f32))
+            .collect();
+        let mut triangles = Vec::new();
+        for j in 0..n {
+            for i in 0..n {
+                let v = j * (n + 1) + i;
+                triangles.extend([v, v + 1, v + n + 2, v, v + n + 2, v + n + 1]);
+            }
+        }
+        HalfEdgeMesh::from_triangles(positions, &triangles).unwrap()
+    }
+
+    fn has_vertex_at(mesh: &HalfEdgeMesh, p: Vec3) -> bool {
+        mesh.vertices().any(|v| mesh.position(v).distance(p) < 1e-6)
+    }
+
+    fn boundary_edge_count(mesh: &HalfEdgeMesh) -> usize {
+        mesh.edges().filter(|&e| mesh.is_boundary_edge(e)).count()
+    }
+
+    #[test]
+    fn loop_applies_the_vertex_and_edge_masks() {
+        let fine = octahedron().loop_subdivision(&Default::default()).unwrap();
+        assert!(fine.validate().is_ok());
+        assert_eq!(fine.face_count(), 32);
+        // Valence 4: beta = (5/8 - (3/8)^2) / 4, and the ring sums to zero.
+        let beta = (0.625 - 0.375 * 0.375) / 4.0;
+        assert!(has_vertex_at(&fine, (1.0 - 4.0 * beta) * Vec3::Z));
+        // 3/8 of the edge's ends plus 1/8 of the opposite vertices, which cancel.
+        assert!(has_vertex_at(&fine, 0.375 * (Vec3::Z + Vec3::X)));
+    }
+
+    #[test]
+    fn catmull_clark_applies_the_vertex_edge_and_face_masks() {
+        let fine = cube()
+            .catmull_clark_subdivision(&Default::default())
+            .unwrap();
+        assert!(fine.validate().is_ok());
+        assert_eq!(fine.face_count(), 24);
+        assert!(fine.faces().all(|f| fine.face_degree(f) == 4));
+        // (F + 2R + (n - 3)P) / n with F = P/3 and R = 2P/3 at a valence 3 corner.
+        assert!(has_vertex_at(&fine, Vec3::ONE * 5.0 / 9.0));
+        // Half the edge midpoint plus a quarter of each neighbouring face point.
+        assert!(has_vertex_at(&fine, Vec3::new(0.75, 0.75, 0.0)));
+        assert!(has_vertex_at(&fine, Vec3::X));
+    }
+
+    #[test]
+    fn sqrt3_trisects_the_boundary_every_other_level() {
+        let mesh = grid(2);
+        let boundary = boundary_edge_count(&mesh);
+        let config = |levels| SubdivisionConfig {
+            levels,
+            ..Default::default()
+        };
+
+        let once = mesh.sqrt3_subdivision(&config(1)).unwrap();
+        assert!(once.validate().is_ok());
+        assert_eq!(boundary_edge_count(&once), 3 * boundary);
+
+        let twice = mesh.sqrt3_subdivision(&config(2)).unwrap();
+        assert!(twice.validate().is_ok());
+        assert_eq!(boundary_edge_count(&twice), 3 * boundary);
+        assert_eq!(twice.face_count(), 3 * once.face_count());
+        assert!(twice.vertices().all(|v| twice.position(v).z == 0.0));
+    }
+
+    #[test]
+    fn sqrt3_rejects_other_polygons() {
+        assert_eq!(
+            cube().sqrt3_subdivision(&Default::default()).unwrap_err(),
+            SubdivisionError::NotTriangleMesh
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::f32::consts::TAU;
+
+use glam::Vec3;
+
+use super::{sharp_neighbours, Creases, Level, Origin};
+use crate::mesh::{FaceId, HalfEdgeMesh, VertexId};
+
+pub(super) fn step(mesh: &HalfEdgeMesh, creases: &Creases, level: u32) -> Level {
+    let vertex_count = mesh.vertex_count();
+    // Kobbelt's boundary rule: sharp edges are cut in three on every other level, starting with the first.
+    let trisect = level.is_multiple_of(2);
+
+    // The neighbour of `v` along its sharp curve away from `from`, or `v` mirrored through itself at a corner
+    // or the end of the curve, so that the masks leave such vertices where they are.
+    let beyond = |v: VertexId, from: VertexId| -> Vec3 {
+        let neighbours = sharp_neighbours(mesh, creases, v);
+        match neighbours[..] {
+            [x, y] => mesh.position(if x == from { y } else { x }),
+            _ => 2.0 * mesh.position(v) - mesh.position(from),
+        }
+    };
+
+    let mut positions = Vec::with_capacity(vertex_count + mesh.face_count());
+    for v in mesh.vertices() {
+        let p = mesh.position(v);
+        let sharp = sharp_neighbours(mesh, creases, v);
+        let n = mesh.vertex_valence(v);
+        if !sharp.is_empty() || n == 0 {
+            positions.push(match sharp[..] {
+                [a, b] if trisect => {
+                    (4.0 * mesh.position(a) + 19.0 * p + 4.0 * mesh.position(b)) / 27.0
+                }
+                _ => p,
+            });
+            continue;
+        }
+        let alpha = (4.0 - 2.0 * (TAU / n as f32).cos()) / 9.0;
+        let ring = mesh
+            .vertex_vertices(v)
+            .map(|u| mesh.position(u))
+            .sum::<Vec3>()
+            / n as f32;
+        positions.push((1.0 - alpha) * p + alpha * ring);
+    }
+    positions.extend(
+        mesh.faces()
+            .map(|f| mesh.face_positions(f).sum::<Vec3>() / 3.0),
+    );
+    let mut origins: Vec<Origin> = mesh
+        .vertices()
+        .map(Origin::Vertex)
+        .chain(mesh.faces().map(Origin::Face))
+        .collect();
+
+    // The two points cutting each trisected edge a->b (as its edge half-edge runs), nearest a first.
+    let mut thirds = vec![None; mesh.edge_count()];
+    if trisect {
+        for e in mesh.edges().filter(|&e| creases.is_sharp(mesh, e)) {
+            let [a, b] = mesh.edge_vertices(e);
+            let (pa, pb) = (mesh.position(a), mesh.position(b));
+            let index = positions.len() as u32;
+            positions.push((beyond(a, b) + 16.0 * pa + 10.0 * pb) / 27.0);
+            positions.push((10.0 * pa + 16.0 * pb + beyond(b, a)) / 27.0);
+            origins.extend([Origin::Edge(e); 2]);
+            thirds[e.index()] = Some([index, index + 1]);
+        }
+    }
+
+    // Splitting every triangle at its centre and then flipping the old edges gives, for each old interior edge
+    // a->b between centres m (left) and n (right), the triangles (a, n, m) and (b, m, n). Sharp edges are not
+    // flipped and keep their triangle (a, b, m), or a fan of three from m when they are trisected.
+    let center = |f: FaceId| (vertex_count + f.index()) as u32;
+    let mut polygons = Vec::new();
+    let mut parents = Vec::new();
+    let mut fine_creases = if trisect {
+        Creases::default()
+    } else {
+        creases.clone()
+    };
+    for e in mesh.edges() {
+        let h = mesh.edge_halfedge(e);
+        let t = mesh.twin(h);
+        let [a, b] = mesh.edge_vertices(e).map(|v| v.index() as u32);
+        if creases.is_sharp(mesh, e) {
+            let path = match thirds[e.index()] {
+                Some([x, y]) => vec![a, x, y, b],
+                None => vec![a, b],
+            };
+            if trisect && !mesh.is_boundary_edge(e) {
+                for pair in path.windows(2) {
+                    fine_creases.insert(pair[0], pair[1]);
+                }
+            }
+            for side in [h, t] {
+                let Some(f) = mesh.face(side) else {
+                    continue;
+                };
+                let forward = side == h;
+                for pair in path.windows(2) {
+                    let (from, to) = if forward {
+                        (pair[0], pair[1])
+                    } else {
+                        (pair[1], pair[0])
+                    };
+                    polygons.push(vec![from, to, center(f)]);
+                    parents.push(f);
+                }
+            }
+        } else {
+            let fh = mesh.face(h).expect("interior edge has two faces");
+            let ft = mesh.face(t).expect("interior edge has two faces");
+            let (m, n) = (center(fh), center(ft));
+            // Each new triangle straddles the old edge; credit one to either face.
+            polygons.push(vec![a, n, m]);
+            polygons.push(vec![b, m, n]);
+            parents.extend([ft, fh]);
+        }
+    }
+
+    Level {
+        positions,
+        origins,
+        polygons,
+        parents,
+        creases: fine_creases,
+    }
+}
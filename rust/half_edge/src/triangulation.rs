@@ -0,0 +1,1141 @@
+//! Constrained Delaunay triangulation of planar straight-line graphs, with optional quality refinement.
+//!
+//! Points are inserted one at a time, in a space-filling curve order, into a Delaunay triangulation of a
+//! large enclosing triangle. Each is found by walking from the previous one and the Delaunay property is
+//! restored by flipping edges. Segments are then forced in by flipping the edges they cross, after Sloan,
+//! and the edges around them flipped back towards Delaunay. Finally the triangles outside the segments and
+//! in holes are discarded, by flooding from the enclosing triangle and from each hole point without
+//! crossing a segment.
+//!
+//! Refinement follows Ruppert's algorithm. Segments with a vertex inside their diametral circle are split,
+//! and triangles with too small an angle or too large an area get a vertex at their circumcentre, unless
+//! that would encroach on a segment, which is split instead. Segments meeting at a small angle are split on
+//! circles around their shared vertex whose radii are powers of two, and triangles whose shortest edge joins
+//! two such segments are not split for their angle, so refinement finishes for any input at angle bounds up
+//! to about 20° and in practice up to about 33°. [`TriangulationConfig::max_steiner_points`] bounds it
+//! regardless.
+//!
+//! Every orientation and in-circle decision is made exactly, in `f64`, with [`crate::predicates`]. The mesh
+//! stores `f32` positions, though, so small triangles far from the origin lose their shape in rounding;
+//! translate such input towards the origin first.
+
+use core::fmt;
+use std::collections::{HashMap, VecDeque};
+
+use glam::{DVec2, Vec2, Vec3};
+
+use crate::{
+    mesh::{BuildError, EdgeId, HalfEdgeMesh, VertexId},
+    predicates::{incircle, orient2d},
+};
+
+/// A planar straight-line graph: points, segments between them that must appear as edges, and points
+/// marking holes.
+#[derive(Clone, Debug, Default)]
+pub struct PlanarGraph {
+    pub points: Vec<Vec2>,
+    /// Pairs of indices into `points`. Segments may share ends and pass through points, but not cross. The
+    /// triangulation covers the region they enclose, or the convex hull of the points if there are none.
+    pub segments: Vec<[u32; 2]>,
+    /// A point inside each hole. The triangles around it are removed out to the nearest segments.
+    pub holes: Vec<Vec2>,
+}
+
+/// Settings for [`PlanarGraph::triangulate`].
+#[derive(Clone, Debug)]
+pub struct TriangulationConfig {
+    /// Refine until no triangle has an angle smaller than this, in radians. Zero leaves angles alone.
+    pub min_angle: f32,
+    /// Refine until no triangle has a larger area than this. Zero leaves areas alone.
+    pub max_area: f32,
+    /// Stop refining after adding this many points.
+    pub max_steiner_points: usize,
+    /// Triangulate the whole convex hull of the points, as if its edges were segments, rather than only the
+    /// region the segments enclose.
+    pub convex_hull: bool,
+}
+
+impl Default for TriangulationConfig {
+    fn default() -> Self {
+        Self {
+            min_angle: 0.0,
+            max_area: 0.0,
+            max_steiner_points: 100_000,
+            convex_hull: false,
+        }
+    }
+}
+
+/// Why a planar graph could not be triangulated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TriangulationError {
+    /// A point has a NaN or infinite coordinate.
+    NonFinitePoint { point: usize },
+    /// A hole point has a NaN or infinite coordinate.
+    NonFiniteHole { hole: usize },
+    /// A segment refers to a point that does not exist, or joins a point to itself.
+    InvalidSegment { segment: usize },
+    /// Two segments cross each other.
+    CrossingSegments { first: usize, second: usize },
+    /// Nothing was left to triangulate: the points are collinear, or the segments enclose no area.
+    Empty,
+    /// The triangles do not form a manifold mesh, as where two regions meet at a single point.
+    Build(BuildError),
+}
+
+impl fmt::Display for TriangulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonFinitePoint { point } => write!(f, "point {point} is not finite"),
+            Self::NonFiniteHole { hole } => write!(f, "hole point {hole} is not finite"),
+            Self::InvalidSegment { segment } => write!(f, "segment {segment} is invalid"),
+            Self::CrossingSegments { first, second } => {
+                write!(f, "segments {first} and {second} cross")
+            }
+            Self::Empty => f.write_str("the graph encloses no area"),
+            Self::Build(err) => write!(f, "triangulation is not a manifold: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for TriangulationError {}
+
+impl From<BuildError> for TriangulationError {
+    fn from(err: BuildError) -> Self {
+        Self::Build(err)
+    }
+}
+
+/// The result of [`PlanarGraph::triangulate`].
+#[derive(Clone, Debug)]
+pub struct Triangulation {
+    /// The triangles, in the plane z = 0.
+    pub mesh: HalfEdgeMesh,
+    /// The vertex of each input point, or `None` for points outside the triangulated region. Coincident
+    /// points share a vertex.
+    pub input_vertices: Vec<Option<VertexId>>,
+    /// The edges that lie along segments.
+    pub segment_edges: Vec<EdgeId>,
+    /// How many vertices refinement added.
+    pub steiner_points: usize,
+}
+
+const NONE: u32 = u32::MAX;
+
+fn undirected(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+/// A counter-clockwise triangle. `neighbours[i]` is across the edge opposite `vertices[i]`.
+#[derive(Clone, Copy, Debug)]
+struct Triangle {
+    vertices: [u32; 3],
+    neighbours: [u32; 3],
+    inside: bool,
+}
+
+/// Where a point lies in the triangulation.
+#[derive(Clone, Copy, Debug)]
+enum Location {
+    Vertex(u32),
+    /// On the edge opposite the given corner.
+    Edge(u32, usize),
+    Face(u32),
+}
+
+/// An edge of a triangle, given as the triangle and the corner opposite the edge.
+type Side = (u32, usize);
+
+struct Triangulator {
+    points: Vec<DVec2>,
+    triangles: Vec<Triangle>,
+    vertex_triangle: Vec<u32>,
+    /// The first of the three vertices of the enclosing triangle.
+    enclosing: u32,
+    /// The segment each constrained edge lies along.
+    segments: HashMap<(u32, u32), u32>,
+    /// The end vertices of each segment, the input ones first and then the convex hull.
+    segment_ends: Vec<[u32; 2]>,
+    /// The segment each vertex added by splitting one lies on.
+    split_from: HashMap<u32, u32>,
+    /// State for the random choices in walks.
+    seed: u32,
+}
+
+impl Triangulator {
+    fn new(points: Vec<DVec2>) -> Self {
+        let (min, max) = points
+            .iter()
+            .fold((DVec2::INFINITY, DVec2::NEG_INFINITY), |(lo, hi), &p| {
+                (lo.min(p), hi.max(p))
+            });
+        let center = (min + max) * 0.5;
+        let r = 4.0 * (max - min).max_element().max(1.0);
+        let enclosing = points.len() as u32;
+        let mut points = points;
+        points.extend([
+            center + DVec2::new(-3.0, -2.0) * r,
+            center + DVec2::new(3.0, -2.0) * r,
+            center + DVec2::new(0.0, 3.0) * r,
+        ]);
+        let mut triangulator = Self {
+            vertex_triangle: vec![NONE; points.len()],
+            points,
+            triangles: Vec::new(),
+            enclosing,
+            segments: HashMap::new(),
+            segment_ends: Vec::new(),
+            split_from: HashMap::new(),
+            seed: 0x9e37_79b9,
+        };
+        triangulator.set(
+            0,
+            [enclosing, enclosing + 1, enclosing + 2],
+            [NONE; 3],
+            true,
+        );
+        triangulator
+    }
+
+    fn point(&self, v: u32) -> DVec2 {
+        self.points[v as usize]
+    }
+
+    fn is_enclosing(&self, v: u32) -> bool {
+        (self.enclosing..self.enclosing + 3).contains(&v)
+    }
+
+    fn is_segment(&self, a: u32, b: u32) -> bool {
+        self.segments.contains_key(&undirected(a, b))
+    }
+
+    fn random(&mut self, n: u32) -> usize {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed % n) as usize
+    }
+
+    /// Store a triangle at index `t`, appending it if `t` is one past the end.
+    fn set(&mut self, t: u32, vertices: [u32; 3], neighbours: [u32; 3], inside: bool) {
+        let triangle = Triangle {
+            vertices,
+            neighbours,
+            inside,
+        };
+        if t as usize == self.triangles.len() {
+            self.triangles.push(triangle);
+        } else {
+            self.triangles[t as usize] = triangle;
+        }
+        for v in vertices {
+            self.vertex_triangle[v as usize] = t;
+        }
+    }
+
+    /// Point the neighbour of `t` that was `old` at `new` instead.
+    fn relink(&mut self, t: u32, old: u32, new: u32) {
+        if t == NONE {
+            return;
+        }
+        let neighbours = &mut self.triangles[t as usize].neighbours;
+        if let Some(n) = neighbours.iter_mut().find(|n| **n == old) {
+            *n = new;
+        }
+    }
+
+    fn corner(&self, t: u32, v: u32) -> usize {
+        self.triangles[t as usize]
+            .vertices
+            .iter()
+            .position(|&w| w == v)
+            .expect("vertex is a corner of the triangle")
+    }
+
+    /// The triangles around `v`, counter-clockwise. Around a corner of the enclosing triangle, which has
+    /// only one side, they start at the clockwise end.
+    fn around(&self, v: u32) -> Vec<u32> {
+        let start = self.vertex_triangle[v as usize];
+        let step = |t: u32, turn: usize| {
+            let k = self.corner(t, v);
+            self.triangles[t as usize].neighbours[(k + turn) % 3]
+        };
+        let mut first = start;
+        if self.is_enclosing(v) {
+            while step(first, 2) != NONE {
+                first = step(first, 2);
+            }
+        }
+        let mut ring = Vec::new();
+        let mut t = first;
+        loop {
+            ring.push(t);
+            t = step(t, 1);
+            if t == first || t == NONE {
+                return ring;
+            }
+        }
+    }
+
+    /// The triangle with the directed edge `a -> b`, and the corner opposite it.
+    fn find_edge(&self, a: u32, b: u32) -> Option<Side> {
+        self.around(a).into_iter().find_map(|t| {
+            let k = self.corner(t, a);
+            (self.triangles[t as usize].vertices[(k + 1) % 3] == b).then_some((t, (k + 2) % 3))
+        })
+    }
+
+    /// The triangle across the edge opposite corner `i` of `t`, and its corner opposite that edge.
+    fn across(&self, (t, i): Side) -> Side {
+        let u = self.triangles[t as usize].neighbours[i];
+        let j = self.triangles[u as usize]
+            .neighbours
+            .iter()
+            .position(|&n| n == t)
+            .expect("neighbours are mutual");
+        (u, j)
+    }
+
+    /// The ends of the edge opposite corner `i` of `t`, counter-clockwise.
+    fn edge(&self, (t, i): Side) -> (u32, u32) {
+        let v = self.triangles[t as usize].vertices;
+        (v[(i + 1) % 3], v[(i + 2) % 3])
+    }
+
+    /// Find `p` by walking from `t`. Unless `through_segments` is set, the walk stops at the first segment in
+    /// the way, which is returned as the error; so is the edge of the enclosing triangle it leaves through.
+    fn locate(&mut self, p: DVec2, mut t: u32, through_segments: bool) -> Result<Location, Side> {
+        'walk: loop {
+            let vertices = self.triangles[t as usize].vertices;
+            let offset = self.random(3);
+            let mut on_edge = None;
+            for k in 0..3 {
+                let i = (offset + k) % 3;
+                let (a, b) = (vertices[(i + 1) % 3], vertices[(i + 2) % 3]);
+                let orientation = orient2d(self.point(a), self.point(b), p);
+                if orientation < 0.0 {
+                    let next = self.triangles[t as usize].neighbours[i];
+                    if next == NONE || !through_segments && self.is_segment(a, b) {
+                        return Err((t, i));
+                    }
+                    t = next;
+                    continue 'walk;
+                }
+                if orientation == 0.0 {
+                    on_edge = Some(i);
+                }
+            }
+            if let Some(&v) = vertices.iter().find(|&&v| self.point(v) == p) {
+                return Ok(Location::Vertex(v));
+            }
+            return Ok(match on_edge {
+                Some(i) => Location::Edge(t, i),
+                None => Location::Face(t),
+            });
+        }
+    }
+
+    /// Add a vertex at `p`, which lies at `location`, unless one is there already.
+    fn insert(&mut self, p: DVec2, location: Location) -> u32 {
+        if let Location::Vertex(v) = location {
+            return v;
+        }
+        self.points.push(p);
+        self.vertex_triangle.push(NONE);
+        let v = self.points.len() as u32 - 1;
+        self.connect(v, location);
+        v
+    }
+
+    /// Connect the existing point `v`, which lies inside the triangle or on the edge `location`, and restore
+    /// the Delaunay property around it.
+    fn connect(&mut self, v: u32, location: Location) {
+        let mut stack = match location {
+            Location::Vertex(_) => return,
+            Location::Face(t) => self.split_triangle(t, v),
+            Location::Edge(t, i) => self.split_edge((t, i), v),
+        };
+        while let Some((t, i)) = stack.pop() {
+            if self.triangles[t as usize].vertices[i] != v {
+                continue;
+            }
+            let (a, b) = self.edge((t, i));
+            if self.triangles[t as usize].neighbours[i] == NONE || self.is_segment(a, b) {
+                continue;
+            }
+            let (u, j) = self.across((t, i));
+            let d = self.triangles[u as usize].vertices[j];
+            if incircle(self.point(v), self.point(a), self.point(b), self.point(d)) > 0.0 {
+                let (first, second) = self.flip((t, i));
+                stack.push((first, 0));
+                stack.push((second, 2));
+            }
+        }
+    }
+
+    /// Split `t` into three around the new vertex `v`, returning the edges opposite it.
+    fn split_triangle(&mut self, t: u32, v: u32) -> Vec<Side> {
+        let Triangle {
+            vertices: [a, b, c],
+            neighbours: [na, nb, nc],
+            inside,
+        } = self.triangles[t as usize];
+        let (t1, t2) = (self.triangles.len() as u32, self.triangles.len() as u32 + 1);
+        self.set(t, [v, b, c], [na, t1, t2], inside);
+        self.set(t1, [a, v, c], [t, nb, t2], inside);
+        self.set(t2, [a, b, v], [t, t1, nc], inside);
+        self.relink(nb, t, t1);
+        self.relink(nc, t, t2);
+        vec![(t, 0), (t1, 1), (t2, 2)]
+    }
+
+    /// Split the edge opposite corner `i` of `t`, and the triangles on both sides, at the new vertex `v`,
+    /// returning the edges opposite it.
+    fn split_edge(&mut self, (t, i): Side, v: u32) -> Vec<Side> {
+        let (u, j) = self.across((t, i));
+        let rotate = |triangle: &Triangle, k: usize| {
+            let turn = |x: [u32; 3]| [x[k], x[(k + 1) % 3], x[(k + 2) % 3]];
+            (
+                turn(triangle.vertices),
+                turn(triangle.neighbours),
+                triangle.inside,
+            )
+        };
+        let ([a, b, c], [_, nb, nc], t_inside) = rotate(&self.triangles[t as usize], i);
+        let ([d, _, _], [_, uc, ub], u_inside) = rotate(&self.triangles[u as usize], j);
+        if let Some(segment) = self.segments.remove(&undirected(b, c)) {
+            self.segments.insert(undirected(b, v), segment);
+            self.segments.insert(undirected(v, c), segment);
+            self.split_from.insert(v, segment);
+        }
+        let (t2, u2) = (self.triangles.len() as u32, self.triangles.len() as u32 + 1);
+        self.set(t, [a, b, v], [u2, t2, nc], t_inside);
+        self.set(t2, [a, v, c], [u, nb, t], t_inside);
+        self.set(u, [d, c, v], [t2, u2, ub], u_inside);
+        self.set(u2, [d, v, b], [t, uc, u], u_inside);
+        self.relink(nb, t, t2);
+        self.relink(uc, u, u2);
+        vec![(t, 2), (t2, 1), (u, 2), (u2, 1)]
+    }
+
+    /// Flip the edge opposite corner `i` of `t`. The new edge runs from that corner, which becomes corner 0
+    /// of the first triangle returned and corner 2 of the second.
+    fn flip(&mut self, (t, i): Side) -> (u32, u32) {
+        let (u, j) = self.across((t, i));
+        let turn = |x: [u32; 3], k: usize| [x[k], x[(k + 1) % 3], x[(k + 2) % 3]];
+        let (tt, ut) = (self.triangles[t as usize], self.triangles[u as usize]);
+        let ([a, b, c], [_, nb, nc]) = (turn(tt.vertices, i), turn(tt.neighbours, i));
+        let ([d, _, _], [_, uc, ub]) = (turn(ut.vertices, j), turn(ut.neighbours, j));
+        self.set(t, [a, b, d], [uc, u, nc], tt.inside);
+        self.set(u, [d, c, a], [nb, t, ub], ut.inside);
+        self.relink(nb, t, u);
+        self.relink(uc, u, t);
+        (t, u)
+    }
+
+    /// The corner of the triangle across `side` opposite it.
+    fn far_corner(&self, side: Side) -> u32 {
+        let (u, j) = self.across(side);
+        self.triangles[u as usize].vertices[j]
+    }
+
+    /// Whether the far corner across `side` lies outside the circumcircle of its triangle, or on it.
+    fn is_delaunay(&self, side: Side) -> bool {
+        let [a, b, c] = self.triangles[side.0 as usize]
+            .vertices
+            .map(|v| self.point(v));
+        incircle(a, b, c, self.point(self.far_corner(side))) <= 0.0
+    }
+
+    /// Make `a - b` an edge along `segment`.
+    fn insert_segment(&mut self, a: u32, b: u32, segment: u32) -> Result<(), TriangulationError> {
+        let mut pending = vec![(a, b)];
+        while let Some((a, b)) = pending.pop() {
+            if self.find_edge(a, b).is_some() {
+                self.segments.entry(undirected(a, b)).or_insert(segment);
+                continue;
+            }
+            let (pa, pb) = (self.point(a), self.point(b));
+            let side = |s: &Self, v: u32| orient2d(pa, pb, s.point(v));
+
+            // The first edge crossed, from the triangle around `a` that the segment leaves through.
+            let mut crossed = Vec::new();
+            let mut current = None;
+            for t in self.around(a) {
+                let k = self.corner(t, a);
+                let v = self.triangles[t as usize].vertices;
+                let (p, q) = (v[(k + 1) % 3], v[(k + 2) % 3]);
+                let (sp, sq) = (side(self, p), side(self, q));
+                if sp == 0.0 && (self.point(p) - pa).dot(pb - pa) > 0.0 {
+                    current = None;
+                    pending.push((p, b));
+                    pending.push((a, p));
+                    crossed.clear();
+                    break;
+                }
+                if sp < 0.0 && sq > 0.0 {
+                    current = Some(((t, k), p, q));
+                    break;
+                }
+            }
+            let mut end = b;
+            // Walk along the segment, collecting the edges it crosses until it reaches `b` or passes
+            // through another vertex.
+            while let Some(((t, k), p, q)) = current {
+                if let Some(&other) = self.segments.get(&undirected(p, q)) {
+                    return Err(TriangulationError::CrossingSegments {
+                        first: other.min(segment) as usize,
+                        second: other.max(segment) as usize,
+                    });
+                }
+                crossed.push((p, q));
+                let (u, j) = self.across((t, k));
+                let r = self.triangles[u as usize].vertices[j];
+                current = if r == b {
+                    None
+                } else {
+                    let sr = side(self, r);
+                    if sr == 0.0 {
+                        end = r;
+                        pending.push((r, b));
+                        None
+                    } else if sr < 0.0 {
+                        Some(((u, (j + 2) % 3), r, q))
+                    } else {
+                        Some(((u, (j + 1) % 3), p, r))
+                    }
+                };
+            }
+            if !crossed.is_empty() {
+                self.force_edge(a, end, crossed);
+                self.segments.insert(undirected(a, end), segment);
+            }
+        }
+        Ok(())
+    }
+
+    /// Flip the `crossed` edges until `a - b` is an edge, then flip the new edges back towards Delaunay.
+    fn force_edge(&mut self, a: u32, b: u32, crossed: Vec<(u32, u32)>) {
+        let (pa, pb) = (self.point(a), self.point(b));
+        let mut queue = VecDeque::from(crossed);
+        let mut created = Vec::new();
+        while let Some((p, q)) = queue.pop_front() {
+            let side = self.find_edge(p, q).expect("crossed edges exist");
+            let c = self.triangles[side.0 as usize].vertices[side.1];
+            let d = self.far_corner(side);
+            let (pc, pd) = (self.point(c), self.point(d));
+            if orient2d(pc, pd, self.point(p)) * orient2d(pc, pd, self.point(q)) >= 0.0 {
+                // The two triangles form a non-convex quadrilateral; come back once others have moved.
+                queue.push_back((p, q));
+                continue;
+            }
+            self.flip(side);
+            let crosses = ![a, b].contains(&c)
+                && ![a, b].contains(&d)
+                && orient2d(pa, pb, pc) * orient2d(pa, pb, pd) < 0.0;
+            if crosses {
+                queue.push_back((c, d));
+            } else {
+                created.push((c, d));
+            }
+        }
+        loop {
+            let mut flipped = false;
+            for edge in &mut created {
+                let (p, q) = *edge;
+                if undirected(p, q) == undirected(a, b) {
+                    continue;
+                }
+                let Some(side) = self.find_edge(p, q) else {
+                    continue;
+                };
+                if !self.is_delaunay(side) {
+                    let c = self.triangles[side.0 as usize].vertices[side.1];
+                    *edge = (c, self.far_corner(side));
+                    self.flip(side);
+                    flipped = true;
+                }
+            }
+            if !flipped {
+                break;
+            }
+        }
+    }
+
+    /// Mark every triangle reachable from `seeds` without crossing a segment as outside.
+    fn carve(&mut self, seeds: Vec<u32>) {
+        let mut stack = seeds;
+        while let Some(t) = stack.pop() {
+            if !self.triangles[t as usize].inside {
+                continue;
+            }
+            self.triangles[t as usize].inside = false;
+            for i in 0..3 {
+                let (a, b) = self.edge((t, i));
+                let u = self.triangles[t as usize].neighbours[i];
+                if u != NONE && !self.is_segment(a, b) {
+                    stack.push(u);
+                }
+            }
+        }
+    }
+}
+
+/// Refinement state: the bounds, and the work still to check.
+struct Refiner {
+    ratio: f64,
+    max_area: f64,
+    max_points: usize,
+    added: usize,
+    /// Segments at each input vertex, so that splits near one shared with others can use concentric shells.
+    segment_degree: HashMap<u32, usize>,
+    encroached: Vec<(u32, u32)>,
+    bad: VecDeque<[u32; 3]>,
+}
+
+impl Refiner {
+    /// Queue the triangles around the new vertex `v`, and the segments among their edges, for checking.
+    fn watch(&mut self, triangulator: &Triangulator, v: u32) {
+        for t in triangulator.around(v) {
+            let triangle = triangulator.triangles[t as usize];
+            if triangle.inside {
+                self.bad.push_back(triangle.vertices);
+            }
+            for i in 0..3 {
+                let (a, b) = triangulator.edge((t, i));
+                if triangulator.is_segment(a, b) {
+                    self.encroached.push((a, b));
+                }
+            }
+        }
+    }
+
+    /// Whether a vertex of an inside triangle next to the segment `a - b` lies in its diametral circle.
+    fn is_encroached(triangulator: &Triangulator, a: u32, b: u32) -> bool {
+        let (pa, pb) = (triangulator.point(a), triangulator.point(b));
+        [(a, b), (b, a)].into_iter().any(|(p, q)| {
+            triangulator.find_edge(p, q).is_some_and(|(t, i)| {
+                let triangle = triangulator.triangles[t as usize];
+                let apex = triangulator.point(triangle.vertices[i]);
+                triangle.inside && (pa - apex).dot(pb - apex) < 0.0
+            })
+        })
+    }
+
+    /// Split the segment `a - b`, near its middle or on a concentric shell around an end it shares with other
+    /// segments.
+    fn split_segment(&mut self, triangulator: &mut Triangulator, a: u32, b: u32) {
+        let Some(side) = triangulator.find_edge(a, b) else {
+            return;
+        };
+        let (pa, pb) = (triangulator.point(a), triangulator.point(b));
+        let is_apex = |v: u32| {
+            !triangulator.split_from.contains_key(&v)
+                && self.segment_degree.get(&v).copied().unwrap_or(0) > 1
+        };
+        let length = pa.distance(pb);
+        let shell = 2f64.powf((length * 0.5).log2().round());
+        let p = match (is_apex(a), is_apex(b)) {
+            (true, false) => pa + (pb - pa) * (shell / length),
+            (false, true) => pb + (pa - pb) * (shell / length),
+            _ => (pa + pb) * 0.5,
+        };
+        let v = triangulator.insert(p, Location::Edge(side.0, side.1));
+        self.added += 1;
+        self.watch(triangulator, v);
+    }
+
+    /// Whether the triangle needs splitting, and may be split.
+    fn is_bad(&self, triangulator: &Triangulator, vertices: [u32; 3]) -> bool {
+        let [a, b, c] = vertices.map(|v| triangulator.point(v));
+        let area = 0.5 * orient2d(a, b, c);
+        if area <= 0.0 {
+            return false;
+        }
+        if self.max_area > 0.0 && area > self.max_area {
+            return true;
+        }
+        if self.ratio == 0.0 {
+            return false;
+        }
+        let lengths = [b.distance(c), c.distance(a), a.distance(b)];
+        let shortest = (0..3)
+            .min_by(|&i, &j| lengths[i].total_cmp(&lengths[j]))
+            .expect("triangles have three sides");
+        let ratio = lengths.iter().product::<f64>() / (4.0 * area * lengths[shortest]);
+        if ratio <= self.ratio {
+            return false;
+        }
+        // A skinny triangle between two segments that meet at a small angle cannot be fixed; leave it.
+        let ends = [vertices[(shortest + 1) % 3], vertices[(shortest + 2) % 3]];
+        let segments = ends.map(|v| triangulator.split_from.get(&v).copied());
+        match segments {
+            [Some(s), Some(t)] if s != t => {
+                let [s, t] = [s, t].map(|s| triangulator.segment_ends[s as usize]);
+                !s.iter().any(|v| t.contains(v))
+            }
+            _ => true,
+        }
+    }
+
+    fn run(&mut self, triangulator: &mut Triangulator) {
+        while self.added < self.max_points {
+            if let Some((a, b)) = self.encroached.pop() {
+                if triangulator.is_segment(a, b) && Self::is_encroached(triangulator, a, b) {
+                    self.split_segment(triangulator, a, b);
+                }
+                continue;
+            }
+            let Some(vertices) = self.bad.pop_front() else {
+                break;
+            };
+            let Some((t, _)) = triangulator.find_edge(vertices[0], vertices[1]) else {
+                continue;
+            };
+            let triangle = triangulator.triangles[t as usize];
+            if !triangle.vertices.contains(&vertices[2])
+                || !triangle.inside
+                || !self.is_bad(triangulator, vertices)
+            {
+                continue;
+            }
+            self.split_triangle(triangulator, t);
+        }
+    }
+
+    /// Insert the circumcentre of `t`, or split the segments it would encroach on.
+    fn split_triangle(&mut self, triangulator: &mut Triangulator, t: u32) {
+        let vertices = triangulator.triangles[t as usize].vertices;
+        let [a, b, c] = vertices.map(|v| triangulator.point(v));
+        let (u, w) = (b - a, c - a);
+        let d = 2.0 * u.perp_dot(w);
+        let center = a + DVec2::new(
+            w.y * u.length_squared() - u.y * w.length_squared(),
+            u.x * w.length_squared() - w.x * u.length_squared(),
+        ) / d;
+        let location = match triangulator.locate(center, t, false) {
+            Ok(location) => location,
+            Err(side) => {
+                let (p, q) = triangulator.edge(side);
+                self.split_segment(triangulator, p, q);
+                self.bad.push_back(vertices);
+                return;
+            }
+        };
+        let start = match location {
+            Location::Vertex(_) => return,
+            Location::Edge(t, _) | Location::Face(t) => t,
+        };
+
+        // The segments on the boundary of the cavity the circumcentre would clear out.
+        let mut encroached = Vec::new();
+        let mut visited = vec![start];
+        let mut stack = vec![start];
+        while let Some(t) = stack.pop() {
+            for i in 0..3 {
+                let (p, q) = triangulator.edge((t, i));
+                let (pp, pq) = (triangulator.point(p), triangulator.point(q));
+                if triangulator.is_segment(p, q) {
+                    if (pp - center).dot(pq - center) < 0.0 {
+                        encroached.push((p, q));
+                    }
+                    continue;
+                }
+                let u = triangulator.triangles[t as usize].neighbours[i];
+                if u == NONE || visited.contains(&u) {
+                    continue;
+                }
+                let [x, y, z] = triangulator.triangles[u as usize]
+                    .vertices
+                    .map(|v| triangulator.point(v));
+                if incircle(x, y, z, center) > 0.0 {
+                    visited.push(u);
+                    stack.push(u);
+                }
+            }
+        }
+        if encroached.is_empty() {
+            let v = triangulator.insert(center, location);
+            self.added += 1;
+            self.watch(triangulator, v);
+        } else {
+            for (p, q) in encroached {
+                self.split_segment(triangulator, p, q);
+            }
+            self.bad.push_back(vertices);
+        }
+    }
+}
+
+/// The points of the convex hull, counter-clockwise, leaving out points along its edges.
+fn convex_hull(points: &[DVec2]) -> Vec<u32> {
+    let mut sorted: Vec<u32> = (0..points.len() as u32).collect();
+    sorted.sort_by(|&a, &b| {
+        let (p, q) = (points[a as usize], points[b as usize]);
+        p.x.total_cmp(&q.x).then(p.y.total_cmp(&q.y))
+    });
+    let mut hull: Vec<u32> = Vec::new();
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let start = hull.len();
+        for v in pass {
+            while hull.len() >= start + 2 {
+                let [a, b] = [hull[hull.len() - 2], hull[hull.len() - 1]];
+                if orient2d(points[a as usize], points[b as usize], points[v as usize]) > 0.0 {
+                    break;
+                }
+                hull.pop();
+            }
+            hull.push(v);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+/// Interleave the bits of two 16-bit numbers, for sorting points along a Z-order curve.
+fn interleave(x: u32, y: u32) -> u64 {
+    (0..16).fold(0, |key, bit| {
+        key | u64::from((x >> bit) & 1) << (2 * bit) | u64::from((y >> bit) & 1) << (2 * bit + 1)
+    })
+}
+
+impl PlanarGraph {
+    /// Triangulate the region the segments enclose, minus the holes, so that every segment is a union of
+    /// edges and every triangle is as Delaunay as the segments allow, then refine it as `config` asks.
+    pub fn triangulate(
+        &self,
+        config: &TriangulationConfig,
+    ) -> Result<Triangulation, TriangulationError> {
+        if self.points.is_empty() {
+            return Err(TriangulationError::Empty);
+        }
+        if let Some(point) = self.points.iter().position(|p| !p.is_finite()) {
+            return Err(TriangulationError::NonFinitePoint { point });
+        }
+        if let Some(hole) = self.holes.iter().position(|p| !p.is_finite()) {
+            return Err(TriangulationError::NonFiniteHole { hole });
+        }
+
+        // Merge coincident points.
+        let mut points: Vec<DVec2> = Vec::new();
+        let mut index = HashMap::new();
+        let input_vertex: Vec<u32> = self
+            .points
+            .iter()
+            .map(|p| {
+                let p = p.as_dvec2() + 0.0;
+                *index
+                    .entry((p.x.to_bits(), p.y.to_bits()))
+                    .or_insert_with(|| {
+                        points.push(p);
+                        points.len() as u32 - 1
+                    })
+            })
+            .collect();
+        let mut segment_ends = Vec::with_capacity(self.segments.len());
+        for (segment, &[a, b]) in self.segments.iter().enumerate() {
+            let ends = [a, b].map(|v| input_vertex.get(v as usize).copied());
+            match ends {
+                [Some(a), Some(b)] if a != b => segment_ends.push([a, b]),
+                _ => return Err(TriangulationError::InvalidSegment { segment }),
+            }
+        }
+        if self.segments.is_empty() || config.convex_hull {
+            let hull = convex_hull(&points);
+            if hull.len() >= 3 {
+                for k in 0..hull.len() {
+                    segment_ends.push([hull[k], hull[(k + 1) % hull.len()]]);
+                }
+            }
+        }
+
+        let mut triangulator = Triangulator::new(points);
+        let (min, max) = triangulator.points[..triangulator.enclosing as usize]
+            .iter()
+            .fold((DVec2::INFINITY, DVec2::NEG_INFINITY), |(lo, hi), &p| {
+                (lo.min(p), hi.max(p))
+            });
+        let scale = 65535.0 / (max - min).max_element().max(f64::MIN_POSITIVE);
+        let mut order: Vec<u32> = (0..triangulator.enclosing).collect();
+        order.sort_by_cached_key(|&v| {
+            let p = (triangulator.point(v) - min) * scale;
+            interleave(p.x as u32, p.y as u32)
+        });
+        let mut last = 0;
+        for v in order {
+            let location = triangulator
+                .locate(triangulator.point(v), last, true)
+                .expect("the enclosing triangle contains every point");
+            triangulator.connect(v, location);
+            last = triangulator.vertex_triangle[v as usize];
+        }
+
+        triangulator.segment_ends = segment_ends.clone();
+        for (segment, &[a, b]) in segment_ends.iter().enumerate() {
+            triangulator.insert_segment(a, b, segment as u32)?;
+        }
+        let outside: Vec<u32> = (0..triangulator.triangles.len() as u32)
+            .filter(|&t| {
+                triangulator.triangles[t as usize]
+                    .vertices
+                    .iter()
+                    .any(|&v| triangulator.is_enclosing(v))
+            })
+            .collect();
+        triangulator.carve(outside);
+        for hole in &self.holes {
+            let Ok(location) = triangulator.locate(hole.as_dvec2(), 0, true) else {
+                continue;
+            };
+            let t = match location {
+                Location::Vertex(v) => triangulator.vertex_triangle[v as usize],
+                Location::Edge(t, _) | Location::Face(t) => t,
+            };
+            triangulator.carve(vec![t]);
+        }
+
+        let mut steiner_points = 0;
+        if config.min_angle > 0.0 || config.max_area > 0.0 {
+            let mut segment_degree = HashMap::new();
+            for &[a, b] in &segment_ends {
+                *segment_degree.entry(a).or_insert(0) += 1;
+                *segment_degree.entry(b).or_insert(0) += 1;
+            }
+            let mut refiner = Refiner {
+                ratio: if config.min_angle > 0.0 {
+                    0.5 / f64::from(config.min_angle).sin()
+                } else {
+                    0.0
+                },
+                max_area: f64::from(config.max_area),
+                max_points: config.max_steiner_points,
+                added: 0,
+                segment_degree,
+                encroached: triangulator.segments.keys().copied().collect(),
+                bad: triangulator
+                    .triangles
+                    .iter()
+                    .filter(|t| t.inside)
+                    .map(|t| t.vertices)
+                    .collect(),
+            };
+            refiner.run(&mut triangulator);
+            steiner_points = refiner.added;
+        }
+
+        emit(&triangulator, &input_vertex, steiner_points)
+    }
+}
+
+/// Build the mesh from the inside triangles.
+fn emit(
+    triangulator: &Triangulator,
+    input_vertex: &[u32],
+    steiner_points: usize,
+) -> Result<Triangulation, TriangulationError> {
+    let mut map = vec![NONE; triangulator.points.len()];
+    let mut used: Vec<u32> = triangulator
+        .triangles
+        .iter()
+        .filter(|t| t.inside)
+        .flat_map(|t| t.vertices)
+        .collect();
+    if used.is_empty() {
+        return Err(TriangulationError::Empty);
+    }
+    used.sort_unstable();
+    used.dedup();
+    let mut positions = Vec::with_capacity(used.len());
+    for v in used {
+        map[v as usize] = positions.len() as u32;
+        let p = triangulator.point(v).as_vec2();
+        positions.push(Vec3::new(p.x, p.y, 0.0));
+    }
+    let indices: Vec<u32> = triangulator
+        .triangles
+        .iter()
+        .filter(|t| t.inside)
+        .flat_map(|t| t.vertices.map(|v| map[v as usize]))
+        .collect();
+    let mesh = HalfEdgeMesh::from_triangles(positions, &indices)?;
+    let vertex =
+        |v: u32| (map[v as usize] != NONE).then(|| VertexId::new(map[v as usize] as usize));
+    let segment_edges = triangulator
+        .segments
+        .keys()
+        .filter_map(|&(a, b)| mesh.find_edge(vertex(a)?, vertex(b)?))
+        .collect();
+    Ok(Triangulation {
+        input_vertices: input_vertex.iter().map(|&v| vertex(v)).collect(),
+        segment_edges,
+        steiner_points,
+        mesh,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2 × 2 square with a 1 × 1 square hole in the middle.
+    fn frame() -> PlanarGraph {
+        let square = |lo: f32, hi: f32| {
+            [
+                Vec2::new(lo, lo),
+                Vec2::new(hi, lo),
+                Vec2::new(hi, hi),
+                Vec2::new(lo, hi),
+            ]
+        };
+        PlanarGraph {
+            points: square(0.0, 2.0)
+                .into_iter()
+                .chain(square(0.5, 1.5))
+                .collect(),
+            segments: vec![
+                [0, 1],
+                [1, 2],
+                [2, 3],
+                [3, 0],
+                [4, 5],
+                [5, 6],
+                [6, 7],
+                [7, 4],
+            ],
+            holes: vec![Vec2::ONE],
+        }
+    }
+
+    fn area(mesh: &HalfEdgeMesh) -> f32 {
+        mesh.faces().map(|f| mesh.face_area(f)).sum()
+    }
+
+    /// Check that the segment edges lying along each segment add up to its length, and that every segment
+    /// edge lies along a segment.
+    fn check_segments(graph: &PlanarGraph, triangulation: &Triangulation) {
+        let mesh = &triangulation.mesh;
+        let along = |e: EdgeId, [a, b]: [u32; 2]| {
+            let (a, b) = (graph.points[a as usize], graph.points[b as usize]);
+            mesh.edge_vertices(e).iter().all(|&v| {
+                let p = mesh.position(v).truncate();
+                (p - a).perp_dot(b - a).abs() < 1e-5 && (p - a).dot(p - b) < 1e-5
+            })
+        };
+        for &segment in &graph.segments {
+            let [a, b] = segment.map(|v| graph.points[v as usize]);
+            let length: f32 = triangulation
+                .segment_edges
+                .iter()
+                .filter(|&&e| along(e, segment))
+                .map(|&e| mesh.edge_length(e))
+                .sum();
+            assert!((length - a.distance(b)).abs() < 1e-5);
+        }
+        for &e in &triangulation.segment_edges {
+            assert!(graph.segments.iter().any(|&segment| along(e, segment)));
+        }
+    }
+
+    #[test]
+    fn segments_and_holes_are_kept() {
+        let graph = frame();
+        let triangulation = graph.triangulate(&Default::default()).unwrap();
+        let mesh = &triangulation.mesh;
+        assert!(mesh.validate().is_ok());
+        assert_eq!(mesh.vertex_count(), 8);
+        assert_eq!(mesh.face_count(), 8);
+        assert_eq!(triangulation.steiner_points, 0);
+        assert!(triangulation.input_vertices.iter().all(Option::is_some));
+        assert!((area(mesh) - 3.0).abs() < 1e-5);
+        assert!(mesh.faces().all(|f| mesh.face_normal(f).z > 0.0));
+        check_segments(&graph, &triangulation);
+    }
+
+    #[test]
+    fn refinement_reaches_the_minimum_angle() {
+        let graph = frame();
+        let min_angle = 30f32.to_radians();
+        let config = TriangulationConfig {
+            min_angle,
+            max_area: 0.05,
+            ..Default::default()
+        };
+        let triangulation = graph.triangulate(&config).unwrap();
+        let mesh = &triangulation.mesh;
+        assert!(mesh.validate().is_ok());
+        assert!(triangulation.steiner_points > 0);
+        assert_eq!(mesh.vertex_count(), 8 + triangulation.steiner_points);
+        let smallest = mesh
+            .halfedges()
+            .filter(|&h| !mesh.is_boundary_halfedge(h))
+            .map(|h| mesh.corner_angle(h))
+            .fold(f32::INFINITY, f32::min);
+        assert!(smallest >= min_angle - 1e-4);
+        assert!(mesh.faces().all(|f| mesh.face_area(f) <= 0.05 + 1e-6));
+        assert!((area(mesh) - 3.0).abs() < 1e-5);
+        check_segments(&graph, &triangulation);
+    }
+
+    #[test]
+    fn segments_are_forced_into_the_delaunay_triangulation() {
+        // The Delaunay triangulation of this flat rhombus joins the two middle points, not the ends.
+        let graph = PlanarGraph {
+            points: vec![
+                Vec2::new(-2.0, 0.0),
+                Vec2::new(0.0, -0.2),
+                Vec2::new(2.0, 0.0),
+                Vec2::new(0.0, 0.2),
+            ],
+            segments: vec![[0, 2]],
+            holes: Vec::new(),
+        };
+        let unconstrained = PlanarGraph {
+            segments: Vec::new(),
+            ..graph.clone()
+        };
+        let config = TriangulationConfig {
+            convex_hull: true,
+            ..Default::default()
+        };
+        let delaunay = unconstrained.triangulate(&config).unwrap();
+        let vertex = |t: &Triangulation, v: usize| t.input_vertices[v].unwrap();
+        assert!(delaunay
+            .mesh
+            .find_edge(vertex(&delaunay, 0), vertex(&delaunay, 2))
+            .is_none());
+
+        let constrained = graph.triangulate(&config).unwrap();
+        let e = constrained
+            .mesh
+            .find_edge(vertex(&constrained, 0), vertex(&constrained, 2));
+        assert!(e.is_some_and(|e| constrained.segment_edges.contains(&e)));
+        assert!((area(&constrained.mesh) - 0.8).abs() < 1e-5);
+    }
+
+    #[test]
+    fn bad_graphs_are_refused() {
+        let mut graph = frame();
+        graph.segments.push([0, 8]);
+        assert!(matches!(
+            graph.triangulate(&Default::default()),
+            Err(TriangulationError::InvalidSegment { segment: 8 })
+        ));
+
+        let mut graph = frame();
+        graph.segments.push([0, 2]);
+        graph.segments.push([1, 3]);
+        assert!(matches!(
+            graph.triangulate(&Default::default()),
+            Err(TriangulationError::CrossingSegments { .. })
+        ));
+
+        let collinear = PlanarGraph {
+            points: vec![Vec2::ZERO, Vec2::X, 2.0 * Vec2::X],
+            ..Default::default()
+        };
+        assert_eq!(
+            collinear.triangulate(&Default::default()).unwrap_err(),
+            TriangulationError::Empty
+        );
+    }
+}
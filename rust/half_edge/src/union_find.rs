@@ -0,0 +1,23 @@
+/// Disjoint sets over `0..n`, with path halving.
+pub(crate) struct UnionFind(Vec<usize>);
+
+impl UnionFind {
+    pub(crate) fn new(n: usize) -> Self {
+        Self((0..n).collect())
+    }
+
+    pub(crate) fn find(&mut self, mut i: usize) -> usize {
+        while self.0[i] != i {
+            self.0[i] = self.0[self.0[i]];
+            i = self.0[i];
+        }
+        i
+    }
+
+    /// Join the sets of `a` and `b`, returning whether they were separate.
+    pub(crate) fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        self.0[a] = b;
+        a != b
+    }
+}
@@ -0,0 +1,444 @@
+//! Structural checks for half-edge meshes and for the raw polygon lists they are built from.
+//!
+//! [`HalfEdgeMesh::validate`] checks the invariants every other module relies on, and never panics, even on
+//! a mesh whose links have been corrupted. [`validate_polygons`] explains why a polygon list would be
+//! rejected by [`HalfEdgeMesh::from_polygons`], reporting every problem rather than just the first.
+
+use core::fmt;
+use std::collections::HashMap;
+
+use crate::mesh::{EdgeId, FaceId, HalfEdgeId, HalfEdgeMesh, VertexId};
+
+/// A single problem found by [`HalfEdgeMesh::validate`] or [`validate_polygons`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// A link points past the end of its arena.
+    DanglingReference { halfedge: HalfEdgeId },
+    /// `twin(twin(h)) != h`, or `h` is its own twin.
+    TwinAsymmetry { halfedge: HalfEdgeId },
+    /// A half-edge and its twin start at the same vertex.
+    TwinSameOrigin { halfedge: HalfEdgeId },
+    /// A half-edge and its twin belong to different edges, or the edge doesn't point back at either of them.
+    EdgeMismatch { edge: EdgeId },
+    /// `prev(next(h)) != h`.
+    NextPrevMismatch { halfedge: HalfEdgeId },
+    /// `next(h)` does not start where `h` ends.
+    DisconnectedNext { halfedge: HalfEdgeId },
+    /// `next(h)` belongs to a different face (or hole) than `h`.
+    FaceLoopMismatch { halfedge: HalfEdgeId },
+    /// Following `next` from the face's half-edge never returns to it, or the face has fewer than 3 sides.
+    BrokenFaceLoop { face: FaceId },
+    /// The face's half-edge does not point back at the face.
+    FaceHalfEdgeMismatch { face: FaceId },
+    /// The vertex's half-edge is missing, out of range or does not leave the vertex, although half-edges do.
+    DanglingVertex { vertex: VertexId },
+    /// A boundary vertex whose stored half-edge is not its outgoing boundary half-edge.
+    BoundaryHalfEdgeNotPreferred { vertex: VertexId },
+    /// More than two faces share the edge `a`-`b`.
+    NonManifoldEdge {
+        a: VertexId,
+        b: VertexId,
+        faces: Vec<usize>,
+    },
+    /// The faces around a vertex form more than one fan (a bow-tie), or the vertex's ring does not reach
+    /// all of its half-edges.
+    NonManifoldVertex { vertex: VertexId },
+    /// Two faces traverse the edge `a`->`b` in the same direction.
+    InconsistentWinding {
+        a: VertexId,
+        b: VertexId,
+        faces: [usize; 2],
+    },
+    /// Two faces use exactly the same set of vertices.
+    DuplicateFace { face: usize, duplicate_of: usize },
+    /// A polygon has fewer than three corners.
+    FaceTooSmall { face: usize },
+    /// A polygon visits the same vertex twice.
+    DegenerateFace { face: usize },
+    /// A polygon refers to a vertex that does not exist.
+    IndexOutOfRange { face: usize, index: u32 },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DanglingReference { halfedge } => {
+                write!(f, "{halfedge} links to an element that does not exist")
+            }
+            Self::TwinAsymmetry { halfedge } => {
+                write!(f, "twin of {halfedge} does not point back at it")
+            }
+            Self::TwinSameOrigin { halfedge } => {
+                write!(f, "{halfedge} and its twin start at the same vertex")
+            }
+            Self::EdgeMismatch { edge } => {
+                write!(f, "half-edges of {edge} disagree about their edge")
+            }
+            Self::NextPrevMismatch { halfedge } => {
+                write!(f, "prev(next({halfedge})) is not {halfedge}")
+            }
+            Self::DisconnectedNext { halfedge } => {
+                write!(f, "next({halfedge}) does not start where {halfedge} ends")
+            }
+            Self::FaceLoopMismatch { halfedge } => {
+                write!(f, "{halfedge} and next({halfedge}) border different faces")
+            }
+            Self::BrokenFaceLoop { face } => write!(f, "the half-edge loop of {face} is broken"),
+            Self::FaceHalfEdgeMismatch { face } => {
+                write!(f, "the half-edge of {face} does not border it")
+            }
+            Self::DanglingVertex { vertex } => {
+                write!(
+                    f,
+                    "{vertex} does not reference one of its outgoing half-edges"
+                )
+            }
+            Self::BoundaryHalfEdgeNotPreferred { vertex } => {
+                write!(
+                    f,
+                    "boundary vertex {vertex} does not reference its boundary half-edge"
+                )
+            }
+            Self::NonManifoldEdge { a, b, faces } => {
+                write!(
+                    f,
+                    "edge {a}-{b} is shared by {} faces: {faces:?}",
+                    faces.len()
+                )
+            }
+            Self::NonManifoldVertex { vertex } => {
+                write!(f, "the faces around {vertex} do not form a single fan")
+            }
+            Self::InconsistentWinding { a, b, faces } => {
+                write!(
+                    f,
+                    "faces {} and {} both traverse {a}->{b}",
+                    faces[0], faces[1]
+                )
+            }
+            Self::DuplicateFace { face, duplicate_of } => {
+                write!(f, "face {face} duplicates face {duplicate_of}")
+            }
+            Self::FaceTooSmall { face } => write!(f, "face {face} has fewer than 3 corners"),
+            Self::DegenerateFace { face } => write!(f, "face {face} visits a vertex twice"),
+            Self::IndexOutOfRange { face, index } => {
+                write!(
+                    f,
+                    "face {face} refers to vertex {index}, which does not exist"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl HalfEdgeMesh {
+    /// Check every connectivity invariant of the mesh, returning all problems found.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        self.validate_halfedges(&mut errors);
+        // Walking loops and rings on top of broken links could report nonsense (or never finish), so stop here
+        // if the basic pointers are wrong.
+        if errors.is_empty() {
+            self.validate_faces(&mut errors);
+            self.validate_vertices(&mut errors);
+        }
+        if errors.is_empty() {
+            let polygons = self.faces().map(|f| {
+                let corners = self.face_vertices(f).map(|v| v.index() as u32).collect();
+                (f.index(), corners)
+            });
+            find_duplicate_faces(polygons, &mut errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_halfedges(&self, errors: &mut Vec<ValidationError>) {
+        let n = self.halfedges.len();
+        let removed_halfedge = |h: HalfEdgeId| {
+            self.edges
+                .get(self.halfedges[h.index()].edge.index())
+                .is_none_or(|e| e.removed)
+        };
+        for (i, he) in self.halfedges.iter().enumerate() {
+            let h = HalfEdgeId::new(i);
+            if he.edge.index() < self.edges.len() && self.edges[he.edge.index()].removed {
+                continue;
+            }
+            if [he.twin, he.next, he.prev]
+                .iter()
+                .any(|&x| x.index() >= n || removed_halfedge(x))
+                || self
+                    .vertices
+                    .get(he.origin.index())
+                    .is_none_or(|v| v.removed)
+                || he.edge.index() >= self.edges.len()
+                || he
+                    .face
+                    .is_some_and(|f| self.faces.get(f.index()).is_none_or(|f| f.removed))
+            {
+                errors.push(ValidationError::DanglingReference { halfedge: h });
+                continue;
+            }
+            let twin = &self.halfedges[he.twin.index()];
+            if he.twin == h || twin.twin != h {
+                errors.push(ValidationError::TwinAsymmetry { halfedge: h });
+            } else if twin.origin == he.origin {
+                errors.push(ValidationError::TwinSameOrigin { halfedge: h });
+            }
+            let next = &self.halfedges[he.next.index()];
+            if next.prev != h {
+                errors.push(ValidationError::NextPrevMismatch { halfedge: h });
+            }
+            if twin.origin != next.origin {
+                errors.push(ValidationError::DisconnectedNext { halfedge: h });
+            }
+            if next.face != he.face {
+                errors.push(ValidationError::FaceLoopMismatch { halfedge: h });
+            }
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            let e = EdgeId::new(i);
+            if edge.removed {
+                continue;
+            }
+            let h = edge.halfedge;
+            if h.index() >= n {
+                errors.push(ValidationError::EdgeMismatch { edge: e });
+                continue;
+            }
+            let t = self.halfedges[h.index()].twin;
+            if self.halfedges[h.index()].edge != e
+                || t.index() >= n
+                || self.halfedges[t.index()].edge != e
+            {
+                errors.push(ValidationError::EdgeMismatch { edge: e });
+            }
+        }
+    }
+
+    fn validate_faces(&self, errors: &mut Vec<ValidationError>) {
+        for f in self.faces() {
+            let start = self.face_halfedge(f);
+            if start.index() >= self.halfedges.len()
+                || self.is_removed_halfedge(start)
+                || self.face(start) != Some(f)
+            {
+                errors.push(ValidationError::FaceHalfEdgeMismatch { face: f });
+                continue;
+            }
+            // With next/prev/face already checked per half-edge, the loop can only fail to close by being
+            // longer than the whole mesh.
+            let mut sides = 0;
+            let mut h = start;
+            loop {
+                sides += 1;
+                h = self.next(h);
+                if h == start || sides > self.halfedges.len() {
+                    break;
+                }
+            }
+            if h != start || sides < 3 {
+                errors.push(ValidationError::BrokenFaceLoop { face: f });
+            }
+        }
+    }
+
+    fn validate_vertices(&self, errors: &mut Vec<ValidationError>) {
+        let mut outgoing = vec![0usize; self.vertices.len()];
+        let mut has_boundary = vec![false; self.vertices.len()];
+        for h in self.halfedges() {
+            let v = self.origin(h).index();
+            outgoing[v] += 1;
+            has_boundary[v] |= self.is_boundary_halfedge(h);
+        }
+
+        for v in self.vertices() {
+            let start = match self.vertex_halfedge(v) {
+                Some(h)
+                    if h.index() < self.halfedges.len()
+                        && !self.is_removed_halfedge(h)
+                        && self.origin(h) == v =>
+                {
+                    h
+                }
+                None if outgoing[v.index()] == 0 => continue,
+                _ => {
+                    errors.push(ValidationError::DanglingVertex { vertex: v });
+                    continue;
+                }
+            };
+            if has_boundary[v.index()] && !self.is_boundary_halfedge(start) {
+                errors.push(ValidationError::BoundaryHalfEdgeNotPreferred { vertex: v });
+            }
+
+            let mut count = 0;
+            let mut boundary_count = 0;
+            let mut h = start;
+            loop {
+                count += 1;
+                boundary_count += usize::from(self.is_boundary_halfedge(h));
+                h = self.twin(self.prev(h));
+                if h == start || count > outgoing[v.index()] {
+                    break;
+                }
+            }
+            if count != outgoing[v.index()] || boundary_count > 1 {
+                errors.push(ValidationError::NonManifoldVertex { vertex: v });
+            }
+        }
+    }
+}
+
+/// Check a raw polygon list (as passed to [`HalfEdgeMesh::from_polygons`]) for everything that would stop
+/// it from becoming a half-edge mesh, duplicate faces included, returning every problem found. An empty result
+/// means construction will succeed.
+pub fn validate_polygons<P: AsRef<[u32]>>(
+    vertex_count: usize,
+    polygons: &[P],
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    let mut usable = Vec::with_capacity(polygons.len());
+    for (face, polygon) in polygons.iter().enumerate() {
+        let polygon = polygon.as_ref();
+        if polygon.len() < 3 {
+            errors.push(ValidationError::FaceTooSmall { face });
+        } else if let Some(&index) = polygon.iter().find(|&&i| i as usize >= vertex_count) {
+            errors.push(ValidationError::IndexOutOfRange { face, index });
+        } else if (1..polygon.len()).any(|i| polygon[..i].contains(&polygon[i])) {
+            errors.push(ValidationError::DegenerateFace { face });
+        } else {
+            usable.push(face);
+        }
+    }
+
+    // Undirected edge -> the faces using it, with the direction each one traverses it in.
+    let mut edge_faces: HashMap<(u32, u32), Vec<(usize, bool)>> = HashMap::new();
+    for &face in &usable {
+        let polygon = polygons[face].as_ref();
+        for (i, &a) in polygon.iter().enumerate() {
+            let b = polygon[(i + 1) % polygon.len()];
+            edge_faces
+                .entry((a.min(b), a.max(b)))
+                .or_default()
+                .push((face, a < b));
+        }
+    }
+    let mut edge_list: Vec<_> = edge_faces.iter().collect();
+    edge_list.sort_unstable_by_key(|(key, _)| **key);
+    for (&(a, b), faces) in &edge_list {
+        let (a, b) = (VertexId::new(a as usize), VertexId::new(b as usize));
+        if faces.len() > 2 {
+            errors.push(ValidationError::NonManifoldEdge {
+                a,
+                b,
+                faces: faces.iter().map(|&(f, _)| f).collect(),
+            });
+        } else if faces.len() == 2 && faces[0].1 == faces[1].1 {
+            let (a, b) = if faces[0].1 { (a, b) } else { (b, a) };
+            errors.push(ValidationError::InconsistentWinding {
+                a,
+                b,
+                faces: [faces[0].0, faces[1].0],
+            });
+        }
+    }
+
+    // Group the corners around each vertex into fans: two faces at `v` are in the same fan if they share an
+    // edge incident to `v`. More than one fan is a bow-tie.
+    let mut corners: HashMap<u32, Vec<usize>> = HashMap::new();
+    for &face in &usable {
+        for &v in polygons[face].as_ref() {
+            corners.entry(v).or_default().push(face);
+        }
+    }
+    let mut vertex_list: Vec<_> = corners.into_iter().collect();
+    vertex_list.sort_unstable_by_key(|(v, _)| *v);
+    for (v, faces) in vertex_list {
+        let mut parent: Vec<usize> = (0..faces.len()).collect();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        let mut by_neighbour: HashMap<u32, usize> = HashMap::new();
+        for (slot, &face) in faces.iter().enumerate() {
+            let polygon = polygons[face].as_ref();
+            let i = polygon.iter().position(|&x| x == v).unwrap_or_default();
+            let n = polygon.len();
+            for neighbour in [polygon[(i + 1) % n], polygon[(i + n - 1) % n]] {
+                if let Some(&other) = by_neighbour.get(&neighbour) {
+                    let (ra, rb) = (find(&mut parent, slot), find(&mut parent, other));
+                    parent[ra] = rb;
+                } else {
+                    by_neighbour.insert(neighbour, slot);
+                }
+            }
+        }
+        let fans = (0..faces.len())
+            .filter(|&i| find(&mut parent, i) == i)
+            .count();
+        if fans > 1 {
+            errors.push(ValidationError::NonManifoldVertex {
+                vertex: VertexId::new(v as usize),
+            });
+        }
+    }
+
+    find_duplicate_faces(
+        usable.iter().map(|&f| (f, polygons[f].as_ref().to_vec())),
+        &mut errors,
+    );
+    errors
+}
+
+/// Report faces whose vertex sets repeat an earlier face. Takes `(face index, corners)` pairs.
+fn find_duplicate_faces(
+    polygons: impl Iterator<Item = (usize, Vec<u32>)>,
+    errors: &mut Vec<ValidationError>,
+) {
+    let mut seen: HashMap<Vec<u32>, usize> = HashMap::new();
+    for (face, mut key) in polygons {
+        key.sort_unstable();
+        if let Some(&duplicate_of) = seen.get(&key) {
+            errors.push(ValidationError::DuplicateFace { face, duplicate_of });
+        } else {
+            seen.insert(key, face);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildError, Vec3};
+
+    #[test]
+    fn builder_and_validator_agree_on_duplicate_faces() {
+        let positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+        let polygons = [[0, 1, 2], [1, 0, 2]];
+        assert_eq!(
+            validate_polygons(3, &polygons),
+            vec![ValidationError::DuplicateFace {
+                face: 1,
+                duplicate_of: 0
+            }]
+        );
+        assert_eq!(
+            HalfEdgeMesh::from_polygons(positions, polygons).unwrap_err(),
+            BuildError::DuplicateFace {
+                face: 1,
+                duplicate_of: 0
+            }
+        );
+    }
+}
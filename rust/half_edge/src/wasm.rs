@@ -0,0 +1,483 @@
+//! JavaScript bindings, exported through wasm-bindgen.
+//!
+//! The [`Mesh`] class wraps a [`MeshData`] and exposes the crate's algorithms as methods. For drawing, it
+//! keeps flat render buffers laid out for a THREE.js `BufferGeometry`: positions, normals, texture
+//! coordinates and a triangle index list. Polygons are fanned into triangles, and vertices are split wherever
+//! their corners disagree on texture coordinates or normals, so render vertices need not match mesh vertices
+//! one to one. Per-vertex inputs and outputs on the JavaScript side (geodesic sources, curvature) use render
+//! vertices.
+//!
+//! The buffer accessors return views straight into wasm memory rather than copies. A view stays valid only
+//! until the next call into the module, which may grow memory or rebuild the buffers; call `.slice()` on it
+//! to keep the data longer. Handing a view to `BufferAttribute` and uploading it right away is fine.
+use std::collections::HashMap;
+
+use glam::{Vec2, Vec3};
+use js_sys::{Float32Array, Uint32Array};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    boolean::BooleanOperation,
+    bvh::{Bvh, Ray, SurfaceHit},
+    differential::NormalWeighting,
+    hole_filling::HoleFillingConfig,
+    io::{obj, ply, stl, MeshData},
+    mesh::{BuildError, HalfEdgeMesh, VertexId},
+    parameterization::ParameterizationConfig,
+    remeshing::RemeshingConfig,
+    repair::RepairConfig,
+    simplification::SimplificationConfig,
+    smoothing::{SmoothingConfig, SmoothingWeights},
+    subdivision::SubdivisionConfig,
+    triangulation::{PlanarGraph, TriangulationConfig},
+};
+
+/// Flat arrays for drawing the mesh, three components per position and normal, two per texture coordinate
+/// and three indices per triangle.
+#[derive(Default)]
+struct RenderBuffers {
+    positions: Vec<f32>,
+    normals: Vec<f32>,
+    uvs: Vec<f32>,
+    indices: Vec<u32>,
+    /// The mesh vertex behind each render vertex.
+    vertices: Vec<u32>,
+}
+
+impl RenderBuffers {
+    fn build(data: &MeshData) -> Self {
+        let mesh = &data.mesh;
+        let vertex_normals = mesh.vertex_normals(NormalWeighting::Area);
+        let mut buffers = Self::default();
+        let (uvs, normals) = (data.uvs(), data.normals());
+        let mut render_vertex: HashMap<(VertexId, [u32; 2], [u32; 3]), u32> = HashMap::new();
+        for f in mesh.faces() {
+            let corners: Vec<u32> = mesh
+                .face_halfedges(f)
+                .map(|h| {
+                    let v = mesh.origin(h);
+                    let uv = uvs.map_or(Vec2::ZERO, |uvs| *mesh.attribute(uvs, h));
+                    let normal = normals.map_or(vertex_normals[v.index()], |normals| {
+                        *mesh.attribute(normals, h)
+                    });
+                    let key = (
+                        v,
+                        uv.to_array().map(f32::to_bits),
+                        normal.to_array().map(f32::to_bits),
+                    );
+                    *render_vertex.entry(key).or_insert_with(|| {
+                        buffers.positions.extend(mesh.position(v).to_array());
+                        buffers.normals.extend(normal.to_array());
+                        buffers.uvs.extend(uv.to_array());
+                        buffers.vertices.push(v.index() as u32);
+                        buffers.vertices.len() as u32 - 1
+                    })
+                })
+                .collect();
+            for i in 1..corners.len() - 1 {
+                buffers
+                    .indices
+                    .extend([corners[0], corners[i], corners[i + 1]]);
+            }
+        }
+        buffers
+    }
+}
+
+/// A half-edge mesh owned by the wasm module.
+#[wasm_bindgen]
+pub struct Mesh {
+    data: MeshData,
+    /// Rebuilt on demand after the mesh changes.
+    buffers: Option<RenderBuffers>,
+    /// Built on the first spatial query and rebuilt after the connectivity changes.
+    bvh: Option<Bvh>,
+}
+
+impl Mesh {
+    fn wrap(data: MeshData) -> Self {
+        Self {
+            data,
+            buffers: None,
+            bvh: None,
+        }
+    }
+
+    /// Group a flat array of coordinates into points, refusing one that is not a multiple of 3 long.
+    fn points(coordinates: &[f32]) -> Result<Vec<Vec3>, JsError> {
+        if !coordinates.len().is_multiple_of(3) {
+            return Err(JsError::new(&format!(
+                "position array has length {}, which is not a multiple of 3",
+                coordinates.len()
+            )));
+        }
+        Ok(coordinates.chunks_exact(3).map(Vec3::from_slice).collect())
+    }
+
+    /// Swap in a mesh with different connectivity. Its attributes, texture coordinates and normals among
+    /// them, must already have been carried over by the operation that built it.
+    fn replace(&mut self, mesh: HalfEdgeMesh) {
+        self.data.mesh = mesh;
+        self.buffers = None;
+        self.bvh = None;
+    }
+
+    fn buffers(&mut self) -> &RenderBuffers {
+        self.buffers
+            .get_or_insert_with(|| RenderBuffers::build(&self.data))
+    }
+
+    /// The hierarchy over the mesh, along with the mesh to query it with.
+    fn bvh(&mut self) -> (&Bvh, &HalfEdgeMesh) {
+        let mesh = &self.data.mesh;
+        (self.bvh.get_or_insert_with(|| Bvh::new(mesh)), mesh)
+    }
+
+    /// Spread one value per mesh vertex over the render vertices.
+    fn per_render_vertex(&mut self, values: &[f32]) -> Vec<f32> {
+        self.buffers()
+            .vertices
+            .iter()
+            .map(|&v| values[v as usize])
+            .collect()
+    }
+}
+
+#[wasm_bindgen]
+impl Mesh {
+    /// Build a triangle mesh from a `Float32Array` of positions (three per vertex) and a `Uint32Array` of
+    /// indices (three per triangle). The triangles must form a manifold surface; use `fromSoup` otherwise.
+    #[wasm_bindgen(constructor)]
+    pub fn new(positions: &[f32], indices: &[u32]) -> Result<Mesh, JsError> {
+        Ok(Self::wrap(MeshData::new(HalfEdgeMesh::from_triangles(
+            Self::points(positions)?,
+            indices,
+        )?)))
+    }
+
+    /// Build a mesh from arbitrary triangles, welding vertices closer than `weldTolerance` and repairing
+    /// whatever else stops them forming a manifold surface. Throws if either array is not a multiple of 3
+    /// long.
+    #[wasm_bindgen(js_name = fromSoup)]
+    pub fn from_soup(
+        positions: &[f32],
+        indices: &[u32],
+        weld_tolerance: f32,
+    ) -> Result<Mesh, JsError> {
+        let positions = Self::points(positions)?;
+        if !indices.len().is_multiple_of(3) {
+            return Err(BuildError::TruncatedIndices { len: indices.len() }.into());
+        }
+        let triangles: Vec<&[u32]> = indices.chunks_exact(3).collect();
+        let config = RepairConfig {
+            weld_tolerance,
+            ..Default::default()
+        };
+        let (mesh, _) = HalfEdgeMesh::from_polygon_soup(&positions, &triangles, &config);
+        Ok(Self::wrap(MeshData::new(mesh)))
+    }
+
+    /// Triangulate a planar region in the z = 0 plane. `points` holds two coordinates per point, `segments`
+    /// two point indices per edge that must appear in the mesh, and `holes` two coordinates per point inside
+    /// a hole. Without segments the convex hull is triangulated. Triangles are refined until none has an
+    /// angle below `minAngle` degrees or an area above `maxArea`, where these are positive.
+    pub fn triangulate(
+        points: &[f32],
+        segments: &[u32],
+        holes: &[f32],
+        min_angle: f32,
+        max_area: f32,
+    ) -> Result<Mesh, JsError> {
+        let graph = PlanarGraph {
+            points: points.chunks_exact(2).map(Vec2::from_slice).collect(),
+            segments: segments.chunks_exact(2).map(|s| [s[0], s[1]]).collect(),
+            holes: holes.chunks_exact(2).map(Vec2::from_slice).collect(),
+        };
+        let config = TriangulationConfig {
+            min_angle: min_angle.max(0.0).to_radians(),
+            max_area: max_area.max(0.0),
+            ..Default::default()
+        };
+        Ok(Self::wrap(MeshData::new(graph.triangulate(&config)?.mesh)))
+    }
+
+    /// Parse an OBJ file from a `Uint8Array`.
+    #[wasm_bindgen(js_name = fromObj)]
+    pub fn from_obj(bytes: &[u8]) -> Result<Mesh, JsError> {
+        Ok(Self::wrap(obj::read(bytes)?))
+    }
+
+    /// Parse a binary or ASCII STL file from a `Uint8Array`, welding vertices closer than `weldTolerance`.
+    #[wasm_bindgen(js_name = fromStl)]
+    pub fn from_stl(bytes: &[u8], weld_tolerance: f32) -> Result<Mesh, JsError> {
+        Ok(Self::wrap(stl::read(bytes, weld_tolerance)?))
+    }
+
+    /// Parse a PLY file from a `Uint8Array`.
+    #[wasm_bindgen(js_name = fromPly)]
+    pub fn from_ply(bytes: &[u8]) -> Result<Mesh, JsError> {
+        Ok(Self::wrap(ply::read(bytes)?))
+    }
+
+    /// Serialize the mesh as an OBJ file, returned as a `Uint8Array`.
+    #[wasm_bindgen(js_name = toObj)]
+    pub fn to_obj(&self) -> Result<Vec<u8>, JsError> {
+        let mut bytes = Vec::new();
+        obj::write(&mut bytes, &self.data)?;
+        Ok(bytes)
+    }
+
+    #[wasm_bindgen(getter, js_name = vertexCount)]
+    pub fn vertex_count(&self) -> usize {
+        self.data.mesh.vertex_count()
+    }
+
+    #[wasm_bindgen(getter, js_name = faceCount)]
+    pub fn face_count(&self) -> usize {
+        self.data.mesh.face_count()
+    }
+
+    /// Statistics about the mesh as a JSON string (see [`crate::statistics::MeshStatistics`]), with
+    /// `histogramBins` bins in each histogram.
+    pub fn statistics(&self, histogram_bins: usize) -> String {
+        self.data.mesh.statistics(histogram_bins).to_json()
+    }
+
+    /// Render vertex positions, three per vertex, as a view into wasm memory.
+    pub fn positions(&mut self) -> Float32Array {
+        // SAFETY: the buffers are only dropped or rebuilt by later calls into the module, and the module
+        // docs tell callers not to hold views across those.
+        unsafe { Float32Array::view(&self.buffers().positions) }
+    }
+
+    /// Render vertex normals, three per vertex, as a view into wasm memory. These are the file's normals
+    /// where it had any, and area-weighted vertex normals otherwise.
+    pub fn normals(&mut self) -> Float32Array {
+        // SAFETY: as for `positions`.
+        unsafe { Float32Array::view(&self.buffers().normals) }
+    }
+
+    /// Render vertex texture coordinates, two per vertex, as a view into wasm memory. All zero if the mesh
+    /// has none.
+    pub fn uvs(&mut self) -> Float32Array {
+        // SAFETY: as for `positions`.
+        unsafe { Float32Array::view(&self.buffers().uvs) }
+    }
+
+    /// Triangle indices into the render vertices, three per triangle, as a view into wasm memory.
+    pub fn indices(&mut self) -> Uint32Array {
+        // SAFETY: as for `positions`.
+        unsafe { Uint32Array::view(&self.buffers().indices) }
+    }
+
+    /// The mesh vertex behind each render vertex, as a view into wasm memory.
+    #[wasm_bindgen(js_name = sourceVertices)]
+    pub fn source_vertices(&mut self) -> Uint32Array {
+        // SAFETY: as for `positions`.
+        unsafe { Uint32Array::view(&self.buffers().vertices) }
+    }
+
+    /// Subdivide `levels` times with `scheme`, one of `"loop"`, `"catmull-clark"` or `"sqrt3"`. Texture
+    /// coordinates and normals are interpolated onto the new corners.
+    pub fn subdivide(&mut self, scheme: &str, levels: u32) -> Result<(), JsError> {
+        let config = SubdivisionConfig {
+            levels,
+            ..Default::default()
+        };
+        let mesh = &self.data.mesh;
+        let fine = match scheme {
+            "loop" => mesh.loop_subdivision(&config)?,
+            "catmull-clark" => mesh.catmull_clark_subdivision(&config)?,
+            "sqrt3" => mesh.sqrt3_subdivision(&config)?,
+            _ => {
+                return Err(JsError::new(&format!(
+                    "unknown subdivision scheme {scheme:?}"
+                )))
+            }
+        };
+        self.replace(fine);
+        Ok(())
+    }
+
+    /// Simplify down to `targetFaces` faces, never performing a collapse with quadric error above
+    /// `maxError` if given. Texture coordinates and normals are carried along. Returns the number of
+    /// collapses.
+    pub fn simplify(
+        &mut self,
+        target_faces: usize,
+        max_error: Option<f64>,
+    ) -> Result<usize, JsError> {
+        let config = SimplificationConfig {
+            target_face_count: target_faces,
+            max_error: max_error.unwrap_or(f64::INFINITY),
+            ..Default::default()
+        };
+        let report = self.data.simplify(&config)?;
+        self.buffers = None;
+        self.bvh = None;
+        Ok(report.collapses)
+    }
+
+    /// Isotropic remeshing towards `targetEdgeLength` (zero for the current mean edge length). Texture
+    /// coordinates and normals follow the splits, collapses and flips.
+    pub fn remesh(&mut self, target_edge_length: f32, iterations: u32) -> Result<(), JsError> {
+        let config = RemeshingConfig {
+            target_edge_length,
+            iterations,
+            ..Default::default()
+        };
+        let mut mesh = std::mem::take(&mut self.data.mesh);
+        mesh.remesh(&config)?;
+        mesh.garbage_collect();
+        self.replace(mesh);
+        Ok(())
+    }
+
+    /// Taubin smoothing, which does not shrink the surface, with cotangent weights if `cotangent` is set
+    /// and uniform weights otherwise. Boundaries stay in place.
+    pub fn smooth(&mut self, iterations: u32, cotangent: bool) -> Result<(), JsError> {
+        let config = SmoothingConfig {
+            iterations,
+            weights: if cotangent {
+                SmoothingWeights::Cotangent
+            } else {
+                SmoothingWeights::Uniform
+            },
+            ..Default::default()
+        };
+        self.data.mesh.smooth(&config)?;
+        self.buffers = None;
+        if let Some(bvh) = &mut self.bvh {
+            bvh.refit(&self.data.mesh);
+        }
+        Ok(())
+    }
+
+    /// Fill every hole with at most `maxHoleEdges` edges (zero for any size) with a refined, faired patch.
+    /// Returns the number of holes filled. Patch corners take the texture coordinates and normals of the
+    /// boundary corners they grow from.
+    #[wasm_bindgen(js_name = fillHoles)]
+    pub fn fill_holes(&mut self, max_hole_edges: usize) -> Result<usize, JsError> {
+        let config = HoleFillingConfig {
+            max_hole_edges,
+            ..Default::default()
+        };
+        let mut mesh = std::mem::take(&mut self.data.mesh);
+        let result = mesh.fill_holes(&config);
+        self.replace(mesh);
+        Ok(result?.len())
+    }
+
+    /// Combine this mesh with `other` by `operation`, one of `"union"`, `"intersection"` or `"difference"`,
+    /// into a new mesh. Both must be closed triangle meshes. The result has no texture coordinates or file
+    /// normals.
+    pub fn boolean(&self, other: &Mesh, operation: &str) -> Result<Mesh, JsError> {
+        let operation = match operation {
+            "union" => BooleanOperation::Union,
+            "intersection" => BooleanOperation::Intersection,
+            "difference" => BooleanOperation::Difference,
+            _ => {
+                return Err(JsError::new(&format!(
+                    "unknown boolean operation {operation:?}"
+                )))
+            }
+        };
+        let result = self.data.mesh.boolean(&other.data.mesh, operation)?;
+        Ok(Self::wrap(MeshData::new(result.mesh)))
+    }
+
+    /// Replace the texture coordinates with a least squares conformal map, cutting seams as needed. Returns
+    /// the number of charts.
+    #[wasm_bindgen(js_name = computeUvs)]
+    pub fn compute_uvs(&mut self) -> Result<usize, JsError> {
+        let parameterization = self.data.compute_uvs(&ParameterizationConfig::default())?;
+        self.buffers = None;
+        Ok(parameterization.charts)
+    }
+
+    /// Geodesic distance from the nearest of the render vertices `sources` to every render vertex, with the
+    /// heat method.
+    #[wasm_bindgen(js_name = geodesicDistances)]
+    pub fn geodesic_distances(&mut self, sources: &[u32]) -> Result<Vec<f32>, JsError> {
+        let vertices = &self.buffers().vertices;
+        let sources = sources
+            .iter()
+            .map(|&s| {
+                vertices
+                    .get(s as usize)
+                    .map(|&v| VertexId::new(v as usize))
+                    .ok_or_else(|| JsError::new(&format!("render vertex {s} does not exist")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let distances = self.data.mesh.geodesic_distances(&sources)?;
+        Ok(self.per_render_vertex(&distances))
+    }
+
+    /// Curvature at every render vertex: `kind` is `"mean"`, `"gaussian"`, `"min"` or `"max"`.
+    pub fn curvature(&mut self, kind: &str) -> Result<Vec<f32>, JsError> {
+        let curvatures = self.data.mesh.curvatures()?;
+        let values: Vec<f32> = match kind {
+            "mean" => curvatures.iter().map(|c| c.mean).collect(),
+            "gaussian" => curvatures.iter().map(|c| c.gaussian).collect(),
+            "min" => curvatures.iter().map(|c| c.min).collect(),
+            "max" => curvatures.iter().map(|c| c.max).collect(),
+            _ => return Err(JsError::new(&format!("unknown curvature {kind:?}"))),
+        };
+        Ok(self.per_render_vertex(&values))
+    }
+
+    /// The first point where the ray from `origin` along `direction` (three numbers each) meets the surface,
+    /// for picking. Distances are in multiples of `direction`.
+    pub fn raycast(&mut self, origin: &[f32], direction: &[f32]) -> Option<Pick> {
+        let ray = Ray::new(Vec3::from_slice(origin), Vec3::from_slice(direction));
+        let (bvh, mesh) = self.bvh();
+        let hit = bvh.cast_ray(mesh, &ray, f32::INFINITY);
+        hit.map(|hit| Pick { hit })
+    }
+
+    /// The point of the surface nearest `point` (three numbers).
+    #[wasm_bindgen(js_name = closestPoint)]
+    pub fn closest_point(&mut self, point: &[f32]) -> Option<Pick> {
+        let (bvh, mesh) = self.bvh();
+        let hit = bvh.closest_point(mesh, Vec3::from_slice(point), f32::INFINITY);
+        hit.map(|hit| Pick { hit })
+    }
+}
+
+/// A point on the surface returned by `Mesh.raycast` and `Mesh.closestPoint`.
+#[wasm_bindgen]
+pub struct Pick {
+    hit: SurfaceHit,
+}
+
+#[wasm_bindgen]
+impl Pick {
+    /// The mesh face the point lies on.
+    #[wasm_bindgen(getter)]
+    pub fn face(&self) -> u32 {
+        self.hit.face.index() as u32
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn distance(&self) -> f32 {
+        self.hit.distance
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn point(&self) -> Vec<f32> {
+        self.hit.point.to_array().to_vec()
+    }
+
+    /// The mesh vertices of the triangle the point lies in. `Mesh.sourceVertices` maps render vertices to
+    /// these.
+    #[wasm_bindgen(getter)]
+    pub fn vertices(&self) -> Vec<u32> {
+        self.hit.vertices.map(|v| v.index() as u32).to_vec()
+    }
+
+    /// The weights of `vertices` that give `point`.
+    #[wasm_bindgen(getter)]
+    pub fn barycentric(&self) -> Vec<f32> {
+        self.hit.barycentric.to_array().to_vec()
+    }
+}
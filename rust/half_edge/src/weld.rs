@@ -0,0 +1,62 @@
+//! Merging coincident vertices.
+
+use std::collections::HashMap;
+
+use glam::Vec3;
+
+/// Merge vertices that lie within `tolerance` of each other.
+///
+/// Returns the merged positions and, for every input vertex, the index of the merged vertex it became. Each
+/// merged vertex keeps the position of the first input vertex that landed on it. With a tolerance of zero only
+/// bit-identical positions are merged.
+pub fn weld_vertices(positions: &[Vec3], tolerance: f32) -> (Vec<Vec3>, Vec<u32>) {
+    let mut welded = Vec::new();
+    let mut remap = Vec::with_capacity(positions.len());
+
+    if tolerance <= 0.0 {
+        let mut seen: HashMap<[u32; 3], u32> = HashMap::new();
+        for &p in positions {
+            let index = *seen
+                .entry(p.to_array().map(f32::to_bits))
+                .or_insert_with(|| {
+                    welded.push(p);
+                    welded.len() as u32 - 1
+                });
+            remap.push(index);
+        }
+        return (welded, remap);
+    }
+
+    // Spatial hash with cells one tolerance wide, so any match is in the 3x3x3 block of cells around a point.
+    let cell_of = |p: Vec3| (p / tolerance).floor().as_ivec3();
+    let mut grid: HashMap<glam::IVec3, Vec<u32>> = HashMap::new();
+    let tolerance_squared = tolerance * tolerance;
+    for &p in positions {
+        let cell = cell_of(p);
+        let mut found = None;
+        'search: for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(candidates) = grid.get(&(cell + glam::IVec3::new(dx, dy, dz))) else {
+                        continue;
+                    };
+                    if let Some(&index) = candidates
+                        .iter()
+                        .find(|&&i| welded[i as usize].distance_squared(p) <= tolerance_squared)
+                    {
+                        found = Some(index);
+                        break 'search;
+                    }
+                }
+            }
+        }
+        let index = found.unwrap_or_else(|| {
+            welded.push(p);
+            let index = welded.len() as u32 - 1;
+            grid.entry(cell).or_default().push(index);
+            index
+        });
+        remap.push(index);
+    }
+    (welded, remap)
+}